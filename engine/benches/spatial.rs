@@ -0,0 +1,129 @@
+//! Compares `SpatialGrid` queries with testing every item, on a map sized grid of tanks.
+//!
+//! Run with `cargo bench --bench spatial`.
+
+extern crate engine;
+extern crate cgmath;
+
+use cgmath::{Point3, Vector3};
+
+use engine::core::{Camera, SpatialGrid};
+use engine::math::{Aabb, Frustum, Ray};
+
+use std::f32;
+use std::time::Instant;
+
+const MAP_SIZE: f32 = 1000.0;
+const TANKS: usize = 10000;
+const QUERIES: usize = 1000;
+
+/// Deterministic pseudo-random numbers in `[0, 1)`.
+struct Lcg(u32);
+
+impl Lcg {
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+        return (self.0 >> 8) as f32 / (1 << 24) as f32;
+    }
+}
+
+fn bench<F: FnMut() -> usize>(name: &str, iterations: usize, mut f: F) {
+    let start = Instant::now();
+    let mut found = 0;
+
+    for _ in 0..iterations {
+        found += f();
+    }
+
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    println!("{:<24} {:>10} ns/iter ({} found)", name, nanos / iterations as u64, found);
+}
+
+fn main() {
+    let mut random = Lcg(7);
+    let tanks: Vec<Aabb> = (0..TANKS).map(|_| {
+        let x = random.next() * MAP_SIZE;
+        let z = random.next() * MAP_SIZE;
+        Aabb::new(Point3::new(x, 0.0, z), Point3::new(x + 2.0, 1.5, z + 3.0))
+    }).collect();
+
+    let centers: Vec<Point3<f32>> = (0..QUERIES).map(|_| {
+        Point3::new(random.next() * MAP_SIZE, 0.0, random.next() * MAP_SIZE)
+    }).collect();
+
+    let mut grid = SpatialGrid::new(16.0);
+    bench("insert", 1, || {
+        for (key, tank) in tanks.iter().enumerate() {
+            grid.insert(key, *tank);
+        }
+        TANKS
+    });
+
+    let mut step = 0;
+    bench("update", TANKS, || {
+        step += 1;
+        let key = step % TANKS;
+        let offset = Vector3::new(0.5, 0.0, 0.0);
+        let moved = Aabb::new(tanks[key].min + offset, tanks[key].max + offset);
+        grid.update(key, moved) as usize
+    });
+
+    let mut query = 0;
+    bench("radius, grid", QUERIES, || {
+        query += 1;
+        grid.query_radius(centers[query % QUERIES], 30.0).len()
+    });
+
+    bench("radius, linear", QUERIES, || {
+        query += 1;
+        let center = centers[query % QUERIES];
+        (0..TANKS).filter(|&key| {
+            let bounds = grid.bounds(key).unwrap();
+            let dx = (bounds.min.x - center.x).max(0.0).max(center.x - bounds.max.x);
+            let dz = (bounds.min.z - center.z).max(0.0).max(center.z - bounds.max.z);
+            dx * dx + dz * dz <= 30.0 * 30.0
+        }).count()
+    });
+
+    bench("ray, grid", QUERIES, || {
+        query += 1;
+        let ray = Ray::new(centers[query % QUERIES] + Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.5));
+        grid.query_ray(&ray, 200.0).len()
+    });
+
+    bench("ray, linear", QUERIES, || {
+        query += 1;
+        let ray = Ray::new(centers[query % QUERIES] + Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.5));
+        (0..TANKS).filter(|&key| {
+            ray.intersect_aabb(&grid.bounds(key).unwrap()).map_or(false, |distance| distance <= 200.0)
+        }).count()
+    });
+
+    bench("ray, grid, unbounded", QUERIES, || {
+        query += 1;
+        let ray = Ray::new(centers[query % QUERIES] + Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.5));
+        grid.query_ray(&ray, f32::INFINITY).len()
+    });
+
+    // A camera above the map looking ahead, seeing a small part of it.
+    let frustums: Vec<Frustum> = centers.iter().map(|&center| {
+        let mut camera = Camera::new();
+        camera.perspective(60.0, 16.0 / 9.0, 0.1, 150.0);
+        camera.look_at(center + Vector3::new(0.0, 30.0, 40.0), center, Vector3::new(0.0, 1.0, 0.0));
+        Frustum::from_matrix(&camera.vp_matrix())
+    }).collect();
+
+    // The grid tests the cells first, so it finds fewer of the boxes which only pass
+    // `Frustum::intersects_aabb` by being near a corner.
+    bench("frustum, grid", QUERIES, || {
+        query += 1;
+        grid.query_frustum(&frustums[query % QUERIES]).len()
+    });
+
+    bench("frustum, linear", QUERIES, || {
+        query += 1;
+        let frustum = &frustums[query % QUERIES];
+        (0..TANKS).filter(|&key| frustum.intersects_aabb(&grid.bounds(key).unwrap())).count()
+    });
+}
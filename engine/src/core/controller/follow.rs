@@ -0,0 +1,99 @@
+extern crate cgmath;
+
+use self::cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+
+use core::{Camera, InputState};
+
+use super::{CameraController, spring_damp};
+
+/// Camera following behind a moving target, e.g. the player's tank.
+///
+/// The target is set each frame with `set_target` and the camera trails it with spring damping,
+/// so it lags behind when the tank accelerates or turns and settles without overshooting.
+/// It doesn't respond to any input.
+#[derive(Copy, Clone, Debug)]
+pub struct FollowController {
+    /// Horizontal distance behind the target.
+    pub distance: f32,
+    /// Height of the camera above the target.
+    pub height: f32,
+    /// Distance in front of the target which is looked at.
+    pub look_ahead: f32,
+    /// Angular frequency of the spring. Higher values follow more tightly.
+    pub stiffness: f32,
+
+    target: Point3<f32>,
+    heading: Vector3<f32>,
+
+    // `None` until the first update or after `snap`.
+    eye: Option<Vector3<f32>>,
+    eye_velocity: Vector3<f32>,
+    look: Vector3<f32>,
+    look_velocity: Vector3<f32>
+}
+
+impl FollowController {
+    /// Create a controller 8 units behind and 4 above the target.
+    pub fn new() -> FollowController {
+        return FollowController {
+            distance: 8.0,
+            height: 4.0,
+            look_ahead: 2.0,
+            stiffness: 6.0,
+            target: Point3::new(0.0, 0.0, 0.0),
+            heading: Vector3::new(0.0, 0.0, -1.0),
+            eye: None,
+            eye_velocity: Vector3::new(0.0, 0.0, 0.0),
+            look: Vector3::new(0.0, 0.0, 0.0),
+            look_velocity: Vector3::new(0.0, 0.0, 0.0)
+        };
+    }
+
+    /// Update the target's position and the direction it faces.
+    ///
+    /// Only the horizontal part of `forward` is used, so the camera doesn't tilt on slopes.
+    /// A vertical `forward` keeps the previous heading.
+    pub fn set_target(&mut self, position: Point3<f32>, forward: Vector3<f32>) {
+        self.target = position;
+
+        let heading = Vector3::new(forward.x, 0.0, forward.z);
+        if heading.magnitude2() > 1e-8 {
+            self.heading = heading.normalize();
+        }
+    }
+
+    /// Jump to the target on the next update instead of moving there, e.g. after a respawn.
+    pub fn snap(&mut self) {
+        self.eye = None;
+    }
+
+    fn rest_position(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let target = self.target.to_vec();
+
+        return (
+            target - self.heading * self.distance + Vector3::new(0.0, self.height, 0.0),
+            target + self.heading * self.look_ahead);
+    }
+}
+
+impl CameraController for FollowController {
+    fn update(&mut self, camera: &mut Camera, _: &InputState, delta: f32) {
+        let (eye_target, look_target) = self.rest_position();
+
+        let eye = match self.eye {
+            Some(eye) => {
+                self.look = spring_damp(self.look, &mut self.look_velocity, look_target, self.stiffness, delta);
+                spring_damp(eye, &mut self.eye_velocity, eye_target, self.stiffness, delta)
+            },
+            None => {
+                self.look = look_target;
+                self.eye_velocity = Vector3::new(0.0, 0.0, 0.0);
+                self.look_velocity = Vector3::new(0.0, 0.0, 0.0);
+                eye_target
+            }
+        };
+        self.eye = Some(eye);
+
+        camera.look_at(Point3::from_vec(eye), Point3::from_vec(self.look), Vector3::new(0.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,86 @@
+extern crate cgmath;
+
+use self::cgmath::{InnerSpace, Point3, Vector3};
+
+use core::{Camera, InputState, Control};
+
+use super::{CameraController, ground_axes};
+
+/// Unconstrained camera for debugging, e.g. to inspect culling or shadow cascades.
+///
+/// * Moves along the view with `MoveForward`, `MoveBack`, `MoveLeft` and `MoveRight`,
+/// and vertically with `MoveUp` and `MoveDown`, faster while `Boost` is held.
+/// * Looks around by dragging the cursor while `Look` is held.
+#[derive(Copy, Clone, Debug)]
+pub struct FreeFlyController {
+    pub position: Point3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Units per second.
+    pub speed: f32,
+    /// Multiplier of `speed` while `Boost` is held.
+    pub boost: f32,
+    /// Degrees per pixel of cursor movement while `Look` is held.
+    pub look_sensitivity: f32
+}
+
+impl FreeFlyController {
+    /// Create a controller at `position` looking down the negative z axis.
+    pub fn new(position: Point3<f32>) -> FreeFlyController {
+        return FreeFlyController {
+            position: position,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 5.0,
+            boost: 4.0,
+            look_sensitivity: 0.2
+        };
+    }
+
+    /// Create a controller with the position and direction of `camera`,
+    /// e.g. to take over the current view for debugging.
+    pub fn from_camera(camera: &Camera) -> FreeFlyController {
+        let forward = camera.forward();
+
+        let mut controller = FreeFlyController::new(camera.position());
+        controller.yaw = (-forward.x).atan2(-forward.z).to_degrees();
+        controller.pitch = forward.y.max(-1.0).min(1.0).asin().to_degrees();
+
+        return controller;
+    }
+
+    /// Get the unit vector the camera looks along.
+    pub fn forward(&self) -> Vector3<f32> {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+
+        return Vector3::new(-pitch.cos() * yaw.sin(), pitch.sin(), -pitch.cos() * yaw.cos());
+    }
+}
+
+impl CameraController for FreeFlyController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta: f32) {
+        if input.is_held(Control::Look) {
+            let cursor_delta = input.cursor_delta();
+            self.yaw -= cursor_delta.x * self.look_sensitivity;
+            self.pitch -= cursor_delta.y * self.look_sensitivity;
+        }
+
+        // Keeps the up vector from becoming parallel to the view.
+        self.pitch = self.pitch.max(-89.0).min(89.0);
+
+        let forward = self.forward();
+        let (_, right) = ground_axes(self.yaw);
+
+        let direction =
+            forward * input.axis(Control::MoveBack, Control::MoveForward) +
+            right * input.axis(Control::MoveLeft, Control::MoveRight) +
+            Vector3::new(0.0, 1.0, 0.0) * input.axis(Control::MoveDown, Control::MoveUp);
+
+        if direction.magnitude2() > 0.0 {
+            let boost = if input.is_held(Control::Boost) { self.boost } else { 1.0 };
+            self.position += direction.normalize() * self.speed * boost * delta;
+        }
+
+        camera.look_at(self.position, self.position + forward, Vector3::new(0.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,76 @@
+//! Reusable ways of driving a `Camera` with the player's input.
+
+extern crate cgmath;
+
+pub mod follow;
+pub mod free_fly;
+pub mod orbit;
+pub mod rts;
+
+use self::cgmath::Vector3;
+
+use core::{Camera, InputState};
+
+/// Moves a `Camera` each frame.
+///
+/// The angles of the controllers are in degrees. The yaw is around the y axis and `0` looks
+/// down the negative z axis like a new `Camera`, the pitch is above the horizon.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::{Scene, Camera, InputState, CameraController, OrbitController};
+/// # use cgmath::Point3;
+/// # fn main() {
+/// # let mut scene = Scene::new(Camera::new());
+/// # let mut input = InputState::new(800, 600);
+/// # let delta = 0.016;
+/// let mut controller = OrbitController::new(Point3::new(0.0, 0.0, 0.0), 10.0);
+///
+/// // Each frame.
+/// controller.update(scene.camera_mut(), &input, delta);
+/// input.end_frame();
+/// # }
+/// ```
+pub trait CameraController {
+    /// Moves `camera` by the `input` of a frame which took `delta` seconds.
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta: f32);
+}
+
+/// Get the unit vector from a point towards the eye which looks at it at `yaw` and `pitch`.
+fn eye_direction(yaw: f32, pitch: f32) -> Vector3<f32> {
+    let (yaw, pitch) = (yaw.to_radians(), pitch.to_radians());
+
+    return Vector3::new(pitch.cos() * yaw.sin(), pitch.sin(), pitch.cos() * yaw.cos());
+}
+
+/// Get the horizontal unit vectors forward and to the right at `yaw`.
+fn ground_axes(yaw: f32) -> (Vector3<f32>, Vector3<f32>) {
+    let yaw = yaw.to_radians();
+
+    return (
+        Vector3::new(-yaw.sin(), 0.0, -yaw.cos()),
+        Vector3::new(yaw.cos(), 0.0, -yaw.sin()));
+}
+
+/// Moves `current` towards `target` with a critically damped spring, i.e. as fast as possible
+/// without overshooting, updating its `velocity`. `omega` is the spring's angular frequency,
+/// higher values follow more tightly.
+///
+/// # References
+/// - Lowe, T. "Critically Damped Ease-In/Out Smoothing", Game Programming Gems 4, 2004
+fn spring_damp(current: Vector3<f32>, velocity: &mut Vector3<f32>, target: Vector3<f32>, omega: f32, delta: f32)
+    -> Vector3<f32>
+{
+    let x = omega * delta;
+    // Approximates `exp(-x)`.
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+
+    let change = current - target;
+    let temp = (*velocity + change * omega) * delta;
+    *velocity = (*velocity - temp * omega) * decay;
+
+    return target + (change + temp) * decay;
+}
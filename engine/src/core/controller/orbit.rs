@@ -0,0 +1,75 @@
+extern crate cgmath;
+
+use self::cgmath::{Point3, Vector3};
+
+use core::{Camera, InputState, Control};
+
+use super::{CameraController, eye_direction};
+
+/// Camera circling around a target, e.g. for inspecting a tank in the garage or a menu background.
+///
+/// * Rotates with `RotateLeft` and `RotateRight`, by dragging the cursor while `Look` is held,
+/// and on its own with `auto_rotate`.
+/// * Zooms with the scroll wheel.
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitController {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    /// Degrees per second while rotating with the controls.
+    pub rotate_speed: f32,
+    /// Degrees per pixel of cursor movement while `Look` is held.
+    pub look_sensitivity: f32,
+    /// Degrees per second of rotation without any input.
+    pub auto_rotate: f32,
+    /// Fraction of `distance` zoomed by a step of the scroll wheel.
+    pub zoom_step: f32
+}
+
+impl OrbitController {
+    /// Create a controller looking at `target` from `distance` away at 30 degrees.
+    pub fn new(target: Point3<f32>, distance: f32) -> OrbitController {
+        return OrbitController {
+            target: target,
+            distance: distance,
+            yaw: 0.0,
+            pitch: 30.0,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            min_pitch: -85.0,
+            max_pitch: 85.0,
+            rotate_speed: 90.0,
+            look_sensitivity: 0.25,
+            auto_rotate: 0.0,
+            zoom_step: 0.1
+        };
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta: f32) {
+        self.yaw += self.auto_rotate * delta;
+        self.yaw -= input.axis(Control::RotateLeft, Control::RotateRight) * self.rotate_speed * delta;
+
+        if input.is_held(Control::Look) {
+            let cursor_delta = input.cursor_delta();
+            self.yaw -= cursor_delta.x * self.look_sensitivity;
+            self.pitch += cursor_delta.y * self.look_sensitivity;
+        }
+
+        // Keeps the up vector from becoming parallel to the view.
+        self.pitch = self.pitch.max(self.min_pitch).min(self.max_pitch);
+
+        self.distance = (self.distance * (1.0 - self.zoom_step).powf(input.scroll_delta()))
+            .max(self.min_distance)
+            .min(self.max_distance);
+
+        let eye = self.target + eye_direction(self.yaw, self.pitch) * self.distance;
+        camera.look_at(eye, self.target, Vector3::new(0.0, 1.0, 0.0));
+    }
+}
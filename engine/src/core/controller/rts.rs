@@ -0,0 +1,113 @@
+extern crate cgmath;
+
+use self::cgmath::{Point3, Vector3};
+
+use core::{Camera, InputState, Control};
+
+use math::Aabb;
+
+use super::{CameraController, eye_direction, ground_axes};
+
+/// Strategy style camera looking down at a point on the ground.
+///
+/// * Scrolls with `MoveForward`, `MoveBack`, `MoveLeft` and `MoveRight` or by moving the cursor
+/// to an edge of the window, faster while `Boost` is held.
+/// * Zooms with the scroll wheel.
+/// * Rotates around the focus with `RotateLeft` and `RotateRight` or by dragging the cursor
+/// horizontally while `Look` is held.
+///
+/// The focus is kept inside `bounds`, e.g. the map.
+#[derive(Copy, Clone, Debug)]
+pub struct RtsController {
+    /// The point on the ground in the center of the view.
+    pub focus: Point3<f32>,
+    /// Distance of the camera from the focus.
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Scrolling speed in multiples of `distance` per second, so it feels the same at any zoom.
+    pub pan_speed: f32,
+    /// Multiplier of `pan_speed` while `Boost` is held.
+    pub boost: f32,
+    /// Width in pixels of the window's borders which scroll the view. `0` disables edge scrolling.
+    pub edge_margin: f32,
+    /// Fraction of `distance` zoomed by a step of the scroll wheel.
+    pub zoom_step: f32,
+    /// Degrees per second while rotating with the controls.
+    pub rotate_speed: f32,
+    /// Degrees per pixel of cursor movement while `Look` is held.
+    pub look_sensitivity: f32,
+    /// The area the focus is kept in. Only the x and z extents are used.
+    pub bounds: Option<Aabb>
+}
+
+impl RtsController {
+    /// Create a controller looking at `focus` from 20 units away at 55 degrees.
+    pub fn new(focus: Point3<f32>) -> RtsController {
+        return RtsController {
+            focus: focus,
+            distance: 20.0,
+            yaw: 0.0,
+            pitch: 55.0,
+            min_distance: 5.0,
+            max_distance: 60.0,
+            pan_speed: 1.0,
+            boost: 2.5,
+            edge_margin: 10.0,
+            zoom_step: 0.1,
+            rotate_speed: 90.0,
+            look_sensitivity: 0.25,
+            bounds: None
+        };
+    }
+
+    /// Get the direction of scrolling, `-1` to `1` on the right (`x`) and forward (`y`) axes.
+    fn pan_direction(&self, input: &InputState) -> (f32, f32) {
+        let mut right = input.axis(Control::MoveLeft, Control::MoveRight);
+        let mut forward = input.axis(Control::MoveBack, Control::MoveForward);
+
+        if let Some((x, y)) = input.cursor() {
+            let (width, height) = input.viewport_size();
+            let margin = self.edge_margin;
+
+            if margin > 0.0 {
+                if x < margin { right -= 1.0; }
+                if x >= width as f32 - margin { right += 1.0; }
+                if y < margin { forward += 1.0; }
+                if y >= height as f32 - margin { forward -= 1.0; }
+            }
+        }
+
+        return (right.max(-1.0).min(1.0), forward.max(-1.0).min(1.0));
+    }
+}
+
+impl CameraController for RtsController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta: f32) {
+        self.yaw -= input.axis(Control::RotateLeft, Control::RotateRight) * self.rotate_speed * delta;
+        if input.is_held(Control::Look) {
+            self.yaw -= input.cursor_delta().x * self.look_sensitivity;
+        }
+
+        let (forward, right) = ground_axes(self.yaw);
+        let (pan_right, pan_forward) = self.pan_direction(input);
+        let boost = if input.is_held(Control::Boost) { self.boost } else { 1.0 };
+        let speed = self.pan_speed * self.distance * boost * delta;
+
+        self.focus += (right * pan_right + forward * pan_forward) * speed;
+
+        if let Some(bounds) = self.bounds {
+            self.focus.x = self.focus.x.max(bounds.min.x).min(bounds.max.x);
+            self.focus.z = self.focus.z.max(bounds.min.z).min(bounds.max.z);
+        }
+
+        self.distance = (self.distance * (1.0 - self.zoom_step).powf(input.scroll_delta()))
+            .max(self.min_distance)
+            .min(self.max_distance);
+
+        let eye = self.focus + eye_direction(self.yaw, self.pitch) * self.distance;
+        camera.look_at(eye, self.focus, Vector3::new(0.0, 1.0, 0.0));
+    }
+}
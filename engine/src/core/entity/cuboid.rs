@@ -1,4 +1,4 @@
-extern crate gl;
+use gliw::gl;
 extern crate cgmath;
 
 use self::cgmath::{
@@ -15,38 +15,46 @@ use gliw::{
     VertexAttrib, AttribFloatFormat
 };
 
-use super::Entity;
+use super::{Entity, lazy_program, with_material};
 
-use core::{Camera, Renderable};
+use core::{Camera, Renderable, Material, Lights, IdPass};
 
-use math::RotMat;
+use math::{RotMat, Aabb, Bounds};
 
+use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 use std::ptr;
 use std::mem;
 
 /// A general purpose cuboid entity.
+///
+/// Each face has its own normal and texture coordinates spanning the whole face.
 #[allow(dead_code)]
 pub struct Cuboid {
     entity: Entity,
     dimensions: Vector3<f32>,
-    color: Vector4<f32>,
+    material: Material,
     priority: u32,
 
     vao: Vao,
     vbo: Buffer,            // FIXME: should be static
     ebo: Buffer,            // FIXME: should be static
-    program: Rc<Program>
+    program: Rc<Program>,
+    // Built on first use by the deferred render path.
+    gbuffer_program: RefCell<Option<Rc<Program>>>,    // FIXME: should be static
+    shadow_program: RefCell<Option<Rc<Program>>>      // FIXME: should be static
 }
 
 impl Cuboid {
     /// Creates a new cuboid from given center, dimensions and color.
+    ///
+    /// The color becomes the base color of the cuboid's `Material`.
     pub fn new(center: Point3<f32>, dimensions: Vector3<f32>, color: Vector4<f32>) -> Cuboid {
         // FIXME: should be static
         let program = ProgramBuilder::new()
             .attach_vs(&Shader::new(ShaderType::Vertex, VS_SRC).unwrap())
-            .attach_fs(&Shader::new(ShaderType::Fragment, FS_SRC).unwrap())
+            .attach_fs(&Shader::new(ShaderType::Fragment, &with_material(FS_SRC)).unwrap())
             .link()
             .unwrap();
 
@@ -62,19 +70,33 @@ impl Cuboid {
             BufferType::ElementArray,
             BufferUsagePattern::StaticDraw);
 
-        let va = VertexAttrib::new(0);
-        va.data_float_format(&vao, &vbo, AttribFloatFormat::Float(3), 0, ptr::null());
-        va.enable(&vao);
+        let stride = (VERTEX_SIZE * mem::size_of::<f32>()) as i32;
+
+        let position = VertexAttrib::new(0);
+        position.data_float_format(&vao, &vbo, AttribFloatFormat::Float(3), stride, ptr::null());
+        position.enable(&vao);
+
+        let normal = VertexAttrib::new(1);
+        normal.data_float_format(&vao, &vbo, AttribFloatFormat::Float(3), stride,
+            (3 * mem::size_of::<f32>()) as *const _);
+        normal.enable(&vao);
+
+        let uv = VertexAttrib::new(2);
+        uv.data_float_format(&vao, &vbo, AttribFloatFormat::Float(2), stride,
+            (6 * mem::size_of::<f32>()) as *const _);
+        uv.enable(&vao);
 
         return Cuboid {
             entity: Entity::from(center, Quaternion::zero(), 1.0),
             dimensions: dimensions,
-            color: color,
+            material: Material::new(color),
             priority: 0,
             vao: vao,
             vbo: vbo,
             ebo: ebo,
-            program: program
+            program: program,
+            gbuffer_program: RefCell::new(None),
+            shadow_program: RefCell::new(None)
         };
     }
 
@@ -82,6 +104,42 @@ impl Cuboid {
     pub fn set_priority(&mut self, priority: u32) {
         self.priority = priority;
     }
+
+    /// Get the material.
+    pub fn material(&self) -> &Material {
+        return &self.material;
+    }
+
+    /// Get mutable reference to the material.
+    pub fn material_mut(&mut self) -> &mut Material {
+        return &mut self.material;
+    }
+
+    /// Set the material.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn gbuffer_program(&self) -> Rc<Program> {
+        return lazy_program(&self.gbuffer_program, VS_SRC, &with_material(GBUFFER_FS_SRC));
+    }
+
+    fn shadow_program(&self) -> Rc<Program> {
+        return lazy_program(&self.shadow_program, SHADOW_VS_SRC, SHADOW_FS_SRC);
+    }
+
+    fn pass_matrices(&self, program: &Rc<Program>, draw_space: Matrix4<f32>, camera: &Camera) {
+        let model_matrix = draw_space * self.model_matrix();
+        let mvp_matrix = camera.vp_matrix() * model_matrix;
+
+        unsafe {
+            Uniform::new(program, "model").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(model_matrix)));
+
+            Uniform::new(program, "mvp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(mvp_matrix)));
+        }
+    }
 }
 
 impl Renderable for Cuboid {
@@ -89,6 +147,10 @@ impl Renderable for Cuboid {
         self.priority
     }
 
+    fn is_transparent(&self) -> bool {
+        return self.material.is_transparent();
+    }
+
     fn model_matrix(&self) -> Matrix4<f32> {
         let scale_matrix = Matrix4::from_nonuniform_scale(
             self.dimensions.x * self.entity.scale,
@@ -101,22 +163,69 @@ impl Renderable for Cuboid {
     }
 
     fn draw(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        self.draw_lit(draw_space, camera, &Lights::unlit());
+    }
+
+    fn draw_lit(&self, draw_space: Matrix4<f32>, camera: &Camera, lights: &Lights) {
         self.vao.bind();
         self.program.bind();
 
-        let mvp_matrix = camera.vp_matrix() * draw_space * self.model_matrix();
+        self.pass_matrices(&self.program, draw_space, camera);
+        self.material.apply(&self.program, 0);
+        lights.apply(&self.program, camera);
 
-        unsafe {
-            Uniform::new(&self.program, "cuboid_color").value(UniformData::FloatVec(4,
-                &mem::transmute::<Vector4<f32>, [f32; 4]>(self.color)));
+        self.ebo.bind();
+
+        unsafe { gl::DrawElements(gl::TRIANGLES, ELEMENTS.len() as i32, gl::UNSIGNED_BYTE, ptr::null()); }
+    }
+
+    fn has_gbuffer_pass(&self) -> bool {
+        return true;
+    }
+
+    fn draw_gbuffer(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        let program = self.gbuffer_program();
+
+        self.vao.bind();
+        program.bind();
+
+        self.pass_matrices(&program, draw_space, camera);
+        self.material.apply(&program, 0);
+
+        self.ebo.bind();
 
-            Uniform::new(&self.program, "mvp").value(UniformData::FloatMat(4, false,
+        unsafe { gl::DrawElements(gl::TRIANGLES, ELEMENTS.len() as i32, gl::UNSIGNED_BYTE, ptr::null()); }
+    }
+
+    fn draw_shadow(&self, draw_space: Matrix4<f32>, light_vp: &Matrix4<f32>) {
+        let program = self.shadow_program();
+
+        self.vao.bind();
+        program.bind();
+
+        let mvp_matrix = light_vp * draw_space * self.model_matrix();
+
+        unsafe {
+            Uniform::new(&program, "mvp").value(UniformData::FloatMat(4, false,
                 &mem::transmute::<Matrix4<f32>, [f32; 16]>(mvp_matrix)));
         }
 
         self.ebo.bind();
 
-        unsafe { gl::DrawElements(gl::TRIANGLES, 12*3, gl::UNSIGNED_BYTE, ptr::null()); }
+        unsafe { gl::DrawElements(gl::TRIANGLES, ELEMENTS.len() as i32, gl::UNSIGNED_BYTE, ptr::null()); }
+    }
+
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {
+        self.vao.bind();
+        pass.set_model(draw_space * self.model_matrix());
+        self.ebo.bind();
+
+        unsafe { gl::DrawElements(gl::TRIANGLES, ELEMENTS.len() as i32, gl::UNSIGNED_BYTE, ptr::null()); }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        // The dimensions are part of the model matrix.
+        return Some(Bounds::Box(Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5))));
     }
 }
 
@@ -137,49 +246,125 @@ impl DerefMut for Cuboid {
 const VS_SRC: &'static str = r#"
     #version 330 core
 
+    uniform mat4 model;
     uniform mat4 mvp;
 
     layout (location = 0) in vec3 vs_position;
+    layout (location = 1) in vec3 vs_normal;
+    layout (location = 2) in vec2 vs_uv;
+
+    out vec3 world_position;
+    out vec3 world_normal;
+    out vec2 uv;
 
     void main() {
+        world_position = (model * vec4(vs_position, 1.0)).xyz;
+        // The inverse transpose keeps the normals perpendicular under non-uniform scaling.
+        world_normal = mat3(transpose(inverse(model))) * vs_normal;
+        uv = vs_uv;
         gl_Position = mvp * vec4(vs_position, 1.0);
     }
 "#;
 
+// The version directive and the declarations are prepended by `with_material`.
 const FS_SRC: &'static str = r#"
+    in vec3 world_position;
+    in vec3 world_normal;
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        vec4 albedo = material_albedo(uv);
+        vec3 lit = shade(world_position, world_normal, albedo.rgb, material_specular, material_shininess);
+        color = vec4(lit + material_emissive, albedo.a);
+    }
+"#;
+
+// The version directive and the declarations are prepended by `with_material`.
+const GBUFFER_FS_SRC: &'static str = r#"
+    in vec3 world_position;
+    in vec3 world_normal;
+    in vec2 uv;
+
+    layout (location = 0) out vec3 albedo;
+    layout (location = 1) out vec3 normal;
+    layout (location = 2) out vec2 material;
+    layout (location = 3) out vec3 emissive;
+
+    void main() {
+        albedo = material_albedo(uv).rgb;
+        normal = normalize(world_normal);
+        material = vec2(material_specular, material_shininess / 256.0);
+        emissive = material_emissive;
+    }
+"#;
+
+const SHADOW_VS_SRC: &'static str = r#"
     #version 330 core
 
-    uniform vec4 cuboid_color;
+    uniform mat4 mvp;
 
-    out vec4 color;
+    layout (location = 0) in vec3 vs_position;
 
     void main() {
-        color = cuboid_color;
+        gl_Position = mvp * vec4(vs_position, 1.0);
     }
 "#;
 
-static VERTICES: [f32; 8*3] = [
-    -0.5, -0.5, -0.5,
-    -0.5, -0.5,  0.5,
-    -0.5,  0.5, -0.5,
-    -0.5,  0.5,  0.5,
-     0.5, -0.5, -0.5,
-     0.5, -0.5,  0.5,
-     0.5,  0.5, -0.5,
-     0.5,  0.5,  0.5,
+const SHADOW_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    void main() {}
+"#;
+
+/// Position, normal and texture coordinates.
+const VERTEX_SIZE: usize = 3 + 3 + 2;
+
+static VERTICES: [f32; 24*VERTEX_SIZE] = [
+    // -x
+    -0.5, -0.5, -0.5,  -1.0,  0.0,  0.0,   0.0,  0.0,
+    -0.5, -0.5,  0.5,  -1.0,  0.0,  0.0,   1.0,  0.0,
+    -0.5,  0.5,  0.5,  -1.0,  0.0,  0.0,   1.0,  1.0,
+    -0.5,  0.5, -0.5,  -1.0,  0.0,  0.0,   0.0,  1.0,
+    // +x
+     0.5, -0.5,  0.5,   1.0,  0.0,  0.0,   0.0,  0.0,
+     0.5, -0.5, -0.5,   1.0,  0.0,  0.0,   1.0,  0.0,
+     0.5,  0.5, -0.5,   1.0,  0.0,  0.0,   1.0,  1.0,
+     0.5,  0.5,  0.5,   1.0,  0.0,  0.0,   0.0,  1.0,
+    // -y
+    -0.5, -0.5, -0.5,   0.0, -1.0,  0.0,   0.0,  0.0,
+     0.5, -0.5, -0.5,   0.0, -1.0,  0.0,   1.0,  0.0,
+     0.5, -0.5,  0.5,   0.0, -1.0,  0.0,   1.0,  1.0,
+    -0.5, -0.5,  0.5,   0.0, -1.0,  0.0,   0.0,  1.0,
+    // +y
+     0.5,  0.5, -0.5,   0.0,  1.0,  0.0,   0.0,  0.0,
+    -0.5,  0.5, -0.5,   0.0,  1.0,  0.0,   1.0,  0.0,
+    -0.5,  0.5,  0.5,   0.0,  1.0,  0.0,   1.0,  1.0,
+     0.5,  0.5,  0.5,   0.0,  1.0,  0.0,   0.0,  1.0,
+    // -z
+     0.5, -0.5, -0.5,   0.0,  0.0, -1.0,   0.0,  0.0,
+    -0.5, -0.5, -0.5,   0.0,  0.0, -1.0,   1.0,  0.0,
+    -0.5,  0.5, -0.5,   0.0,  0.0, -1.0,   1.0,  1.0,
+     0.5,  0.5, -0.5,   0.0,  0.0, -1.0,   0.0,  1.0,
+    // +z
+    -0.5, -0.5,  0.5,   0.0,  0.0,  1.0,   0.0,  0.0,
+     0.5, -0.5,  0.5,   0.0,  0.0,  1.0,   1.0,  0.0,
+     0.5,  0.5,  0.5,   0.0,  0.0,  1.0,   1.0,  1.0,
+    -0.5,  0.5,  0.5,   0.0,  0.0,  1.0,   0.0,  1.0,
 ];
 
 static ELEMENTS: [u8; 12*3] = [
-    0, 1, 3,
-    0, 3, 2,
-    5, 4, 6,
-    5, 6, 7,
-    1, 0, 4,
-    1, 4, 5,
-    6, 2, 3,
-    6, 3, 7,
-    4, 0, 2,
-    4, 2, 6,
-    3, 1, 5,
-    3, 5, 7,
+    0, 1, 2,
+    0, 2, 3,
+    4, 5, 6,
+    4, 6, 7,
+    8, 9, 10,
+    8, 10, 11,
+    12, 13, 14,
+    12, 14, 15,
+    16, 17, 18,
+    16, 18, 19,
+    20, 21, 22,
+    20, 22, 23,
 ];
@@ -0,0 +1,353 @@
+extern crate cgmath;
+
+use self::cgmath::{
+    VectorSpace, EuclideanSpace, SquareMatrix,
+    Point3, Vector4, Matrix4, Quaternion
+};
+
+use gliw::{Program, ProgramBuilder, Shader, ShaderType, Uniform, UniformData};
+
+use super::{Entity, lazy_program, with_material};
+
+use core::{Camera, Renderable, Material, Lights, Mesh, GpuMesh, Model, Primitive, IdPass};
+
+use math::{RotMat, Aabb, Bounds, Ray, RayHit};
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::mem;
+
+/// An entity drawing one or more uploaded `Mesh`es, each with its own `Material`.
+///
+/// The meshes' vertex colors are multiplied with the materials' albedo.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[macro_use] extern crate engine;
+/// # use engine::core::{Model, MeshRenderable, Scene, Camera};
+/// # fn main() {
+/// let model = Model::load_obj("resources/models/tank.obj").unwrap();
+/// let tank = wrap!(MeshRenderable::from_model(&model));
+///
+/// let mut scene = Scene::new(Camera::new());
+/// scene.add(Scene::node(&tank));
+/// # }
+/// ```
+pub struct MeshRenderable {
+    entity: Entity,
+    parts: Vec<(GpuMesh, Material)>,
+    bounds: Option<Aabb>,
+    pick_mesh: Option<Mesh>,
+    priority: u32,
+
+    program: Rc<Program>,
+    // Built on first use by the deferred render path.
+    gbuffer_program: RefCell<Option<Rc<Program>>>,    // FIXME: should be static
+    shadow_program: RefCell<Option<Rc<Program>>>      // FIXME: should be static
+}
+
+impl MeshRenderable {
+    /// Uploads `mesh` and creates a renderable at the origin drawing it with `material`.
+    pub fn new(mesh: &Mesh, material: Material) -> MeshRenderable {
+        return MeshRenderable::from_parts(vec![(mesh.upload(), material)], mesh.bounds());
+    }
+
+    /// Uploads all parts of `model` and creates a renderable at the origin drawing them.
+    pub fn from_model(model: &Model) -> MeshRenderable {
+        let bounds = model.parts.iter()
+            .filter_map(|part| part.mesh.bounds())
+            .fold(None, |bounds: Option<Aabb>, part| Some(match bounds {
+                Some(bounds) => bounds.union(&part),
+                None => part
+            }));
+
+        return MeshRenderable::from_parts(model.parts.iter()
+            .map(|part| (part.mesh.upload(), part.material.clone()))
+            .collect(), bounds);
+    }
+
+    /// Generates and uploads a `Primitive` and creates a renderable at `center` drawing it in `color`.
+    ///
+    /// # Panics
+    /// Same as the `Mesh` generator of the primitive.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate engine;
+    /// # extern crate cgmath;
+    /// # use engine::core::{MeshRenderable, Primitive};
+    /// # use cgmath::{Point3, Vector4};
+    /// # fn main() {
+    /// let shell = MeshRenderable::primitive(Point3::new(0.0, 1.0, 0.0),
+    ///     Primitive::UvSphere { radius: 0.1, segments: 12, rings: 8 },
+    ///     Vector4::new(0.2, 0.2, 0.2, 1.0));
+    /// # }
+    /// ```
+    pub fn primitive(center: Point3<f32>, primitive: Primitive, color: Vector4<f32>) -> MeshRenderable {
+        let mut renderable = MeshRenderable::new(&primitive.mesh(), Material::new(color));
+        renderable.position = center;
+
+        return renderable;
+    }
+
+    /// Set rendering priority.
+    pub fn set_priority(&mut self, priority: u32) {
+        self.priority = priority;
+    }
+
+    /// Set a mesh to test rays against instead of the bounds, in model space.
+    ///
+    /// Usually the drawn mesh itself or a simplified version of it. See `Renderable::intersect_ray`.
+    pub fn set_pick_mesh(&mut self, mesh: Option<Mesh>) {
+        self.pick_mesh = mesh;
+    }
+
+    /// Get the number of drawn meshes.
+    pub fn part_count(&self) -> usize {
+        return self.parts.len();
+    }
+
+    /// Get the material of the mesh at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn material(&self, index: usize) -> &Material {
+        return &self.parts[index].1;
+    }
+
+    /// Get mutable reference to the material of the mesh at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn material_mut(&mut self, index: usize) -> &mut Material {
+        return &mut self.parts[index].1;
+    }
+
+    fn from_parts(parts: Vec<(GpuMesh, Material)>, bounds: Option<Aabb>) -> MeshRenderable {
+        // FIXME: should be static
+        let program = ProgramBuilder::new()
+            .attach_vs(&Shader::new(ShaderType::Vertex, VS_SRC).unwrap())
+            .attach_fs(&Shader::new(ShaderType::Fragment, &with_material(FS_SRC)).unwrap())
+            .link()
+            .unwrap();
+
+        return MeshRenderable {
+            entity: Entity::from(Point3::new(0.0, 0.0, 0.0), Quaternion::zero(), 1.0),
+            parts: parts,
+            bounds: bounds,
+            pick_mesh: None,
+            priority: 0,
+            program: program,
+            gbuffer_program: RefCell::new(None),
+            shadow_program: RefCell::new(None)
+        };
+    }
+
+    fn gbuffer_program(&self) -> Rc<Program> {
+        return lazy_program(&self.gbuffer_program, VS_SRC, &with_material(GBUFFER_FS_SRC));
+    }
+
+    fn shadow_program(&self) -> Rc<Program> {
+        return lazy_program(&self.shadow_program, SHADOW_VS_SRC, SHADOW_FS_SRC);
+    }
+
+    fn pass_matrices(&self, program: &Rc<Program>, draw_space: Matrix4<f32>, camera: &Camera) {
+        let model_matrix = draw_space * self.model_matrix();
+        let mvp_matrix = camera.vp_matrix() * model_matrix;
+
+        unsafe {
+            Uniform::new(program, "model").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(model_matrix)));
+
+            Uniform::new(program, "mvp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(mvp_matrix)));
+        }
+    }
+}
+
+impl Renderable for MeshRenderable {
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Transparent if any part's material is, since the parts are drawn together.
+    fn is_transparent(&self) -> bool {
+        return self.parts.iter().any(|&(_, ref material)| material.is_transparent());
+    }
+
+    fn model_matrix(&self) -> Matrix4<f32> {
+        let scale_matrix = Matrix4::from_scale(self.entity.scale);
+        let rotation_matrix = Matrix4::from_quat(&self.entity.orientation);
+        let translate_matrix = Matrix4::from_translation(self.entity.position.to_vec());
+
+        translate_matrix * rotation_matrix * scale_matrix
+    }
+
+    fn draw(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        self.draw_lit(draw_space, camera, &Lights::unlit());
+    }
+
+    fn draw_lit(&self, draw_space: Matrix4<f32>, camera: &Camera, lights: &Lights) {
+        self.program.bind();
+
+        self.pass_matrices(&self.program, draw_space, camera);
+        lights.apply(&self.program, camera);
+
+        for &(ref mesh, ref material) in self.parts.iter() {
+            material.apply(&self.program, 0);
+            mesh.draw();
+        }
+    }
+
+    fn has_gbuffer_pass(&self) -> bool {
+        return true;
+    }
+
+    fn draw_gbuffer(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        let program = self.gbuffer_program();
+        program.bind();
+
+        self.pass_matrices(&program, draw_space, camera);
+
+        for &(ref mesh, ref material) in self.parts.iter() {
+            material.apply(&program, 0);
+            mesh.draw();
+        }
+    }
+
+    fn draw_shadow(&self, draw_space: Matrix4<f32>, light_vp: &Matrix4<f32>) {
+        let program = self.shadow_program();
+        program.bind();
+
+        let mvp_matrix = light_vp * draw_space * self.model_matrix();
+
+        unsafe {
+            Uniform::new(&program, "mvp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(mvp_matrix)));
+        }
+
+        for &(ref mesh, _) in self.parts.iter() {
+            mesh.draw();
+        }
+    }
+
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {
+        pass.set_model(draw_space * self.model_matrix());
+
+        for &(ref mesh, _) in self.parts.iter() {
+            mesh.draw();
+        }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        return self.bounds.map(Bounds::Box);
+    }
+
+    /// Tests the pick mesh if there is one, otherwise the bounds.
+    fn intersect_ray(&self, ray: &Ray) -> Option<RayHit> {
+        let model_matrix = self.model_matrix();
+
+        return match self.pick_mesh {
+            Some(ref mesh) => model_matrix.invert()
+                .and_then(|inverse| mesh.intersect_ray(&ray.transform(&inverse)))
+                .map(|hit| hit.transform(&model_matrix)),
+            None => self.bounds.and_then(|aabb| ray.hit_obb(&aabb, &model_matrix))
+        };
+    }
+}
+
+impl Deref for MeshRenderable {
+    type Target = Entity;
+
+    fn deref(&self) -> &Entity {
+        &self.entity
+    }
+}
+
+impl DerefMut for MeshRenderable {
+    fn deref_mut(&mut self) -> &mut Entity {
+        &mut self.entity
+    }
+}
+
+const VS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform mat4 model;
+    uniform mat4 mvp;
+
+    layout (location = 0) in vec3 vs_position;
+    layout (location = 1) in vec3 vs_normal;
+    layout (location = 2) in vec2 vs_uv;
+    layout (location = 3) in vec4 vs_color;
+
+    out vec3 world_position;
+    out vec3 world_normal;
+    out vec2 uv;
+    out vec4 vertex_color;
+
+    void main() {
+        world_position = (model * vec4(vs_position, 1.0)).xyz;
+        world_normal = mat3(transpose(inverse(model))) * vs_normal;
+        uv = vs_uv;
+        vertex_color = vs_color;
+        gl_Position = mvp * vec4(vs_position, 1.0);
+    }
+"#;
+
+// The version directive and the declarations are prepended by `with_material`.
+const FS_SRC: &'static str = r#"
+    in vec3 world_position;
+    in vec3 world_normal;
+    in vec2 uv;
+    in vec4 vertex_color;
+
+    out vec4 color;
+
+    void main() {
+        vec4 albedo = material_albedo(uv) * vertex_color;
+        vec3 lit = shade(world_position, world_normal, albedo.rgb, material_specular, material_shininess);
+        color = vec4(lit + material_emissive, albedo.a);
+    }
+"#;
+
+// The version directive and the declarations are prepended by `with_material`.
+const GBUFFER_FS_SRC: &'static str = r#"
+    in vec3 world_position;
+    in vec3 world_normal;
+    in vec2 uv;
+    in vec4 vertex_color;
+
+    layout (location = 0) out vec3 albedo;
+    layout (location = 1) out vec3 normal;
+    layout (location = 2) out vec2 material;
+    layout (location = 3) out vec3 emissive;
+
+    void main() {
+        albedo = (material_albedo(uv) * vertex_color).rgb;
+        normal = normalize(world_normal);
+        material = vec2(material_specular, material_shininess / 256.0);
+        emissive = material_emissive;
+    }
+"#;
+
+const SHADOW_VS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform mat4 mvp;
+
+    layout (location = 0) in vec3 vs_position;
+
+    void main() {
+        gl_Position = mvp * vec4(vs_position, 1.0);
+    }
+"#;
+
+const SHADOW_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    void main() {}
+"#;
@@ -2,13 +2,16 @@ extern crate cgmath;
 
 pub mod cuboid;
 pub mod component;
+pub mod mesh_renderable;
 
 use self::cgmath::{
     VectorSpace, Rotation,
     Point3, Vector3, Quaternion
 };
 
-use core::{Data, EventEmitter, Listener};
+use core::{Data, EventEmitter, Listener, MATERIAL_GLSL, LIGHTING_GLSL};
+
+use gliw::{Program, ProgramBuilder, Shader, ShaderType};
 
 use self::component::Component;
 
@@ -119,3 +122,24 @@ impl DerefMut for Entity {
         &mut self.emitter
     }
 }
+
+/// Builds the program in `cell` on first use, for renderables which only need it
+/// in some render paths.
+fn lazy_program(cell: &RefCell<Option<Rc<Program>>>, vs_src: &str, fs_src: &str) -> Rc<Program> {
+    let mut program = cell.borrow_mut();
+
+    if program.is_none() {
+        *program = Some(ProgramBuilder::new()
+            .attach_vs(&Shader::new(ShaderType::Vertex, vs_src).unwrap())
+            .attach_fs(&Shader::new(ShaderType::Fragment, fs_src).unwrap())
+            .link()
+            .unwrap());
+    }
+
+    return program.as_ref().unwrap().clone();
+}
+
+/// Prepends the version directive and the material and lighting declarations to a fragment shader.
+fn with_material(fs_src: &str) -> String {
+    return format!("#version 330 core\n{}{}{}", MATERIAL_GLSL, LIGHTING_GLSL, fs_src);
+}
@@ -0,0 +1,155 @@
+extern crate cgmath;
+
+use self::cgmath::{Quaternion, InnerSpace};
+
+/// The node property driven by a `Channel`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AnimationPath {
+    Translation,
+    Rotation,
+    Scale
+}
+
+/// How the values between the keyframes of a `Channel` are computed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Straight line between the keyframes, spherical for rotations.
+    Linear,
+    /// The value of the previous keyframe.
+    Step,
+    /// Hermite spline with explicit tangents.
+    CubicSpline
+}
+
+/// Keyframes of one property of one node.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    /// Index of the animated node in `GltfAsset::nodes`.
+    pub node: usize,
+    pub path: AnimationPath,
+    pub interpolation: Interpolation,
+    /// Keyframe times in seconds, in increasing order.
+    pub times: Vec<f32>,
+    /// Keyframe values, 3 components per keyframe for translations and scales
+    /// and 4 (`x, y, z, w`) for rotations.
+    ///
+    /// Cubic splines have an in-tangent, a value and an out-tangent per keyframe.
+    pub values: Vec<f32>
+}
+
+impl Channel {
+    /// Get the number of components of each value.
+    pub fn components(&self) -> usize {
+        return match self.path {
+            AnimationPath::Rotation => 4,
+            _ => 3
+        };
+    }
+
+    /// Computes the value at `time`, clamped to the keyframes' range.
+    ///
+    /// Rotations are normalized quaternions in `x, y, z, w` order.
+    ///
+    /// # Panics
+    /// Panics if there are no keyframes, not enough values for them or if the times are NaN.
+    /// Channels loaded by `GltfAsset` have finite, increasing times.
+    pub fn sample(&self, time: f32) -> Vec<f32> {
+        let n = self.components();
+        let stride = if self.interpolation == Interpolation::CubicSpline { 3 * n } else { n };
+        // The value of a cubic spline keyframe is between its tangents.
+        let offset = if self.interpolation == Interpolation::CubicSpline { n } else { 0 };
+
+        if self.times.is_empty() || self.values.len() < self.times.len() * stride {
+            panic!(ERR_KEYFRAMES);
+        }
+
+        let value = |key: usize| &self.values[key * stride + offset..key * stride + offset + n];
+        let last = self.times.len() - 1;
+
+        if time <= self.times[0] || time.is_nan() {
+            return value(0).to_vec();
+        }
+
+        if time >= self.times[last] {
+            return value(last).to_vec();
+        }
+
+        // The first keyframe after `time`, which is at least 1 after the checks above.
+        let next = match self.times.binary_search_by(|t| t.partial_cmp(&time).unwrap()) {
+            Ok(key) => return value(key).to_vec(),
+            Err(key) => key
+        };
+        let prev = next - 1;
+
+        let dt = self.times[next] - self.times[prev];
+        let t = (time - self.times[prev]) / dt;
+
+        let mut result = match self.interpolation {
+            Interpolation::Step => value(prev).to_vec(),
+            Interpolation::Linear => {
+                if self.path == AnimationPath::Rotation {
+                    return slerp(value(prev), value(next), t);
+                }
+
+                value(prev).iter().zip(value(next).iter()).map(|(a, b)| a + (b - a) * t).collect()
+            },
+            Interpolation::CubicSpline => {
+                let out_tangent = &self.values[prev * stride + 2 * n..prev * stride + 3 * n];
+                let in_tangent = &self.values[next * stride..next * stride + n];
+
+                let t2 = t * t;
+                let t3 = t2 * t;
+
+                (0..n).map(|i| {
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * value(prev)[i] +
+                    (t3 - 2.0 * t2 + t) * dt * out_tangent[i] +
+                    (-2.0 * t3 + 3.0 * t2) * value(next)[i] +
+                    (t3 - t2) * dt * in_tangent[i]
+                }).collect()
+            }
+        };
+
+        if self.path == AnimationPath::Rotation {
+            let length = result.iter().fold(0.0, |sum, c| sum + c * c).sqrt();
+            if length > 0.0 {
+                for c in result.iter_mut() {
+                    *c /= length;
+                }
+            }
+        }
+
+        return result;
+    }
+}
+
+/// A named set of `Channel`s played together, see `GltfAsset::animate`.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<Channel>
+}
+
+impl Animation {
+    /// Get the time of the last keyframe of all channels.
+    pub fn duration(&self) -> f32 {
+        return self.channels.iter()
+            .filter_map(|channel| channel.times.last())
+            .fold(0.0, |duration, &time| duration.max(time));
+    }
+}
+
+/// Spherical interpolation along the shortest path between two `x, y, z, w` quaternions.
+fn slerp(a: &[f32], b: &[f32], t: f32) -> Vec<f32> {
+    let a = Quaternion::new(a[3], a[0], a[1], a[2]);
+    let mut b = Quaternion::new(b[3], b[0], b[1], b[2]);
+
+    if a.dot(b) < 0.0 {
+        b = -b;
+    }
+
+    let q = a.slerp(b, t).normalize();
+
+    return vec![q.v.x, q.v.y, q.v.z, q.s];
+}
+
+const ERR_KEYFRAMES: &'static str = "Animation channel must have values for all of its keyframes";
@@ -0,0 +1,331 @@
+//! Minimal JSON parser for the glTF documents.
+
+use std::collections::HashMap;
+
+/// A parsed JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(HashMap<String, Json>)
+}
+
+impl Json {
+    /// Parses a JSON document.
+    pub fn parse(src: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: src.chars().collect(),
+            pos: 0,
+            depth: 0
+        };
+
+        let value = match parser.value() {
+            Ok(value) => value,
+            Err(err) => return Err(err)
+        };
+
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error(ERR_TRAILING));
+        }
+
+        return Ok(value);
+    }
+
+    /// Get a member of an object. `None` if this is not an object or there is no such member.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        return match *self {
+            Json::Object(ref members) => members.get(key),
+            _ => None
+        };
+    }
+
+    /// Get the number, if this is one.
+    pub fn as_f64(&self) -> Option<f64> {
+        return match *self {
+            Json::Number(number) => Some(number),
+            _ => None
+        };
+    }
+
+    /// Get the number as an index, if this is a non-negative integer.
+    pub fn as_usize(&self) -> Option<usize> {
+        return match *self {
+            Json::Number(number) if number >= 0.0 && number.fract() == 0.0 => Some(number as usize),
+            _ => None
+        };
+    }
+
+    /// Get the string, if this is one.
+    pub fn as_str(&self) -> Option<&str> {
+        return match *self {
+            Json::String(ref string) => Some(string),
+            _ => None
+        };
+    }
+
+    /// Get the elements, if this is an array.
+    pub fn as_array(&self) -> Option<&[Json]> {
+        return match *self {
+            Json::Array(ref elements) => Some(elements),
+            _ => None
+        };
+    }
+
+    /// Get the elements of an array of numbers as `f32`s.
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        let elements = match self.as_array() {
+            Some(elements) => elements,
+            None => return None
+        };
+
+        let mut numbers = Vec::with_capacity(elements.len());
+        for element in elements {
+            match element.as_f64() {
+                Some(number) => numbers.push(number as f32),
+                None => return None
+            }
+        }
+
+        return Some(numbers);
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Number of arrays and objects enclosing the current value.
+    depth: usize
+}
+
+impl Parser {
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        return match self.peek() {
+            Some('{') => self.nested(Parser::object),
+            Some('[') => self.nested(Parser::array),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_digit(10) => self.number(),
+            _ => Err(self.error(ERR_UNEXPECTED))
+        };
+    }
+
+    /// Parses an array or object, failing instead of overflowing the stack on deeply nested documents.
+    fn nested(&mut self, parse: fn(&mut Parser) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(ERR_TOO_DEEP));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        return value;
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        let mut members = HashMap::new();
+        self.pos += 1;
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error(ERR_UNEXPECTED));
+            }
+
+            let key = match self.string() {
+                Ok(key) => key,
+                Err(err) => return Err(err)
+            };
+
+            self.skip_whitespace();
+            if self.next() != Some(':') {
+                return Err(self.error(ERR_UNEXPECTED));
+            }
+
+            match self.value() {
+                Ok(value) => members.insert(key, value),
+                Err(err) => return Err(err)
+            };
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err(self.error(ERR_UNEXPECTED))
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        let mut elements = Vec::new();
+        self.pos += 1;
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            match self.value() {
+                Ok(value) => elements.push(value),
+                Err(err) => return Err(err)
+            }
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(elements)),
+                _ => return Err(self.error(ERR_UNEXPECTED))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let mut string = String::new();
+        self.pos += 1;
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => match self.unicode_escape() {
+                            Ok(c) => c,
+                            Err(err) => return Err(err)
+                        },
+                        _ => return Err(self.error(ERR_ESCAPE))
+                    };
+
+                    string.push(escaped);
+                },
+                Some(c) => string.push(c),
+                None => return Err(self.error(ERR_UNEXPECTED))
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = match self.hex4() {
+            Some(code) => code,
+            None => return Err(self.error(ERR_ESCAPE))
+        };
+
+        // Characters outside the basic multilingual plane are escaped as surrogate pairs.
+        let code = if high >= 0xD800 && high < 0xDC00 {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error(ERR_ESCAPE));
+            }
+
+            match self.hex4() {
+                Some(low) if low >= 0xDC00 && low < 0xE000 => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                _ => return Err(self.error(ERR_ESCAPE))
+            }
+        } else {
+            high
+        };
+
+        return match ::std::char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => Err(self.error(ERR_ESCAPE))
+        };
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            match self.next().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return None
+            }
+        }
+
+        return Some(code);
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+
+        while let Some(c) = self.peek() {
+            if c.is_digit(10) || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        let text: String = self.chars[start..self.pos].iter().cloned().collect();
+
+        return match text.parse::<f64>() {
+            Ok(number) => Ok(Json::Number(number)),
+            Err(_) => Err(self.error(ERR_NUMBER))
+        };
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        for expected in literal.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error(ERR_UNEXPECTED));
+            }
+        }
+
+        return Ok(value);
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ' ' || c == '\t' || c == '\n' || c == '\r' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.pos).cloned();
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+
+        return c;
+    }
+
+    fn error(&self, message: &str) -> String {
+        return format!("{} {}", message, self.pos);
+    }
+}
+
+/// Far more than glTF documents need, their node hierarchies are flattened to indices.
+const MAX_DEPTH: usize = 128;
+
+const ERR_UNEXPECTED: &'static str = "Unexpected JSON character at";
+const ERR_TRAILING: &'static str = "Trailing JSON characters at";
+const ERR_ESCAPE: &'static str = "Invalid JSON escape sequence at";
+const ERR_NUMBER: &'static str = "Invalid JSON number at";
+const ERR_TOO_DEEP: &'static str = "JSON nested too deeply at";
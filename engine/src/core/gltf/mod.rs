@@ -0,0 +1,947 @@
+//! glTF 2.0 import.
+//!
+//! # References
+//! * [glTF 2.0 Specification](https://github.com/KhronosGroup/glTF/tree/master/specification/2.0)
+
+extern crate cgmath;
+
+mod json;
+
+pub mod animation;
+
+use self::cgmath::{
+    InnerSpace, SquareMatrix,
+    Point3, Vector2, Vector3, Vector4, Matrix3, Matrix4, Quaternion
+};
+
+use gliw::{gl, Texture};
+
+use core::{Image, ImageFormat, Camera, Composition, Renderable, Scene, NodeHandle, Material, Lights, Mesh, Model, ModelPart, MeshRenderable, IdPass};
+
+use math::{RotMat, Aabb, Bounds};
+
+use self::animation::{Animation, AnimationPath, Channel, Interpolation};
+use self::json::Json;
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::rc::Rc;
+
+/// A node of a glTF hierarchy, wrapped in a `Composition` holding its children.
+///
+/// Its transform is relative to the parent node.
+pub struct GltfNode {
+    pub name: String,
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+    mesh: Option<Rc<MeshRenderable>>
+}
+
+impl GltfNode {
+    /// Get the node's mesh, which may be shared with other nodes.
+    pub fn mesh(&self) -> Option<&Rc<MeshRenderable>> {
+        return self.mesh.as_ref();
+    }
+}
+
+impl Renderable for GltfNode {
+    fn is_transparent(&self) -> bool {
+        return self.mesh.as_ref().map_or(false, |mesh| mesh.is_transparent());
+    }
+
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_translation(self.translation) *
+            Matrix4::from_quat(&self.rotation) *
+            Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
+    }
+
+    fn draw(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        if let Some(ref mesh) = self.mesh {
+            mesh.draw(draw_space * self.model_matrix(), camera);
+        }
+    }
+
+    fn draw_lit(&self, draw_space: Matrix4<f32>, camera: &Camera, lights: &Lights) {
+        if let Some(ref mesh) = self.mesh {
+            mesh.draw_lit(draw_space * self.model_matrix(), camera, lights);
+        }
+    }
+
+    /// Nodes without a mesh have nothing to draw either way.
+    fn has_gbuffer_pass(&self) -> bool {
+        return self.mesh.as_ref().map_or(true, |mesh| mesh.has_gbuffer_pass());
+    }
+
+    fn draw_gbuffer(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        if let Some(ref mesh) = self.mesh {
+            mesh.draw_gbuffer(draw_space * self.model_matrix(), camera);
+        }
+    }
+
+    fn draw_shadow(&self, draw_space: Matrix4<f32>, light_vp: &Matrix4<f32>) {
+        if let Some(ref mesh) = self.mesh {
+            mesh.draw_shadow(draw_space * self.model_matrix(), light_vp);
+        }
+    }
+
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {
+        if let Some(ref mesh) = self.mesh {
+            mesh.draw_id(draw_space * self.model_matrix(), pass);
+        }
+    }
+
+    /// Nodes without a mesh are a point at their origin.
+    fn bounds(&self) -> Option<Bounds> {
+        return match self.mesh {
+            Some(ref mesh) => mesh.bounds().map(|bounds| bounds.transform(&mesh.model_matrix())),
+            None => Some(Bounds::Box(Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 0.0))))
+        };
+    }
+}
+
+/// Node hierarchy, meshes, materials and animations imported from a glTF 2.0 file.
+///
+/// Each node becomes a `Composition<GltfNode>` with its children attached, so adding the roots
+/// to a `Scene` draws the whole hierarchy. Like any wrapped `Renderable`, the nodes are only
+/// held weakly by the scene, so the asset has to be kept alive for as long as it is drawn.
+///
+/// The mesh primitives become `ModelPart`s of a `MeshRenderable` shared by the nodes using the mesh.
+/// Points and lines are skipped. The metallic-roughness materials are approximated with
+/// the engine's `Material`:
+///
+/// * `baseColorFactor` - base color.
+/// * `roughnessFactor` - shininess, `2 / roughness^4 - 2` clamped to [1, 256].
+/// * `metallicFactor` - specular intensity, from `0.25` for dielectrics to `1.0` for metals,
+/// scaled by `1 - roughness`.
+/// * `emissiveFactor` - emissive color.
+/// * `baseColorTexture` - texture. PNG and BMP images are supported, from files, data URIs or
+/// buffer views. Materials with other images, e.g. JPEG, are left untextured.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::core::{GltfAsset, Scene, Camera};
+/// let tower = GltfAsset::load("resources/models/tower.glb").unwrap();
+///
+/// let mut scene = Scene::new(Camera::new());
+/// tower.add_to(&mut scene);
+///
+/// // In the render loop
+/// # let time = 0.0;
+/// if let Some(idle) = tower.animation("idle") {
+///     tower.animate(idle, time);
+/// }
+/// scene.draw();
+/// ```
+pub struct GltfAsset {
+    nodes: Vec<Rc<RefCell<Composition<GltfNode>>>>,
+    roots: Vec<usize>,
+    animations: Vec<Animation>
+}
+
+impl GltfAsset {
+    /// Loads a `.gltf` or `.glb` file, resolving external buffers and images relative to its directory.
+    ///
+    /// The format is detected by the contents rather than the extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GltfAsset, String> {
+        let path = path.as_ref();
+        let mut data = Vec::new();
+
+        if let Err(err) = File::open(path).and_then(|mut file| file.read_to_end(&mut data)) {
+            return Err(format!("{}: {}", path.display(), err));
+        }
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        if data.starts_with(GLB_MAGIC) {
+            return GltfAsset::from_glb(&data, dir);
+        }
+
+        return match String::from_utf8(data) {
+            Ok(src) => GltfAsset::from_gltf(&src, dir),
+            Err(_) => Err(invalid("file, it is neither JSON nor GLB"))
+        };
+    }
+
+    /// Imports a glTF JSON document, resolving external buffers and images relative to `dir`.
+    pub fn from_gltf(src: &str, dir: &Path) -> Result<GltfAsset, String> {
+        return match Json::parse(src) {
+            Ok(doc) => GltfAsset::from_json(&doc, None, dir),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Imports a binary glTF container, resolving external buffers and images relative to `dir`.
+    pub fn from_glb(data: &[u8], dir: &Path) -> Result<GltfAsset, String> {
+        if data.len() < 20 || !data.starts_with(GLB_MAGIC) || read_u32(data, 4) != 2 {
+            return Err(invalid("GLB header"));
+        }
+
+        let length = (read_u32(data, 8) as usize).min(data.len());
+        let mut json = None;
+        let mut bin = None;
+        let mut offset = 12;
+
+        while offset + 8 <= length {
+            let chunk_length = read_u32(data, offset) as usize;
+            let chunk_type = read_u32(data, offset + 4);
+            let start = offset + 8;
+
+            if start + chunk_length > length {
+                return Err(invalid("GLB chunk"));
+            }
+
+            let chunk = &data[start..start + chunk_length];
+            match chunk_type {
+                GLB_JSON if json.is_none() => json = Some(chunk),
+                GLB_BIN if bin.is_none() => bin = Some(chunk.to_vec()),
+                // Unknown chunks must be ignored.
+                _ => {}
+            }
+
+            // Chunks are padded to 4 bytes.
+            offset = start + (chunk_length + 3) / 4 * 4;
+        }
+
+        let src = match json.and_then(|json| String::from_utf8(json.to_vec()).ok()) {
+            Some(src) => src,
+            None => return Err(invalid("GLB JSON chunk"))
+        };
+
+        return match Json::parse(&src) {
+            Ok(doc) => GltfAsset::from_json(&doc, bin, dir),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Get all nodes, in the order of the file.
+    pub fn nodes(&self) -> &[Rc<RefCell<Composition<GltfNode>>>] {
+        return &self.nodes;
+    }
+
+    /// Get the indices of the root nodes of the default scene.
+    pub fn roots(&self) -> &[usize] {
+        return &self.roots;
+    }
+
+    /// Get the first node named `name`.
+    pub fn node(&self, name: &str) -> Option<&Rc<RefCell<Composition<GltfNode>>>> {
+        return self.nodes.iter().find(|node| node.borrow().name == name);
+    }
+
+    /// Adds the root nodes to `scene`, returning their handles in the order of `roots`.
+    pub fn add_to(&self, scene: &mut Scene) -> Vec<NodeHandle> {
+        return self.roots.iter().map(|&root| scene.add(Rc::downgrade(&self.nodes[root]))).collect();
+    }
+
+    /// Get the animations.
+    pub fn animations(&self) -> &[Animation] {
+        return &self.animations;
+    }
+
+    /// Get the index of the first animation named `name`.
+    pub fn animation(&self, name: &str) -> Option<usize> {
+        return self.animations.iter().position(|animation| animation.name == name);
+    }
+
+    /// Poses the nodes as animation `index` is at `time` seconds, looping after its duration.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn animate(&self, index: usize, time: f32) {
+        let animation = &self.animations[index];
+        let duration = animation.duration();
+        let time = if duration > 0.0 { time % duration + if time < 0.0 { duration } else { 0.0 } } else { 0.0 };
+
+        for channel in animation.channels.iter() {
+            let value = channel.sample(time);
+            let mut node = self.nodes[channel.node].borrow_mut();
+
+            match channel.path {
+                AnimationPath::Translation => node.translation = Vector3::new(value[0], value[1], value[2]),
+                AnimationPath::Rotation => node.rotation = Quaternion::new(value[3], value[0], value[1], value[2]),
+                AnimationPath::Scale => node.scale = Vector3::new(value[0], value[1], value[2])
+            }
+        }
+    }
+
+    fn from_json(doc: &Json, bin: Option<Vec<u8>>, dir: &Path) -> Result<GltfAsset, String> {
+        match doc.get("asset").and_then(|asset| asset.get("version")).and_then(Json::as_str) {
+            Some(version) if version.starts_with("2.") => {},
+            _ => return Err(invalid("version, only glTF 2.0 is supported"))
+        }
+
+        let buffers = match load_buffers(doc, bin, dir) {
+            Ok(buffers) => buffers,
+            Err(err) => return Err(err)
+        };
+
+        // Images shared by several materials are loaded once.
+        let mut textures = vec![None; array(doc, "images").len()];
+
+        let mut materials = Vec::new();
+        for material in array(doc, "materials") {
+            match load_material(doc, material, &buffers, &mut textures, dir) {
+                Ok(material) => materials.push(material),
+                Err(err) => return Err(err)
+            }
+        }
+
+        let mut meshes = Vec::new();
+        for mesh in array(doc, "meshes") {
+            match load_mesh(doc, mesh, &buffers, &materials) {
+                Ok(model) => meshes.push(Rc::new(MeshRenderable::from_model(&model))),
+                Err(err) => return Err(err)
+            }
+        }
+
+        let node_docs = array(doc, "nodes");
+        let mut nodes = Vec::with_capacity(node_docs.len());
+        let mut parents = vec![None; node_docs.len()];
+
+        for (index, node) in node_docs.iter().enumerate() {
+            let mesh = match node.get("mesh").map(Json::as_usize) {
+                Some(Some(mesh)) if mesh < meshes.len() => Some(meshes[mesh].clone()),
+                Some(_) => return Err(invalid(&format!("mesh of node {}", index))),
+                None => None
+            };
+
+            let (translation, rotation, scale) = match node_transform(node) {
+                Some(transform) => transform,
+                None => return Err(invalid(&format!("transform of node {}", index)))
+            };
+
+            nodes.push(wrap!(Composition::new(GltfNode {
+                name: node.get("name").and_then(Json::as_str).unwrap_or("").to_string(),
+                translation: translation,
+                rotation: rotation,
+                scale: scale,
+                mesh: mesh
+            })));
+
+            for child in node.get("children").and_then(Json::as_array).unwrap_or(&[]) {
+                match child.as_usize() {
+                    Some(child) if child < node_docs.len() && parents[child].is_none() && child != index => {
+                        parents[child] = Some(index);
+                    },
+                    _ => return Err(invalid(&format!("children of node {}", index)))
+                }
+            }
+        }
+
+        // With a single parent per node, a cycle shows up as a parent chain longer than the node count.
+        for start in 0..nodes.len() {
+            let mut current = start;
+            let mut depth = 0;
+
+            while let Some(parent) = parents[current] {
+                current = parent;
+                depth += 1;
+
+                if depth > nodes.len() {
+                    return Err(invalid("node hierarchy, it has a cycle"));
+                }
+            }
+        }
+
+        for (child, parent) in parents.iter().enumerate() {
+            if let Some(parent) = *parent {
+                nodes[parent].borrow_mut().attach(Rc::downgrade(&nodes[child]));
+            }
+        }
+
+        let scenes = array(doc, "scenes");
+        let scene = doc.get("scene").and_then(Json::as_usize).unwrap_or(0);
+
+        let roots = match scenes.get(scene) {
+            Some(scene) => {
+                let mut roots = Vec::new();
+                for root in scene.get("nodes").and_then(Json::as_array).unwrap_or(&[]) {
+                    match root.as_usize() {
+                        Some(root) if root < nodes.len() && parents[root].is_none() => roots.push(root),
+                        _ => return Err(invalid("scene nodes"))
+                    }
+                }
+                roots
+            },
+            None => (0..nodes.len()).filter(|&node| parents[node].is_none()).collect()
+        };
+
+        let mut animations = Vec::new();
+        for (index, animation) in array(doc, "animations").iter().enumerate() {
+            match load_animation(doc, animation, &buffers, nodes.len()) {
+                Ok(mut loaded) => {
+                    if loaded.name.is_empty() {
+                        loaded.name = format!("animation_{}", index);
+                    }
+                    animations.push(loaded);
+                },
+                Err(err) => return Err(err)
+            }
+        }
+
+        return Ok(GltfAsset {
+            nodes: nodes,
+            roots: roots,
+            animations: animations
+        });
+    }
+}
+
+fn load_buffers(doc: &Json, mut bin: Option<Vec<u8>>, dir: &Path) -> Result<Vec<Vec<u8>>, String> {
+    let mut buffers = Vec::new();
+
+    for (index, buffer) in array(doc, "buffers").iter().enumerate() {
+        let length = buffer.get("byteLength").and_then(Json::as_usize).unwrap_or(0);
+
+        let data = match buffer.get("uri").and_then(Json::as_str) {
+            Some(uri) if uri.starts_with("data:") => {
+                match uri.find(";base64,").and_then(|start| decode_base64(&uri[start + 8..])) {
+                    Some(data) => data,
+                    None => return Err(invalid(&format!("data URI of buffer {}", index)))
+                }
+            },
+            Some(uri) => {
+                let path = dir.join(uri);
+                let mut data = Vec::new();
+
+                if let Err(err) = File::open(&path).and_then(|mut file| file.read_to_end(&mut data)) {
+                    return Err(format!("{}: {}", path.display(), err));
+                }
+                data
+            },
+            // Only the first buffer can refer to the GLB binary chunk.
+            None if index == 0 && bin.is_some() => bin.take().unwrap(),
+            None => return Err(invalid(&format!("buffer {} without data", index)))
+        };
+
+        if data.len() < length {
+            return Err(invalid(&format!("length of buffer {}", index)));
+        }
+
+        buffers.push(data);
+    }
+
+    return Ok(buffers);
+}
+
+fn load_material(doc: &Json, material: &Json, buffers: &[Vec<u8>], textures: &mut Vec<Option<Option<Rc<Texture>>>>,
+                 dir: &Path) -> Result<Material, String>
+{
+    let pbr = material.get("pbrMetallicRoughness");
+    let factor = |name: &str, default: f32| {
+        pbr.and_then(|pbr| pbr.get(name)).and_then(Json::as_f64).map_or(default, |value| value as f32)
+    };
+
+    let color = pbr.and_then(|pbr| pbr.get("baseColorFactor")).and_then(Json::as_f32_vec).unwrap_or(vec![1.0; 4]);
+    if color.len() != 4 {
+        return Err(invalid("baseColorFactor"));
+    }
+
+    let metallic = factor("metallicFactor", 1.0).max(0.0).min(1.0);
+    let roughness = factor("roughnessFactor", 1.0).max(0.0).min(1.0);
+
+    let mut result = Material::new(Vector4::new(color[0], color[1], color[2], color[3]));
+    result.specular = (0.25 + 0.75 * metallic) * (1.0 - roughness);
+    result.shininess = (2.0 / roughness.max(0.01).powi(4) - 2.0).max(1.0).min(256.0);
+
+    if let Some(emissive) = material.get("emissiveFactor").and_then(Json::as_f32_vec) {
+        if emissive.len() != 3 {
+            return Err(invalid("emissiveFactor"));
+        }
+        result.emissive = Vector3::new(emissive[0], emissive[1], emissive[2]);
+    }
+
+    let image = pbr
+        .and_then(|pbr| pbr.get("baseColorTexture"))
+        .and_then(|texture| texture.get("index")).and_then(Json::as_usize)
+        .and_then(|texture| array(doc, "textures").get(texture))
+        .and_then(|texture| texture.get("source")).and_then(Json::as_usize);
+
+    if let Some(image) = image {
+        if image >= textures.len() {
+            return Err(invalid(&format!("source of image {}", image)));
+        }
+
+        // Images are loaded once, including the ones in formats that can't be decoded.
+        if textures[image].is_none() {
+            match load_image(doc, &array(doc, "images")[image], buffers, dir) {
+                Ok(data) => textures[image] = Some(data.map(|data| Rc::new(upload_texture(&data)))),
+                Err(err) => return Err(format!("{} {}: {}", ERR_IMAGE, image, err))
+            }
+        }
+
+        result.texture = textures[image].clone().and_then(|texture| texture);
+    }
+
+    return Ok(result);
+}
+
+/// Loads an image, or `None` if it isn't in a format `Image::decode` recognises.
+fn load_image(doc: &Json, image: &Json, buffers: &[Vec<u8>], dir: &Path) -> Result<Option<Image>, String> {
+    let data = match (image.get("uri").and_then(Json::as_str), image.get("bufferView").and_then(Json::as_usize)) {
+        (Some(uri), _) if uri.starts_with("data:") => {
+            match uri.find(";base64,").and_then(|start| decode_base64(&uri[start + 8..])) {
+                Some(data) => data,
+                None => return Err(invalid("data URI"))
+            }
+        },
+        (Some(uri), _) => {
+            let path = dir.join(uri);
+            let mut data = Vec::new();
+
+            if let Err(err) = File::open(&path).and_then(|mut file| file.read_to_end(&mut data)) {
+                return Err(format!("{}: {}", path.display(), err));
+            }
+            data
+        },
+        (None, Some(view)) => {
+            let view = match array(doc, "bufferViews").get(view) {
+                Some(view) => view,
+                None => return Err(invalid("buffer view"))
+            };
+
+            let buffer = view.get("buffer").and_then(Json::as_usize).and_then(|buffer| buffers.get(buffer));
+            let offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+            let length = view.get("byteLength").and_then(Json::as_usize).unwrap_or(0);
+
+            match buffer {
+                Some(buffer) if offset.checked_add(length).map_or(false, |end| end <= buffer.len()) => {
+                    buffer[offset..offset + length].to_vec()
+                },
+                _ => return Err(invalid("buffer view"))
+            }
+        },
+        (None, None) => return Err(invalid("image without data"))
+    };
+
+    if ImageFormat::from_signature(&data).is_none() {
+        return Ok(None);
+    }
+
+    return Image::decode(&data).map(Some);
+}
+
+/// Uploads a base color texture, repeating and mipmapped.
+fn upload_texture(image: &Image) -> Texture {
+    let texture = image.to_texture();
+
+    unsafe {
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+    }
+
+    return texture;
+}
+
+fn load_mesh(doc: &Json, mesh: &Json, buffers: &[Vec<u8>], materials: &[Material]) -> Result<Model, String> {
+    let name = mesh.get("name").and_then(Json::as_str).unwrap_or("");
+    let mut parts = Vec::new();
+
+    for primitive in mesh.get("primitives").and_then(Json::as_array).unwrap_or(&[]) {
+        let mode = primitive.get("mode").and_then(Json::as_usize).unwrap_or(MODE_TRIANGLES);
+        if mode != MODE_TRIANGLES && mode != MODE_TRIANGLE_STRIP && mode != MODE_TRIANGLE_FAN {
+            continue;
+        }
+
+        let attribute = |name: &str| primitive.get("attributes").and_then(|attributes| attributes.get(name)).and_then(Json::as_usize);
+        let mut result = Mesh::new();
+
+        let positions = match attribute("POSITION").map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, 3))) => values.clone(),
+            Some(Err(err)) => return Err(err),
+            _ => return Err(invalid(&format!("POSITION of mesh {}", name)))
+        };
+        result.positions = positions.chunks(3).map(|v| Vector3::new(v[0] as f32, v[1] as f32, v[2] as f32)).collect();
+        let count = result.positions.len();
+
+        match attribute("NORMAL").map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, 3))) if values.len() == count * 3 => {
+                result.normals = values.chunks(3).map(|v| Vector3::new(v[0] as f32, v[1] as f32, v[2] as f32)).collect();
+            },
+            Some(Err(err)) => return Err(err),
+            Some(_) => return Err(invalid(&format!("NORMAL of mesh {}", name))),
+            None => {}
+        }
+
+        match attribute("TEXCOORD_0").map(|accessor| read_accessor(doc, buffers, accessor)) {
+            // glTF puts the texture origin at the top left, OpenGL at the bottom left.
+            Some(Ok((ref values, 2))) if values.len() == count * 2 => {
+                result.uvs = values.chunks(2).map(|v| Vector2::new(v[0] as f32, 1.0 - v[1] as f32)).collect();
+            },
+            Some(Err(err)) => return Err(err),
+            Some(_) => return Err(invalid(&format!("TEXCOORD_0 of mesh {}", name))),
+            None => {}
+        }
+
+        match attribute("COLOR_0").map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, components))) if (components == 3 || components == 4) && values.len() == count * components => {
+                result.colors = values.chunks(components).map(|v| {
+                    Vector4::new(v[0] as f32, v[1] as f32, v[2] as f32, if components == 4 { v[3] as f32 } else { 1.0 })
+                }).collect();
+            },
+            Some(Err(err)) => return Err(err),
+            Some(_) => return Err(invalid(&format!("COLOR_0 of mesh {}", name))),
+            None => {}
+        }
+
+        let indices: Vec<u32> = match primitive.get("indices").and_then(Json::as_usize).map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, 1))) => values.iter().map(|&index| index as u32).collect(),
+            Some(Err(err)) => return Err(err),
+            Some(_) => return Err(invalid(&format!("indices of mesh {}", name))),
+            None => (0..count as u32).collect()
+        };
+
+        if indices.iter().any(|&index| index as usize >= count) {
+            return Err(invalid(&format!("indices of mesh {}", name)));
+        }
+
+        result.indices = match mode {
+            MODE_TRIANGLE_STRIP => (2..indices.len()).flat_map(|i| {
+                // Every other triangle is flipped to keep the winding.
+                if i % 2 == 0 {
+                    vec![indices[i - 2], indices[i - 1], indices[i]]
+                } else {
+                    vec![indices[i - 1], indices[i - 2], indices[i]]
+                }
+            }).collect(),
+            MODE_TRIANGLE_FAN => (2..indices.len()).flat_map(|i| vec![indices[0], indices[i - 1], indices[i]]).collect(),
+            _ => indices[..indices.len() / 3 * 3].to_vec()
+        };
+
+        if result.normals.is_empty() {
+            result.compute_normals();
+        }
+
+        let material = match primitive.get("material").map(Json::as_usize) {
+            Some(Some(material)) if material < materials.len() => materials[material].clone(),
+            Some(_) => return Err(invalid(&format!("material of mesh {}", name))),
+            None => Material::new(Vector4::new(1.0, 1.0, 1.0, 1.0))
+        };
+
+        parts.push(ModelPart {
+            name: name.to_string(),
+            mesh: result,
+            material: material
+        });
+    }
+
+    return Ok(Model { parts: parts });
+}
+
+fn load_animation(doc: &Json, animation: &Json, buffers: &[Vec<u8>], node_count: usize) -> Result<Animation, String> {
+    let name = animation.get("name").and_then(Json::as_str).unwrap_or("").to_string();
+    let samplers = animation.get("samplers").and_then(Json::as_array).unwrap_or(&[]);
+    let mut channels = Vec::new();
+
+    for channel in animation.get("channels").and_then(Json::as_array).unwrap_or(&[]) {
+        let target = channel.get("target");
+        let error = || invalid(&format!("channel of animation {}", name));
+
+        let node = match target.and_then(|target| target.get("node")).and_then(Json::as_usize) {
+            Some(node) if node < node_count => node,
+            // Channels without a node are for extensions.
+            None => continue,
+            Some(_) => return Err(error())
+        };
+
+        let path = match target.and_then(|target| target.get("path")).and_then(Json::as_str) {
+            Some("translation") => AnimationPath::Translation,
+            Some("rotation") => AnimationPath::Rotation,
+            Some("scale") => AnimationPath::Scale,
+            // Morph target weights are not supported.
+            Some("weights") => continue,
+            _ => return Err(error())
+        };
+
+        let sampler = match channel.get("sampler").and_then(Json::as_usize).and_then(|sampler| samplers.get(sampler)) {
+            Some(sampler) => sampler,
+            None => return Err(error())
+        };
+
+        let interpolation = match sampler.get("interpolation").and_then(Json::as_str).unwrap_or("LINEAR") {
+            "LINEAR" => Interpolation::Linear,
+            "STEP" => Interpolation::Step,
+            "CUBICSPLINE" => Interpolation::CubicSpline,
+            _ => return Err(error())
+        };
+
+        let times = match sampler.get("input").and_then(Json::as_usize).map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, 1))) if !values.is_empty() => values.iter().map(|&time| time as f32).collect::<Vec<f32>>(),
+            Some(Err(err)) => return Err(err),
+            _ => return Err(error())
+        };
+
+        // Sampling relies on the order, which NaNs do not have.
+        if times.iter().any(|time| !time.is_finite()) || times.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(invalid(&format!("keyframe times of animation {}, they must be finite and increasing", name)));
+        }
+
+        let values = match sampler.get("output").and_then(Json::as_usize).map(|accessor| read_accessor(doc, buffers, accessor)) {
+            Some(Ok((ref values, _))) => values.iter().map(|&value| value as f32).collect::<Vec<f32>>(),
+            Some(Err(err)) => return Err(err),
+            None => return Err(error())
+        };
+
+        let loaded = Channel {
+            node: node,
+            path: path,
+            interpolation: interpolation,
+            times: times,
+            values: values
+        };
+
+        let per_key = if interpolation == Interpolation::CubicSpline { 3 } else { 1 } * loaded.components();
+        if loaded.values.len() != loaded.times.len() * per_key {
+            return Err(error());
+        }
+
+        channels.push(loaded);
+    }
+
+    return Ok(Animation {
+        name: name,
+        channels: channels
+    });
+}
+
+/// Reads an accessor as `f64`s, returning them along with the number of components per element.
+///
+/// Normalized integers are mapped to [0, 1] or [-1, 1].
+fn read_accessor(doc: &Json, buffers: &[Vec<u8>], index: usize) -> Result<(Vec<f64>, usize), String> {
+    let error = || invalid(&format!("accessor {}", index));
+
+    let accessor = match array(doc, "accessors").get(index) {
+        Some(accessor) => accessor,
+        None => return Err(error())
+    };
+
+    if accessor.get("sparse").is_some() {
+        return Err(format!("{} {}", ERR_UNSUPPORTED, "sparse accessors"));
+    }
+
+    let components = match accessor.get("type").and_then(Json::as_str) {
+        Some("SCALAR") => 1,
+        Some("VEC2") => 2,
+        Some("VEC3") => 3,
+        Some("VEC4") => 4,
+        Some("MAT2") => 4,
+        Some("MAT3") => 9,
+        Some("MAT4") => 16,
+        _ => return Err(error())
+    };
+
+    let component_type = accessor.get("componentType").and_then(Json::as_usize).unwrap_or(0);
+    let component_size = match component_type {
+        5120 | 5121 => 1,
+        5122 | 5123 => 2,
+        5125 | 5126 => 4,
+        _ => return Err(error())
+    };
+
+    let count = match accessor.get("count").and_then(Json::as_usize) {
+        Some(count) => count,
+        None => return Err(error())
+    };
+
+    let total = match count.checked_mul(components) {
+        Some(total) => total,
+        None => return Err(error())
+    };
+
+    let normalized = accessor.get("normalized") == Some(&Json::Bool(true));
+
+    let view = match accessor.get("bufferView").map(Json::as_usize) {
+        Some(Some(view)) => match array(doc, "bufferViews").get(view) {
+            Some(view) => view,
+            None => return Err(error())
+        },
+        Some(None) => return Err(error()),
+        // Accessors without a buffer view are all zeros.
+        None if total <= MAX_ZERO_ACCESSOR => return Ok((vec![0.0; total], components)),
+        None => return Err(error())
+    };
+
+    let buffer = match view.get("buffer").and_then(Json::as_usize).and_then(|buffer| buffers.get(buffer)) {
+        Some(buffer) => buffer,
+        None => return Err(error())
+    };
+
+    let view_offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+    let view_length = view.get("byteLength").and_then(Json::as_usize).unwrap_or(0);
+    let element_size = components * component_size;
+    let stride = view.get("byteStride").and_then(Json::as_usize).unwrap_or(element_size);
+
+    let view_end = match view_offset.checked_add(view_length) {
+        Some(view_end) if view_end <= buffer.len() => view_end,
+        _ => return Err(error())
+    };
+
+    let offset = match view_offset.checked_add(accessor.get("byteOffset").and_then(Json::as_usize).unwrap_or(0)) {
+        Some(offset) => offset,
+        None => return Err(error())
+    };
+
+    if count > 0 {
+        let end = (count - 1).checked_mul(stride)
+            .and_then(|end| end.checked_add(offset))
+            .and_then(|end| end.checked_add(element_size));
+
+        match end {
+            Some(end) if end <= view_end => (),
+            _ => return Err(error())
+        }
+    }
+
+    let mut values = Vec::with_capacity(total);
+    for element in 0..count {
+        for component in 0..components {
+            let at = offset + element * stride + component * component_size;
+            let bytes = &buffer[at..at + component_size];
+
+            let value = match component_type {
+                5120 => {
+                    let value = bytes[0] as i8 as f64;
+                    if normalized { (value / 127.0).max(-1.0) } else { value }
+                },
+                5121 => {
+                    let value = bytes[0] as f64;
+                    if normalized { value / 255.0 } else { value }
+                },
+                5122 => {
+                    let value = (bytes[0] as u16 | (bytes[1] as u16) << 8) as i16 as f64;
+                    if normalized { (value / 32767.0).max(-1.0) } else { value }
+                },
+                5123 => {
+                    let value = (bytes[0] as u16 | (bytes[1] as u16) << 8) as f64;
+                    if normalized { value / 65535.0 } else { value }
+                },
+                5125 => read_u32(bytes, 0) as f64,
+                5126 => f32::from_bits(read_u32(bytes, 0)) as f64,
+                _ => unreachable!()
+            };
+
+            values.push(value);
+        }
+    }
+
+    return Ok((values, components));
+}
+
+/// Get the translation, rotation and scale of a node, decomposing its matrix if it has one.
+fn node_transform(node: &Json) -> Option<(Vector3<f32>, Quaternion<f32>, Vector3<f32>)> {
+    if let Some(matrix) = node.get("matrix") {
+        let m = match matrix.as_f32_vec() {
+            Some(ref m) if m.len() == 16 => m.clone(),
+            _ => return None
+        };
+
+        let x = Vector3::new(m[0], m[1], m[2]);
+        let y = Vector3::new(m[4], m[5], m[6]);
+        let z = Vector3::new(m[8], m[9], m[10]);
+
+        let mut scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+        if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+            return Some((Vector3::new(m[12], m[13], m[14]), Quaternion::new(1.0, 0.0, 0.0, 0.0), scale));
+        }
+
+        // A mirroring matrix is a rotation with a negative scale.
+        if Matrix3::from_cols(x, y, z).determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let rotation = Matrix3::from_cols(x / scale.x, y / scale.y, z / scale.z);
+
+        return Some((Vector3::new(m[12], m[13], m[14]), Quaternion::from(rotation).normalize(), scale));
+    }
+
+    let vector = |name: &str, default: Vector3<f32>| {
+        match node.get(name).map(Json::as_f32_vec) {
+            Some(Some(ref v)) if v.len() == 3 => Some(Vector3::new(v[0], v[1], v[2])),
+            Some(_) => None,
+            None => Some(default)
+        }
+    };
+
+    let translation = match vector("translation", Vector3::new(0.0, 0.0, 0.0)) {
+        Some(translation) => translation,
+        None => return None
+    };
+
+    let scale = match vector("scale", Vector3::new(1.0, 1.0, 1.0)) {
+        Some(scale) => scale,
+        None => return None
+    };
+
+    let rotation = match node.get("rotation").map(Json::as_f32_vec) {
+        Some(Some(ref q)) if q.len() == 4 => Quaternion::new(q[3], q[0], q[1], q[2]),
+        Some(_) => return None,
+        None => Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    };
+
+    return Some((translation, rotation, scale));
+}
+
+/// Get the elements of a top level array of the document, empty if it is missing.
+fn array<'a>(doc: &'a Json, name: &str) -> &'a [Json] {
+    return doc.get(name).and_then(Json::as_array).unwrap_or(&[]);
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    return data[offset] as u32 |
+        (data[offset + 1] as u32) << 8 |
+        (data[offset + 2] as u32) << 16 |
+        (data[offset + 3] as u32) << 24;
+}
+
+fn decode_base64(src: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(src.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+
+    for c in src.bytes() {
+        let value = match c {
+            b'A'...b'Z' => c - b'A',
+            b'a'...b'z' => c - b'a' + 26,
+            b'0'...b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None
+        };
+
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            data.push((bits >> bit_count) as u8);
+        }
+    }
+
+    return Some(data);
+}
+
+fn invalid(what: &str) -> String {
+    return format!("{} {}", ERR_INVALID, what);
+}
+
+const GLB_MAGIC: &'static [u8] = b"glTF";
+const GLB_JSON: u32 = 0x4E4F534A;
+const GLB_BIN: u32 = 0x004E4942;
+
+/// The largest accessor without a buffer view that is zero-filled, in components.
+const MAX_ZERO_ACCESSOR: usize = 1 << 24;
+
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+const ERR_INVALID: &'static str = "Invalid glTF";
+const ERR_UNSUPPORTED: &'static str = "Unsupported glTF feature:";
+const ERR_IMAGE: &'static str = "Failed to load glTF image";
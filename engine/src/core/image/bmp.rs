@@ -0,0 +1,125 @@
+//! BMP encoder and decoder.
+//!
+//! Writes 24bpp bitmaps with a `BITMAPINFOHEADER`, the same kind `TextureBuilder2D` loads.
+//! Reads uncompressed 24bpp and 32bpp bitmaps.
+//!
+//! # References
+//! * [BMP file format](https://en.wikipedia.org/wiki/BMP_file_format)
+
+use super::Image;
+
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+
+pub fn encode(image: &Image) -> Vec<u8> {
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+
+    // Each row is padded to a multiple of 4 bytes.
+    let row_size = (width * 3 + 3) & !3;
+    let data_size = (row_size * height) as u32;
+    let data_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+
+    let mut bmp = Vec::with_capacity((data_offset + data_size) as usize);
+
+    // BITMAPFILEHEADER
+    bmp.extend_from_slice(b"BM");
+    push_u32_le(&mut bmp, data_offset + data_size);
+    push_u32_le(&mut bmp, 0);
+    push_u32_le(&mut bmp, data_offset);
+
+    // BITMAPINFOHEADER
+    push_u32_le(&mut bmp, INFO_HEADER_SIZE);
+    push_u32_le(&mut bmp, width as u32);
+    push_u32_le(&mut bmp, height as u32);
+    push_u16_le(&mut bmp, 1);               // planes
+    push_u16_le(&mut bmp, 24);              // bits per pixel
+    push_u32_le(&mut bmp, 0);               // BI_RGB, no compression
+    push_u32_le(&mut bmp, data_size);
+    push_u32_le(&mut bmp, 2835);            // 72 DPI horizontally
+    push_u32_le(&mut bmp, 2835);            // 72 DPI vertically
+    push_u32_le(&mut bmp, 0);               // colors in the palette
+    push_u32_le(&mut bmp, 0);               // important colors
+
+    // The rows are stored from bottom to top, the pixels as BGR.
+    let padding = row_size - width * 3;
+    for y in (0..height).rev() {
+        let row = &image.data()[y * width * 4..(y + 1) * width * 4];
+        for pixel in row.chunks(4) {
+            bmp.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+        for _ in 0..padding {
+            bmp.push(0);
+        }
+    }
+
+    return bmp;
+}
+
+/// Checks if `data` starts with the BMP signature.
+pub fn is_bmp(data: &[u8]) -> bool {
+    return data.starts_with(b"BM");
+}
+
+/// Decodes an uncompressed 24bpp or 32bpp bitmap. The fourth byte of 32bpp pixels is ignored.
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+    if !is_bmp(data) {
+        return Err(String::from(ERR_SIGNATURE));
+    }
+
+    if data.len() < (FILE_HEADER_SIZE + INFO_HEADER_SIZE) as usize || read_u32_le(data, 14) < INFO_HEADER_SIZE {
+        return Err(String::from(ERR_HEADER));
+    }
+
+    let data_offset = read_u32_le(data, 10) as usize;
+    let width = read_u32_le(data, 18) as i32;
+    let height = read_u32_le(data, 22) as i32;
+    let bits_per_pixel = data[28] as u16 | (data[29] as u16) << 8;
+    let compression = read_u32_le(data, 30);
+
+    if (bits_per_pixel != 24 && bits_per_pixel != 32) || compression != 0 {
+        return Err(String::from(ERR_UNSUPPORTED));
+    }
+
+    // A negative height means the rows are stored from top to bottom.
+    let bottom_up = height > 0;
+    let (width, height) = (width.abs() as usize, height.abs() as usize);
+    if width == 0 || height == 0 {
+        return Err(String::from(ERR_HEADER));
+    }
+
+    let pixel_size = bits_per_pixel as usize / 8;
+    let row_size = (width * pixel_size + 3) & !3;
+    if data_offset > data.len() || (data.len() - data_offset) / row_size < height {
+        return Err(String::from(ERR_TRUNCATED));
+    }
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let stored = if bottom_up { height - 1 - y } else { y };
+        let row = &data[data_offset + stored * row_size..];
+
+        for pixel in row[..width * pixel_size].chunks(pixel_size) {
+            rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+        }
+    }
+
+    return Ok(Image::new(width as u32, height as u32, rgba));
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> u32 {
+    return data[offset] as u32 | (data[offset + 1] as u32) << 8 | (data[offset + 2] as u32) << 16 | (data[offset + 3] as u32) << 24;
+}
+
+fn push_u16_le(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+fn push_u32_le(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+const ERR_SIGNATURE: &'static str = "Not a BMP file";
+const ERR_HEADER: &'static str = "Invalid BMP header";
+const ERR_UNSUPPORTED: &'static str = "Only uncompressed 24bpp and 32bpp BMP images are supported";
+const ERR_TRUNCATED: &'static str = "BMP file ends unexpectedly";
@@ -0,0 +1,384 @@
+//! Zlib decompressor for reading PNG image data.
+//!
+//! Decodes all three deflate block types: stored, fixed Huffman and dynamic Huffman.
+//!
+//! # References
+//! * [RFC 1950 - ZLIB](https://tools.ietf.org/html/rfc1950)
+//! * [RFC 1951 - DEFLATE](https://tools.ietf.org/html/rfc1951)
+
+use super::png::adler32;
+
+/// Reads `count` bits or returns `ERR_TRUNCATED` from the enclosing function.
+macro_rules! try_bits {
+    ($reader: expr, $count: expr) => {
+        match $reader.bits($count) {
+            Some(bits) => bits,
+            None => return Err(String::from(ERR_TRUNCATED))
+        }
+    }
+}
+
+/// Decompresses a zlib stream, failing if it is corrupt or inflates to more than `limit` bytes.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err(String::from(ERR_TRUNCATED));
+    }
+
+    // Deflate compression, no preset dictionary, and a header checksum.
+    let (cmf, flg) = (data[0] as u32, data[1] as u32);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 || flg & 0x20 != 0 || (cmf << 8 | flg) % 31 != 0 {
+        return Err(String::from(ERR_HEADER));
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let out = match inflate(&mut reader, limit) {
+        Ok(out) => out,
+        Err(err) => return Err(err)
+    };
+
+    let end = 2 + reader.byte_position();
+    if data.len() < end + 4 {
+        return Err(String::from(ERR_TRUNCATED));
+    }
+
+    let checksum = (data[end] as u32) << 24 | (data[end + 1] as u32) << 16 | (data[end + 2] as u32) << 8 | data[end + 3] as u32;
+    if checksum != adler32(&out) {
+        return Err(String::from(ERR_CHECKSUM));
+    }
+
+    return Ok(out);
+}
+
+fn inflate(reader: &mut BitReader, limit: usize) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = try_bits!(reader, 1) == 1;
+
+        let result = match try_bits!(reader, 2) {
+            0 => stored_block(reader, &mut out, limit),
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                huffman_block(reader, &mut out, limit, &lengths, &distances)
+            },
+            2 => match dynamic_codes(reader) {
+                Ok((lengths, distances)) => huffman_block(reader, &mut out, limit, &lengths, &distances),
+                Err(err) => Err(err)
+            },
+            _ => Err(String::from(ERR_BLOCK_TYPE))
+        };
+
+        if let Err(err) = result {
+            return Err(err);
+        }
+
+        if is_final {
+            return Ok(out);
+        }
+    }
+}
+
+fn stored_block(reader: &mut BitReader, out: &mut Vec<u8>, limit: usize) -> Result<(), String> {
+    reader.align();
+
+    let len = try_bits!(reader, 16);
+    let nlen = try_bits!(reader, 16);
+    if len != !nlen & 0xFFFF {
+        return Err(String::from(ERR_STORED_LENGTH));
+    }
+
+    let bytes = match reader.bytes(len as usize) {
+        Some(bytes) => bytes,
+        None => return Err(String::from(ERR_TRUNCATED))
+    };
+
+    if out.len() + bytes.len() > limit {
+        return Err(String::from(ERR_TOO_LARGE));
+    }
+
+    out.extend_from_slice(bytes);
+    return Ok(());
+}
+
+fn huffman_block(reader: &mut BitReader, out: &mut Vec<u8>, limit: usize,
+                 lengths: &Huffman, distances: &Huffman) -> Result<(), String>
+{
+    loop {
+        let symbol = match lengths.decode(reader) {
+            Ok(symbol) => symbol as usize,
+            Err(err) => return Err(err)
+        };
+
+        if symbol < 256 {
+            if out.len() >= limit {
+                return Err(String::from(ERR_TOO_LARGE));
+            }
+            out.push(symbol as u8);
+            continue;
+        }
+
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let symbol = symbol - 257;
+        if symbol >= LENGTH_BASE.len() {
+            return Err(String::from(ERR_SYMBOL));
+        }
+        let length = LENGTH_BASE[symbol] as usize + try_bits!(reader, LENGTH_EXTRA[symbol]) as usize;
+
+        let symbol = match distances.decode(reader) {
+            Ok(symbol) => symbol as usize,
+            Err(err) => return Err(err)
+        };
+        if symbol >= DISTANCE_BASE.len() {
+            return Err(String::from(ERR_SYMBOL));
+        }
+        let distance = DISTANCE_BASE[symbol] as usize + try_bits!(reader, DISTANCE_EXTRA[symbol]) as usize;
+
+        if distance > out.len() {
+            return Err(String::from(ERR_DISTANCE));
+        }
+        if out.len() + length > limit {
+            return Err(String::from(ERR_TOO_LARGE));
+        }
+
+        // The copy may overlap the bytes it produces, e.g. to repeat a single byte.
+        let start = out.len() - distance;
+        for i in 0..length {
+            let byte = out[start + i];
+            out.push(byte);
+        }
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [8u8; 288];
+    for length in lengths[144..256].iter_mut() {
+        *length = 9;
+    }
+    for length in lengths[256..280].iter_mut() {
+        *length = 7;
+    }
+
+    // The fixed codes are complete, so building them can not fail.
+    return (Huffman::new(&lengths).unwrap(), Huffman::new(&[5; 30]).unwrap());
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+    let literal_count = try_bits!(reader, 5) as usize + 257;
+    let distance_count = try_bits!(reader, 5) as usize + 1;
+    let code_length_count = try_bits!(reader, 4) as usize + 4;
+
+    if literal_count > 286 || distance_count > 30 {
+        return Err(String::from(ERR_CODE_LENGTHS));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[index] = try_bits!(reader, 3) as u8;
+    }
+
+    let code_length_code = match Huffman::new(&code_lengths) {
+        Ok(code) => code,
+        Err(err) => return Err(err)
+    };
+
+    // The literal/length and distance code lengths form a single sequence.
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol = match code_length_code.decode(reader) {
+            Ok(symbol) => symbol,
+            Err(err) => return Err(err)
+        };
+
+        let (length, repeat) = match symbol {
+            symbol if symbol < 16 => (symbol as u8, 1),
+            16 => match lengths.last() {
+                Some(&previous) => (previous, 3 + try_bits!(reader, 2) as usize),
+                None => return Err(String::from(ERR_CODE_LENGTHS))
+            },
+            17 => (0, 3 + try_bits!(reader, 3) as usize),
+            _ => (0, 11 + try_bits!(reader, 7) as usize)
+        };
+
+        if lengths.len() + repeat > literal_count + distance_count {
+            return Err(String::from(ERR_CODE_LENGTHS));
+        }
+
+        for _ in 0..repeat {
+            lengths.push(length);
+        }
+    }
+
+    // Without an end of block code the block would never end.
+    if lengths[256] == 0 {
+        return Err(String::from(ERR_CODE_LENGTHS));
+    }
+
+    let literals = match Huffman::new(&lengths[..literal_count]) {
+        Ok(code) => code,
+        Err(err) => return Err(err)
+    };
+
+    return match Huffman::new(&lengths[literal_count..]) {
+        Ok(distances) => Ok((literals, distances)),
+        Err(err) => Err(err)
+    };
+}
+
+/// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; MAX_BITS + 1],
+    /// Symbols ordered by their codes.
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    /// Builds the code from the code length of each symbol, with `0` for unused symbols.
+    ///
+    /// Fails if there are more codes of some length than the shorter codes leave room for.
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths.iter() {
+            counts[length as usize] += 1;
+        }
+
+        let mut left: i32 = 1;
+        for length in 1..MAX_BITS + 1 {
+            left = left * 2 - counts[length] as i32;
+            if left < 0 {
+                return Err(String::from(ERR_CODE_LENGTHS));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate().filter(|&(_, &length)| length != 0) {
+            symbols[offsets[length as usize] as usize] = symbol as u16;
+            offsets[length as usize] += 1;
+        }
+
+        return Ok(Huffman {
+            counts: counts,
+            symbols: symbols
+        });
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        // The first code and the index of its symbol for the current length.
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for length in 1..MAX_BITS + 1 {
+            code |= try_bits!(reader, 1) as i32;
+
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        return Err(String::from(ERR_SYMBOL));
+    }
+}
+
+/// Reads the bits of a deflate stream, least significant first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        return BitReader {
+            data: data,
+            position: 0,
+            buffer: 0,
+            count: 0
+        };
+    }
+
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        while self.count < count {
+            let byte = match self.data.get(self.position) {
+                Some(&byte) => byte,
+                None => return None
+            };
+
+            self.buffer |= (byte as u32) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+
+        let value = self.buffer & ((1u32 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+
+        return Some(value);
+    }
+
+    /// Skips to the next byte boundary.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    /// Reads whole bytes, must be aligned.
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.position + count > self.data.len() {
+            return None;
+        }
+
+        self.position += count;
+        return Some(&self.data[self.position - count..self.position]);
+    }
+
+    /// Get the position of the first byte after the bits read so far.
+    fn byte_position(&self) -> usize {
+        return self.position - (self.count / 8) as usize;
+    }
+}
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+
+const ERR_TRUNCATED: &'static str = "Compressed data ends unexpectedly";
+const ERR_HEADER: &'static str = "Unsupported zlib header";
+const ERR_CHECKSUM: &'static str = "Decompressed data checksum mismatch";
+const ERR_BLOCK_TYPE: &'static str = "Invalid deflate block type";
+const ERR_STORED_LENGTH: &'static str = "Invalid deflate stored block length";
+const ERR_CODE_LENGTHS: &'static str = "Invalid deflate Huffman code lengths";
+const ERR_SYMBOL: &'static str = "Invalid deflate Huffman code";
+const ERR_DISTANCE: &'static str = "Deflate distance is too far back";
+const ERR_TOO_LARGE: &'static str = "Decompressed data is larger than expected";
@@ -0,0 +1,238 @@
+//! In-memory images and their encoding to and decoding from files.
+
+mod bmp;
+mod inflate;
+mod png;
+
+pub mod recorder;
+
+use gliw::{gl, Attachment, Framebuffer, Texture, TextureType};
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Supported image file formats.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ImageFormat {
+    /// Lossless, with alpha. The pixel data is written uncompressed,
+    /// any non-interlaced file can be read.
+    Png,
+    /// 24bpp without alpha, can be loaded back with `TextureBuilder2D`.
+    /// Uncompressed 24bpp and 32bpp files can be read.
+    Bmp,
+}
+
+impl ImageFormat {
+    /// Get the format matching the extension of `path`, e.g. `Png` for `"shot.png"`.
+    pub fn from_path(path: &str) -> Option<ImageFormat> {
+        let extension = match Path::new(path).extension() {
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+            None => return None
+        };
+
+        return match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "bmp" => Some(ImageFormat::Bmp),
+            _ => None
+        };
+    }
+
+    /// Get the format of the file `data` from its signature, regardless of its name.
+    pub fn from_signature(data: &[u8]) -> Option<ImageFormat> {
+        if png::is_png(data) {
+            return Some(ImageFormat::Png);
+        }
+
+        if bmp::is_bmp(data) {
+            return Some(ImageFormat::Bmp);
+        }
+
+        return None;
+    }
+
+    /// Get the file extension for the format, without the dot.
+    pub fn extension(&self) -> &'static str {
+        return match *self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+        };
+    }
+}
+
+/// RGBA image with 8 bits per channel, stored row by row from top to bottom.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::core::Image;
+/// // ...after drawing the scene and before swapping the buffers
+/// let screenshot = Image::capture_default(0, 0, 800, 600);
+/// screenshot.save("screenshot.png").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>
+}
+
+impl Image {
+    /// Create an image from RGBA `data` ordered from top to bottom.
+    ///
+    /// # Panics
+    /// Panics if the length of `data` is not `width * height * 4`.
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Image {
+        if data.len() != width as usize * height as usize * 4 {
+            panic!(ERR_DATA_SIZE);
+        }
+
+        return Image {
+            width: width,
+            height: height,
+            data: data
+        };
+    }
+
+    /// Decodes a PNG or BMP file, detecting the format from its signature.
+    ///
+    /// Fails if the format is not supported or the file is corrupt.
+    pub fn decode(data: &[u8]) -> Result<Image, String> {
+        return match ImageFormat::from_signature(data) {
+            Some(ImageFormat::Png) => png::decode(data),
+            Some(ImageFormat::Bmp) => bmp::decode(data),
+            None => Err(String::from(ERR_UNKNOWN_SIGNATURE))
+        };
+    }
+
+    /// Reads the `(x, y, width, height)` rectangle of the back buffer of the default framebuffer.
+    ///
+    /// See `Framebuffer::read_default_pixels`.
+    pub fn capture_default(x: i32, y: i32, width: i32, height: i32) -> Image {
+        let data = Framebuffer::read_default_pixels(x, y, width, height);
+        return Image::from_bottom_up(width as u32, height as u32, data);
+    }
+
+    /// Reads the `(x, y, width, height)` rectangle of the color `attachment` of `framebuffer`.
+    ///
+    /// See `Framebuffer::read_pixels`.
+    pub fn capture(framebuffer: &Framebuffer, attachment: Attachment, x: i32, y: i32, width: i32, height: i32) -> Image {
+        let data = framebuffer.read_pixels(attachment, x, y, width, height);
+        return Image::from_bottom_up(width as u32, height as u32, data);
+    }
+
+    /// Get the width in pixels.
+    pub fn width(&self) -> u32 {
+        return self.width;
+    }
+
+    /// Get the height in pixels.
+    pub fn height(&self) -> u32 {
+        return self.height;
+    }
+
+    /// Get the RGBA data ordered from top to bottom.
+    pub fn data(&self) -> &[u8] {
+        return &self.data;
+    }
+
+    /// Get the RGBA value of the pixel at column `x` and row `y`, counted from the top left corner.
+    ///
+    /// # Panics
+    /// Panics if the pixel is outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            panic!(ERR_OUT_OF_BOUNDS);
+        }
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        return [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]];
+    }
+
+    /// Encodes the image in the given file `format`.
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        return match format {
+            ImageFormat::Png => png::encode(self),
+            ImageFormat::Bmp => bmp::encode(self),
+        };
+    }
+
+    /// Uploads the image to a new `RGBA8` 2D texture with `GL_LINEAR` filters.
+    ///
+    /// The rows are uploaded from bottom to top, the same as bitmaps loaded by `TextureBuilder2D`,
+    /// so the texture coordinate `(0, 0)` is the bottom left corner of the image.
+    /// Binds the texture.
+    pub fn to_texture(&self) -> Texture {
+        let row_size = self.width as usize * 4;
+        let mut flipped = Vec::with_capacity(self.data.len());
+        if row_size > 0 {
+            for row in self.data.chunks(row_size).rev() {
+                flipped.extend_from_slice(row);
+            }
+        }
+
+        let texture = Texture::new(TextureType::Tex2D);
+        texture.bind();
+
+        unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                self.width as i32,
+                self.height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                flipped.as_ptr() as *const _);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        }
+
+        return texture;
+    }
+
+    /// Writes the image to `path` in the format matching its extension.
+    ///
+    /// Fails if the extension is not supported or the file can not be written.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        return match ImageFormat::from_path(path) {
+            Some(format) => self.save_as(path, format),
+            None => Err(format!("{} `{}`", ERR_UNKNOWN_FORMAT, path))
+        };
+    }
+
+    /// Writes the image to `path` in the given `format`, regardless of the extension.
+    pub fn save_as(&self, path: &str, format: ImageFormat) -> Result<(), String> {
+        let mut file = match File::create(path) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("{}", err))
+        };
+
+        return match file.write_all(&self.encode(format)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("{}", err))
+        };
+    }
+
+    /// OpenGL returns the rows from bottom to top.
+    fn from_bottom_up(width: u32, height: u32, data: Vec<u8>) -> Image {
+        let row_size = width as usize * 4;
+        if row_size == 0 {
+            return Image::new(width, height, data);
+        }
+
+        let mut flipped = Vec::with_capacity(data.len());
+        for row in data.chunks(row_size).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        return Image::new(width, height, flipped);
+    }
+}
+
+const ERR_DATA_SIZE: &'static str = "Image data size does not match its dimensions";
+const ERR_OUT_OF_BOUNDS: &'static str = "Pixel is outside of the image";
+const ERR_UNKNOWN_FORMAT: &'static str = "Unsupported image format for";
+const ERR_UNKNOWN_SIGNATURE: &'static str = "Unsupported image format, only PNG and BMP can be decoded";
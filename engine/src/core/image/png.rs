@@ -0,0 +1,433 @@
+//! PNG encoder and decoder.
+//!
+//! The encoder wraps the image data in stored (uncompressed) deflate blocks, which keeps it
+//! trivial and fast at the cost of file size. The decoder reads any non-interlaced PNG.
+//!
+//! # References
+//! * [PNG Specification](https://www.w3.org/TR/PNG/)
+//! * [RFC 1950 - ZLIB](https://tools.ietf.org/html/rfc1950)
+//! * [RFC 1951 - DEFLATE](https://tools.ietf.org/html/rfc1951)
+
+use super::Image;
+use super::inflate::zlib_decompress;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+const COLOR_TYPE_GRAY: u8 = 0;
+const COLOR_TYPE_RGB: u8 = 2;
+const COLOR_TYPE_PALETTE: u8 = 3;
+const COLOR_TYPE_GRAY_ALPHA: u8 = 4;
+const COLOR_TYPE_RGBA: u8 = 6;
+
+const FILTER_NONE: u8 = 0;
+const FILTER_SUB: u8 = 1;
+const FILTER_UP: u8 = 2;
+const FILTER_AVERAGE: u8 = 3;
+const FILTER_PAETH: u8 = 4;
+
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+pub fn encode(image: &Image) -> Vec<u8> {
+    let mut png = Vec::new();
+    png.extend_from_slice(&SIGNATURE);
+
+    let mut header = Vec::with_capacity(13);
+    push_u32_be(&mut header, image.width());
+    push_u32_be(&mut header, image.height());
+    // Bit depth, color type, compression, filter and interlace methods.
+    header.extend_from_slice(&[8, COLOR_TYPE_RGBA, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // Each scanline is prefixed by its filter type.
+    let row_size = image.width() as usize * 4;
+    let mut scanlines = Vec::with_capacity((row_size + 1) * image.height() as usize);
+    if row_size > 0 {
+        for row in image.data().chunks(row_size) {
+            scanlines.push(FILTER_NONE);
+            scanlines.extend_from_slice(row);
+        }
+    }
+
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    return png;
+}
+
+/// Checks if `data` starts with the PNG signature.
+pub fn is_png(data: &[u8]) -> bool {
+    return data.starts_with(&SIGNATURE);
+}
+
+/// Decodes a non-interlaced PNG of any color type and bit depth.
+///
+/// 16 bit channels are truncated to 8 bits. Ancillary chunks other than the transparency
+/// are ignored, e.g. the gamma.
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+    if !is_png(data) {
+        return Err(String::from(ERR_SIGNATURE));
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut color_key: Option<[u16; 3]> = None;
+    let mut compressed = Vec::new();
+    let mut offset = SIGNATURE.len();
+
+    loop {
+        if data.len() - offset < 12 {
+            return Err(String::from(ERR_TRUNCATED));
+        }
+
+        let length = read_u32_be(&data[offset..]) as usize;
+        if data.len() - offset - 12 < length {
+            return Err(String::from(ERR_TRUNCATED));
+        }
+
+        let chunk_type = &data[offset + 4..offset + 8];
+        let chunk = &data[offset + 8..offset + 8 + length];
+
+        if read_u32_be(&data[offset + 8 + length..]) != crc32(&data[offset + 4..offset + 8 + length]) {
+            return Err(String::from(ERR_CHUNK_CRC));
+        }
+
+        offset += 12 + length;
+
+        if header.is_none() && chunk_type != b"IHDR" {
+            return Err(String::from(ERR_HEADER));
+        }
+
+        match chunk_type {
+            b"IHDR" => header = match Header::parse(chunk) {
+                Ok(header) => Some(header),
+                Err(err) => return Err(err)
+            },
+            b"PLTE" => {
+                if chunk.len() % 3 != 0 {
+                    return Err(String::from(ERR_PALETTE));
+                }
+                palette = chunk.chunks(3).map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect();
+            },
+            b"tRNS" => {
+                // The alpha of the palette entries, or a single transparent color.
+                let read_u16 = |i: usize| (chunk[i * 2] as u16) << 8 | chunk[i * 2 + 1] as u16;
+
+                match header.as_ref().unwrap().color_type {
+                    COLOR_TYPE_PALETTE => for (entry, &alpha) in palette.iter_mut().zip(chunk.iter()) {
+                        entry[3] = alpha;
+                    },
+                    COLOR_TYPE_GRAY if chunk.len() == 2 => color_key = Some([read_u16(0), 0, 0]),
+                    COLOR_TYPE_RGB if chunk.len() == 6 => color_key = Some([read_u16(0), read_u16(1), read_u16(2)]),
+                    _ => return Err(String::from(ERR_TRANSPARENCY))
+                }
+            },
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.unwrap();
+    if header.color_type == COLOR_TYPE_PALETTE && palette.is_empty() {
+        return Err(String::from(ERR_PALETTE));
+    }
+
+    // Each scanline is prefixed by its filter type.
+    let row_size = ((header.width as u64 * header.bits_per_pixel() as u64 + 7) / 8) as usize;
+    let size = (row_size + 1) as u64 * header.height as u64;
+    if size > MAX_DATA_SIZE {
+        return Err(String::from(ERR_TOO_LARGE));
+    }
+
+    let scanlines = match zlib_decompress(&compressed, size as usize) {
+        Ok(scanlines) => scanlines,
+        Err(err) => return Err(err)
+    };
+
+    if scanlines.len() != size as usize {
+        return Err(String::from(ERR_TRUNCATED));
+    }
+
+    // The filters work on whole bytes, referring to the same channel of the previous pixel.
+    let pixel_size = ((header.bits_per_pixel() + 7) / 8) as usize;
+
+    let mut rgba = Vec::with_capacity(header.width as usize * header.height as usize * 4);
+    let mut previous = vec![0; row_size];
+
+    for scanline in scanlines.chunks(row_size + 1) {
+        let mut row = scanline[1..].to_vec();
+        if let Err(err) = unfilter(scanline[0], &mut row, &previous, pixel_size) {
+            return Err(err);
+        }
+
+        if let Err(err) = header.expand_row(&row, &palette, color_key, &mut rgba) {
+            return Err(err);
+        }
+
+        previous = row;
+    }
+
+    return Ok(Image::new(header.width, header.height, rgba));
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8
+}
+
+impl Header {
+    fn parse(chunk: &[u8]) -> Result<Header, String> {
+        if chunk.len() != 13 {
+            return Err(String::from(ERR_HEADER));
+        }
+
+        let header = Header {
+            width: read_u32_be(&chunk[0..]),
+            height: read_u32_be(&chunk[4..]),
+            bit_depth: chunk[8],
+            color_type: chunk[9]
+        };
+
+        let valid_depth = match header.color_type {
+            COLOR_TYPE_GRAY => [1, 2, 4, 8, 16].contains(&header.bit_depth),
+            COLOR_TYPE_PALETTE => [1, 2, 4, 8].contains(&header.bit_depth),
+            COLOR_TYPE_RGB | COLOR_TYPE_GRAY_ALPHA | COLOR_TYPE_RGBA => [8, 16].contains(&header.bit_depth),
+            _ => false
+        };
+
+        // The compression and filter methods have a single option.
+        if header.width == 0 || header.height == 0 || !valid_depth || chunk[10] != 0 || chunk[11] != 0 {
+            return Err(String::from(ERR_HEADER));
+        }
+
+        if chunk[12] != 0 {
+            return Err(String::from(ERR_INTERLACED));
+        }
+
+        return Ok(header);
+    }
+
+    fn channels(&self) -> u32 {
+        return match self.color_type {
+            COLOR_TYPE_RGB => 3,
+            COLOR_TYPE_GRAY_ALPHA => 2,
+            COLOR_TYPE_RGBA => 4,
+            _ => 1
+        };
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        return self.channels() * self.bit_depth as u32;
+    }
+
+    /// Converts an unfiltered row to RGBA with 8 bits per channel.
+    fn expand_row(&self, row: &[u8], palette: &[[u8; 4]], color_key: Option<[u16; 3]>, rgba: &mut Vec<u8>) -> Result<(), String> {
+        let depth = self.bit_depth as usize;
+        let channels = self.channels() as usize;
+        let max = (1u32 << depth) - 1;
+
+        // Samples narrower than a byte are packed from the most significant bit.
+        let sample = |index: usize| -> u16 {
+            return match depth {
+                16 => (row[index * 2] as u16) << 8 | row[index * 2 + 1] as u16,
+                8 => row[index] as u16,
+                _ => {
+                    let bit = index * depth;
+                    ((row[bit / 8] >> (8 - depth - bit % 8)) as u32 & max) as u16
+                }
+            };
+        };
+        let to_u8 = |value: u16| (value as u32 * 255 / max) as u8;
+
+        for x in 0..self.width as usize {
+            let first = x * channels;
+
+            let pixel = match self.color_type {
+                COLOR_TYPE_GRAY => {
+                    let gray = sample(first);
+                    let alpha = if color_key == Some([gray, 0, 0]) { 0 } else { 255 };
+                    [to_u8(gray), to_u8(gray), to_u8(gray), alpha]
+                },
+                COLOR_TYPE_RGB => {
+                    let rgb = [sample(first), sample(first + 1), sample(first + 2)];
+                    let alpha = if color_key == Some(rgb) { 0 } else { 255 };
+                    [to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2]), alpha]
+                },
+                COLOR_TYPE_PALETTE => match palette.get(sample(first) as usize) {
+                    Some(&entry) => entry,
+                    None => return Err(String::from(ERR_PALETTE))
+                },
+                COLOR_TYPE_GRAY_ALPHA => {
+                    let gray = to_u8(sample(first));
+                    [gray, gray, gray, to_u8(sample(first + 1))]
+                },
+                _ => [to_u8(sample(first)), to_u8(sample(first + 1)), to_u8(sample(first + 2)), to_u8(sample(first + 3))]
+            };
+
+            rgba.extend_from_slice(&pixel);
+        }
+
+        return Ok(());
+    }
+}
+
+/// Reverses the `filter` of `row` in place, given the previous unfiltered row.
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], pixel_size: usize) -> Result<(), String> {
+    for i in 0..row.len() {
+        let left = if i >= pixel_size { row[i - pixel_size] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= pixel_size { previous[i - pixel_size] } else { 0 };
+
+        let predictor = match filter {
+            FILTER_NONE => 0,
+            FILTER_SUB => left,
+            FILTER_UP => up,
+            FILTER_AVERAGE => ((left as u16 + up as u16) / 2) as u8,
+            FILTER_PAETH => paeth(left, up, up_left),
+            _ => return Err(String::from(ERR_FILTER))
+        };
+
+        row[i] = row[i].wrapping_add(predictor);
+    }
+
+    return Ok(());
+}
+
+/// Picks whichever of the neighbours is closest to `left + up - up_left`.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
+
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        return left;
+    }
+
+    if distance_up <= distance_up_left {
+        return up;
+    }
+
+    return up_left;
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    push_u32_be(png, data.len() as u32);
+
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+
+    // The checksum covers the type and the data, but not the length.
+    let crc = crc32(&png[start..]);
+    push_u32_be(png, crc);
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib = Vec::with_capacity(data.len() + data.len() / MAX_STORED_BLOCK * 5 + 11);
+
+    // Deflate with a 32K window, no preset dictionary, fastest compression level.
+    zlib.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        // An empty final block.
+        zlib.extend_from_slice(&[1, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        zlib.push(is_final as u8);
+        zlib.extend_from_slice(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+        zlib.extend_from_slice(block);
+    }
+
+    push_u32_be(&mut zlib, adler32(data));
+
+    return zlib;
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(0xFFFFFFFF, |crc, byte| CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8));
+    return crc ^ 0xFFFFFFFF;
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    // Reducing once per 5552 bytes is the most that fits in 32 bits.
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+
+    return (b << 16) | a;
+}
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    return (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32;
+}
+
+fn push_u32_be(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+/// CRC-32 of each byte value with the reversed polynomial `0xEDB88320`.
+const CRC_TABLE: [u32; 256] = [
+    0x00000000, 0x77073096, 0xEE0E612C, 0x990951BA, 0x076DC419, 0x706AF48F, 0xE963A535, 0x9E6495A3,
+    0x0EDB8832, 0x79DCB8A4, 0xE0D5E91E, 0x97D2D988, 0x09B64C2B, 0x7EB17CBD, 0xE7B82D07, 0x90BF1D91,
+    0x1DB71064, 0x6AB020F2, 0xF3B97148, 0x84BE41DE, 0x1ADAD47D, 0x6DDDE4EB, 0xF4D4B551, 0x83D385C7,
+    0x136C9856, 0x646BA8C0, 0xFD62F97A, 0x8A65C9EC, 0x14015C4F, 0x63066CD9, 0xFA0F3D63, 0x8D080DF5,
+    0x3B6E20C8, 0x4C69105E, 0xD56041E4, 0xA2677172, 0x3C03E4D1, 0x4B04D447, 0xD20D85FD, 0xA50AB56B,
+    0x35B5A8FA, 0x42B2986C, 0xDBBBC9D6, 0xACBCF940, 0x32D86CE3, 0x45DF5C75, 0xDCD60DCF, 0xABD13D59,
+    0x26D930AC, 0x51DE003A, 0xC8D75180, 0xBFD06116, 0x21B4F4B5, 0x56B3C423, 0xCFBA9599, 0xB8BDA50F,
+    0x2802B89E, 0x5F058808, 0xC60CD9B2, 0xB10BE924, 0x2F6F7C87, 0x58684C11, 0xC1611DAB, 0xB6662D3D,
+    0x76DC4190, 0x01DB7106, 0x98D220BC, 0xEFD5102A, 0x71B18589, 0x06B6B51F, 0x9FBFE4A5, 0xE8B8D433,
+    0x7807C9A2, 0x0F00F934, 0x9609A88E, 0xE10E9818, 0x7F6A0DBB, 0x086D3D2D, 0x91646C97, 0xE6635C01,
+    0x6B6B51F4, 0x1C6C6162, 0x856530D8, 0xF262004E, 0x6C0695ED, 0x1B01A57B, 0x8208F4C1, 0xF50FC457,
+    0x65B0D9C6, 0x12B7E950, 0x8BBEB8EA, 0xFCB9887C, 0x62DD1DDF, 0x15DA2D49, 0x8CD37CF3, 0xFBD44C65,
+    0x4DB26158, 0x3AB551CE, 0xA3BC0074, 0xD4BB30E2, 0x4ADFA541, 0x3DD895D7, 0xA4D1C46D, 0xD3D6F4FB,
+    0x4369E96A, 0x346ED9FC, 0xAD678846, 0xDA60B8D0, 0x44042D73, 0x33031DE5, 0xAA0A4C5F, 0xDD0D7CC9,
+    0x5005713C, 0x270241AA, 0xBE0B1010, 0xC90C2086, 0x5768B525, 0x206F85B3, 0xB966D409, 0xCE61E49F,
+    0x5EDEF90E, 0x29D9C998, 0xB0D09822, 0xC7D7A8B4, 0x59B33D17, 0x2EB40D81, 0xB7BD5C3B, 0xC0BA6CAD,
+    0xEDB88320, 0x9ABFB3B6, 0x03B6E20C, 0x74B1D29A, 0xEAD54739, 0x9DD277AF, 0x04DB2615, 0x73DC1683,
+    0xE3630B12, 0x94643B84, 0x0D6D6A3E, 0x7A6A5AA8, 0xE40ECF0B, 0x9309FF9D, 0x0A00AE27, 0x7D079EB1,
+    0xF00F9344, 0x8708A3D2, 0x1E01F268, 0x6906C2FE, 0xF762575D, 0x806567CB, 0x196C3671, 0x6E6B06E7,
+    0xFED41B76, 0x89D32BE0, 0x10DA7A5A, 0x67DD4ACC, 0xF9B9DF6F, 0x8EBEEFF9, 0x17B7BE43, 0x60B08ED5,
+    0xD6D6A3E8, 0xA1D1937E, 0x38D8C2C4, 0x4FDFF252, 0xD1BB67F1, 0xA6BC5767, 0x3FB506DD, 0x48B2364B,
+    0xD80D2BDA, 0xAF0A1B4C, 0x36034AF6, 0x41047A60, 0xDF60EFC3, 0xA867DF55, 0x316E8EEF, 0x4669BE79,
+    0xCB61B38C, 0xBC66831A, 0x256FD2A0, 0x5268E236, 0xCC0C7795, 0xBB0B4703, 0x220216B9, 0x5505262F,
+    0xC5BA3BBE, 0xB2BD0B28, 0x2BB45A92, 0x5CB36A04, 0xC2D7FFA7, 0xB5D0CF31, 0x2CD99E8B, 0x5BDEAE1D,
+    0x9B64C2B0, 0xEC63F226, 0x756AA39C, 0x026D930A, 0x9C0906A9, 0xEB0E363F, 0x72076785, 0x05005713,
+    0x95BF4A82, 0xE2B87A14, 0x7BB12BAE, 0x0CB61B38, 0x92D28E9B, 0xE5D5BE0D, 0x7CDCEFB7, 0x0BDBDF21,
+    0x86D3D2D4, 0xF1D4E242, 0x68DDB3F8, 0x1FDA836E, 0x81BE16CD, 0xF6B9265B, 0x6FB077E1, 0x18B74777,
+    0x88085AE6, 0xFF0F6A70, 0x66063BCA, 0x11010B5C, 0x8F659EFF, 0xF862AE69, 0x616BFFD3, 0x166CCF45,
+    0xA00AE278, 0xD70DD2EE, 0x4E048354, 0x3903B3C2, 0xA7672661, 0xD06016F7, 0x4969474D, 0x3E6E77DB,
+    0xAED16A4A, 0xD9D65ADC, 0x40DF0B66, 0x37D83BF0, 0xA9BCAE53, 0xDEBB9EC5, 0x47B2CF7F, 0x30B5FFE9,
+    0xBDBDF21C, 0xCABAC28A, 0x53B39330, 0x24B4A3A6, 0xBAD03605, 0xCDD70693, 0x54DE5729, 0x23D967BF,
+    0xB3667A2E, 0xC4614AB8, 0x5D681B02, 0x2A6F2B94, 0xB40BBE37, 0xC30C8EA1, 0x5A05DF1B, 0x2D02EF8D,
+];
+
+/// Larger images are most likely corrupt.
+const MAX_DATA_SIZE: u64 = 1 << 30;
+
+const ERR_SIGNATURE: &'static str = "Not a PNG file";
+const ERR_TRUNCATED: &'static str = "PNG file ends unexpectedly";
+const ERR_CHUNK_CRC: &'static str = "PNG chunk checksum mismatch";
+const ERR_HEADER: &'static str = "Invalid PNG header";
+const ERR_INTERLACED: &'static str = "Interlaced PNG images are not supported";
+const ERR_PALETTE: &'static str = "Invalid PNG palette";
+const ERR_TRANSPARENCY: &'static str = "Invalid PNG transparency";
+const ERR_FILTER: &'static str = "Invalid PNG filter type";
+const ERR_TOO_LARGE: &'static str = "PNG image is too large";
@@ -0,0 +1,154 @@
+//! Frame sequence capture.
+
+use super::{Image, ImageFormat};
+
+use gliw::{Attachment, Framebuffer};
+
+use std::fs;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Dumps numbered frames while driving the game with a fixed simulated timestep.
+///
+/// Instead of the wall clock the game should advance by `timestep()` each frame and use `time()`
+/// as the current time, so the recorded sequence plays back at exactly `fps` no matter how long
+/// each frame took to render and save. The frames are encoded and written on a background thread.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::core::{FrameRecorder, ImageFormat};
+/// let mut recorder = FrameRecorder::new("capture", ImageFormat::Png, 60, 800, 600).unwrap();
+///
+/// while recorder.frame() < 600 {
+///     let time = recorder.time();
+///     // ...update the world with `time` and draw the scene
+///     recorder.capture();
+///     // ...swap the buffers
+/// }
+///
+/// let frames = recorder.finish().unwrap();
+/// ```
+pub struct FrameRecorder {
+    dir: String,
+    format: ImageFormat,
+    fps: u32,
+    width: i32,
+    height: i32,
+    frame: u32,
+    sender: Option<Sender<(String, Image)>>,
+    writer: Option<JoinHandle<Result<(), String>>>
+}
+
+impl FrameRecorder {
+    /// Creates a recorder writing `width` by `height` frames to `dir`, creating it if needed.
+    ///
+    /// # Panics
+    /// Panics if `fps` is zero.
+    pub fn new(dir: &str, format: ImageFormat, fps: u32, width: i32, height: i32) -> Result<FrameRecorder, String> {
+        if fps == 0 {
+            panic!(ERR_ZERO_FPS);
+        }
+
+        if let Err(err) = fs::create_dir_all(dir) {
+            return Err(format!("{} `{}`: {}", ERR_CREATE_DIR, dir, err));
+        }
+
+        let (sender, receiver) = mpsc::channel::<(String, Image)>();
+        let writer = thread::spawn(move || {
+            let mut result = Ok(());
+
+            // Keep draining after a failure so the render thread never blocks on a dead writer.
+            for (path, image) in receiver {
+                if result.is_ok() {
+                    result = image.save_as(&path, format);
+                }
+            }
+
+            return result;
+        });
+
+        return Ok(FrameRecorder {
+            dir: dir.to_string(),
+            format: format,
+            fps: fps,
+            width: width,
+            height: height,
+            frame: 0,
+            sender: Some(sender),
+            writer: Some(writer)
+        });
+    }
+
+    /// Get the number of captured frames, which is also the index of the next one.
+    pub fn frame(&self) -> u32 {
+        return self.frame;
+    }
+
+    /// Get the simulated time step between two frames in seconds.
+    pub fn timestep(&self) -> f64 {
+        return 1.0 / self.fps as f64;
+    }
+
+    /// Get the simulated time of the current frame in seconds.
+    pub fn time(&self) -> f64 {
+        return self.frame as f64 * self.timestep();
+    }
+
+    /// Captures the back buffer of the default framebuffer as the next frame.
+    ///
+    /// Should be called after drawing and before swapping the buffers.
+    pub fn capture(&mut self) {
+        let image = Image::capture_default(0, 0, self.width, self.height);
+        self.push(image);
+    }
+
+    /// Captures the color `attachment` of `framebuffer` as the next frame.
+    pub fn capture_from(&mut self, framebuffer: &Framebuffer, attachment: Attachment) {
+        let image = Image::capture(framebuffer, attachment, 0, 0, self.width, self.height);
+        self.push(image);
+    }
+
+    /// Waits for all frames to be written and returns their count.
+    ///
+    /// Fails with the first error that occurred while writing.
+    pub fn finish(mut self) -> Result<u32, String> {
+        return match self.join() {
+            Ok(_) => Ok(self.frame),
+            Err(err) => Err(err)
+        };
+    }
+
+    fn push(&mut self, image: Image) {
+        let path = format!("{}/frame_{:05}.{}", self.dir, self.frame, self.format.extension());
+        self.frame += 1;
+
+        if let Some(ref sender) = self.sender {
+            // The writer only hangs up if it panicked, which `join` reports.
+            let _ = sender.send((path, image));
+        }
+    }
+
+    fn join(&mut self) -> Result<(), String> {
+        // Closing the channel ends the writer loop.
+        self.sender = None;
+
+        return match self.writer.take() {
+            Some(writer) => match writer.join() {
+                Ok(result) => result,
+                Err(_) => Err(ERR_WRITER_PANICKED.to_string())
+            },
+            None => Ok(())
+        };
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+const ERR_ZERO_FPS: &'static str = "Frame recorder fps must be greater than zero";
+const ERR_CREATE_DIR: &'static str = "Failed to create the frame directory";
+const ERR_WRITER_PANICKED: &'static str = "Frame writer thread panicked";
@@ -0,0 +1,143 @@
+extern crate cgmath;
+
+use self::cgmath::Vector2;
+
+use std::collections::HashSet;
+
+/// Abstract controls, bound to keys or mouse buttons by the game, see `InputState`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Control {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    RotateLeft,
+    RotateRight,
+    /// Rotates the view with the cursor while held, e.g. bound to the right mouse button.
+    Look,
+    /// Speeds up the movement while held.
+    Boost
+}
+
+/// Snapshot of the input of a frame which drives the `CameraController`s.
+///
+/// The engine doesn't depend on a windowing library, so the game forwards its window events
+/// to the state and calls `end_frame` after the controllers are updated, which resets
+/// the per frame cursor movement and scrolling.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::core::{InputState, Control};
+/// let mut input = InputState::new(800, 600);
+///
+/// // From the window events.
+/// input.set_control(Control::MoveForward, true);
+/// input.move_cursor(400.0, 300.0);
+/// input.scroll(1.0);
+///
+/// // Once per frame, after updating the controllers.
+/// input.end_frame();
+/// ```
+pub struct InputState {
+    controls: HashSet<Control>,
+    cursor: Option<(f32, f32)>,
+    cursor_delta: Vector2<f32>,
+    scroll: f32,
+    viewport: (i32, i32)
+}
+
+impl InputState {
+    /// Create a state with nothing held for a viewport of the given size.
+    pub fn new(width: i32, height: i32) -> InputState {
+        return InputState {
+            controls: HashSet::new(),
+            cursor: None,
+            cursor_delta: Vector2::new(0.0, 0.0),
+            scroll: 0.0,
+            viewport: (width, height)
+        };
+    }
+
+    /// Set whether `control` is held.
+    pub fn set_control(&mut self, control: Control, held: bool) {
+        if held {
+            self.controls.insert(control);
+        } else {
+            self.controls.remove(&control);
+        }
+    }
+
+    /// Check if `control` is held.
+    pub fn is_held(&self, control: Control) -> bool {
+        return self.controls.contains(&control);
+    }
+
+    /// Get `1` if only `positive` is held, `-1` if only `negative` is, `0` otherwise.
+    pub fn axis(&self, negative: Control, positive: Control) -> f32 {
+        let mut value = 0.0;
+
+        if self.is_held(positive) {
+            value += 1.0;
+        }
+        if self.is_held(negative) {
+            value -= 1.0;
+        }
+
+        return value;
+    }
+
+    /// Update the cursor position in window coordinates, starting from the top left corner.
+    ///
+    /// The movement since the previous position is accumulated until `end_frame`.
+    pub fn move_cursor(&mut self, x: f32, y: f32) {
+        if let Some((previous_x, previous_y)) = self.cursor {
+            self.cursor_delta += Vector2::new(x - previous_x, y - previous_y);
+        }
+
+        self.cursor = Some((x, y));
+    }
+
+    /// Forget the cursor position, e.g. when it leaves the window.
+    pub fn leave_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Get the cursor position. `None` if it's outside of the window or wasn't moved yet.
+    pub fn cursor(&self) -> Option<(f32, f32)> {
+        return self.cursor;
+    }
+
+    /// Get how much the cursor moved this frame, in pixels.
+    pub fn cursor_delta(&self) -> Vector2<f32> {
+        return self.cursor_delta;
+    }
+
+    /// Accumulate the scroll wheel's offset, positive away from the user.
+    pub fn scroll(&mut self, offset: f32) {
+        self.scroll += offset;
+    }
+
+    /// Get how much the scroll wheel moved this frame.
+    pub fn scroll_delta(&self) -> f32 {
+        return self.scroll;
+    }
+
+    /// Update the viewport size, e.g. on a window resize event.
+    pub fn set_viewport_size(&mut self, width: i32, height: i32) {
+        self.viewport = (width, height);
+    }
+
+    /// Get the viewport size.
+    pub fn viewport_size(&self) -> (i32, i32) {
+        return self.viewport;
+    }
+
+    /// Resets the cursor movement and the scrolling of the frame.
+    pub fn end_frame(&mut self) {
+        self.cursor_delta = Vector2::new(0.0, 0.0);
+        self.scroll = 0.0;
+    }
+}
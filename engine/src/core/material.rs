@@ -0,0 +1,91 @@
+extern crate cgmath;
+
+use self::cgmath::{Vector3, Vector4};
+
+use gliw::{Program, Texture, Uniform, UniformData};
+
+use std::rc::Rc;
+
+/// Surface properties for Blinn-Phong shading.
+///
+/// Shaders get them through the uniforms declared in `MATERIAL_GLSL`, see `Material::apply`.
+#[derive(Clone)]
+pub struct Material {
+    /// RGBA color, multiplied with the texture if there is one.
+    pub base_color: Vector4<f32>,
+    pub texture: Option<Rc<Texture>>,
+    /// Intensity of the specular highlights, in the range [0, 1].
+    pub specular: f32,
+    /// Specular exponent, in the range [1, 256]. Higher values give smaller, sharper highlights.
+    pub shininess: f32,
+    /// Light emitted by the surface itself, unaffected by the lights.
+    pub emissive: Vector3<f32>
+}
+
+impl Material {
+    /// Create an untextured material with moderate highlights and no emission.
+    pub fn new(base_color: Vector4<f32>) -> Material {
+        return Material {
+            base_color: base_color,
+            texture: None,
+            specular: 0.5,
+            shininess: 32.0,
+            emissive: Vector3::new(0.0, 0.0, 0.0)
+        };
+    }
+
+    /// Create a white material with the given `texture`.
+    pub fn textured(texture: Rc<Texture>) -> Material {
+        let mut material = Material::new(Vector4::new(1.0, 1.0, 1.0, 1.0));
+        material.texture = Some(texture);
+
+        return material;
+    }
+
+    /// Whether the base color is translucent.
+    ///
+    /// The texture's alpha is not taken into account.
+    pub fn is_transparent(&self) -> bool {
+        return self.base_color.w < 1.0;
+    }
+
+    /// Passes the material to the uniforms of `program` declared in `MATERIAL_GLSL`.
+    ///
+    /// The texture is bound to `tex_unit`.
+    pub fn apply(&self, program: &Rc<Program>, tex_unit: u32) {
+        let color = self.base_color;
+        Uniform::new(program, "material_base_color").value(UniformData::Float4(color.x, color.y, color.z, color.w));
+        Uniform::new(program, "material_specular").value(UniformData::Float1(self.specular));
+        Uniform::new(program, "material_shininess").value(UniformData::Float1(self.shininess));
+        Uniform::new(program, "material_emissive").value(UniformData::Float3(self.emissive.x, self.emissive.y, self.emissive.z));
+
+        match self.texture {
+            Some(ref texture) => {
+                texture.pass_to(program, "material_texture", tex_unit);
+                Uniform::new(program, "material_has_texture").value(UniformData::Int1(1));
+            },
+            None => {
+                Uniform::new(program, "material_has_texture").value(UniformData::Int1(0));
+            }
+        }
+    }
+}
+
+/// GLSL declarations of the `Material` uniforms, to be placed after the `#version` directive.
+///
+/// `material_albedo(uv)` gives the base color multiplied by the texture.
+pub const MATERIAL_GLSL: &'static str = r#"
+    uniform vec4 material_base_color;
+    uniform int material_has_texture;
+    uniform sampler2D material_texture;
+    uniform float material_specular;
+    uniform float material_shininess;
+    uniform vec3 material_emissive;
+
+    vec4 material_albedo(vec2 uv) {
+        if (material_has_texture != 0) {
+            return material_base_color * texture(material_texture, uv);
+        }
+        return material_base_color;
+    }
+"#;
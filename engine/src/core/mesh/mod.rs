@@ -0,0 +1,257 @@
+extern crate cgmath;
+
+pub mod obj;
+mod primitives;
+
+pub use self::primitives::Primitive;
+
+use self::cgmath::{EuclideanSpace, Vector2, Vector3, Vector4, Point3, InnerSpace};
+
+use gliw::{
+    gl,
+    Buffer, BufferType, BufferUsagePattern,
+    Vao, VertexAttrib, AttribFloatFormat
+};
+
+use core::Material;
+
+use math::{Aabb, Ray, RayHit};
+
+use std::mem;
+use std::path::Path;
+use std::ptr;
+
+/// CPU side triangle mesh.
+///
+/// The vertex attributes are stored in separate arrays indexed by `indices`, three per triangle.
+/// `normals`, `uvs` and `colors` are optional and can be left empty,
+/// otherwise they must have as many elements as `positions`.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub positions: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    pub uvs: Vec<Vector2<f32>>,
+    pub colors: Vec<Vector4<f32>>,
+    pub indices: Vec<u32>
+}
+
+impl Mesh {
+    /// Create an empty mesh.
+    pub fn new() -> Mesh {
+        return Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            indices: Vec::new()
+        };
+    }
+
+    /// Get the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        return self.positions.len();
+    }
+
+    /// Get the number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        return self.indices.len() / 3;
+    }
+
+    /// Get the box containing all positions. `None` if there are none.
+    pub fn bounds(&self) -> Option<Aabb> {
+        let points: Vec<Point3<f32>> = self.positions.iter().map(|&p| Point3::from_vec(p)).collect();
+
+        return Aabb::from_points(&points);
+    }
+
+    /// Replaces the normals with smooth ones, averaged from the adjacent triangles
+    /// and weighted by their area.
+    ///
+    /// # Panics
+    /// Panics if an index is out of range.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vector3::new(0.0, 0.0, 0.0); self.positions.len()];
+
+        for triangle in self.indices.chunks(3) {
+            if triangle.len() < 3 {
+                break;
+            }
+
+            let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+            if a >= normals.len() || b >= normals.len() || c >= normals.len() {
+                panic!(ERR_INDEX_RANGE);
+            }
+
+            // The cross product's length is twice the triangle's area.
+            let normal = (self.positions[b] - self.positions[a]).cross(self.positions[c] - self.positions[a]);
+            normals[a] += normal;
+            normals[b] += normal;
+            normals[c] += normal;
+        }
+
+        self.normals = normals.into_iter().map(|normal| {
+            if normal.magnitude2() > 0.0 { normal.normalize() } else { normal }
+        }).collect();
+    }
+
+    /// Get the closest triangle hit by `ray`, with the triangle's normal facing the ray.
+    ///
+    /// Tests every triangle after the bounds, so it's meant for picking rather than for
+    /// many rays per frame.
+    ///
+    /// # Panics
+    /// Panics if an index is out of range.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<RayHit> {
+        match self.bounds() {
+            Some(bounds) => if ray.intersect_aabb(&bounds).is_none() { return None; },
+            None => return None
+        }
+
+        let mut closest: Option<RayHit> = None;
+
+        for triangle in self.indices.chunks(3) {
+            if triangle.len() < 3 {
+                break;
+            }
+
+            let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+            if a >= self.positions.len() || b >= self.positions.len() || c >= self.positions.len() {
+                panic!(ERR_INDEX_RANGE);
+            }
+
+            let hit = ray.hit_triangle(
+                Point3::from_vec(self.positions[a]),
+                Point3::from_vec(self.positions[b]),
+                Point3::from_vec(self.positions[c]));
+
+            if let Some(mut hit) = hit {
+                if closest.map_or(true, |closest| hit.distance < closest.distance) {
+                    if hit.normal.dot(ray.direction) > 0.0 {
+                        hit.normal = -hit.normal;
+                    }
+                    closest = Some(hit);
+                }
+            }
+        }
+
+        return closest;
+    }
+
+    /// Uploads the mesh to the GPU.
+    ///
+    /// The vertices are interleaved with the attribute locations:
+    ///
+    /// * `0` - `vec3` position.
+    /// * `1` - `vec3` normal, zero if there are none.
+    /// * `2` - `vec2` texture coordinates, zero if there are none.
+    /// * `3` - `vec4` color, white if there are none.
+    ///
+    /// # Panics
+    /// Panics if the attribute arrays have different lengths or an index is out of range.
+    pub fn upload(&self) -> GpuMesh {
+        let count = self.positions.len();
+
+        if (!self.normals.is_empty() && self.normals.len() != count) ||
+           (!self.uvs.is_empty() && self.uvs.len() != count) ||
+           (!self.colors.is_empty() && self.colors.len() != count) {
+            panic!(ERR_ATTRIBUTE_COUNT);
+        }
+
+        if self.indices.iter().any(|&index| index as usize >= count) {
+            panic!(ERR_INDEX_RANGE);
+        }
+
+        let mut vertices = Vec::with_capacity(count * VERTEX_SIZE);
+        for i in 0..count {
+            let position = self.positions[i];
+            let normal = self.normals.get(i).cloned().unwrap_or(Vector3::new(0.0, 0.0, 0.0));
+            let uv = self.uvs.get(i).cloned().unwrap_or(Vector2::new(0.0, 0.0));
+            let color = self.colors.get(i).cloned().unwrap_or(Vector4::new(1.0, 1.0, 1.0, 1.0));
+
+            vertices.extend_from_slice(&[
+                position.x, position.y, position.z,
+                normal.x, normal.y, normal.z,
+                uv.x, uv.y,
+                color.x, color.y, color.z, color.w
+            ]);
+        }
+
+        let vao = Vao::new();
+        let vbo = Buffer::from_data(&vertices, BufferType::Array, BufferUsagePattern::StaticDraw);
+        let ebo = Buffer::from_data(&self.indices, BufferType::ElementArray, BufferUsagePattern::StaticDraw);
+
+        let stride = (VERTEX_SIZE * mem::size_of::<f32>()) as i32;
+        let mut offset = 0;
+
+        for (location, &size) in [3, 3, 2, 4].iter().enumerate() {
+            let va = VertexAttrib::new(location as i32);
+            va.data_float_format(&vao, &vbo, AttribFloatFormat::Float(size), stride,
+                (offset * mem::size_of::<f32>()) as *const _);
+            va.enable(&vao);
+
+            offset += size as usize;
+        }
+
+        return GpuMesh {
+            vao: vao,
+            vbo: vbo,
+            ebo: ebo,
+            index_count: self.indices.len() as i32
+        };
+    }
+}
+
+/// A `Mesh` uploaded to the GPU, see `Mesh::upload`.
+pub struct GpuMesh {
+    vao: Vao,
+    #[allow(dead_code)]
+    vbo: Buffer,
+    ebo: Buffer,
+    index_count: i32
+}
+
+impl GpuMesh {
+    /// Get the number of indices drawn.
+    pub fn index_count(&self) -> i32 {
+        return self.index_count;
+    }
+
+    /// Draws the triangles with the bound program.
+    pub fn draw(&self) {
+        self.vao.bind();
+        self.ebo.bind();
+
+        unsafe { gl::DrawElements(gl::TRIANGLES, self.index_count, gl::UNSIGNED_INT, ptr::null()); }
+    }
+}
+
+/// A named `Mesh` and the `Material` it is drawn with.
+#[derive(Clone)]
+pub struct ModelPart {
+    pub name: String,
+    pub mesh: Mesh,
+    pub material: Material
+}
+
+/// A model made of one or more parts, e.g. loaded from a file.
+///
+/// See `MeshRenderable::from_model` for drawing it.
+#[derive(Clone)]
+pub struct Model {
+    pub parts: Vec<ModelPart>
+}
+
+impl Model {
+    /// Loads a Wavefront OBJ file along with the MTL files it references.
+    ///
+    /// See `obj::parse` for the supported features.
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Model, String> {
+        return obj::load(path.as_ref());
+    }
+}
+
+/// Position, normal, texture coordinates and color.
+const VERTEX_SIZE: usize = 3 + 3 + 2 + 4;
+
+const ERR_ATTRIBUTE_COUNT: &'static str = "Mesh attributes must be empty or have as many elements as the positions";
+const ERR_INDEX_RANGE: &'static str = "Mesh index out of range";
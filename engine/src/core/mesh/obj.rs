@@ -0,0 +1,427 @@
+//! Wavefront OBJ and MTL loading.
+//!
+//! # References
+//! * [Object Files (.obj)](http://paulbourke.net/dataformats/obj/)
+//! * [MTL material format](http://paulbourke.net/dataformats/mtl/)
+
+extern crate cgmath;
+
+use self::cgmath::{Vector2, Vector3, Vector4};
+
+use gliw::{gl, Texture};
+
+use core::{Image, ImageFormat, Material};
+
+use super::{Mesh, Model, ModelPart};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Loads an OBJ file, resolving the MTL files and textures relative to its directory.
+pub fn load(path: &Path) -> Result<Model, String> {
+    let src = match read_file(path) {
+        Ok(src) => src,
+        Err(err) => return Err(err)
+    };
+
+    return parse(&src, path.parent().unwrap_or(Path::new("")));
+}
+
+/// Parses the contents of an OBJ file, resolving the MTL files and textures relative to `dir`.
+///
+/// Supports:
+///
+/// * `v` with optional vertex colors (`v x y z r g b`), `vt` and `vn`.
+/// * `f` polygons with any of the `v`, `v/vt`, `v//vn` and `v/vt/vn` forms
+/// and negative (relative) indices. Polygons are triangulated as fans, so they should be convex.
+/// * `o`, `g` and `usemtl`, each starting a new `ModelPart`.
+/// * `mtllib`, see `parse_mtl`.
+///
+/// Other statements are ignored. Parts without normals get smooth ones, see `Mesh::compute_normals`.
+/// Parts using an unknown material get a white one.
+pub fn parse(src: &str, dir: &Path) -> Result<Model, String> {
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut materials = HashMap::new();
+    let mut builder = PartBuilder::new("default".to_string(), default_material());
+    let mut parts = Vec::new();
+
+    for (number, line) in src.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        let invalid = || format!("{} {}: {}", ERR_OBJ_STATEMENT, number + 1, line);
+
+        match keyword {
+            "v" => {
+                let values = match parse_floats(&args) {
+                    Some(ref values) if values.len() >= 3 => values.clone(),
+                    _ => return Err(invalid())
+                };
+
+                positions.push(Vector3::new(values[0], values[1], values[2]));
+                if values.len() >= 6 {
+                    colors.push(Some(Vector4::new(values[3], values[4], values[5], 1.0)));
+                } else {
+                    colors.push(None);
+                }
+            },
+            "vt" => {
+                match parse_floats(&args) {
+                    Some(ref values) if values.len() >= 2 => uvs.push(Vector2::new(values[0], values[1])),
+                    Some(ref values) if values.len() == 1 => uvs.push(Vector2::new(values[0], 0.0)),
+                    _ => return Err(invalid())
+                }
+            },
+            "vn" => {
+                match parse_floats(&args) {
+                    Some(ref values) if values.len() >= 3 => normals.push(Vector3::new(values[0], values[1], values[2])),
+                    _ => return Err(invalid())
+                }
+            },
+            "f" => {
+                if args.len() < 3 {
+                    return Err(invalid());
+                }
+
+                let mut polygon = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    match parse_vertex(arg, positions.len(), uvs.len(), normals.len()) {
+                        Some(vertex) => polygon.push(builder.vertex(vertex, &positions, &colors, &uvs, &normals)),
+                        None => return Err(invalid())
+                    }
+                }
+
+                for i in 1..polygon.len() - 1 {
+                    builder.mesh.indices.extend_from_slice(&[polygon[0], polygon[i], polygon[i + 1]]);
+                }
+            },
+            "o" | "g" => {
+                let name = if args.is_empty() { "default".to_string() } else { args.join(" ") };
+                let material = builder.material.clone();
+                builder.finish(&mut parts);
+                builder = PartBuilder::new(name, material);
+            },
+            "usemtl" => {
+                let material = materials.get(&args.join(" ")).cloned().unwrap_or(default_material());
+                let name = builder.name.clone();
+                builder.finish(&mut parts);
+                builder = PartBuilder::new(name, material);
+            },
+            "mtllib" => {
+                for file in args.iter() {
+                    let mtl_path = dir.join(file);
+                    let loaded = read_file(&mtl_path).and_then(|mtl_src| parse_mtl(&mtl_src, dir));
+
+                    match loaded {
+                        Ok(loaded) => materials.extend(loaded),
+                        Err(err) => return Err(err)
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    builder.finish(&mut parts);
+
+    return Ok(Model { parts: parts });
+}
+
+/// Parses the contents of an MTL file, loading the textures relative to `dir`.
+///
+/// The statements map to the `Material` as follows:
+///
+/// * `Kd` - base color, `d` or `Tr` - its alpha.
+/// * `Ks` - specular intensity, the average of the components.
+/// * `Ns` - shininess, clamped to [1, 256].
+/// * `Ke` - emissive color.
+/// * `map_Kd` - texture. PNG and BMP images are supported, materials with others, e.g. JPEG, are left untextured.
+///
+/// Other statements are ignored.
+pub fn parse_mtl(src: &str, dir: &Path) -> Result<HashMap<String, Material>, String> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+
+    for (number, line) in src.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        let invalid = || format!("{} {}: {}", ERR_MTL_STATEMENT, number + 1, line);
+
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+
+            current = Some((args.join(" "), default_material()));
+            continue;
+        }
+
+        let material = match current {
+            Some((_, ref mut material)) => material,
+            // Statements before the first material have nothing to apply to.
+            None => continue
+        };
+
+        let values = parse_floats(&args);
+
+        match keyword {
+            "Kd" => {
+                match values {
+                    Some(ref values) if values.len() >= 3 => {
+                        material.base_color = Vector4::new(values[0], values[1], values[2], material.base_color.w);
+                    },
+                    _ => return Err(invalid())
+                }
+            },
+            "d" | "Tr" => {
+                match values {
+                    Some(ref values) if values.len() == 1 => {
+                        material.base_color.w = if keyword == "d" { values[0] } else { 1.0 - values[0] };
+                    },
+                    _ => return Err(invalid())
+                }
+            },
+            "Ks" => {
+                match values {
+                    Some(ref values) if values.len() >= 3 => {
+                        material.specular = (values[0] + values[1] + values[2]) / 3.0;
+                    },
+                    _ => return Err(invalid())
+                }
+            },
+            "Ns" => {
+                match values {
+                    Some(ref values) if values.len() == 1 => material.shininess = values[0].max(1.0).min(256.0),
+                    _ => return Err(invalid())
+                }
+            },
+            "Ke" => {
+                match values {
+                    Some(ref values) if values.len() >= 3 => {
+                        material.emissive = Vector3::new(values[0], values[1], values[2]);
+                    },
+                    _ => return Err(invalid())
+                }
+            },
+            "map_Kd" => {
+                // Options like `-s 1 1 1` come before the file name.
+                let file = match args.last() {
+                    Some(file) => file,
+                    None => return Err(invalid())
+                };
+
+                let path = dir.join(file);
+                let mut data = Vec::new();
+
+                if let Err(err) = File::open(&path).and_then(|mut file| file.read_to_end(&mut data)) {
+                    return Err(format!("{}: {}", path.display(), err));
+                }
+
+                if ImageFormat::from_signature(&data).is_some() {
+                    match Image::decode(&data) {
+                        Ok(image) => material.texture = Some(Rc::new(upload_texture(&image))),
+                        Err(err) => return Err(format!("{}: {}", path.display(), err))
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current.take() {
+        materials.insert(name, material);
+    }
+
+    return Ok(materials);
+}
+
+/// Zero based position, texture coordinates and normal indices of a face vertex.
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// Accumulates the vertices of a part, merging the face vertices with the same indices.
+struct PartBuilder {
+    name: String,
+    material: Material,
+    mesh: Mesh,
+    vertices: HashMap<VertexKey, u32>,
+    has_colors: bool,
+    has_uvs: bool,
+    has_normals: bool,
+    missing_normals: bool
+}
+
+impl PartBuilder {
+    fn new(name: String, material: Material) -> PartBuilder {
+        return PartBuilder {
+            name: name,
+            material: material,
+            mesh: Mesh::new(),
+            vertices: HashMap::new(),
+            has_colors: false,
+            has_uvs: false,
+            has_normals: false,
+            missing_normals: false
+        };
+    }
+
+    fn vertex(&mut self, key: VertexKey,
+              positions: &[Vector3<f32>], colors: &[Option<Vector4<f32>>],
+              uvs: &[Vector2<f32>], normals: &[Vector3<f32>]) -> u32
+    {
+        if let Some(&index) = self.vertices.get(&key) {
+            return index;
+        }
+
+        let (position, uv, normal) = key;
+        let index = self.mesh.positions.len() as u32;
+
+        self.mesh.positions.push(positions[position]);
+
+        let color = colors[position];
+        self.has_colors |= color.is_some();
+        self.mesh.colors.push(color.unwrap_or(Vector4::new(1.0, 1.0, 1.0, 1.0)));
+
+        self.has_uvs |= uv.is_some();
+        self.mesh.uvs.push(uv.map_or(Vector2::new(0.0, 0.0), |uv| uvs[uv]));
+
+        self.has_normals |= normal.is_some();
+        self.missing_normals |= normal.is_none();
+        self.mesh.normals.push(normal.map_or(Vector3::new(0.0, 0.0, 0.0), |normal| normals[normal]));
+
+        self.vertices.insert(key, index);
+
+        return index;
+    }
+
+    fn finish(mut self, parts: &mut Vec<ModelPart>) {
+        if self.mesh.indices.is_empty() {
+            return;
+        }
+
+        if !self.has_colors {
+            self.mesh.colors.clear();
+        }
+
+        if !self.has_uvs {
+            self.mesh.uvs.clear();
+        }
+
+        if !self.has_normals || self.missing_normals {
+            self.mesh.compute_normals();
+        }
+
+        parts.push(ModelPart {
+            name: self.name,
+            mesh: self.mesh,
+            material: self.material
+        });
+    }
+}
+
+fn default_material() -> Material {
+    return Material::new(Vector4::new(1.0, 1.0, 1.0, 1.0));
+}
+
+/// Uploads a diffuse texture, repeating and mipmapped.
+fn upload_texture(image: &Image) -> Texture {
+    let texture = image.to_texture();
+
+    unsafe {
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+    }
+
+    return texture;
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    let mut src = String::new();
+
+    return match File::open(path).and_then(|mut file| file.read_to_string(&mut src)) {
+        Ok(_) => Ok(src),
+        Err(err) => Err(format!("{}: {}", path.display(), err))
+    };
+}
+
+fn parse_floats(args: &[&str]) -> Option<Vec<f32>> {
+    let mut values = Vec::with_capacity(args.len());
+
+    for arg in args.iter() {
+        match arg.parse::<f32>() {
+            Ok(value) => values.push(value),
+            Err(_) => return None
+        }
+    }
+
+    return Some(values);
+}
+
+/// Parses a face vertex, e.g. `1/2/3`, into zero based indices.
+fn parse_vertex(arg: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Option<VertexKey> {
+    let mut indices = arg.split('/');
+
+    let position = match indices.next().and_then(|index| resolve_index(index, position_count)) {
+        Some(position) => position,
+        None => return None
+    };
+
+    let uv = match indices.next() {
+        Some("") | None => None,
+        Some(index) => match resolve_index(index, uv_count) {
+            Some(uv) => Some(uv),
+            None => return None
+        }
+    };
+
+    let normal = match indices.next() {
+        Some("") | None => None,
+        Some(index) => match resolve_index(index, normal_count) {
+            Some(normal) => Some(normal),
+            None => return None
+        }
+    };
+
+    return Some((position, uv, normal));
+}
+
+/// Resolves a one based or negative (counting back from the last element) index.
+fn resolve_index(index: &str, count: usize) -> Option<usize> {
+    let index = match index.parse::<i64>() {
+        Ok(index) => index,
+        Err(_) => return None
+    };
+
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return None;
+    }
+
+    return Some(resolved as usize);
+}
+
+const ERR_OBJ_STATEMENT: &'static str = "Invalid OBJ statement at line";
+const ERR_MTL_STATEMENT: &'static str = "Invalid MTL statement at line";
@@ -0,0 +1,396 @@
+extern crate cgmath;
+
+use self::cgmath::{Vector2, Vector3, InnerSpace};
+
+use super::Mesh;
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+/// Parameters of a procedurally generated `Mesh`.
+///
+/// All shapes are centered at the origin with Y as their axis, except `Arrow`
+/// which points from the origin along +Y.
+///
+/// See `MeshRenderable::primitive`.
+#[derive(Copy, Clone, Debug)]
+pub enum Primitive {
+    /// See `Mesh::uv_sphere`.
+    UvSphere { radius: f32, segments: u32, rings: u32 },
+    /// See `Mesh::icosphere`.
+    Icosphere { radius: f32, subdivisions: u32 },
+    /// See `Mesh::cylinder`.
+    Cylinder { radius: f32, height: f32, segments: u32 },
+    /// See `Mesh::cone`.
+    Cone { radius: f32, height: f32, segments: u32 },
+    /// See `Mesh::capsule`.
+    Capsule { radius: f32, height: f32, segments: u32, rings: u32 },
+    /// See `Mesh::torus`.
+    Torus { radius: f32, tube_radius: f32, segments: u32, sides: u32 },
+    /// See `Mesh::plane`.
+    Plane { width: f32, depth: f32, x_segments: u32, z_segments: u32 },
+    /// See `Mesh::arrow`.
+    Arrow { length: f32, shaft_radius: f32, head_radius: f32, head_length: f32, segments: u32 }
+}
+
+impl Primitive {
+    /// Generates the mesh.
+    ///
+    /// # Panics
+    /// Same as the `Mesh` generator of the primitive.
+    pub fn mesh(&self) -> Mesh {
+        return match *self {
+            Primitive::UvSphere { radius, segments, rings } => Mesh::uv_sphere(radius, segments, rings),
+            Primitive::Icosphere { radius, subdivisions } => Mesh::icosphere(radius, subdivisions),
+            Primitive::Cylinder { radius, height, segments } => Mesh::cylinder(radius, height, segments),
+            Primitive::Cone { radius, height, segments } => Mesh::cone(radius, height, segments),
+            Primitive::Capsule { radius, height, segments, rings } => Mesh::capsule(radius, height, segments, rings),
+            Primitive::Torus { radius, tube_radius, segments, sides } => Mesh::torus(radius, tube_radius, segments, sides),
+            Primitive::Plane { width, depth, x_segments, z_segments } => Mesh::plane(width, depth, x_segments, z_segments),
+            Primitive::Arrow { length, shaft_radius, head_radius, head_length, segments } =>
+                Mesh::arrow(length, shaft_radius, head_radius, head_length, segments)
+        };
+    }
+}
+
+impl Mesh {
+    /// Generates a sphere from `rings` stacks of `segments` quads, like the lines of latitude
+    /// and longitude of a globe.
+    ///
+    /// The texture wraps around once horizontally and goes from the bottom to the top pole.
+    ///
+    /// # Panics
+    /// Panics if `segments` is less than 3 or `rings` is less than 2.
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+        check_tessellation(segments, 3);
+        check_tessellation(rings, 2);
+
+        let profile: Vec<ProfilePoint> = (0..rings + 1).map(|ring| {
+            let angle = PI * ring as f32 / rings as f32 - PI * 0.5;
+            ProfilePoint::new(radius * angle.cos(), radius * angle.sin(), angle.cos(), angle.sin())
+        }).collect();
+
+        let mut mesh = Mesh::new();
+        revolve(&mut mesh, &profile, segments);
+
+        return mesh;
+    }
+
+    /// Generates a sphere by subdividing an icosahedron `subdivisions` times,
+    /// giving evenly sized triangles.
+    ///
+    /// Each subdivision quadruples the 20 triangles of the icosahedron.
+    /// The texture coordinates are mapped like `uv_sphere`'s, so they are
+    /// stretched on the triangles crossing the seam at -Z.
+    ///
+    /// # Panics
+    /// Panics if `subdivisions` is greater than 8.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Mesh {
+        if subdivisions > 8 {
+            panic!(ERR_SUBDIVISIONS);
+        }
+
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut positions: Vec<Vector3<f32>> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0)
+        ].iter().map(|&(x, y, z)| Vector3::new(x, y, z).normalize()).collect();
+
+        let mut indices: Vec<u32> = vec![
+            0, 11, 5,   0, 5, 1,    0, 1, 7,    0, 7, 10,   0, 10, 11,
+            1, 5, 9,    5, 11, 4,   11, 10, 2,  10, 7, 6,   7, 1, 8,
+            3, 9, 4,    3, 4, 2,    3, 2, 6,    3, 6, 8,    3, 8, 9,
+            4, 9, 5,    2, 4, 11,   6, 2, 10,   8, 6, 7,    9, 8, 1
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut subdivided = Vec::with_capacity(indices.len() * 4);
+
+            {
+                let mut midpoint = |a: u32, b: u32| {
+                    let key = if a < b { (a, b) } else { (b, a) };
+
+                    return *midpoints.entry(key).or_insert_with(|| {
+                        let point = (positions[a as usize] + positions[b as usize]).normalize();
+                        positions.push(point);
+                        (positions.len() - 1) as u32
+                    });
+                };
+
+                for triangle in indices.chunks(3) {
+                    let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+                    let ab = midpoint(a, b);
+                    let bc = midpoint(b, c);
+                    let ca = midpoint(c, a);
+
+                    subdivided.extend_from_slice(&[a, ab, ca, b, bc, ab, c, ca, bc, ab, bc, ca]);
+                }
+            }
+
+            indices = subdivided;
+        }
+
+        let mut mesh = Mesh::new();
+        mesh.uvs = positions.iter().map(|p| {
+            Vector2::new((-p.z).atan2(p.x) / (2.0 * PI) + 0.5, p.y.max(-1.0).min(1.0).asin() / PI + 0.5)
+        }).collect();
+        mesh.normals = positions.clone();
+        mesh.positions = positions.into_iter().map(|p| p * radius).collect();
+        mesh.indices = indices;
+
+        return mesh;
+    }
+
+    /// Generates a closed cylinder.
+    ///
+    /// # Panics
+    /// Panics if `segments` is less than 3.
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Mesh {
+        check_tessellation(segments, 3);
+
+        let half = height * 0.5;
+        let mut mesh = Mesh::new();
+
+        revolve(&mut mesh, &[
+            ProfilePoint::new(radius, -half, 1.0, 0.0),
+            ProfilePoint::new(radius, half, 1.0, 0.0)
+        ], segments);
+        disk(&mut mesh, radius, -half, segments);
+        disk(&mut mesh, radius, half, segments);
+
+        return mesh;
+    }
+
+    /// Generates a cone with its base at the bottom and its tip at the top.
+    ///
+    /// # Panics
+    /// Panics if `segments` is less than 3.
+    pub fn cone(radius: f32, height: f32, segments: u32) -> Mesh {
+        check_tessellation(segments, 3);
+
+        let half = height * 0.5;
+        let mut mesh = Mesh::new();
+
+        cone_side(&mut mesh, radius, -half, half, segments);
+        disk(&mut mesh, radius, -half, segments);
+
+        return mesh;
+    }
+
+    /// Generates a cylinder of the given `height` with a hemisphere on each end,
+    /// so the total height is `height + 2 * radius`.
+    ///
+    /// `rings` is the number of stacks of each hemisphere.
+    ///
+    /// # Panics
+    /// Panics if `segments` is less than 3 or `rings` is less than 1.
+    pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Mesh {
+        check_tessellation(segments, 3);
+        check_tessellation(rings, 1);
+
+        let half = height * 0.5;
+        let mut profile = Vec::with_capacity(2 * rings as usize + 2);
+
+        for &(center, from) in [(-half, -PI * 0.5), (half, 0.0)].iter() {
+            for ring in 0..rings + 1 {
+                let angle = from + PI * 0.5 * ring as f32 / rings as f32;
+                profile.push(ProfilePoint::new(radius * angle.cos(), center + radius * angle.sin(), angle.cos(), angle.sin()));
+            }
+        }
+
+        let mut mesh = Mesh::new();
+        revolve(&mut mesh, &profile, segments);
+
+        return mesh;
+    }
+
+    /// Generates a ring in the XZ plane, `radius` being the distance from the center to the middle
+    /// of the tube.
+    ///
+    /// `segments` is the number of quads around the ring and `sides` around the tube.
+    ///
+    /// # Panics
+    /// Panics if `segments` or `sides` is less than 3.
+    pub fn torus(radius: f32, tube_radius: f32, segments: u32, sides: u32) -> Mesh {
+        check_tessellation(segments, 3);
+        check_tessellation(sides, 3);
+
+        let profile: Vec<ProfilePoint> = (0..sides + 1).map(|side| {
+            let angle = 2.0 * PI * side as f32 / sides as f32 - PI;
+            ProfilePoint::new(radius + tube_radius * angle.cos(), tube_radius * angle.sin(), angle.cos(), angle.sin())
+        }).collect();
+
+        let mut mesh = Mesh::new();
+        revolve(&mut mesh, &profile, segments);
+
+        return mesh;
+    }
+
+    /// Generates a grid in the XZ plane facing +Y.
+    ///
+    /// The texture covers the whole plane with U along +X and V along -Z.
+    ///
+    /// # Panics
+    /// Panics if `x_segments` or `z_segments` is 0.
+    pub fn plane(width: f32, depth: f32, x_segments: u32, z_segments: u32) -> Mesh {
+        check_tessellation(x_segments, 1);
+        check_tessellation(z_segments, 1);
+
+        let mut mesh = Mesh::new();
+
+        for row in 0..z_segments + 1 {
+            for column in 0..x_segments + 1 {
+                let u = column as f32 / x_segments as f32;
+                let v = row as f32 / z_segments as f32;
+
+                mesh.positions.push(Vector3::new((u - 0.5) * width, 0.0, (0.5 - v) * depth));
+                mesh.normals.push(Vector3::new(0.0, 1.0, 0.0));
+                mesh.uvs.push(Vector2::new(u, v));
+            }
+        }
+
+        grid_indices(&mut mesh, 0, x_segments, z_segments);
+
+        return mesh;
+    }
+
+    /// Generates an arrow pointing from the origin along +Y, made of a cylindrical shaft and a cone.
+    ///
+    /// The head is clamped to the arrow's length.
+    ///
+    /// # Panics
+    /// Panics if `segments` is less than 3.
+    pub fn arrow(length: f32, shaft_radius: f32, head_radius: f32, head_length: f32, segments: u32) -> Mesh {
+        check_tessellation(segments, 3);
+
+        let head_start = (length - head_length).max(0.0);
+        let mut mesh = Mesh::new();
+
+        disk(&mut mesh, shaft_radius, 0.0, segments);
+        revolve(&mut mesh, &[
+            ProfilePoint::new(shaft_radius, 0.0, 1.0, 0.0),
+            ProfilePoint::new(shaft_radius, head_start, 1.0, 0.0)
+        ], segments);
+        // The underside of the head around the shaft.
+        revolve(&mut mesh, &[
+            ProfilePoint::new(shaft_radius, head_start, 0.0, -1.0),
+            ProfilePoint::new(head_radius, head_start, 0.0, -1.0)
+        ], segments);
+        cone_side(&mut mesh, head_radius, head_start, length, segments);
+
+        return mesh;
+    }
+}
+
+/// A point of the outline revolved by `revolve`, with its outward normal.
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    normal_radius: f32,
+    normal_y: f32
+}
+
+impl ProfilePoint {
+    fn new(radius: f32, y: f32, normal_radius: f32, normal_y: f32) -> ProfilePoint {
+        return ProfilePoint {
+            radius: radius,
+            y: y,
+            normal_radius: normal_radius,
+            normal_y: normal_y
+        };
+    }
+}
+
+/// Appends the surface swept by revolving `profile` around the Y axis, from the bottom up.
+///
+/// The seam is duplicated so the texture can wrap around. V follows the profile's length.
+fn revolve(mesh: &mut Mesh, profile: &[ProfilePoint], segments: u32) {
+    let base = mesh.positions.len() as u32;
+
+    let mut lengths = vec![0.0];
+    for pair in profile.windows(2) {
+        let step = Vector2::new(pair[1].radius - pair[0].radius, pair[1].y - pair[0].y).magnitude();
+        let last = lengths[lengths.len() - 1];
+        lengths.push(last + step);
+    }
+    let total = lengths[lengths.len() - 1];
+
+    for (point, length) in profile.iter().zip(lengths.iter()) {
+        for segment in 0..segments + 1 {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (2.0 * PI * u).sin_cos();
+
+            mesh.positions.push(Vector3::new(point.radius * cos, point.y, -point.radius * sin));
+            mesh.normals.push(Vector3::new(point.normal_radius * cos, point.normal_y, -point.normal_radius * sin).normalize());
+            mesh.uvs.push(Vector2::new(u, if total > 0.0 { length / total } else { 0.0 }));
+        }
+    }
+
+    grid_indices(mesh, base, segments, profile.len() as u32 - 1);
+}
+
+/// Appends the slanted side of a cone with its base at `bottom` and its tip at `top`.
+fn cone_side(mesh: &mut Mesh, radius: f32, bottom: f32, top: f32, segments: u32) {
+    // Perpendicular to the slant.
+    let normal = Vector2::new(top - bottom, radius).normalize();
+
+    revolve(mesh, &[
+        ProfilePoint::new(radius, bottom, normal.x, normal.y),
+        ProfilePoint::new(0.0, top, normal.x, normal.y)
+    ], segments);
+}
+
+/// Appends a disk at height `y`, facing +Y if it is the top of the mesh (`y > 0`) and -Y otherwise.
+///
+/// The texture is mapped from above, with V along -Z.
+fn disk(mesh: &mut Mesh, radius: f32, y: f32, segments: u32) {
+    let up = y > 0.0;
+    let normal = Vector3::new(0.0, if up { 1.0 } else { -1.0 }, 0.0);
+    let center = mesh.positions.len() as u32;
+
+    mesh.positions.push(Vector3::new(0.0, y, 0.0));
+    mesh.normals.push(normal);
+    mesh.uvs.push(Vector2::new(0.5, 0.5));
+
+    for segment in 0..segments + 1 {
+        let (sin, cos) = (2.0 * PI * segment as f32 / segments as f32).sin_cos();
+
+        mesh.positions.push(Vector3::new(radius * cos, y, -radius * sin));
+        mesh.normals.push(normal);
+        mesh.uvs.push(Vector2::new(0.5 + 0.5 * cos, 0.5 + 0.5 * sin));
+    }
+
+    for segment in 0..segments {
+        let (a, b) = (center + 1 + segment, center + 2 + segment);
+
+        if up {
+            mesh.indices.extend_from_slice(&[center, a, b]);
+        } else {
+            mesh.indices.extend_from_slice(&[center, b, a]);
+        }
+    }
+}
+
+/// Appends the triangles of a grid of `columns + 1` by `rows + 1` vertices starting at `base`,
+/// row by row. The rows go along the second surface direction, counter-clockwise from the first.
+fn grid_indices(mesh: &mut Mesh, base: u32, columns: u32, rows: u32) {
+    for row in 0..rows {
+        for column in 0..columns {
+            let a = base + row * (columns + 1) + column;
+            let b = a + columns + 1;
+
+            mesh.indices.extend_from_slice(&[a, a + 1, b + 1, a, b + 1, b]);
+        }
+    }
+}
+
+fn check_tessellation(count: u32, min: u32) {
+    if count < min {
+        panic!("{} {}", ERR_TESSELLATION, min);
+    }
+}
+
+const ERR_TESSELLATION: &'static str = "Primitive tessellation too low, the minimum is";
+const ERR_SUBDIVISIONS: &'static str = "Icosphere subdivisions must be at most 8";
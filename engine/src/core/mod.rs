@@ -3,22 +3,62 @@
 //! It contains any virtual world abstractions and helper structures.
 
 mod color;
+mod controller;
 mod data_ptr;
 mod entity;
 mod event_emitter;
+mod gltf;
+mod image;
+mod input;
+mod material;
+mod mesh;
+mod post;
+mod render_graph;
 mod scene;
+mod spatial;
 
 pub use self::color::Color;
 
+pub use self::controller::CameraController;
+pub use self::controller::follow::FollowController;
+pub use self::controller::free_fly::FreeFlyController;
+pub use self::controller::orbit::OrbitController;
+pub use self::controller::rts::RtsController;
+
 pub use self::data_ptr::Data;
 
 pub use self::entity::Entity;
 pub use self::entity::component::{Component, SubCallback};
 pub use self::entity::cuboid::Cuboid;
+pub use self::entity::mesh_renderable::MeshRenderable;
 
 pub use self::event_emitter::{Event, EventEmitter, Listener};
 
-pub use self::scene::Scene;
-pub use self::scene::camera::Camera;
+pub use self::gltf::{GltfAsset, GltfNode};
+pub use self::gltf::animation::{Animation, AnimationPath, Channel, Interpolation};
+
+pub use self::image::{Image, ImageFormat};
+pub use self::image::recorder::FrameRecorder;
+
+pub use self::input::{InputState, Control};
+
+pub use self::material::{Material, MATERIAL_GLSL};
+
+pub use self::mesh::{Mesh, GpuMesh, Model, ModelPart, Primitive};
+pub use self::mesh::obj;
+
+pub use self::post::{PostStack, PostEffect, ToneMapOperator};
+pub use self::post::lut::ColorLut;
+
+pub use self::render_graph::{RenderGraph, RenderPass, PassContext, Target, TargetDesc, TargetPool};
+
+pub use self::scene::{Scene, RenderPath, CullStats, PickHit, NodeHandle, NodeParent, NodeId, TransformNode};
+pub use self::scene::camera::{Camera, Projection};
 pub use self::scene::composition::Composition;
+pub use self::scene::deferred::DeferredRenderer;
+pub use self::scene::id_picker::{IdPicker, IdPass};
+pub use self::scene::light::{Lights, PointLight, SpotLight, DirectionalLight, LIGHTING_GLSL, MAX_POINT_LIGHTS, MAX_SPOT_LIGHTS};
 pub use self::scene::renderable::Renderable;
+pub use self::scene::shadow::{ShadowMap, ShadowSettings, Cascade, MAX_CASCADES};
+
+pub use self::spatial::SpatialGrid;
@@ -0,0 +1,103 @@
+extern crate cgmath;
+
+use self::cgmath::Vector3;
+
+use gliw::{Texture, TextureType, InternalFormat};
+
+use core::Image;
+
+/// 3D color lookup table used by `PostEffect::ColorGrade`.
+///
+/// Maps each RGB color to a graded one, with linear interpolation between the `size`³ entries.
+pub struct ColorLut {
+    size: u32,
+    texture: Texture
+}
+
+impl ColorLut {
+    /// Create a table which leaves the colors unchanged.
+    pub fn identity(size: u32) -> ColorLut {
+        return ColorLut::from_fn(size, |color| color);
+    }
+
+    /// Create a table by evaluating `grade` for each entry.
+    ///
+    /// The colors passed to and returned from `grade` are in the range [0, 1].
+    ///
+    /// # Panics
+    /// Panics if `size` is less than 2.
+    pub fn from_fn<F: Fn(Vector3<f32>) -> Vector3<f32>>(size: u32, grade: F) -> ColorLut {
+        if size < 2 {
+            panic!(ERR_LUT_SIZE);
+        }
+
+        let max = (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let color = grade(Vector3::new(r as f32 / max, g as f32 / max, b as f32 / max));
+                    data.extend_from_slice(&[to_byte(color.x), to_byte(color.y), to_byte(color.z), 255]);
+                }
+            }
+        }
+
+        return ColorLut::from_data(size, &data);
+    }
+
+    /// Create a table from a strip of `size` square slices laid out from left to right,
+    /// the common format exported by image editors.
+    ///
+    /// Red grows from left to right within a slice, green from top to bottom
+    /// and blue from slice to slice.
+    ///
+    /// Fails if `image` is not `size * size` pixels wide and `size` pixels high.
+    pub fn from_image(image: &Image) -> Result<ColorLut, String> {
+        let size = image.height();
+
+        if size < 2 || image.width() != size * size {
+            return Err(format!("{} {}x{}", ERR_LUT_IMAGE_SIZE, image.width(), image.height()));
+        }
+
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let pixel = image.pixel(b * size + r, g);
+                    data.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+                }
+            }
+        }
+
+        return Ok(ColorLut::from_data(size, &data));
+    }
+
+    /// Get the number of entries along each axis.
+    pub fn size(&self) -> u32 {
+        return self.size;
+    }
+
+    /// Get the 3D texture holding the table.
+    pub fn texture(&self) -> &Texture {
+        return &self.texture;
+    }
+
+    fn from_data(size: u32, data: &[u8]) -> ColorLut {
+        let texture = Texture::new(TextureType::Tex3D);
+        texture.image_3d(size as i32, size as i32, size as i32, InternalFormat::RGBA8, data);
+
+        return ColorLut {
+            size: size,
+            texture: texture
+        };
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    return (value.max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+}
+
+const ERR_LUT_SIZE: &'static str = "Color lookup table size must be at least 2";
+const ERR_LUT_IMAGE_SIZE: &'static str = "Color lookup table image must be a strip of square slices, got";
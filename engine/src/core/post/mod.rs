@@ -0,0 +1,658 @@
+//! Post-processing of the rendered image.
+
+pub mod lut;
+
+use gliw::{
+    gl, Gliw,
+    Attachment,
+    Program, ProgramBuilder, Shader, ShaderType,
+    InternalFormat, Texture,
+    Uniform, UniformData,
+    Vao
+};
+
+use core::render_graph::{RenderGraph, PassContext, Target, TargetDesc};
+
+use self::lut::ColorLut;
+
+use std::rc::Rc;
+
+/// Curve mapping HDR colors to the displayable range.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ToneMapOperator {
+    /// `c / (1 + c)`, never saturates.
+    Reinhard,
+    /// Fit of the ACES filmic curve, with more contrast and saturated highlights.
+    Aces,
+}
+
+/// An effect of a `PostStack`.
+///
+/// The fields can be changed at any time through `PostStack::effect_mut`.
+#[derive(Clone)]
+pub enum PostEffect {
+    /// Maps the HDR image to LDR after scaling it by `exposure`.
+    /// Effects after it should expect colors in the range [0, 1].
+    ToneMap {
+        exposure: f32,
+        operator: ToneMapOperator
+    },
+    /// Fast approximate anti-aliasing. Should come after `ToneMap`, since it works on perceived luma.
+    Fxaa,
+    /// Adds a blurred copy of the colors brighter than `threshold`, scaled by `intensity`.
+    /// The blur is done at half resolution and repeated `iterations` times to widen it.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        iterations: u32
+    },
+    /// Darkens the image from `radius` away from the center outwards, over a `softness` wide band.
+    /// The distances are relative to the height of the image.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        softness: f32
+    },
+    /// Remaps the colors through a 3D lookup table, blended with the original by `strength`.
+    ColorGrade {
+        lut: Rc<ColorLut>,
+        strength: f32
+    },
+    /// Shakes and ripples the image by up to `amplitude` of its height, `frequency` times a second.
+    /// Animated by `PostStack::set_time`.
+    ScreenShake {
+        amplitude: f32,
+        frequency: f32
+    },
+}
+
+impl PostEffect {
+    /// Get the name used for the effect's passes in a `RenderGraph`.
+    pub fn name(&self) -> &'static str {
+        return match *self {
+            PostEffect::ToneMap { .. } => "tone_map",
+            PostEffect::Fxaa => "fxaa",
+            PostEffect::Bloom { .. } => "bloom",
+            PostEffect::Vignette { .. } => "vignette",
+            PostEffect::ColorGrade { .. } => "color_grade",
+            PostEffect::ScreenShake { .. } => "screen_shake",
+        };
+    }
+}
+
+/// Ordered chain of post-processing effects.
+///
+/// Each effect is rendered with a full-screen triangle sampling the result of the previous one,
+/// starting from an offscreen color target, as passes of a `RenderGraph`. The intermediate
+/// targets are allocated by the graph and are `RGBA16F`, so HDR values survive until `ToneMap`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate cgmath;
+/// # extern crate engine;
+/// # use cgmath::Vector4;
+/// # use engine::core::{Camera, Scene, RenderGraph, TargetDesc, TargetPool, PostStack, PostEffect, ToneMapOperator};
+/// # use engine::gliw::{Attachment, InternalFormat};
+/// # fn main() {
+/// # let scene = Scene::new(Camera::new());
+/// # let mut pool = TargetPool::new();
+/// let mut post = PostStack::new().unwrap();
+/// post.push(PostEffect::Bloom { threshold: 1.0, intensity: 0.5, iterations: 2 });
+/// post.push(PostEffect::ToneMap { exposure: 1.0, operator: ToneMapOperator::Aces });
+/// post.push(PostEffect::Fxaa);
+///
+/// // Every frame
+/// let mut graph = RenderGraph::new(800, 600);
+/// let hdr = graph.create_target("hdr", TargetDesc::new(800, 600, InternalFormat::RGBA16F));
+/// let depth = graph.create_target("depth", TargetDesc::new(800, 600, InternalFormat::Depth24));
+///
+/// graph.add_scene_pass(&scene)
+///     .write(Attachment::Color(0), hdr)
+///     .write(Attachment::Depth, depth)
+///     .clear_color(Vector4::new(0.0, 0.0, 0.0, 1.0))
+///     .clear_depth(1.0);
+///
+/// post.add_passes(&mut graph, hdr, RenderGraph::backbuffer(), 800, 600);
+/// graph.execute(&mut pool);
+/// # }
+/// ```
+pub struct PostStack {
+    effects: Vec<(PostEffect, bool)>,
+    time: f32,
+
+    copy_program: Rc<Program>,
+    tone_map_program: Rc<Program>,
+    fxaa_program: Rc<Program>,
+    bright_program: Rc<Program>,
+    blur_program: Rc<Program>,
+    bloom_program: Rc<Program>,
+    vignette_program: Rc<Program>,
+    color_grade_program: Rc<Program>,
+    shake_program: Rc<Program>,
+
+    screen_vao: Vao
+}
+
+impl PostStack {
+    /// Create an empty stack.
+    ///
+    /// Fails if the shaders fail to compile.
+    pub fn new() -> Result<PostStack, String> {
+        let mut programs = Vec::new();
+
+        for fs_src in [COPY_FS_SRC, TONE_MAP_FS_SRC, FXAA_FS_SRC, BRIGHT_FS_SRC, BLUR_FS_SRC,
+                       BLOOM_FS_SRC, VIGNETTE_FS_SRC, COLOR_GRADE_FS_SRC, SHAKE_FS_SRC].iter() {
+            match build_program(SCREEN_VS_SRC, fs_src) {
+                Ok(program) => programs.push(program),
+                Err(err) => return Err(err)
+            }
+        }
+
+        let mut programs = programs.into_iter();
+
+        return Ok(PostStack {
+            effects: Vec::new(),
+            time: 0.0,
+
+            copy_program: programs.next().unwrap(),
+            tone_map_program: programs.next().unwrap(),
+            fxaa_program: programs.next().unwrap(),
+            bright_program: programs.next().unwrap(),
+            blur_program: programs.next().unwrap(),
+            bloom_program: programs.next().unwrap(),
+            vignette_program: programs.next().unwrap(),
+            color_grade_program: programs.next().unwrap(),
+            shake_program: programs.next().unwrap(),
+
+            screen_vao: Vao::new()
+        });
+    }
+
+    /// Appends an enabled `effect` and returns its index.
+    pub fn push(&mut self, effect: PostEffect) -> usize {
+        self.effects.push((effect, true));
+        return self.effects.len() - 1;
+    }
+
+    /// Inserts an enabled `effect` at `index`, shifting the later ones.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of effects.
+    pub fn insert(&mut self, index: usize, effect: PostEffect) {
+        self.effects.insert(index, (effect, true));
+    }
+
+    /// Removes and returns the effect at `index`, shifting the later ones.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> PostEffect {
+        return self.effects.remove(index).0;
+    }
+
+    /// Moves the effect at `from` so that it ends up at index `to`.
+    ///
+    /// # Panics
+    /// Panics if either index is out of bounds.
+    pub fn move_effect(&mut self, from: usize, to: usize) {
+        let effect = self.effects.remove(from);
+        self.effects.insert(to, effect);
+    }
+
+    /// Get the number of effects, including the disabled ones.
+    pub fn len(&self) -> usize {
+        return self.effects.len();
+    }
+
+    /// Get the effect at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn effect(&self, index: usize) -> &PostEffect {
+        return &self.effects[index].0;
+    }
+
+    /// Get mutable reference to the effect at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn effect_mut(&mut self, index: usize) -> &mut PostEffect {
+        return &mut self.effects[index].0;
+    }
+
+    /// Checks if the effect at `index` is applied.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn is_enabled(&self, index: usize) -> bool {
+        return self.effects[index].1;
+    }
+
+    /// Enables or disables the effect at `index` without removing it.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        self.effects[index].1 = enabled;
+    }
+
+    /// Set the time in seconds used by animated effects.
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Adds the passes of the enabled effects to `graph`, reading `input` and writing `output`.
+    ///
+    /// `width` and `height` are the size of `input` and `output`.
+    /// If no effect is enabled `input` is copied to `output`.
+    pub fn add_passes<'a>(&'a self, graph: &mut RenderGraph<'a>, input: Target, output: Target, width: i32, height: i32) {
+        let enabled: Vec<&PostEffect> = self.effects.iter()
+            .filter(|&&(_, enabled)| enabled)
+            .map(|&(ref effect, _)| effect)
+            .collect();
+
+        if enabled.is_empty() {
+            let program = &self.copy_program;
+            graph.add_pass("post_copy")
+                .read(input)
+                .write(Attachment::Color(0), output)
+                .execute(move |ctx| {
+                    ctx.texture(input).pass_to(program, "source", 0);
+                    self.draw_screen();
+                });
+            return;
+        }
+
+        let desc = TargetDesc::new(width, height, InternalFormat::RGBA16F);
+        let mut source = input;
+
+        for (index, &effect) in enabled.iter().enumerate() {
+            let target = if index == enabled.len() - 1 {
+                output
+            } else {
+                graph.create_target(effect.name(), desc)
+            };
+
+            match *effect {
+                PostEffect::Bloom { threshold, intensity, iterations } => {
+                    self.add_bloom_passes(graph, source, target, width, height, threshold, intensity, iterations);
+                },
+                _ => {
+                    graph.add_pass(effect.name())
+                        .read(source)
+                        .write(Attachment::Color(0), target)
+                        .execute(move |ctx| self.apply(effect, ctx, source));
+                }
+            }
+
+            source = target;
+        }
+    }
+
+    fn add_bloom_passes<'a>(&'a self, graph: &mut RenderGraph<'a>, source: Target, target: Target,
+                            width: i32, height: i32, threshold: f32, intensity: f32, iterations: u32)
+    {
+        // Upsampled when added to the source, so it needs filtering.
+        let half = TargetDesc::new((width / 2).max(1), (height / 2).max(1), InternalFormat::RGBA16F).with_linear_filter();
+        let source_texel = (1.0 / width as f32, 1.0 / height as f32);
+
+        let bright = graph.create_target("bloom_bright", half);
+        let program = &self.bright_program;
+        graph.add_pass("bloom_bright")
+            .read(source)
+            .write(Attachment::Color(0), bright)
+            .execute(move |ctx| {
+                ctx.texture(source).pass_to(program, "source", 0);
+                Uniform::new(program, "source_texel").value(UniformData::Float2(source_texel.0, source_texel.1));
+                Uniform::new(program, "threshold").value(UniformData::Float1(threshold));
+                self.draw_screen();
+            });
+
+        let mut blurred = bright;
+        for _ in 0..iterations.max(1) {
+            for &(name, direction) in [("bloom_blur_h", (1.0, 0.0)), ("bloom_blur_v", (0.0, 1.0))].iter() {
+                let next = graph.create_target(name, half);
+                let program = &self.blur_program;
+                let previous = blurred;
+
+                graph.add_pass(name)
+                    .read(previous)
+                    .write(Attachment::Color(0), next)
+                    .execute(move |ctx| {
+                        ctx.texture(previous).pass_to(program, "source", 0);
+                        Uniform::new(program, "direction").value(UniformData::Float2(
+                            direction.0 / ctx.width() as f32,
+                            direction.1 / ctx.height() as f32));
+                        self.draw_screen();
+                    });
+
+                blurred = next;
+            }
+        }
+
+        let program = &self.bloom_program;
+        graph.add_pass("bloom")
+            .read(source)
+            .read(blurred)
+            .write(Attachment::Color(0), target)
+            .execute(move |ctx| {
+                ctx.texture(source).pass_to(program, "source", 0);
+                ctx.texture(blurred).pass_to(program, "bloom", 1);
+                Uniform::new(program, "intensity").value(UniformData::Float1(intensity));
+                self.draw_screen();
+            });
+    }
+
+    fn apply(&self, effect: &PostEffect, ctx: &PassContext, source: Target) {
+        let texture = ctx.texture(source);
+
+        match *effect {
+            PostEffect::ToneMap { exposure, operator } => {
+                let program = self.bind_source(&self.tone_map_program, texture);
+                Uniform::new(program, "exposure").value(UniformData::Float1(exposure));
+                Uniform::new(program, "operator").value(UniformData::Int1(operator as i32));
+            },
+            PostEffect::Fxaa => {
+                let program = self.bind_source(&self.fxaa_program, texture);
+                Uniform::new(program, "texel").value(UniformData::Float2(1.0 / ctx.width() as f32, 1.0 / ctx.height() as f32));
+            },
+            PostEffect::Vignette { intensity, radius, softness } => {
+                let program = self.bind_source(&self.vignette_program, texture);
+                Uniform::new(program, "aspect").value(UniformData::Float1(ctx.width() as f32 / ctx.height() as f32));
+                Uniform::new(program, "intensity").value(UniformData::Float1(intensity));
+                Uniform::new(program, "radius").value(UniformData::Float1(radius));
+                Uniform::new(program, "softness").value(UniformData::Float1(softness));
+            },
+            PostEffect::ColorGrade { ref lut, strength } => {
+                let program = self.bind_source(&self.color_grade_program, texture);
+                lut.texture().pass_to(program, "lut", 1);
+                Uniform::new(program, "lut_size").value(UniformData::Float1(lut.size() as f32));
+                Uniform::new(program, "strength").value(UniformData::Float1(strength));
+            },
+            PostEffect::ScreenShake { amplitude, frequency } => {
+                let program = self.bind_source(&self.shake_program, texture);
+                Uniform::new(program, "aspect").value(UniformData::Float1(ctx.width() as f32 / ctx.height() as f32));
+                Uniform::new(program, "amplitude").value(UniformData::Float1(amplitude));
+                Uniform::new(program, "phase").value(UniformData::Float1(self.time * frequency));
+            },
+            PostEffect::Bloom { .. } => unreachable!()
+        }
+
+        self.draw_screen();
+    }
+
+    fn bind_source<'p>(&self, program: &'p Rc<Program>, texture: &Texture) -> &'p Rc<Program> {
+        texture.pass_to(program, "source", 0);
+        return program;
+    }
+
+    fn draw_screen(&self) {
+        Gliw::disable(gl::DEPTH_TEST);
+
+        self.screen_vao.bind();
+        unsafe { gl::DrawArrays(gl::TRIANGLES, 0, 3); }
+
+        Gliw::enable(gl::DEPTH_TEST);
+    }
+}
+
+fn build_program(vs_src: &str, fs_src: &str) -> Result<Rc<Program>, String> {
+    let vs = match Shader::new(ShaderType::Vertex, vs_src) {
+        Ok(shader) => shader,
+        Err(err) => return Err(err)
+    };
+
+    let fs = match Shader::new(ShaderType::Fragment, fs_src) {
+        Ok(shader) => shader,
+        Err(err) => return Err(err)
+    };
+
+    return ProgramBuilder::new()
+        .attach_vs(&vs)
+        .attach_fs(&fs)
+        .link();
+}
+
+const SCREEN_VS_SRC: &'static str = r#"
+    #version 330 core
+
+    out vec2 uv;
+
+    void main() {
+        // A single triangle covering the whole screen.
+        uv = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+        gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    }
+"#;
+
+const COPY_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        color = texture(source, uv);
+    }
+"#;
+
+const TONE_MAP_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform float exposure;
+    uniform int operator;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        vec3 hdr = texture(source, uv).rgb * exposure;
+
+        if (operator == 0) {
+            color = vec4(hdr / (1.0 + hdr), 1.0);
+        } else {
+            // Krzysztof Narkowicz's fit of the ACES curve.
+            color = vec4(clamp((hdr * (2.51 * hdr + 0.03)) / (hdr * (2.43 * hdr + 0.59) + 0.14), 0.0, 1.0), 1.0);
+        }
+    }
+"#;
+
+const FXAA_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform vec2 texel;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    const float REDUCE_MIN = 1.0 / 128.0;
+    const float REDUCE_MUL = 1.0 / 8.0;
+    const float SPAN_MAX = 8.0;
+
+    float luma(vec3 rgb) {
+        return dot(rgb, vec3(0.299, 0.587, 0.114));
+    }
+
+    void main() {
+        float luma_nw = luma(texture(source, uv + vec2(-1.0, -1.0) * texel).rgb);
+        float luma_ne = luma(texture(source, uv + vec2( 1.0, -1.0) * texel).rgb);
+        float luma_sw = luma(texture(source, uv + vec2(-1.0,  1.0) * texel).rgb);
+        float luma_se = luma(texture(source, uv + vec2( 1.0,  1.0) * texel).rgb);
+        vec4 center = texture(source, uv);
+        float luma_m = luma(center.rgb);
+
+        float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+        float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+        // The edge direction is perpendicular to the luma gradient.
+        vec2 dir = vec2(
+            -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+             ((luma_nw + luma_sw) - (luma_ne + luma_se)));
+
+        float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+        float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+        dir = clamp(dir * rcp_dir_min, -SPAN_MAX, SPAN_MAX) * texel;
+
+        vec3 rgb_a = 0.5 * (
+            texture(source, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+            texture(source, uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+        vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+            texture(source, uv - dir * 0.5).rgb +
+            texture(source, uv + dir * 0.5).rgb);
+
+        // The wider sample crossed another edge, fall back to the narrow one.
+        float luma_b = luma(rgb_b);
+        color = vec4((luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b, center.a);
+    }
+"#;
+
+const BRIGHT_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform vec2 source_texel;
+    uniform float threshold;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        // Average the 4 source pixels covered by this half resolution pixel.
+        vec3 sum =
+            texture(source, uv + vec2(-0.5, -0.5) * source_texel).rgb +
+            texture(source, uv + vec2( 0.5, -0.5) * source_texel).rgb +
+            texture(source, uv + vec2(-0.5,  0.5) * source_texel).rgb +
+            texture(source, uv + vec2( 0.5,  0.5) * source_texel).rgb;
+        vec3 rgb = sum * 0.25;
+
+        float brightness = max(rgb.r, max(rgb.g, rgb.b));
+        float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+
+        color = vec4(rgb * contribution, 1.0);
+    }
+"#;
+
+const BLUR_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform vec2 direction;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+    void main() {
+        vec3 sum = texture(source, uv).rgb * WEIGHTS[0];
+
+        for (int i = 1; i < 5; i++) {
+            sum += texture(source, uv + direction * float(i)).rgb * WEIGHTS[i];
+            sum += texture(source, uv - direction * float(i)).rgb * WEIGHTS[i];
+        }
+
+        color = vec4(sum, 1.0);
+    }
+"#;
+
+const BLOOM_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform sampler2D bloom;
+    uniform float intensity;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        vec4 base = texture(source, uv);
+        color = vec4(base.rgb + texture(bloom, uv).rgb * intensity, base.a);
+    }
+"#;
+
+const VIGNETTE_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform float aspect;
+    uniform float intensity;
+    uniform float radius;
+    uniform float softness;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        vec4 base = texture(source, uv);
+
+        float dist = length((uv - 0.5) * vec2(aspect, 1.0));
+        float vignette = smoothstep(radius + softness, radius, dist);
+
+        color = vec4(base.rgb * mix(1.0, vignette, intensity), base.a);
+    }
+"#;
+
+const COLOR_GRADE_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform sampler3D lut;
+    uniform float lut_size;
+    uniform float strength;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        vec4 base = texture(source, uv);
+        vec3 rgb = clamp(base.rgb, 0.0, 1.0);
+
+        // Sample at the texel centers so the end entries map exactly to 0 and 1.
+        vec3 coord = rgb * ((lut_size - 1.0) / lut_size) + 0.5 / lut_size;
+        vec3 graded = texture(lut, coord).rgb;
+
+        color = vec4(mix(rgb, graded, strength), base.a);
+    }
+"#;
+
+const SHAKE_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D source;
+    uniform float aspect;
+    uniform float amplitude;
+    uniform float phase;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    const float TAU = 6.2831853;
+
+    void main() {
+        // Incommensurate frequencies so the motion does not look periodic.
+        vec2 shake = vec2(
+            sin(phase * TAU * 1.3) + 0.5 * sin(phase * TAU * 2.9 + 1.7),
+            cos(phase * TAU * 1.1) + 0.5 * sin(phase * TAU * 3.7 + 0.4)) * (amplitude / 1.5);
+
+        vec2 ripple = vec2(
+            sin(uv.y * 20.0 + phase * TAU),
+            cos(uv.x * 20.0 * aspect + phase * TAU)) * amplitude * 0.1;
+
+        color = texture(source, clamp(uv + (shake + ripple) * vec2(1.0 / aspect, 1.0), 0.0, 1.0));
+    }
+"#;
@@ -0,0 +1,607 @@
+extern crate cgmath;
+
+use self::cgmath::Vector4;
+
+use gliw::{
+    gl,
+    Attachment, Framebuffer, FramebufferTarget,
+    Texture, TextureType, InternalFormat
+};
+
+use core::Scene;
+
+/// Handle to a render target declared in a `RenderGraph`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Target(usize);
+
+/// Size and format of a render target allocated by a `RenderGraph`.
+#[derive(Copy, Clone, PartialEq)]
+pub struct TargetDesc {
+    pub width: i32,
+    pub height: i32,
+    pub format: InternalFormat,
+    /// Sample the texture with `GL_LINEAR` filters instead of `GL_NEAREST`.
+    ///
+    /// Targets share textures only with targets filtered the same way.
+    pub linear: bool
+}
+
+impl TargetDesc {
+    pub fn new(width: i32, height: i32, format: InternalFormat) -> TargetDesc {
+        return TargetDesc {
+            width: width,
+            height: height,
+            format: format,
+            linear: false
+        };
+    }
+
+    /// Get the same description with `GL_LINEAR` filters, e.g. for targets upsampled when read.
+    pub fn with_linear_filter(mut self) -> TargetDesc {
+        self.linear = true;
+        return self;
+    }
+}
+
+/// A pass of a `RenderGraph` along with the targets it reads and writes.
+///
+/// See `RenderGraph::add_pass`.
+pub struct RenderPass<'a> {
+    name: String,
+    reads: Vec<Target>,
+    writes: Vec<(Attachment, Target)>,
+    clear_color: Option<Vector4<f32>>,
+    clear_depth: Option<f32>,
+    keep: bool,
+    execute: Option<Box<Fn(&PassContext) + 'a>>
+}
+
+impl<'a> RenderPass<'a> {
+    /// Declares that the pass samples `target`.
+    ///
+    /// The pass will run after all passes that write `target`.
+    pub fn read(&mut self, target: Target) -> &mut RenderPass<'a> {
+        self.reads.push(target);
+        return self;
+    }
+
+    /// Declares that the pass renders to `target` through `attachment`.
+    ///
+    /// All targets written by a pass must have the same size.
+    /// Passes writing the same target run in the order they were added.
+    pub fn write(&mut self, attachment: Attachment, target: Target) -> &mut RenderPass<'a> {
+        self.writes.push((attachment, target));
+        return self;
+    }
+
+    /// Clears the written color attachments to `color` before executing.
+    ///
+    /// Targets allocated by the graph have undefined contents when first written,
+    /// so the first pass writing them should clear them.
+    pub fn clear_color(&mut self, color: Vector4<f32>) -> &mut RenderPass<'a> {
+        self.clear_color = Some(color);
+        return self;
+    }
+
+    /// Clears the written depth attachment to `depth` before executing.
+    pub fn clear_depth(&mut self, depth: f32) -> &mut RenderPass<'a> {
+        self.clear_depth = Some(depth);
+        return self;
+    }
+
+    /// Prevents the pass from being culled even if nothing uses its output.
+    ///
+    /// Useful for passes with side effects the graph does not know about, e.g. writing buffers.
+    pub fn keep(&mut self) -> &mut RenderPass<'a> {
+        self.keep = true;
+        return self;
+    }
+
+    /// Set the function issuing the draw calls of the pass.
+    ///
+    /// It is called with the pass's framebuffer bound, the viewport set to its size
+    /// and the attachments cleared.
+    pub fn execute<F: Fn(&PassContext) + 'a>(&mut self, f: F) -> &mut RenderPass<'a> {
+        self.execute = Some(Box::new(f));
+        return self;
+    }
+}
+
+/// The state passed to `RenderPass::execute`.
+pub struct PassContext<'g> {
+    name: &'g str,
+    width: i32,
+    height: i32,
+    reads: &'g [Target],
+    textures: &'g [Option<&'g Texture>]
+}
+
+impl<'g> PassContext<'g> {
+    /// Get the name of the pass.
+    pub fn name(&self) -> &str {
+        return self.name;
+    }
+
+    /// Get the width of the pass's attachments.
+    pub fn width(&self) -> i32 {
+        return self.width;
+    }
+
+    /// Get the height of the pass's attachments.
+    pub fn height(&self) -> i32 {
+        return self.height;
+    }
+
+    /// Get the texture of `target` for sampling.
+    ///
+    /// # Panics
+    /// Panics if `target` was not declared with `RenderPass::read`.
+    pub fn texture(&self, target: Target) -> &Texture {
+        if !self.reads.contains(&target) {
+            panic!(ERR_UNDECLARED_READ);
+        }
+
+        return match self.textures[target.0] {
+            Some(texture) => texture,
+            None => panic!(ERR_READ_BACKBUFFER)
+        };
+    }
+}
+
+/// Textures and framebuffers allocated by `RenderGraph`s, kept alive between frames.
+///
+/// Anything not used by the last executed graph is released, so resizing the targets
+/// frees the old ones on the next frame.
+pub struct TargetPool {
+    textures: Vec<PooledTexture>,
+    framebuffers: Vec<(Vec<(Attachment, u32)>, Framebuffer)>
+}
+
+struct PooledTexture {
+    desc: TargetDesc,
+    texture: Texture,
+    busy_until: Option<usize>
+}
+
+impl TargetPool {
+    pub fn new() -> TargetPool {
+        return TargetPool {
+            textures: Vec::new(),
+            framebuffers: Vec::new()
+        };
+    }
+
+    /// Get a texture matching `desc` which is not in use before the pass at `first`
+    /// and mark it as used until the pass at `last`.
+    fn acquire(&mut self, desc: TargetDesc, first: usize, last: usize) -> usize {
+        let free = self.textures.iter().position(|pooled| {
+            pooled.desc == desc && match pooled.busy_until {
+                Some(until) => until < first,
+                None => true
+            }
+        });
+
+        let index = match free {
+            Some(index) => index,
+            None => {
+                let texture = Texture::new(TextureType::Tex2D);
+                texture.alloc_2d(desc.width, desc.height, desc.format);
+
+                if desc.linear {
+                    unsafe {
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                    }
+                }
+
+                self.textures.push(PooledTexture {
+                    desc: desc,
+                    texture: texture,
+                    busy_until: None
+                });
+                self.textures.len() - 1
+            }
+        };
+
+        self.textures[index].busy_until = Some(last);
+        return index;
+    }
+
+    /// Releases the textures unused by the last graph and the framebuffers referencing them.
+    fn trim(&mut self) {
+        self.textures.retain(|pooled| pooled.busy_until.is_some());
+
+        let handles: Vec<u32> = self.textures.iter().map(|pooled| pooled.texture.handle()).collect();
+        self.framebuffers.retain(|&(ref key, _)| {
+            key.iter().all(|&(_, handle)| handles.contains(&handle))
+        });
+
+        for pooled in self.textures.iter_mut() {
+            pooled.busy_until = None;
+        }
+    }
+}
+
+enum TargetKind<'a> {
+    Backbuffer,
+    Transient(TargetDesc),
+    Imported(&'a Texture, i32, i32)
+}
+
+struct TargetInfo<'a> {
+    name: String,
+    kind: TargetKind<'a>
+}
+
+/// Frame scheduler for multi-pass rendering.
+///
+/// Passes declare the targets they read and write, from which the graph:
+///
+/// * Orders the passes so that each target is read only after all passes writing it have run.
+/// * Culls the passes whose output is never used. Only passes writing the backbuffer
+/// or imported targets, and passes marked with `RenderPass::keep`, are used by themselves.
+/// * Allocates textures for the transient targets from a `TargetPool`, letting targets with
+/// the same description share a texture when their lifetimes in the frame do not overlap.
+///
+/// The graph is meant to be rebuilt every frame, the pool is what persists.
+///
+/// # Panics
+/// `execute` panics if the passes depend on each other cyclicly, if a pass reads and writes
+/// the same target, if a used target is never written or if a pass mixes the backbuffer
+/// with other targets.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate cgmath;
+/// # extern crate engine;
+/// # use cgmath::Vector4;
+/// # use engine::core::{Camera, Scene, RenderGraph, TargetDesc, TargetPool};
+/// # use engine::gliw::{Attachment, InternalFormat};
+/// # fn main() {
+/// # let scene = Scene::new(Camera::new());
+/// let mut pool = TargetPool::new();
+///
+/// // Every frame
+/// let mut graph = RenderGraph::new(800, 600);
+/// let hdr = graph.create_target("hdr", TargetDesc::new(800, 600, InternalFormat::RGBA16F));
+/// let depth = graph.create_target("depth", TargetDesc::new(800, 600, InternalFormat::Depth24));
+///
+/// graph.add_scene_pass(&scene)
+///     .write(Attachment::Color(0), hdr)
+///     .write(Attachment::Depth, depth)
+///     .clear_color(Vector4::new(0.0, 0.0, 0.0, 1.0))
+///     .clear_depth(1.0);
+///
+/// graph.add_pass("post")
+///     .read(hdr)
+///     .write(Attachment::Color(0), RenderGraph::backbuffer())
+///     .execute(move |ctx| {
+///         let hdr_texture = ctx.texture(hdr);
+///         // ...tonemap to the screen
+///     });
+///
+/// graph.execute(&mut pool);
+/// # }
+/// ```
+pub struct RenderGraph<'a> {
+    width: i32,
+    height: i32,
+    targets: Vec<TargetInfo<'a>>,
+    passes: Vec<RenderPass<'a>>
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Create an empty graph for a default framebuffer of the given size.
+    pub fn new(width: i32, height: i32) -> RenderGraph<'a> {
+        return RenderGraph {
+            width: width,
+            height: height,
+            targets: vec![TargetInfo {
+                name: "backbuffer".to_string(),
+                kind: TargetKind::Backbuffer
+            }],
+            passes: Vec::new()
+        };
+    }
+
+    /// Get the target representing the default framebuffer.
+    ///
+    /// It can only be written and only by passes which write nothing else.
+    pub fn backbuffer() -> Target {
+        return Target(0);
+    }
+
+    /// Declares a target whose texture is allocated by the graph.
+    pub fn create_target(&mut self, name: &str, desc: TargetDesc) -> Target {
+        return self.push_target(name, TargetKind::Transient(desc));
+    }
+
+    /// Declares a target backed by an existing `texture` of the given size.
+    ///
+    /// Its contents are kept after the frame, so passes writing it are never culled.
+    pub fn import_target(&mut self, name: &str, texture: &'a Texture, width: i32, height: i32) -> Target {
+        return self.push_target(name, TargetKind::Imported(texture, width, height));
+    }
+
+    /// Adds a pass and returns it for declaring its targets.
+    pub fn add_pass(&mut self, name: &str) -> &mut RenderPass<'a> {
+        self.passes.push(RenderPass {
+            name: name.to_string(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            clear_color: None,
+            clear_depth: None,
+            keep: false,
+            execute: None
+        });
+
+        let last = self.passes.len() - 1;
+        return &mut self.passes[last];
+    }
+
+    /// Adds an `"opaque"` pass which draws `scene` with `Scene::draw`.
+    pub fn add_scene_pass(&mut self, scene: &'a Scene) -> &mut RenderPass<'a> {
+        return self.add_pass("opaque").execute(move |_| scene.draw());
+    }
+
+    /// Get the names of the passes that will run, in order.
+    pub fn schedule(&self) -> Vec<&str> {
+        return self.compile().iter().map(|&pass| self.passes[pass].name.as_str()).collect();
+    }
+
+    /// Runs the passes using and updating the textures of `pool`.
+    ///
+    /// Leaves the default framebuffer bound with the viewport covering it.
+    pub fn execute(&self, pool: &mut TargetPool) {
+        let order = self.compile();
+
+        // Lifetime of each transient target as positions in `order`.
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.targets.len()];
+        for (position, &pass) in order.iter().enumerate() {
+            let pass = &self.passes[pass];
+            let mut used = pass.reads.clone();
+            used.extend(pass.writes.iter().map(|&(_, target)| target));
+
+            for target in used {
+                lifetimes[target.0] = match lifetimes[target.0] {
+                    Some((first, _)) => Some((first, position)),
+                    None => Some((position, position))
+                };
+            }
+        }
+
+        let mut slots: Vec<Option<usize>> = vec![None; self.targets.len()];
+        for position in 0..order.len() {
+            for (target, info) in self.targets.iter().enumerate() {
+                if let (&TargetKind::Transient(desc), Some((first, last))) = (&info.kind, lifetimes[target]) {
+                    if first == position {
+                        slots[target] = Some(pool.acquire(desc, first, last));
+                    }
+                }
+            }
+        }
+
+        {
+            let TargetPool { ref textures, ref mut framebuffers } = *pool;
+
+            let resolved: Vec<Option<&Texture>> = self.targets.iter().enumerate().map(|(target, info)| {
+                match info.kind {
+                    TargetKind::Backbuffer => None,
+                    TargetKind::Transient(_) => slots[target].map(|slot| &textures[slot].texture),
+                    TargetKind::Imported(texture, _, _) => Some(texture)
+                }
+            }).collect();
+
+            for &pass in order.iter() {
+                self.run_pass(&self.passes[pass], &resolved, framebuffers);
+            }
+        }
+
+        pool.trim();
+
+        Framebuffer::bind_default(FramebufferTarget::Both);
+        unsafe { gl::Viewport(0, 0, self.width, self.height); }
+    }
+
+    fn push_target(&mut self, name: &str, kind: TargetKind<'a>) -> Target {
+        self.targets.push(TargetInfo {
+            name: name.to_string(),
+            kind: kind
+        });
+
+        return Target(self.targets.len() - 1);
+    }
+
+    /// Validates the passes and returns the indices of the ones to run, in order.
+    fn compile(&self) -> Vec<usize> {
+        let backbuffer = RenderGraph::backbuffer();
+
+        for pass in self.passes.iter() {
+            if pass.writes.iter().any(|&(_, target)| pass.reads.contains(&target)) {
+                panic!("{} `{}`", ERR_FEEDBACK_LOOP, pass.name);
+            }
+
+            let writes_backbuffer = pass.writes.iter().any(|&(_, target)| target == backbuffer);
+            if writes_backbuffer && pass.writes.iter().any(|&(_, target)| target != backbuffer) {
+                panic!("{} `{}`", ERR_MIXED_BACKBUFFER, pass.name);
+            }
+
+            if pass.reads.contains(&backbuffer) {
+                panic!("{} `{}`", ERR_READ_BACKBUFFER, pass.name);
+            }
+        }
+
+        // A pass depends on all writers of the targets it reads
+        // and on the earlier added writers of the targets it writes.
+        let dependencies: Vec<Vec<usize>> = self.passes.iter().enumerate().map(|(index, pass)| {
+            let mut deps = Vec::new();
+
+            for (other, other_pass) in self.passes.iter().enumerate() {
+                if other == index {
+                    continue;
+                }
+
+                let depends = other_pass.writes.iter().any(|&(_, written)| {
+                    pass.reads.contains(&written) ||
+                    (other < index && pass.writes.iter().any(|&(_, target)| target == written))
+                });
+
+                if depends {
+                    deps.push(other);
+                }
+            }
+
+            deps
+        }).collect();
+
+        // Mark the passes reachable from the ones with visible output.
+        let mut live = vec![false; self.passes.len()];
+        let mut stack: Vec<usize> = (0..self.passes.len()).filter(|&index| {
+            let pass = &self.passes[index];
+            pass.keep || pass.writes.iter().any(|&(_, target)| {
+                match self.targets[target.0].kind {
+                    TargetKind::Transient(_) => false,
+                    _ => true
+                }
+            })
+        }).collect();
+
+        while let Some(index) = stack.pop() {
+            if live[index] {
+                continue;
+            }
+
+            live[index] = true;
+            stack.extend(dependencies[index].iter().cloned());
+        }
+
+        for (index, pass) in self.passes.iter().enumerate().filter(|&(index, _)| live[index]) {
+            for target in pass.reads.iter() {
+                let written = dependencies[index].iter().any(|&dep| {
+                    self.passes[dep].writes.iter().any(|&(_, written)| written == *target)
+                });
+
+                if !written {
+                    if let TargetKind::Transient(_) = self.targets[target.0].kind {
+                        panic!("{} `{}`", ERR_UNWRITTEN_TARGET, self.targets[target.0].name);
+                    }
+                }
+            }
+        }
+
+        // Topological sort, preferring the earlier added passes when there is a choice.
+        let mut order = Vec::new();
+        let mut done = vec![false; self.passes.len()];
+
+        while order.len() < live.iter().filter(|&&is_live| is_live).count() {
+            let next = (0..self.passes.len()).find(|&index| {
+                live[index] && !done[index] && dependencies[index].iter().all(|&dep| done[dep])
+            });
+
+            match next {
+                Some(index) => {
+                    done[index] = true;
+                    order.push(index);
+                },
+                None => panic!(ERR_CYCLE)
+            }
+        }
+
+        return order;
+    }
+
+    fn run_pass(&self, pass: &RenderPass, textures: &[Option<&Texture>], framebuffers: &mut Vec<(Vec<(Attachment, u32)>, Framebuffer)>) {
+        let (width, height) = match pass.writes.first() {
+            Some(&(_, target)) => match self.targets[target.0].kind {
+                TargetKind::Backbuffer => (self.width, self.height),
+                TargetKind::Transient(desc) => (desc.width, desc.height),
+                TargetKind::Imported(_, width, height) => (width, height)
+            },
+            None => (self.width, self.height)
+        };
+
+        let writes_backbuffer = pass.writes.iter().any(|&(_, target)| target == RenderGraph::backbuffer());
+
+        if writes_backbuffer {
+            Framebuffer::bind_default(FramebufferTarget::Both);
+        } else if !pass.writes.is_empty() {
+            let key: Vec<(Attachment, u32)> = pass.writes.iter().map(|&(attachment, target)| {
+                (attachment, textures[target.0].unwrap().handle())
+            }).collect();
+
+            let cached = framebuffers.iter().position(|&(ref cached_key, _)| *cached_key == key);
+            let index = match cached {
+                Some(index) => index,
+                None => {
+                    framebuffers.push((key, build_framebuffer(pass, textures)));
+                    framebuffers.len() - 1
+                }
+            };
+
+            framebuffers[index].1.bind(FramebufferTarget::Both);
+        }
+
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+
+            if let Some(color) = pass.clear_color {
+                let color = [color.x, color.y, color.z, color.w];
+
+                for &(attachment, _) in pass.writes.iter() {
+                    if let Attachment::Color(index) = attachment {
+                        gl::ClearBufferfv(gl::COLOR, index as i32, color.as_ptr());
+                    }
+                }
+            }
+
+            if let Some(depth) = pass.clear_depth {
+                gl::DepthMask(gl::TRUE);
+                gl::ClearBufferfv(gl::DEPTH, 0, &depth);
+            }
+        }
+
+        if let Some(ref execute) = pass.execute {
+            execute(&PassContext {
+                name: &pass.name,
+                width: width,
+                height: height,
+                reads: &pass.reads,
+                textures: textures
+            });
+        }
+    }
+}
+
+fn build_framebuffer(pass: &RenderPass, textures: &[Option<&Texture>]) -> Framebuffer {
+    let framebuffer = Framebuffer::new();
+    let mut draw_buffers = Vec::new();
+
+    for &(attachment, target) in pass.writes.iter() {
+        framebuffer.attach_texture(attachment, textures[target.0].unwrap(), 0);
+
+        if let Attachment::Color(index) = attachment {
+            let index = index as usize;
+            if draw_buffers.len() <= index {
+                draw_buffers.resize(index + 1, None);
+            }
+            draw_buffers[index] = Some(attachment);
+        }
+    }
+
+    framebuffer.draw_buffers(&draw_buffers);
+
+    if let Err(err) = framebuffer.check_status() {
+        panic!("{} `{}`: {}", ERR_INCOMPLETE_PASS, pass.name, err);
+    }
+
+    return framebuffer;
+}
+
+const ERR_FEEDBACK_LOOP: &'static str = "Render pass reads and writes the same target";
+const ERR_MIXED_BACKBUFFER: &'static str = "Render pass writes the backbuffer along with other targets";
+const ERR_READ_BACKBUFFER: &'static str = "The backbuffer can not be read by render passes";
+const ERR_UNWRITTEN_TARGET: &'static str = "Render target is read but never written";
+const ERR_UNDECLARED_READ: &'static str = "Render target was not declared as read by the pass";
+const ERR_CYCLE: &'static str = "Render graph passes depend on each other cyclicly";
+const ERR_INCOMPLETE_PASS: &'static str = "Framebuffer of render pass is incomplete";
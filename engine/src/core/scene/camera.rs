@@ -1,13 +1,96 @@
 extern crate cgmath;
 
-use self::cgmath::{Point3, Vector3, Matrix4, SquareMatrix, Deg};
+use self::cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3, Vector4, Matrix4, SquareMatrix, Deg};
+
+use math::Ray;
+
+/// The projection of a `Camera`, along with the parameters it was made from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// `fovy` is the vertical field of view in degrees and `aspect` the viewport's width divided by its height.
+    Perspective { fovy: f32, aspect: f32, near: f32, far: f32 },
+    /// The view space box which is visible, e.g. the area around a tank for the minimap.
+    ///
+    /// Resizing keeps the box's height, see `with_aspect`. For a HUD drawn in pixels, set the box to
+    /// the viewport's size with `Camera::orthographic` after every resize instead.
+    Orthographic { left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32 },
+    /// A matrix of which the parameters are unknown, see `Camera::from_matrices`.
+    Matrix(Matrix4<f32>)
+}
+
+impl Projection {
+    /// Get the projection matrix.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        return match *self {
+            Projection::Perspective { fovy, aspect, near, far } =>
+                cgmath::perspective(Deg::new(fovy), aspect, near, far),
+            Projection::Orthographic { left, right, bottom, top, near, far } =>
+                cgmath::ortho(left, right, bottom, top, near, far),
+            Projection::Matrix(matrix) => matrix
+        };
+    }
+
+    /// Get the distances to the near and far clip planes. `None` for `Projection::Matrix`.
+    pub fn clip_planes(&self) -> Option<(f32, f32)> {
+        return match *self {
+            Projection::Perspective { near, far, .. } => Some((near, far)),
+            Projection::Orthographic { near, far, .. } => Some((near, far)),
+            Projection::Matrix(_) => None
+        };
+    }
+
+    /// Get the projection for a viewport with the given `aspect`, e.g. after the window was resized.
+    ///
+    /// An orthographic projection keeps its height and horizontal center, so the view isn't stretched.
+    /// `Projection::Matrix` is left as is.
+    pub fn with_aspect(&self, aspect: f32) -> Projection {
+        return match *self {
+            Projection::Perspective { fovy, near, far, .. } =>
+                Projection::Perspective { fovy: fovy, aspect: aspect, near: near, far: far },
+            Projection::Orthographic { left, right, bottom, top, near, far } => {
+                let center = (left + right) * 0.5;
+                let half_width = (top - bottom) * aspect * 0.5;
+
+                Projection::Orthographic {
+                    left: center - half_width,
+                    right: center + half_width,
+                    bottom: bottom,
+                    top: top,
+                    near: near,
+                    far: far
+                }
+            },
+            Projection::Matrix(matrix) => Projection::Matrix(matrix)
+        };
+    }
+}
 
 #[derive(Copy, Clone)]
 /// Holds view and projection matrices.
 ///
-/// See `Scene`.
+/// The projection is either perspective, e.g. for the game's view, or orthographic,
+/// e.g. for the minimap or the HUD. See `Projection` and `Scene`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::Camera;
+/// # use cgmath::{Point3, Vector3};
+/// # fn main() {
+/// // Looking down at the 40x40 battlefield.
+/// let mut minimap = Camera::new();
+/// minimap.orthographic(-20.0, 20.0, -20.0, 20.0, 0.1, 100.0);
+/// minimap.look_at(Point3::new(0.0, 50.0, 0.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+///
+/// // Where on the ground the minimap was clicked.
+/// let target = minimap.screen_to_ground(80.0, 120.0, 200.0, 200.0, 0.0);
+/// # }
+/// ```
 pub struct Camera {
     view_matrix: Matrix4<f32>,
+    projection: Projection,
     proj_matrix: Matrix4<f32>,
     // Reduces draw call computations
     vp_matrix: Matrix4<f32>
@@ -18,6 +101,7 @@ impl Camera {
     pub fn new() -> Camera {
         return Camera {
             view_matrix: Matrix4::identity(),
+            projection: Projection::Matrix(Matrix4::identity()),
             proj_matrix: Matrix4::identity(),
             vp_matrix: Matrix4::identity()
         };
@@ -27,11 +111,27 @@ impl Camera {
     pub fn from_matrices(view_matrix: Matrix4<f32>, proj_matrix: Matrix4<f32>) -> Camera {
         return Camera {
             view_matrix: view_matrix,
+            projection: Projection::Matrix(proj_matrix),
             proj_matrix: proj_matrix,
             vp_matrix: proj_matrix * view_matrix
         };
     }
 
+    /// Get view matrix.
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        return self.view_matrix;
+    }
+
+    /// Get projection matrix.
+    pub fn proj_matrix(&self) -> Matrix4<f32> {
+        return self.proj_matrix;
+    }
+
+    /// Get the projection.
+    pub fn projection(&self) -> Projection {
+        return self.projection;
+    }
+
     /// Get VP matrix.
     pub fn vp_matrix(&self) -> Matrix4<f32> {
         return self.vp_matrix;
@@ -45,7 +145,125 @@ impl Camera {
 
     /// Update the projection matrix.
     pub fn perspective(&mut self, fovy: f32, aspect: f32, near: f32, far: f32) {
-        self.proj_matrix = cgmath::perspective(Deg::new(fovy), aspect, near, far);
+        self.set_projection(Projection::Perspective { fovy: fovy, aspect: aspect, near: near, far: far });
+    }
+
+    /// Update the projection matrix to an orthographic one, see `Projection::Orthographic`.
+    pub fn orthographic(&mut self, left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) {
+        self.set_projection(Projection::Orthographic {
+            left: left,
+            right: right,
+            bottom: bottom,
+            top: top,
+            near: near,
+            far: far
+        });
+    }
+
+    /// Update the projection.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.proj_matrix = projection.matrix();
         self.vp_matrix = self.proj_matrix * self.view_matrix;
     }
+
+    /// Update the projection's aspect ratio, see `Projection::with_aspect`.
+    pub fn set_aspect(&mut self, aspect: f32) {
+        let projection = self.projection.with_aspect(aspect);
+        self.set_projection(projection);
+    }
+
+    /// Update the projection's aspect ratio to match a viewport, e.g. on a window resize event.
+    ///
+    /// Empty sizes, e.g. of a minimized window, are ignored.
+    pub fn set_viewport_size(&mut self, width: i32, height: i32) {
+        if width > 0 && height > 0 {
+            self.set_aspect(width as f32 / height as f32);
+        }
+    }
+
+    /// Get the camera's position in world space.
+    pub fn position(&self) -> Point3<f32> {
+        return Point3::from_vec(self.camera_matrix().w.truncate());
+    }
+
+    /// Get the unit vector in world space the camera looks along.
+    pub fn forward(&self) -> Vector3<f32> {
+        // The camera looks down the negative z axis.
+        return -self.camera_matrix().z.truncate().normalize();
+    }
+
+    /// Get the camera's unit up vector in world space, perpendicular to `forward`.
+    pub fn up(&self) -> Vector3<f32> {
+        return self.camera_matrix().y.truncate().normalize();
+    }
+
+    /// Get the camera's unit right vector in world space.
+    pub fn right(&self) -> Vector3<f32> {
+        return self.camera_matrix().x.truncate().normalize();
+    }
+
+    /// Get the window coordinates of a world space point and its depth, see `unproject`.
+    ///
+    /// `None` if the point is in the plane of the camera or behind it with a perspective projection.
+    /// Points outside of the viewport give coordinates outside of it.
+    pub fn project(&self, point: Point3<f32>, width: f32, height: f32) -> Option<Point3<f32>> {
+        let clip = self.vp_matrix * point.to_homogeneous();
+
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+
+        return Some(Point3::new(
+            (ndc.x + 1.0) * 0.5 * width,
+            (1.0 - ndc.y) * 0.5 * height,
+            (ndc.z + 1.0) * 0.5));
+    }
+
+    /// Get the world space point under the window coordinates `x`, `y` at `depth`.
+    ///
+    /// The window coordinates start from the top left corner like the cursor position,
+    /// `width` and `height` are the viewport's size. `depth` is `0` at the near plane
+    /// and `1` at the far one, like in the depth buffer.
+    /// `None` if the VP matrix is not invertible.
+    pub fn unproject(&self, x: f32, y: f32, depth: f32, width: f32, height: f32) -> Option<Point3<f32>> {
+        let inverse = match self.vp_matrix.invert() {
+            Some(inverse) => inverse,
+            None => return None
+        };
+
+        let ndc = Vector4::new(2.0 * x / width - 1.0, 1.0 - 2.0 * y / height, 2.0 * depth - 1.0, 1.0);
+        let world = inverse * ndc;
+
+        if world.w == 0.0 {
+            return None;
+        }
+
+        return Some(Point3::from_vec(world.truncate() / world.w));
+    }
+
+    /// Get the ray from the near plane through the window coordinates `x`, `y`,
+    /// e.g. to find what the cursor is over. See `unproject` and `Scene::pick`.
+    pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Option<Ray> {
+        let near = self.unproject(x, y, 0.0, width, height);
+        let far = self.unproject(x, y, 1.0, width, height);
+
+        return match (near, far) {
+            (Some(near), Some(far)) if near != far => Some(Ray::new(near, far - near)),
+            _ => None
+        };
+    }
+
+    /// Get the point on the horizontal plane at `ground` under the window coordinates `x`, `y`,
+    /// e.g. where to move on a click. See `screen_ray` and `Ray::intersect_ground`.
+    pub fn screen_to_ground(&self, x: f32, y: f32, width: f32, height: f32, ground: f32) -> Option<Point3<f32>> {
+        return self.screen_ray(x, y, width, height).and_then(|ray| ray.intersect_ground(ground));
+    }
+
+    /// Get the inverse of the view matrix, i.e. the camera's transformation in world space.
+    fn camera_matrix(&self) -> Matrix4<f32> {
+        return self.view_matrix.invert().unwrap_or(Matrix4::identity());
+    }
 }
@@ -1,13 +1,21 @@
 extern crate cgmath;
 
-use self::cgmath::Matrix4;
+use self::cgmath::{
+    EuclideanSpace, InnerSpace, SquareMatrix, Transform,
+    Point3, Vector3, Matrix3, Matrix4, Quaternion
+};
 
-use super::node_container::NodeContainer;
+use super::node_container::{NodeContainer, NodeHandle, NodeParent};
 
 use super::camera::Camera;
+use super::id_picker::IdPass;
+use super::light::Lights;
 use super::renderable::Renderable;
+use super::TransformNode;
 
-use std::rc::Weak;
+use math::{Bounds, Ray, RayHit};
+
+use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
 
@@ -23,29 +31,149 @@ use std::ops::{Deref, DerefMut};
 /// feature - it can hold other `Composition`s as children.
 ///
 /// It's also self sustaining like the `Scene`.
+///
+/// Each composition is a `TransformNode` so the world space transformation of nested objects,
+/// e.g. the muzzle of a turret attached to a hull, can be queried outside of drawing.
+/// The model matrix of the wrapped object is cached and only recomputed after mutable access
+/// through `DerefMut`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[macro_use] extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::{Scene, Camera, Composition, Cuboid};
+/// # use cgmath::{Point3, Vector3, Vector4};
+/// # fn main() {
+/// let green = Vector4::new(0.2, 0.4, 0.2, 1.0);
+/// let hull = wrap!(Composition::new(Cuboid::new(
+///     Point3::new(0.0, 0.25, 0.0), Vector3::new(1.0, 0.5, 2.0), green)));
+/// let turret = wrap!(Composition::new(Cuboid::new(
+///     Point3::new(0.0, 0.5, 0.0), Vector3::new(0.6, 0.3, 0.6), green)));
+/// hull.borrow_mut().attach(Scene::node(&turret));
+///
+/// let mut scene = Scene::new(Camera::new());
+/// scene.add(Scene::node(&hull));
+///
+/// let muzzle = turret.borrow().local_to_world(Point3::new(0.0, 0.0, 2.0));
+/// # }
+/// ```
 pub struct Composition<T: Renderable> {
     renderable: T,
+    transform: Rc<TransformNode>,
     children: RefCell<NodeContainer>
 }
 
 impl<T: Renderable> Composition<T> {
     /// Create a new composition wrapper for a `Renderable`.
     pub fn new(renderable: T) -> Composition<T> {
+        let transform = Rc::new(TransformNode::new(renderable.model_matrix()));
+
         return Composition {
             renderable: renderable,
-            children: RefCell::new(NodeContainer::new())
+            transform: transform.clone(),
+            children: RefCell::new(NodeContainer::new(Some(transform)))
         };
     }
 
     /// Attaches a relative `Renderable` object.
     ///
     /// See `Scene.add` for more info.
-    pub fn attach<R>(&mut self, renderable: Weak<RefCell<R>>) -> &mut Self
+    pub fn attach<R>(&mut self, renderable: Weak<RefCell<R>>) -> NodeHandle
         where R: Renderable + 'static
     {
-        self.children.borrow_mut().add(renderable);
+        return self.add_node(renderable);
+    }
+
+    /// Check if the node of `handle` is a direct child of the composition.
+    pub fn contains(&self, handle: &NodeHandle) -> bool {
+        return self.children.borrow().contains(handle);
+    }
+
+    /// Get the composition's node in the transform hierarchy.
+    pub fn transform(&self) -> &Rc<TransformNode> {
+        return &self.transform;
+    }
+
+    /// Get the `Composition` this one is attached to.
+    ///
+    /// `None` if there is no parent or the parent itself was not added to a `Scene` or a `Composition`.
+    pub fn parent(&self) -> Option<Rc<RefCell<Renderable>>> {
+        return self.transform.parent().and_then(|parent| parent.renderable());
+    }
+
+    /// Get the transformation from the wrapped object's model space to world space.
+    ///
+    /// See `TransformNode::world_matrix`.
+    pub fn world_matrix(&self) -> Matrix4<f32> {
+        self.sync();
+
+        return self.transform.world_matrix();
+    }
 
-        return self;
+    /// Get the position of the wrapped object's origin in world space.
+    pub fn world_position(&self) -> Point3<f32> {
+        return Point3::from_vec(self.world_matrix().w.truncate());
+    }
+
+    /// Get the orientation of the wrapped object in world space, ignoring any scale.
+    pub fn world_orientation(&self) -> Quaternion<f32> {
+        let world = self.world_matrix();
+        let rotation = Matrix3::from_cols(
+            world.x.truncate().normalize(),
+            world.y.truncate().normalize(),
+            world.z.truncate().normalize());
+
+        return Quaternion::from(rotation).normalize();
+    }
+
+    /// Transforms a point from the wrapped object's model space, which is also
+    /// the space the children are in, to world space.
+    pub fn local_to_world(&self, point: Point3<f32>) -> Point3<f32> {
+        return self.world_matrix().transform_point(point);
+    }
+
+    /// Transforms a point from world space to the wrapped object's model space.
+    ///
+    /// `None` if the world matrix can't be inverted, e.g. due to a scale of `0`.
+    pub fn world_to_local(&self, point: Point3<f32>) -> Option<Point3<f32>> {
+        return self.world_matrix().invert().map(|inverse| inverse.transform_point(point));
+    }
+
+    /// Transforms a direction from the wrapped object's model space to world space.
+    pub fn local_to_world_vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        return self.world_matrix().transform_vector(vector);
+    }
+
+    /// Transforms a direction from world space to the wrapped object's model space.
+    ///
+    /// `None` if the world matrix can't be inverted, e.g. due to a scale of `0`.
+    pub fn world_to_local_vector(&self, vector: Vector3<f32>) -> Option<Vector3<f32>> {
+        return self.world_matrix().invert().map(|inverse| inverse.transform_vector(vector));
+    }
+
+    /// Get the volume containing the wrapped object and all visible children in world space.
+    ///
+    /// See `Renderable::bounds`.
+    pub fn world_bounds(&self) -> Option<Bounds> {
+        return self.bounds().map(|bounds| bounds.transform(&self.world_matrix()));
+    }
+
+    /// Refreshes the cached model matrix after mutable access.
+    fn sync(&self) {
+        if self.transform.local_dirty.get() {
+            self.transform.set_local(self.renderable.model_matrix());
+        }
+    }
+
+    fn local_matrix(&self) -> Matrix4<f32> {
+        self.sync();
+
+        return self.transform.local.get();
+    }
+
+    fn each_child<F: FnMut(&Renderable)>(&self, f: F) {
+        self.children.borrow_mut().each(f);
     }
 }
 
@@ -54,22 +182,106 @@ impl<T: Renderable> Renderable for Composition<T> {
         return self.renderable.priority();
     }
 
+    /// Transparent if the wrapped object or any visible child is, since they are drawn together.
+    fn is_transparent(&self) -> bool {
+        let mut transparent = self.renderable.is_transparent();
+        self.each_child(|child| transparent = transparent || child.is_transparent());
+
+        return transparent;
+    }
+
     fn model_matrix(&self) -> Matrix4<f32> {
-        return self.renderable.model_matrix();
+        return self.local_matrix();
     }
 
     fn draw(&self, draw_space: Matrix4<f32>, camera: &Camera) {
         self.renderable.draw(draw_space, camera);
+        self.each_child(|child| child.draw(draw_space * self.local_matrix(), camera));
+    }
+
+    fn draw_lit(&self, draw_space: Matrix4<f32>, camera: &Camera, lights: &Lights) {
+        self.renderable.draw_lit(draw_space, camera, lights);
+        self.each_child(|child| child.draw_lit(draw_space * self.local_matrix(), camera, lights));
+    }
+
+    /// Only if the wrapped object and all visible children have one, since they are drawn together.
+    fn has_gbuffer_pass(&self) -> bool {
+        let mut has_gbuffer_pass = self.renderable.has_gbuffer_pass();
+        self.each_child(|child| has_gbuffer_pass = has_gbuffer_pass && child.has_gbuffer_pass());
+
+        return has_gbuffer_pass;
+    }
+
+    fn draw_gbuffer(&self, draw_space: Matrix4<f32>, camera: &Camera) {
+        self.renderable.draw_gbuffer(draw_space, camera);
+        self.each_child(|child| child.draw_gbuffer(draw_space * self.local_matrix(), camera));
+    }
+
+    fn draw_shadow(&self, draw_space: Matrix4<f32>, light_vp: &Matrix4<f32>) {
+        self.renderable.draw_shadow(draw_space, light_vp);
+        self.each_child(|child| child.draw_shadow(draw_space * self.local_matrix(), light_vp));
+    }
+
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {
+        self.renderable.draw_id(draw_space, pass);
+        self.each_child(|child| child.draw_id(draw_space * self.local_matrix(), pass));
+    }
 
-        self.children.borrow_mut().retain(|child_wk| {
-            match child_wk.upgrade() {
-                Some(child) => {
-                    child.borrow().draw(draw_space * self.renderable.model_matrix(), camera);
-                    return true;
+    /// Encloses the wrapped object's bounds and the visible children's.
+    /// `None` if any of them is unbounded.
+    fn bounds(&self) -> Option<Bounds> {
+        let mut bounds = self.renderable.bounds();
+        let mut unbounded = bounds.is_none();
+
+        self.each_child(|child| {
+            match child.bounds() {
+                Some(child_bounds) => {
+                    let child_bounds = child_bounds.transform(&child.model_matrix());
+                    bounds = Some(match bounds {
+                        Some(bounds) => bounds.union(&child_bounds),
+                        None => child_bounds
+                    });
                 },
-                None => return false
+                None => unbounded = true
+            }
+        });
+
+        return if unbounded { None } else { bounds };
+    }
+
+    /// The closest hit of the wrapped object and the visible children.
+    fn intersect_ray(&self, ray: &Ray) -> Option<RayHit> {
+        let mut closest = self.renderable.intersect_ray(ray);
+
+        let local_matrix = self.local_matrix();
+        let local_ray = match local_matrix.invert() {
+            Some(inverse) => ray.transform(&inverse),
+            None => return closest
+        };
+
+        self.each_child(|child| {
+            if let Some(hit) = child.intersect_ray(&local_ray) {
+                if closest.map_or(true, |closest| hit.distance < closest.distance) {
+                    closest = Some(hit.transform(&local_matrix));
+                }
             }
         });
+
+        return closest;
+    }
+
+    fn transform_node(&self) -> Option<&TransformNode> {
+        return Some(&self.transform);
+    }
+}
+
+impl<T: Renderable> NodeParent for Composition<T> {
+    fn add_node(&mut self, node: Weak<RefCell<Renderable>>) -> NodeHandle {
+        return self.children.borrow_mut().add(node);
+    }
+
+    fn parent_node(&self) -> Option<&TransformNode> {
+        return Some(&self.transform);
     }
 }
 
@@ -83,6 +295,9 @@ impl<T: Renderable> Deref for Composition<T> {
 
 impl<T: Renderable> DerefMut for Composition<T> {
     fn deref_mut(&mut self) -> &mut T {
+        // The model matrix may change through the returned reference.
+        self.transform.local_dirty.set(true);
+
         return &mut self.renderable;
     }
 }
@@ -0,0 +1,677 @@
+extern crate cgmath;
+
+use self::cgmath::{Matrix4, SquareMatrix, Vector3, InnerSpace, EuclideanSpace};
+
+use gliw::{
+    gl, Gliw, DepthFunction,
+    Attachment, Framebuffer, FramebufferTarget,
+    Buffer, BufferType, BufferUsagePattern,
+    Program, ProgramBuilder, Shader, ShaderType,
+    Texture, TextureType, InternalFormat,
+    Uniform, UniformData,
+    Vao, VertexAttrib, AttribFloatFormat
+};
+
+use super::camera::Camera;
+use super::light::{Lights, PointLight, SpotLight, DirectionalLight};
+use super::shadow::{ShadowMap, ShadowSettings};
+
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+
+/// Renderer for the deferred shading path of a `Scene`.
+///
+/// Renders in up to four passes, the shadow pass being optional:
+///
+/// * Geometry - the renderables write their surface properties to the G-buffer
+/// with `Renderable::draw_gbuffer`.
+/// * Shadow - if shadows are enabled, the `ShadowMap` of the `DirectionalLight` is rendered
+/// with `Renderable::draw_shadow`.
+/// * Lighting - the `DirectionalLight` is applied to the whole screen, while the contribution
+/// of each `PointLight` and `SpotLight` is accumulated by drawing a volume enclosing its radius,
+/// so only the affected pixels get shaded.
+/// * Composition - the lit image is written to the framebuffer which was bound for drawing
+/// when `draw` was called, along with the depth, so objects drawn afterwards are still
+/// depth tested correctly.
+///
+/// Fragment shaders used with `draw_gbuffer` have to write:
+///
+/// * `layout (location = 0) out vec3` - albedo.
+/// * `layout (location = 1) out vec3` - world space normal.
+/// * `layout (location = 2) out vec2` - specular intensity and shininess divided by 256, in the range [0, 1].
+/// * `layout (location = 3) out vec3` - emissive color, added to the lit result.
+///
+/// Leaves that framebuffer bound with depth testing (`DepthFunction::Less`)
+/// and back face culling enabled.
+///
+/// # References
+/// * [Deferred Shading](https://www.opengl.org/wiki/Deferred_Shading)
+pub struct DeferredRenderer {
+    width: i32,
+    height: i32,
+
+    gbuffer: Framebuffer,
+    albedo: Texture,
+    normal: Texture,
+    material: Texture,
+    emissive: Texture,
+    depth: Texture,
+
+    light_buffer: Framebuffer,
+    light_accum: Texture,
+
+    shadow_map: Option<ShadowMap>,
+
+    light_program: Rc<Program>,
+    directional_program: Rc<Program>,
+    compose_program: Rc<Program>,
+
+    volume_vao: Vao,
+    #[allow(dead_code)]
+    volume_vbo: Buffer,
+    volume_ebo: Buffer,
+    screen_vao: Vao
+}
+
+impl DeferredRenderer {
+    /// Create a renderer with a G-buffer of the given size, which should match the viewport.
+    ///
+    /// Fails if the shaders fail to compile or the framebuffers are incomplete.
+    pub fn new(width: i32, height: i32) -> Result<DeferredRenderer, String> {
+        let light_program = match build_program(LIGHT_VS_SRC, LIGHT_FS_SRC) {
+            Ok(program) => program,
+            Err(err) => return Err(err)
+        };
+
+        let directional_program = match build_program(COMPOSE_VS_SRC, DIRECTIONAL_FS_SRC) {
+            Ok(program) => program,
+            Err(err) => return Err(err)
+        };
+
+        let compose_program = match build_program(COMPOSE_VS_SRC, COMPOSE_FS_SRC) {
+            Ok(program) => program,
+            Err(err) => return Err(err)
+        };
+
+        let volume_vao = Vao::new();
+        let volume_vbo = Buffer::from_data(&VOLUME_VERTICES, BufferType::Array, BufferUsagePattern::StaticDraw);
+        let volume_ebo = Buffer::from_data(&VOLUME_ELEMENTS, BufferType::ElementArray, BufferUsagePattern::StaticDraw);
+
+        let va = VertexAttrib::new(0);
+        va.data_float_format(&volume_vao, &volume_vbo, AttribFloatFormat::Float(3), 0, ptr::null());
+        va.enable(&volume_vao);
+
+        let renderer = DeferredRenderer {
+            width: width,
+            height: height,
+
+            gbuffer: Framebuffer::new(),
+            albedo: Texture::new(TextureType::Tex2D),
+            normal: Texture::new(TextureType::Tex2D),
+            material: Texture::new(TextureType::Tex2D),
+            emissive: Texture::new(TextureType::Tex2D),
+            depth: Texture::new(TextureType::Tex2D),
+
+            light_buffer: Framebuffer::new(),
+            light_accum: Texture::new(TextureType::Tex2D),
+
+            shadow_map: None,
+
+            light_program: light_program,
+            directional_program: directional_program,
+            compose_program: compose_program,
+
+            volume_vao: volume_vao,
+            volume_vbo: volume_vbo,
+            volume_ebo: volume_ebo,
+            screen_vao: Vao::new()
+        };
+
+        renderer.alloc_targets();
+
+        renderer.gbuffer.attach_texture(Attachment::Color(0), &renderer.albedo, 0);
+        renderer.gbuffer.attach_texture(Attachment::Color(1), &renderer.normal, 0);
+        renderer.gbuffer.attach_texture(Attachment::Color(2), &renderer.material, 0);
+        renderer.gbuffer.attach_texture(Attachment::Color(3), &renderer.emissive, 0);
+        renderer.gbuffer.attach_texture(Attachment::Depth, &renderer.depth, 0);
+        renderer.gbuffer.draw_buffers(&[
+            Some(Attachment::Color(0)),
+            Some(Attachment::Color(1)),
+            Some(Attachment::Color(2)),
+            Some(Attachment::Color(3))
+        ]);
+
+        renderer.light_buffer.attach_texture(Attachment::Color(0), &renderer.light_accum, 0);
+
+        let status = renderer.gbuffer.check_status().and_then(|_| renderer.light_buffer.check_status());
+        Framebuffer::bind_default(FramebufferTarget::Both);
+
+        return match status {
+            Ok(_) => Ok(renderer),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Reallocates the G-buffer for a new viewport size.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        self.alloc_targets();
+    }
+
+    /// Enables shadows cast from the directional light.
+    ///
+    /// Fails if the shadow map framebuffers are incomplete.
+    pub fn enable_shadows(&mut self, settings: ShadowSettings) -> Result<(), String> {
+        return match ShadowMap::new(settings) {
+            Ok(shadow_map) => {
+                self.shadow_map = Some(shadow_map);
+                Ok(())
+            },
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Disables shadows and frees the shadow map.
+    pub fn disable_shadows(&mut self) {
+        self.shadow_map = None;
+    }
+
+    /// Get the shadow map, if shadows are enabled.
+    pub fn shadow_map(&self) -> Option<&ShadowMap> {
+        return self.shadow_map.as_ref();
+    }
+
+    /// Get mutable reference to the shadow map, if shadows are enabled.
+    pub fn shadow_map_mut(&mut self) -> Option<&mut ShadowMap> {
+        return self.shadow_map.as_mut();
+    }
+
+    /// Runs all passes.
+    ///
+    /// `draw_geometry` should issue the `Renderable::draw_gbuffer` calls and `draw_shadow_casters`
+    /// the `Renderable::draw_shadow` calls with the light view projection matrix it is given.
+    pub fn draw<F, S>(&self, lights: &Lights, camera: &Camera, draw_geometry: F, draw_shadow_casters: S)
+        where F: FnOnce(), S: Fn(&Matrix4<f32>)
+    {
+        let mut target = 0;
+        unsafe { gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut target); }
+
+        if let (Some(ref sun), Some(shadow_map)) = (lights.directional, self.shadow_map.as_ref()) {
+            shadow_map.render(sun, camera, draw_shadow_casters);
+        }
+
+        self.geometry_pass(draw_geometry);
+        self.lighting_pass(lights, camera);
+        self.composition_pass(lights.ambient, target as u32);
+    }
+
+    fn geometry_pass<F: FnOnce()>(&self, draw_geometry: F) {
+        self.gbuffer.bind(FramebufferTarget::Both);
+
+        unsafe {
+            gl::Viewport(0, 0, self.width, self.height);
+            gl::DepthMask(gl::TRUE);
+        }
+
+        Gliw::enable(gl::DEPTH_TEST);
+        Gliw::depth_func(DepthFunction::Less);
+        Gliw::clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        draw_geometry();
+    }
+
+    fn lighting_pass(&self, lights: &Lights, camera: &Camera) {
+        self.light_buffer.bind(FramebufferTarget::Both);
+
+        unsafe { gl::ClearBufferfv(gl::COLOR, 0, [0.0f32; 4].as_ptr()); }
+
+        if let Some(ref sun) = lights.directional {
+            self.directional_light(sun, camera);
+        }
+
+        unsafe {
+
+            // Front faces are culled so that the volumes still work with the camera inside them.
+            gl::DepthMask(gl::FALSE);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+            gl::CullFace(gl::FRONT);
+        }
+
+        Gliw::disable(gl::DEPTH_TEST);
+        Gliw::enable(gl::BLEND);
+        Gliw::enable(gl::CULL_FACE);
+
+        let program = &self.light_program;
+        self.albedo.pass_to(program, "gbuffer_albedo", 0);
+        self.normal.pass_to(program, "gbuffer_normal", 1);
+        self.material.pass_to(program, "gbuffer_material", 2);
+        self.depth.pass_to(program, "gbuffer_depth", 3);
+
+        let vp_matrix = camera.vp_matrix();
+        let inv_vp_matrix = vp_matrix.invert().unwrap_or(Matrix4::identity());
+        let eye = camera.view_matrix().invert().unwrap_or(Matrix4::identity()).w;
+
+        unsafe {
+            Uniform::new(program, "inv_vp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(inv_vp_matrix)));
+        }
+        Uniform::new(program, "viewport_size").value(UniformData::Float2(self.width as f32, self.height as f32));
+        Uniform::new(program, "eye_position").value(UniformData::Float3(eye.x, eye.y, eye.z));
+
+        self.volume_vao.bind();
+        self.volume_ebo.bind();
+
+        Uniform::new(program, "light_is_spot").value(UniformData::Int1(0));
+        for light in lights.points.iter() {
+            self.light_volume(light, vp_matrix);
+        }
+
+        // A spot light's cone is a part of its sphere, so the same volume is used.
+        Uniform::new(program, "light_is_spot").value(UniformData::Int1(1));
+        for spot in lights.spots.iter() {
+            let direction = spot.direction.normalize();
+            let (inner, outer) = spot.cone_cosines();
+
+            Uniform::new(program, "spot_direction").value(UniformData::Float3(direction.x, direction.y, direction.z));
+            Uniform::new(program, "spot_cone").value(UniformData::Float2(inner, outer));
+
+            self.light_volume(&point_of(spot), vp_matrix);
+        }
+
+        unsafe {
+            gl::CullFace(gl::BACK);
+            gl::DepthMask(gl::TRUE);
+        }
+
+        Gliw::disable(gl::BLEND);
+    }
+
+    fn light_volume(&self, light: &PointLight, vp_matrix: Matrix4<f32>) {
+        let program = &self.light_program;
+        let model_matrix =
+            Matrix4::from_translation(light.position.to_vec()) *
+            Matrix4::from_scale(light.radius * VOLUME_SCALE);
+        let color = light.color * light.intensity;
+
+        unsafe {
+            Uniform::new(program, "mvp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(vp_matrix * model_matrix)));
+        }
+        Uniform::new(program, "light_position").value(UniformData::Float3(light.position.x, light.position.y, light.position.z));
+        Uniform::new(program, "light_color").value(UniformData::Float3(color.x, color.y, color.z));
+        Uniform::new(program, "light_radius").value(UniformData::Float1(light.radius));
+
+        unsafe { gl::DrawElements(gl::TRIANGLES, VOLUME_ELEMENTS.len() as i32, gl::UNSIGNED_BYTE, ptr::null()); }
+    }
+
+    fn directional_light(&self, sun: &DirectionalLight, camera: &Camera) {
+        unsafe {
+            gl::DepthMask(gl::FALSE);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+
+        Gliw::disable(gl::DEPTH_TEST);
+        Gliw::enable(gl::BLEND);
+
+        let program = &self.directional_program;
+        self.albedo.pass_to(program, "gbuffer_albedo", 0);
+        self.normal.pass_to(program, "gbuffer_normal", 1);
+        self.material.pass_to(program, "gbuffer_material", 2);
+        self.depth.pass_to(program, "gbuffer_depth", 3);
+
+        let inv_vp_matrix = camera.vp_matrix().invert().unwrap_or(Matrix4::identity());
+        let eye = camera.view_matrix().invert().unwrap_or(Matrix4::identity()).w;
+        let direction = sun.direction.normalize();
+        let color = sun.color * sun.intensity;
+
+        unsafe {
+            Uniform::new(program, "inv_vp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(inv_vp_matrix)));
+            Uniform::new(program, "view").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(camera.view_matrix())));
+        }
+        Uniform::new(program, "eye_position").value(UniformData::Float3(eye.x, eye.y, eye.z));
+        Uniform::new(program, "light_direction").value(UniformData::Float3(direction.x, direction.y, direction.z));
+        Uniform::new(program, "light_color").value(UniformData::Float3(color.x, color.y, color.z));
+
+        match self.shadow_map {
+            Some(ref shadow_map) => {
+                let settings = shadow_map.settings();
+                let cascades = shadow_map.cascades();
+
+                let mut matrices = Vec::with_capacity(cascades.len() * 16);
+                let mut splits = Vec::with_capacity(cascades.len());
+                let mut normal_offsets = Vec::with_capacity(cascades.len());
+
+                for cascade in cascades.iter() {
+                    // Maps the clip space of the cascade to texture coordinates and depth in [0, 1].
+                    let matrix = Matrix4::from_translation(Vector3::new(0.5, 0.5, 0.5)) *
+                        Matrix4::from_scale(0.5) * cascade.vp_matrix;
+                    let matrix: [f32; 16] = unsafe { mem::transmute(matrix) };
+
+                    matrices.extend_from_slice(&matrix);
+                    splits.push(cascade.split);
+                    normal_offsets.push(cascade.texel_size * settings.normal_bias);
+                }
+
+                shadow_map.texture().pass_to(program, "shadow_map", 4);
+
+                Uniform::new(program, "cascade_count").value(UniformData::Int1(cascades.len() as i32));
+                if !cascades.is_empty() {
+                    Uniform::new(program, "cascade_matrices").value(UniformData::FloatMat(4, false, &matrices));
+                    Uniform::new(program, "cascade_splits").value(UniformData::FloatVec(1, &splits));
+                    Uniform::new(program, "cascade_normal_offsets").value(UniformData::FloatVec(1, &normal_offsets));
+                }
+                Uniform::new(program, "depth_bias").value(UniformData::Float1(settings.depth_bias));
+                Uniform::new(program, "pcf_radius").value(UniformData::Int1(settings.pcf_radius.max(0)));
+                Uniform::new(program, "shadow_texel").value(UniformData::Float1(1.0 / settings.resolution as f32));
+            },
+            None => {
+                Uniform::new(program, "cascade_count").value(UniformData::Int1(0));
+            }
+        }
+
+        self.screen_vao.bind();
+        unsafe { gl::DrawArrays(gl::TRIANGLES, 0, 3); }
+
+        Gliw::disable(gl::BLEND);
+        unsafe { gl::DepthMask(gl::TRUE); }
+    }
+
+    fn composition_pass(&self, ambient: Vector3<f32>, target: u32) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, target); }
+
+        // The depth is written by the shader, so the test has to pass unconditionally.
+        Gliw::enable(gl::DEPTH_TEST);
+        Gliw::depth_func(DepthFunction::Always);
+
+        let program = &self.compose_program;
+        self.albedo.pass_to(program, "gbuffer_albedo", 0);
+        self.light_accum.pass_to(program, "light_accum", 1);
+        self.depth.pass_to(program, "gbuffer_depth", 2);
+        self.emissive.pass_to(program, "gbuffer_emissive", 3);
+        Uniform::new(program, "ambient").value(UniformData::Float3(ambient.x, ambient.y, ambient.z));
+
+        self.screen_vao.bind();
+        unsafe { gl::DrawArrays(gl::TRIANGLES, 0, 3); }
+
+        Gliw::depth_func(DepthFunction::Less);
+    }
+
+    fn alloc_targets(&self) {
+        self.albedo.alloc_2d(self.width, self.height, InternalFormat::RGBA8);
+        self.normal.alloc_2d(self.width, self.height, InternalFormat::RGBA16F);
+        self.material.alloc_2d(self.width, self.height, InternalFormat::RG8);
+        self.emissive.alloc_2d(self.width, self.height, InternalFormat::RGBA16F);
+        self.depth.alloc_2d(self.width, self.height, InternalFormat::Depth24);
+        self.light_accum.alloc_2d(self.width, self.height, InternalFormat::RGBA16F);
+    }
+}
+
+fn point_of(spot: &SpotLight) -> PointLight {
+    let mut light = PointLight::new(spot.position, spot.color, spot.radius);
+    light.intensity = spot.intensity;
+
+    return light;
+}
+
+fn build_program(vs_src: &str, fs_src: &str) -> Result<Rc<Program>, String> {
+    let vs = match Shader::new(ShaderType::Vertex, vs_src) {
+        Ok(shader) => shader,
+        Err(err) => return Err(err)
+    };
+
+    let fs = match Shader::new(ShaderType::Fragment, fs_src) {
+        Ok(shader) => shader,
+        Err(err) => return Err(err)
+    };
+
+    return ProgramBuilder::new()
+        .attach_vs(&vs)
+        .attach_fs(&fs)
+        .link();
+}
+
+/// Scale of the unit icosahedron so that it encloses the unit sphere.
+const VOLUME_SCALE: f32 = 1.26;
+
+const LIGHT_VS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform mat4 mvp;
+
+    layout (location = 0) in vec3 vs_position;
+
+    void main() {
+        gl_Position = mvp * vec4(vs_position, 1.0);
+    }
+"#;
+
+const LIGHT_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D gbuffer_albedo;
+    uniform sampler2D gbuffer_normal;
+    uniform sampler2D gbuffer_material;
+    uniform sampler2D gbuffer_depth;
+
+    uniform mat4 inv_vp;
+    uniform vec2 viewport_size;
+    uniform vec3 eye_position;
+
+    uniform vec3 light_position;
+    uniform vec3 light_color;
+    uniform float light_radius;
+
+    uniform int light_is_spot;
+    uniform vec3 spot_direction;
+    // Cosines of the inner and outer cone angles.
+    uniform vec2 spot_cone;
+
+    out vec4 color;
+
+    void main() {
+        vec2 uv = gl_FragCoord.xy / viewport_size;
+
+        float depth = texture(gbuffer_depth, uv).r;
+        if (depth == 1.0) {
+            discard;
+        }
+
+        vec4 world = inv_vp * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+        vec3 position = world.xyz / world.w;
+
+        vec3 to_light = light_position - position;
+        float dist = length(to_light);
+        if (dist > light_radius) {
+            discard;
+        }
+
+        vec3 albedo = texture(gbuffer_albedo, uv).rgb;
+        vec3 normal = normalize(texture(gbuffer_normal, uv).xyz);
+        vec2 material = texture(gbuffer_material, uv).rg;
+
+        vec3 l = to_light / dist;
+        vec3 h = normalize(l + normalize(eye_position - position));
+
+        float falloff = clamp(1.0 - pow(dist / light_radius, 4.0), 0.0, 1.0);
+        float attenuation = falloff * falloff / (dist * dist + 1.0);
+
+        if (light_is_spot != 0) {
+            attenuation *= smoothstep(spot_cone.y, spot_cone.x, dot(-l, spot_direction));
+        }
+
+        float diffuse = max(dot(normal, l), 0.0);
+        float specular = diffuse > 0.0 ? material.r * pow(max(dot(normal, h), 0.0), material.g * 256.0) : 0.0;
+
+        color = vec4((albedo * diffuse + specular) * light_color * attenuation, 1.0);
+    }
+"#;
+
+const DIRECTIONAL_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    const int MAX_CASCADES = 4;
+
+    uniform sampler2D gbuffer_albedo;
+    uniform sampler2D gbuffer_normal;
+    uniform sampler2D gbuffer_material;
+    uniform sampler2D gbuffer_depth;
+    uniform sampler2DArrayShadow shadow_map;
+
+    uniform mat4 inv_vp;
+    uniform mat4 view;
+    uniform vec3 eye_position;
+
+    uniform vec3 light_direction;
+    uniform vec3 light_color;
+
+    uniform int cascade_count;
+    uniform mat4 cascade_matrices[MAX_CASCADES];
+    uniform float cascade_splits[MAX_CASCADES];
+    uniform float cascade_normal_offsets[MAX_CASCADES];
+    uniform float depth_bias;
+    uniform int pcf_radius;
+    uniform float shadow_texel;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    float shadow(vec3 position, vec3 normal) {
+        float view_depth = -(view * vec4(position, 1.0)).z;
+
+        int cascade = -1;
+        for (int i = cascade_count - 1; i >= 0; i--) {
+            if (view_depth < cascade_splits[i]) {
+                cascade = i;
+            }
+        }
+
+        if (cascade < 0) {
+            return 1.0;
+        }
+
+        vec3 offset_position = position + normal * cascade_normal_offsets[cascade];
+        vec4 coord = cascade_matrices[cascade] * vec4(offset_position, 1.0);
+
+        if (any(lessThan(coord.xy, vec2(0.0))) || any(greaterThan(coord.xy, vec2(1.0)))) {
+            return 1.0;
+        }
+
+        // Percentage closer filtering over a square kernel.
+        float lit = 0.0;
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            for (int x = -pcf_radius; x <= pcf_radius; x++) {
+                vec2 offset = vec2(x, y) * shadow_texel;
+                lit += texture(shadow_map, vec4(coord.xy + offset, float(cascade), coord.z - depth_bias));
+            }
+        }
+
+        float samples = float((2 * pcf_radius + 1) * (2 * pcf_radius + 1));
+        return lit / samples;
+    }
+
+    void main() {
+        float depth = texture(gbuffer_depth, uv).r;
+        if (depth == 1.0) {
+            discard;
+        }
+
+        vec4 world = inv_vp * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+        vec3 position = world.xyz / world.w;
+
+        vec3 albedo = texture(gbuffer_albedo, uv).rgb;
+        vec3 normal = normalize(texture(gbuffer_normal, uv).xyz);
+        vec2 material = texture(gbuffer_material, uv).rg;
+
+        vec3 l = -light_direction;
+        vec3 h = normalize(l + normalize(eye_position - position));
+
+        float diffuse = max(dot(normal, l), 0.0);
+        float specular = diffuse > 0.0 ? material.r * pow(max(dot(normal, h), 0.0), material.g * 256.0) : 0.0;
+
+        float visibility = diffuse > 0.0 ? shadow(position, normal) : 0.0;
+
+        color = vec4((albedo * diffuse + specular) * light_color * visibility, 1.0);
+    }
+"#;
+
+const COMPOSE_VS_SRC: &'static str = r#"
+    #version 330 core
+
+    out vec2 uv;
+
+    void main() {
+        // A single triangle covering the whole screen.
+        uv = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+        gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    }
+"#;
+
+const COMPOSE_FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D gbuffer_albedo;
+    uniform sampler2D light_accum;
+    uniform sampler2D gbuffer_depth;
+    uniform sampler2D gbuffer_emissive;
+
+    uniform vec3 ambient;
+
+    in vec2 uv;
+
+    out vec4 color;
+
+    void main() {
+        float depth = texture(gbuffer_depth, uv).r;
+        if (depth == 1.0) {
+            discard;
+        }
+
+        color = vec4(texture(gbuffer_albedo, uv).rgb * ambient + texture(light_accum, uv).rgb +
+            texture(gbuffer_emissive, uv).rgb, 1.0);
+        gl_FragDepth = depth;
+    }
+"#;
+
+// Icosahedron with unit circumradius.
+static VOLUME_VERTICES: [f32; 12*3] = [
+    -0.525731,  0.850651,  0.0,
+     0.525731,  0.850651,  0.0,
+    -0.525731, -0.850651,  0.0,
+     0.525731, -0.850651,  0.0,
+     0.0,      -0.525731,  0.850651,
+     0.0,       0.525731,  0.850651,
+     0.0,      -0.525731, -0.850651,
+     0.0,       0.525731, -0.850651,
+     0.850651,  0.0,      -0.525731,
+     0.850651,  0.0,       0.525731,
+    -0.850651,  0.0,      -0.525731,
+    -0.850651,  0.0,       0.525731,
+];
+
+static VOLUME_ELEMENTS: [u8; 20*3] = [
+    0, 11, 5,
+    0, 5, 1,
+    0, 1, 7,
+    0, 7, 10,
+    0, 10, 11,
+    1, 5, 9,
+    5, 11, 4,
+    11, 10, 2,
+    10, 7, 6,
+    7, 1, 8,
+    3, 9, 4,
+    3, 4, 2,
+    3, 2, 6,
+    3, 6, 8,
+    3, 8, 9,
+    4, 9, 5,
+    2, 4, 11,
+    6, 2, 10,
+    8, 6, 7,
+    9, 8, 1,
+];
@@ -0,0 +1,270 @@
+extern crate cgmath;
+
+use self::cgmath::Matrix4;
+
+use gliw::{
+    gl, Gliw, DepthFunction,
+    Attachment, Framebuffer, FramebufferTarget, Renderbuffer,
+    Program, ProgramBuilder, Shader, ShaderType,
+    Texture, TextureType, InternalFormat,
+    Uniform, UniformData
+};
+
+use super::NodeId;
+
+use std::mem;
+use std::rc::Rc;
+
+/// Pixel perfect picking by rendering the scene's node ids to an integer framebuffer.
+///
+/// Unlike `Scene::pick`, which tests rays against volumes, the ids are read back from what was
+/// actually drawn, so thin objects, e.g. a tank's barrel, can be selected exactly. The renderables
+/// opt in with `Renderable::draw_id` and only have to draw their geometry, the picker provides
+/// the shaders. See `Scene::draw_ids`.
+///
+/// Each pixel holds the `NodeId` of the node directly in the scene which drew it, so a
+/// `Composition` is picked as a whole, like with `Scene::pick`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::core::{Scene, Camera, IdPicker};
+/// # let scene = Scene::new(Camera::new());
+/// let picker = IdPicker::new(800, 600).unwrap();
+///
+/// // On click, with the cursor position.
+/// # let (x, y) = (400, 300);
+/// scene.draw_ids(&picker);
+/// if let Some(id) = picker.id_at(x, y) {
+///     println!("Selected node {}", id);
+/// }
+///
+/// // On releasing a selection rectangle.
+/// let selected = picker.ids_in(100, 100, 200, 150);
+/// ```
+pub struct IdPicker {
+    width: i32,
+    height: i32,
+
+    framebuffer: Framebuffer,
+    ids: Texture,
+    depth: Renderbuffer,
+
+    program: Rc<Program>
+}
+
+/// The state of an `IdPicker` pass, passed to `Renderable::draw_id`.
+///
+/// Its program is bound when `draw_id` is called. It reads vertex positions from attribute location `0`.
+pub struct IdPass<'a> {
+    program: &'a Rc<Program>,
+    vp_matrix: Matrix4<f32>
+}
+
+impl IdPicker {
+    /// Create a picker with an id buffer of the given size, which should match the viewport.
+    ///
+    /// Fails if the shaders fail to compile or the framebuffer is incomplete.
+    pub fn new(width: i32, height: i32) -> Result<IdPicker, String> {
+        let vs = match Shader::new(ShaderType::Vertex, VS_SRC) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err)
+        };
+
+        let fs = match Shader::new(ShaderType::Fragment, FS_SRC) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err)
+        };
+
+        let program = match ProgramBuilder::new().attach_vs(&vs).attach_fs(&fs).link() {
+            Ok(program) => program,
+            Err(err) => return Err(err)
+        };
+
+        let picker = IdPicker {
+            width: width,
+            height: height,
+            framebuffer: Framebuffer::new(),
+            ids: Texture::new(TextureType::Tex2D),
+            depth: Renderbuffer::new(),
+            program: program
+        };
+
+        picker.alloc_targets();
+
+        picker.framebuffer.attach_texture(Attachment::Color(0), &picker.ids, 0);
+        picker.framebuffer.attach_renderbuffer(Attachment::Depth, &picker.depth);
+
+        let status = picker.framebuffer.check_status();
+        Framebuffer::bind_default(FramebufferTarget::Both);
+
+        return match status {
+            Ok(_) => Ok(picker),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Reallocates the id buffer for a new viewport size.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        self.alloc_targets();
+    }
+
+    /// Get the id buffer.
+    pub fn texture(&self) -> &Texture {
+        return &self.ids;
+    }
+
+    /// Renders the ids of the nodes into the id buffer.
+    ///
+    /// `draw_nodes` should draw every node with `Renderable::draw_id`, calling `IdPass::set_node`
+    /// before each of them. Restores the framebuffer, the viewport, the depth state and the program
+    /// which were set before.
+    ///
+    /// See `Scene::draw_ids`.
+    pub fn render<F: FnOnce(&IdPass)>(&self, vp_matrix: Matrix4<f32>, draw_nodes: F) {
+        let mut target = 0;
+        let mut viewport = [0; 4];
+        let mut depth_test = 0;
+        let mut depth_func = 0;
+        let mut depth_mask = 0;
+        let mut program = 0;
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut target);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::DEPTH_TEST, &mut depth_test);
+            gl::GetIntegerv(gl::DEPTH_FUNC, &mut depth_func);
+            gl::GetIntegerv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+        }
+
+        self.framebuffer.bind(FramebufferTarget::Draw);
+
+        unsafe {
+            gl::Viewport(0, 0, self.width, self.height);
+            gl::DepthMask(gl::TRUE);
+            gl::ClearBufferuiv(gl::COLOR, 0, [0u32; 4].as_ptr());
+        }
+
+        Gliw::enable(gl::DEPTH_TEST);
+        Gliw::depth_func(DepthFunction::Less);
+        Gliw::clear(gl::DEPTH_BUFFER_BIT);
+
+        self.program.bind();
+
+        let pass = IdPass {
+            program: &self.program,
+            vp_matrix: vp_matrix
+        };
+
+        draw_nodes(&pass);
+
+        unsafe {
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target as u32);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+            gl::DepthFunc(depth_func as u32);
+            gl::DepthMask(depth_mask as u8);
+            gl::UseProgram(program as u32);
+        }
+
+        if depth_test == 0 {
+            Gliw::disable(gl::DEPTH_TEST);
+        }
+    }
+
+    /// Get the id of the node drawn at the window coordinates `x`, `y` by the last `render`,
+    /// `None` if there is none or the coordinates are outside of the id buffer.
+    ///
+    /// The window coordinates start from the top left corner like the cursor position.
+    pub fn id_at(&self, x: i32, y: i32) -> Option<NodeId> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let ids = self.framebuffer.read_integer_pixels(Attachment::Color(0), x, self.height - 1 - y, 1, 1);
+
+        return node_id(ids[0]);
+    }
+
+    /// Get the ids of the nodes with any pixel inside the rectangle at the window coordinates
+    /// `x`, `y` with the given size, e.g. for box selection. The ids are sorted and unique.
+    ///
+    /// The rectangle is clipped to the id buffer. See `id_at`.
+    pub fn ids_in(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<NodeId> {
+        // Any corner can be dragged from.
+        let (x, width) = if width < 0 { (x + width, -width) } else { (x, width) };
+        let (y, height) = if height < 0 { (y + height, -height) } else { (y, height) };
+
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = (x + width).min(self.width);
+        let bottom = (y + height).min(self.height);
+
+        if left >= right || top >= bottom {
+            return Vec::new();
+        }
+
+        let mut ids: Vec<NodeId> = self.framebuffer
+            .read_integer_pixels(Attachment::Color(0), left, self.height - bottom, right - left, bottom - top)
+            .into_iter()
+            .filter_map(node_id)
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+
+        return ids;
+    }
+
+    fn alloc_targets(&self) {
+        self.ids.alloc_2d(self.width, self.height, InternalFormat::R32UI);
+        self.depth.storage(InternalFormat::Depth24, self.width, self.height);
+    }
+}
+
+impl<'a> IdPass<'a> {
+    /// Set the id written by the geometry drawn next.
+    pub fn set_node(&self, id: NodeId) {
+        // Zero is no node.
+        Uniform::new(self.program, "id").value(UniformData::Uint1((id as u32).wrapping_add(1)));
+    }
+
+    /// Set the model matrix of the geometry drawn next.
+    pub fn set_model(&self, model_matrix: Matrix4<f32>) {
+        let mvp_matrix = self.vp_matrix * model_matrix;
+
+        unsafe {
+            Uniform::new(self.program, "mvp").value(UniformData::FloatMat(4, false,
+                &mem::transmute::<Matrix4<f32>, [f32; 16]>(mvp_matrix)));
+        }
+    }
+}
+
+fn node_id(pixel: u32) -> Option<NodeId> {
+    return if pixel == 0 { None } else { Some(pixel as NodeId - 1) };
+}
+
+const VS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform mat4 mvp;
+
+    layout (location = 0) in vec3 vs_position;
+
+    void main() {
+        gl_Position = mvp * vec4(vs_position, 1.0);
+    }
+"#;
+
+const FS_SRC: &'static str = r#"
+    #version 330 core
+
+    uniform uint id;
+
+    layout (location = 0) out uint node_id;
+
+    void main() {
+        node_id = id;
+    }
+"#;
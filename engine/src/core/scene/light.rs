@@ -0,0 +1,269 @@
+extern crate cgmath;
+
+use self::cgmath::{Point3, Vector3, Matrix4, SquareMatrix, InnerSpace};
+
+use gliw::{Program, Uniform, UniformData};
+
+use super::camera::Camera;
+
+use std::rc::Rc;
+
+/// The maximum number of point lights passed to forward shaders by `Lights::apply`.
+pub const MAX_POINT_LIGHTS: usize = 8;
+/// The maximum number of spot lights passed to forward shaders by `Lights::apply`.
+pub const MAX_SPOT_LIGHTS: usize = 4;
+
+/// Omnidirectional light with limited range.
+///
+/// See `Scene::add_light`.
+#[derive(Copy, Clone, Debug)]
+pub struct PointLight {
+    pub position: Point3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// Distance at which the light fades out completely.
+    pub radius: f32
+}
+
+impl PointLight {
+    /// Create a new point light with intensity `1.0`.
+    pub fn new(position: Point3<f32>, color: Vector3<f32>, radius: f32) -> PointLight {
+        return PointLight {
+            position: position,
+            color: color,
+            intensity: 1.0,
+            radius: radius
+        };
+    }
+}
+
+/// Light infinitely far away, lighting everything from the same `direction`, like the sun.
+///
+/// See `Scene::set_directional_light`.
+#[derive(Copy, Clone, Debug)]
+pub struct DirectionalLight {
+    /// Direction the light travels in. Does not have to be normalized.
+    pub direction: Vector3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32
+}
+
+impl DirectionalLight {
+    /// Create a new directional light with intensity `1.0`.
+    pub fn new(direction: Vector3<f32>, color: Vector3<f32>) -> DirectionalLight {
+        return DirectionalLight {
+            direction: direction,
+            color: color,
+            intensity: 1.0
+        };
+    }
+}
+
+/// Light emitted from a point in a cone, like a flashlight.
+///
+/// See `Scene::add_spot_light`.
+#[derive(Copy, Clone, Debug)]
+pub struct SpotLight {
+    pub position: Point3<f32>,
+    /// Direction of the cone's axis. Does not have to be normalized.
+    pub direction: Vector3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// Distance at which the light fades out completely.
+    pub radius: f32,
+    /// Angle from the axis in degrees at which the light starts to fade out.
+    pub inner_angle: f32,
+    /// Angle from the axis in degrees after which there is no light.
+    pub outer_angle: f32
+}
+
+impl SpotLight {
+    /// Create a new spot light with intensity `1.0` and a cone fading from 20 to 30 degrees.
+    pub fn new(position: Point3<f32>, direction: Vector3<f32>, color: Vector3<f32>, radius: f32) -> SpotLight {
+        return SpotLight {
+            position: position,
+            direction: direction,
+            color: color,
+            intensity: 1.0,
+            radius: radius,
+            inner_angle: 20.0,
+            outer_angle: 30.0
+        };
+    }
+
+    /// Get the cosines of the inner and outer angles, as used by the shaders.
+    pub fn cone_cosines(&self) -> (f32, f32) {
+        let outer = self.outer_angle.to_radians();
+        let inner = self.inner_angle.to_radians().min(outer);
+
+        return (inner.cos(), outer.cos());
+    }
+}
+
+/// Snapshot of the lights of a `Scene`, passed to `Renderable::draw_lit`.
+#[derive(Clone, Debug)]
+pub struct Lights {
+    /// Light reaching every surface from all directions.
+    pub ambient: Vector3<f32>,
+    pub directional: Option<DirectionalLight>,
+    pub points: Vec<PointLight>,
+    pub spots: Vec<SpotLight>
+}
+
+impl Lights {
+    /// Create an empty set with ambient light `(0.1, 0.1, 0.1)`.
+    pub fn new() -> Lights {
+        return Lights {
+            ambient: Vector3::new(0.1, 0.1, 0.1),
+            directional: None,
+            points: Vec::new(),
+            spots: Vec::new()
+        };
+    }
+
+    /// Create a set with only full white ambient light, which shows the surfaces' plain colors.
+    pub fn unlit() -> Lights {
+        let mut lights = Lights::new();
+        lights.ambient = Vector3::new(1.0, 1.0, 1.0);
+
+        return lights;
+    }
+
+    /// Passes the lights and the eye position of `camera` to the uniforms of `program`
+    /// declared in `LIGHTING_GLSL`.
+    ///
+    /// Only the first `MAX_POINT_LIGHTS` point lights and `MAX_SPOT_LIGHTS` spot lights are passed.
+    pub fn apply(&self, program: &Rc<Program>, camera: &Camera) {
+        let eye = camera.view_matrix().invert().unwrap_or(Matrix4::identity()).w;
+        Uniform::new(program, "eye_position").value(UniformData::Float3(eye.x, eye.y, eye.z));
+        Uniform::new(program, "ambient_light").value(UniformData::Float3(self.ambient.x, self.ambient.y, self.ambient.z));
+
+        match self.directional {
+            Some(ref light) => {
+                let direction = light.direction.normalize();
+                let color = light.color * light.intensity;
+                Uniform::new(program, "has_directional_light").value(UniformData::Int1(1));
+                Uniform::new(program, "directional_light_direction").value(UniformData::Float3(direction.x, direction.y, direction.z));
+                Uniform::new(program, "directional_light_color").value(UniformData::Float3(color.x, color.y, color.z));
+            },
+            None => {
+                Uniform::new(program, "has_directional_light").value(UniformData::Int1(0));
+            }
+        }
+
+        let points = &self.points[..self.points.len().min(MAX_POINT_LIGHTS)];
+        Uniform::new(program, "point_light_count").value(UniformData::Int1(points.len() as i32));
+
+        if !points.is_empty() {
+            let mut positions = Vec::with_capacity(points.len() * 3);
+            let mut colors = Vec::with_capacity(points.len() * 3);
+            let mut radii = Vec::with_capacity(points.len());
+
+            for light in points {
+                let color = light.color * light.intensity;
+                positions.extend_from_slice(&[light.position.x, light.position.y, light.position.z]);
+                colors.extend_from_slice(&[color.x, color.y, color.z]);
+                radii.push(light.radius);
+            }
+
+            Uniform::new(program, "point_light_positions").value(UniformData::FloatVec(3, &positions));
+            Uniform::new(program, "point_light_colors").value(UniformData::FloatVec(3, &colors));
+            Uniform::new(program, "point_light_radii").value(UniformData::FloatVec(1, &radii));
+        }
+
+        let spots = &self.spots[..self.spots.len().min(MAX_SPOT_LIGHTS)];
+        Uniform::new(program, "spot_light_count").value(UniformData::Int1(spots.len() as i32));
+
+        if !spots.is_empty() {
+            let mut positions = Vec::with_capacity(spots.len() * 3);
+            let mut directions = Vec::with_capacity(spots.len() * 3);
+            let mut colors = Vec::with_capacity(spots.len() * 3);
+            let mut radii = Vec::with_capacity(spots.len());
+            let mut cones = Vec::with_capacity(spots.len() * 2);
+
+            for light in spots {
+                let direction = light.direction.normalize();
+                let color = light.color * light.intensity;
+                let (inner, outer) = light.cone_cosines();
+                positions.extend_from_slice(&[light.position.x, light.position.y, light.position.z]);
+                directions.extend_from_slice(&[direction.x, direction.y, direction.z]);
+                colors.extend_from_slice(&[color.x, color.y, color.z]);
+                radii.push(light.radius);
+                cones.extend_from_slice(&[inner, outer]);
+            }
+
+            Uniform::new(program, "spot_light_positions").value(UniformData::FloatVec(3, &positions));
+            Uniform::new(program, "spot_light_directions").value(UniformData::FloatVec(3, &directions));
+            Uniform::new(program, "spot_light_colors").value(UniformData::FloatVec(3, &colors));
+            Uniform::new(program, "spot_light_radii").value(UniformData::FloatVec(1, &radii));
+            Uniform::new(program, "spot_light_cones").value(UniformData::FloatVec(2, &cones));
+        }
+    }
+}
+
+/// GLSL declarations of the `Lights` uniforms, to be placed after the `#version` directive.
+///
+/// `shade(position, normal, albedo, specular, shininess)` gives the Blinn-Phong lit color
+/// of a surface point in world space, including the ambient light.
+pub const LIGHTING_GLSL: &'static str = r#"
+    const int MAX_POINT_LIGHTS = 8;
+    const int MAX_SPOT_LIGHTS = 4;
+
+    uniform vec3 eye_position;
+    uniform vec3 ambient_light;
+
+    uniform int has_directional_light;
+    uniform vec3 directional_light_direction;
+    uniform vec3 directional_light_color;
+
+    uniform int point_light_count;
+    uniform vec3 point_light_positions[MAX_POINT_LIGHTS];
+    uniform vec3 point_light_colors[MAX_POINT_LIGHTS];
+    uniform float point_light_radii[MAX_POINT_LIGHTS];
+
+    uniform int spot_light_count;
+    uniform vec3 spot_light_positions[MAX_SPOT_LIGHTS];
+    uniform vec3 spot_light_directions[MAX_SPOT_LIGHTS];
+    uniform vec3 spot_light_colors[MAX_SPOT_LIGHTS];
+    uniform float spot_light_radii[MAX_SPOT_LIGHTS];
+    uniform vec2 spot_light_cones[MAX_SPOT_LIGHTS];
+
+    vec3 blinn_phong(vec3 n, vec3 l, vec3 v, vec3 albedo, float specular, float shininess) {
+        float diffuse = max(dot(n, l), 0.0);
+        float highlight = diffuse > 0.0 ? specular * pow(max(dot(n, normalize(l + v)), 0.0), shininess) : 0.0;
+        return albedo * diffuse + highlight;
+    }
+
+    float falloff(float dist, float radius) {
+        float fade = clamp(1.0 - pow(dist / radius, 4.0), 0.0, 1.0);
+        return fade * fade / (dist * dist + 1.0);
+    }
+
+    vec3 shade(vec3 position, vec3 normal, vec3 albedo, float specular, float shininess) {
+        vec3 n = normalize(normal);
+        vec3 v = normalize(eye_position - position);
+        vec3 color = albedo * ambient_light;
+
+        if (has_directional_light != 0) {
+            color += blinn_phong(n, -directional_light_direction, v, albedo, specular, shininess) * directional_light_color;
+        }
+
+        for (int i = 0; i < point_light_count; i++) {
+            vec3 to_light = point_light_positions[i] - position;
+            float dist = length(to_light);
+            color += blinn_phong(n, to_light / dist, v, albedo, specular, shininess) *
+                point_light_colors[i] * falloff(dist, point_light_radii[i]);
+        }
+
+        for (int i = 0; i < spot_light_count; i++) {
+            vec3 to_light = spot_light_positions[i] - position;
+            float dist = length(to_light);
+            vec3 l = to_light / dist;
+            float cone = smoothstep(spot_light_cones[i].y, spot_light_cones[i].x, dot(-l, spot_light_directions[i]));
+            color += blinn_phong(n, l, v, albedo, specular, shininess) *
+                spot_light_colors[i] * falloff(dist, spot_light_radii[i]) * cone;
+        }
+
+        return color;
+    }
+"#;
@@ -4,26 +4,92 @@ mod node_container;
 
 pub mod camera;
 pub mod composition;
+pub mod deferred;
+pub mod id_picker;
+pub mod light;
 pub mod renderable;
+pub mod shadow;
 
-use self::cgmath::{Matrix4, SquareMatrix};
+use self::cgmath::{Matrix4, SquareMatrix, Transform, Point3, Vector3};
 
 use self::node_container::NodeContainer;
 
+pub use self::node_container::{NodeHandle, NodeParent, NodeId};
+
 use self::camera::Camera;
+use self::deferred::DeferredRenderer;
+use self::id_picker::IdPicker;
+use self::light::{Lights, PointLight, SpotLight, DirectionalLight};
 use self::renderable::Renderable;
 
+use core::SpatialGrid;
+
+use gliw::{gl, Gliw};
+
+use math::{Frustum, Ray};
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::f32;
+
 use std::rc::{Rc, Weak};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+/// The way a `Scene` renders its objects.
+pub enum RenderPath {
+    /// Each `Renderable` is drawn directly to the bound framebuffer with `Renderable::draw_lit`.
+    /// Shadows are ignored.
+    Forward,
+    /// The `Renderable`s are drawn to a G-buffer and shaded by the scene's lights afterwards.
+    /// Transparent ones and the ones without `Renderable::has_gbuffer_pass` are drawn forward
+    /// on top of the result.
+    Deferred(DeferredRenderer)
+}
+
+/// The number of renderables drawn and culled by the last `Scene::draw`.
+///
+/// Only counts the direct children of the scene, since a `Composition` is culled as a whole.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CullStats {
+    pub drawn: u32,
+    pub culled: u32
+}
+
+/// The closest `Renderable` under the cursor, see `Scene::pick`.
+#[derive(Clone)]
+pub struct PickHit {
+    /// The id of the node directly in the scene, see `NodeHandle::id`.
+    pub id: NodeId,
+    /// The node directly in the scene. For a `Composition` it's the whole hierarchy,
+    /// e.g. the tank rather than its turret.
+    pub renderable: Rc<RefCell<Renderable>>,
+    /// The distance from the ray's origin.
+    pub distance: f32,
+    pub point: Point3<f32>,
+    pub normal: Vector3<f32>
+}
 
 /// Single threaded structure used for rendering `Renderable` objects.
 ///
 /// The scene uses a render priority system where the lower priority targets will be rendered earlier
 /// meaning that they will get overlapped by higher priority objects.
-/// It also sustains itself by removing any invalid `Weak` refs from the rendering queue.
+/// Within the same priority the opaque renderables are drawn front to back and the transparent ones
+/// last, back to front with alpha blending, see `Renderable::is_transparent`.
+/// It also sustains itself by removing any invalid `Weak` refs from the rendering queue and the lights.
+///
+/// Renderables with `Renderable::bounds` outside of the camera's frustum are not drawn,
+/// and the shadow casters outside of each light frustum are not drawn in its shadow pass.
 pub struct Scene {
     camera: Camera,
-    render_queue: RefCell<NodeContainer>
+    render_queue: RefCell<NodeContainer>,
+    lights: RefCell<Vec<Weak<RefCell<PointLight>>>>,
+    spot_lights: RefCell<Vec<Weak<RefCell<SpotLight>>>>,
+    directional_light: Option<DirectionalLight>,
+    ambient: Vector3<f32>,
+    render_path: RenderPath,
+    culling: bool,
+    cull_stats: Cell<CullStats>,
+    spatial_index: Option<Rc<RefCell<SpatialGrid<NodeId>>>>
 }
 
 impl Scene {
@@ -31,15 +97,67 @@ impl Scene {
     pub fn new(camera: Camera) -> Scene {
         return Scene {
             camera: camera,
-            render_queue: RefCell::new(NodeContainer::new())
+            render_queue: RefCell::new(NodeContainer::new(None)),
+            lights: RefCell::new(Vec::new()),
+            spot_lights: RefCell::new(Vec::new()),
+            directional_light: None,
+            ambient: Vector3::new(0.1, 0.1, 0.1),
+            render_path: RenderPath::Forward,
+            culling: true,
+            cull_stats: Cell::new(CullStats::default()),
+            spatial_index: None
         };
     }
 
+    /// Get reference to the scene's camera.
+    pub fn camera(&self) -> &Camera {
+        return &self.camera;
+    }
+
     /// Get mutable reference to the scene's camera.
     pub fn camera_mut(&mut self) -> &mut Camera {
         return &mut self.camera;
     }
 
+    /// Get mutable reference to the scene's render path.
+    pub fn render_path_mut(&mut self) -> &mut RenderPath {
+        return &mut self.render_path;
+    }
+
+    /// Set the render path. Defaults to `RenderPath::Forward`.
+    pub fn set_render_path(&mut self, render_path: RenderPath) -> &mut Self {
+        self.render_path = render_path;
+
+        return self;
+    }
+
+    /// Enable or disable frustum culling. Enabled by default.
+    pub fn set_culling(&mut self, culling: bool) -> &mut Self {
+        self.culling = culling;
+
+        return self;
+    }
+
+    /// Set a spatial index of the scene's nodes to cull and pick them with, instead of their bounds.
+    ///
+    /// The scene keeps the bounded nodes directly in it in the index, moving them when their
+    /// model matrix changes and evicting them once removed or dropped. This happens on every `draw`
+    /// and `pick`, so an index shared with game code can lag a frame behind. Nodes whose bounds change
+    /// without moving have to be updated with `SpatialGrid::insert_node`.
+    ///
+    /// The nodes of `Composition`s are not indexed and are culled with their parent.
+    pub fn set_spatial_index(&mut self, index: Option<Rc<RefCell<SpatialGrid<NodeId>>>>) -> &mut Self {
+        self.render_queue.borrow_mut().set_index(index.clone());
+        self.spatial_index = index;
+
+        return self;
+    }
+
+    /// Get the number of renderables drawn and culled by the last `draw`.
+    pub fn cull_stats(&self) -> CullStats {
+        return self.cull_stats.get();
+    }
+
     /// Downgrade a wrapped `Renderable`.
     ///
     /// See `engine::wrap!`
@@ -52,26 +170,486 @@ impl Scene {
     ///
     /// When adding two or more renderables with the same priority,
     /// the earlier added will have lower priority.
-    pub fn add<R>(&mut self, renderable: Weak<RefCell<R>>) -> &mut Self
+    /// Changing a renderable's priority afterwards moves it on the next `draw`.
+    ///
+    /// The returned handle can hide or remove the renderable without dropping it.
+    pub fn add<R>(&mut self, renderable: Weak<RefCell<R>>) -> NodeHandle
         where R: Renderable + 'static
     {
-        // The &mut self can be just &self but this way it shows the logical mutation.
+        return self.add_node(renderable);
+    }
+
+    /// Check if the node of `handle` is directly in the scene rather than in a `Composition`.
+    pub fn contains(&self, handle: &NodeHandle) -> bool {
+        return self.render_queue.borrow().contains(handle);
+    }
+
+    /// Add a point light to the scene.
+    ///
+    /// `RenderPath::Forward` only uses the first `MAX_POINT_LIGHTS` of them.
+    pub fn add_light(&mut self, light: Weak<RefCell<PointLight>>) -> &mut Self {
+        self.lights.borrow_mut().push(light);
+
+        return self;
+    }
+
+    /// Add a spot light to the scene.
+    ///
+    /// `RenderPath::Forward` only uses the first `MAX_SPOT_LIGHTS` of them.
+    pub fn add_spot_light(&mut self, light: Weak<RefCell<SpotLight>>) -> &mut Self {
+        self.spot_lights.borrow_mut().push(light);
+
+        return self;
+    }
+
+    /// Set the directional light, e.g. the sun. There is none by default.
+    ///
+    /// `RenderPath::Deferred` can also make it cast shadows, see `DeferredRenderer::enable_shadows`.
+    pub fn set_directional_light(&mut self, light: Option<DirectionalLight>) -> &mut Self {
+        self.directional_light = light;
+
+        return self;
+    }
+
+    /// Get mutable reference to the directional light.
+    pub fn directional_light_mut(&mut self) -> Option<&mut DirectionalLight> {
+        return self.directional_light.as_mut();
+    }
 
-        self.render_queue.borrow_mut().add(renderable);
+    /// Set the ambient light color applied to all surfaces. Defaults to `(0.1, 0.1, 0.1)`.
+    pub fn set_ambient(&mut self, ambient: Vector3<f32>) -> &mut Self {
+        self.ambient = ambient;
 
         return self;
     }
 
+    /// Get a snapshot of the scene's lights, as passed to `Renderable::draw_lit`.
+    pub fn lights(&self) -> Lights {
+        return Lights {
+            ambient: self.ambient,
+            directional: self.directional_light,
+            points: collect(&self.lights),
+            spots: collect(&self.spot_lights)
+        };
+    }
+
+    /// Find the closest visible `Renderable` under the window coordinates `x`, `y`,
+    /// e.g. the cursor position. `width` and `height` are the viewport's size.
+    ///
+    /// See `Camera::screen_ray`, `pick_ray` and `Renderable::intersect_ray`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use engine::core::{Scene, Camera};
+    /// # let scene = Scene::new(Camera::new());
+    /// # let (x, y, width, height) = (0.0, 0.0, 800.0, 600.0);
+    /// match scene.pick(x, y, width, height) {
+    ///     Some(hit) => println!("Selected node {} at {:?}", hit.id, hit.point),
+    ///     None => {
+    ///         // Move the selection to the clicked spot on the ground.
+    ///         let target = scene.camera().screen_ray(x, y, width, height)
+    ///             .and_then(|ray| ray.intersect_ground(0.0));
+    ///     }
+    /// }
+    /// ```
+    pub fn pick(&self, x: f32, y: f32, width: f32, height: f32) -> Option<PickHit> {
+        return self.camera.screen_ray(x, y, width, height).and_then(|ray| self.pick_ray(&ray));
+    }
+
+    /// Find the closest visible `Renderable` hit by a world space `ray`.
+    ///
+    /// The nodes in the spatial index are only tested if the ray hits their indexed bounds.
+    pub fn pick_ray(&self, ray: &Ray) -> Option<PickHit> {
+        // Refreshes the index before querying it.
+        let nodes = self.render_queue.borrow_mut().nodes();
+
+        let index = self.spatial_index.as_ref().map(|index| index.borrow());
+        let indexed_hits: Option<HashSet<NodeId>> = index.as_ref()
+            .map(|index| index.query_ray(ray, f32::INFINITY).into_iter().map(|hit| hit.0).collect());
+
+        let mut closest: Option<PickHit> = None;
+
+        for (id, node) in nodes {
+            if let (Some(index), Some(indexed_hits)) = (index.as_ref(), indexed_hits.as_ref()) {
+                if index.contains(id) && !indexed_hits.contains(&id) {
+                    continue;
+                }
+            }
+
+            let hit = match node.borrow().intersect_ray(ray) {
+                Some(hit) => hit,
+                None => continue
+            };
+
+            if closest.as_ref().map_or(true, |closest| hit.distance < closest.distance) {
+                closest = Some(PickHit {
+                    id: id,
+                    renderable: node.clone(),
+                    distance: hit.distance,
+                    point: hit.point,
+                    normal: hit.normal
+                });
+            }
+        }
+
+        return closest;
+    }
+
     /// Draw all `Renderable` objects.
+    ///
+    /// The opaque renderables are drawn first, front to back, then the transparent ones back to front
+    /// with alpha blending and without depth writes. See `Renderable::is_transparent`.
     pub fn draw(&self) {
-        self.render_queue.borrow_mut().retain(|renderable_wk| {
-            match renderable_wk.upgrade() {
-                Some(renderable) => {
-                    renderable.borrow().draw(Matrix4::identity(), &self.camera);
-                    return true;
-                },
-                None => return false
+        let lights = self.lights();
+
+        let vp_matrix = self.camera.vp_matrix();
+
+        let (nodes, stats) = self.visible_nodes(&vp_matrix);
+        self.cull_stats.set(stats);
+
+        let (opaque, transparent) = self.sort_for_drawing(nodes);
+
+        match self.render_path {
+            RenderPath::Forward => {
+                for node in opaque.iter() {
+                    node.borrow().draw_lit(Matrix4::identity(), &self.camera, &lights);
+                }
+            },
+            RenderPath::Deferred(ref renderer) => {
+                let (deferred, forward): (Vec<_>, Vec<_>) = opaque.into_iter()
+                    .partition(|node| node.borrow().has_gbuffer_pass());
+
+                renderer.draw(&lights, &self.camera, || {
+                    for node in deferred.iter() {
+                        node.borrow().draw_gbuffer(Matrix4::identity(), &self.camera);
+                    }
+                }, |light_vp| {
+                    self.each_visible(light_vp, |_, renderable| renderable.draw_shadow(Matrix4::identity(), light_vp));
+                });
+
+                // Depth tested against the composed G-buffer.
+                for node in forward.iter() {
+                    node.borrow().draw_lit(Matrix4::identity(), &self.camera, &lights);
+                }
+            }
+        }
+
+        // The G-buffer has a single surface per pixel, so the transparent renderables are always
+        // drawn forward, on top of the result.
+        self.draw_transparent(&transparent, &lights);
+    }
+
+    /// Renders the ids of the visible nodes with `Renderable::draw_id` for `picker` to read back.
+    ///
+    /// Meant to be called on demand, e.g. on click, rather than every frame. Nodes outside of the
+    /// camera's frustum are culled like in `draw`, but not counted in `cull_stats`.
+    pub fn draw_ids(&self, picker: &IdPicker) {
+        let vp_matrix = self.camera.vp_matrix();
+
+        picker.render(vp_matrix, |pass| {
+            self.each_visible(&vp_matrix, |id, renderable| {
+                pass.set_node(id);
+                renderable.draw_id(Matrix4::identity(), pass);
+            });
+        });
+    }
+
+    /// Calls `f` for each renderable which may be visible through `vp_matrix`.
+    fn each_visible<F: Fn(NodeId, &Renderable)>(&self, vp_matrix: &Matrix4<f32>, f: F) -> CullStats {
+        let (nodes, stats) = self.visible_nodes(vp_matrix);
+
+        for (id, node) in nodes {
+            f(id, &*node.borrow());
+        }
+
+        return stats;
+    }
+
+    /// Get the renderables which may be visible through `vp_matrix` in order of priority.
+    fn visible_nodes(&self, vp_matrix: &Matrix4<f32>) -> (Vec<(NodeId, Rc<RefCell<Renderable>>)>, CullStats) {
+        let frustum = if self.culling { Some(Frustum::from_matrix(vp_matrix)) } else { None };
+        let mut stats = CullStats::default();
+
+        // Refreshes the index before querying it.
+        let mut nodes = self.render_queue.borrow_mut().nodes();
+
+        let index = self.spatial_index.as_ref().map(|index| index.borrow());
+        let indexed_visible: Option<HashSet<NodeId>> = match (frustum, index.as_ref()) {
+            (Some(ref frustum), Some(index)) => Some(index.query_frustum(frustum).into_iter().collect()),
+            _ => None
+        };
+
+        nodes.retain(|&(id, ref node)| {
+            if let Some(ref frustum) = frustum {
+                let renderable = node.borrow();
+                let visible = match (index.as_ref(), indexed_visible.as_ref()) {
+                    (Some(index), Some(indexed_visible)) if index.contains(id) => indexed_visible.contains(&id),
+                    _ => match renderable.bounds() {
+                        Some(bounds) => frustum.intersects(&bounds.transform(&renderable.model_matrix())),
+                        None => true
+                    }
+                };
+
+                if !visible {
+                    stats.culled += 1;
+                    return false;
+                }
+            }
+
+            stats.drawn += 1;
+            return true;
+        });
+
+        return (nodes, stats);
+    }
+
+    /// Splits the nodes into the opaque ones sorted front to back and the transparent ones sorted
+    /// back to front. Priority comes before the depth in both.
+    fn sort_for_drawing(&self, nodes: Vec<(NodeId, Rc<RefCell<Renderable>>)>)
+        -> (Vec<Rc<RefCell<Renderable>>>, Vec<Rc<RefCell<Renderable>>>)
+    {
+        let view_matrix = self.camera.view_matrix();
+
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
+
+        for (_, node) in nodes {
+            let (priority, depth, is_transparent) = {
+                let renderable = node.borrow();
+                (renderable.priority(), view_depth(&*renderable, &view_matrix), renderable.is_transparent())
+            };
+
+            if is_transparent {
+                transparent.push((priority, -depth, node));
+            } else {
+                opaque.push((priority, depth, node));
+            }
+        }
+
+        // Stable, so equal depths keep the order of insertion.
+        let by_key = |a: &(u32, f32, Rc<RefCell<Renderable>>), b: &(u32, f32, Rc<RefCell<Renderable>>)| {
+            a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        };
+        opaque.sort_by(&by_key);
+        transparent.sort_by(&by_key);
+
+        return (
+            opaque.into_iter().map(|node| node.2).collect(),
+            transparent.into_iter().map(|node| node.2).collect());
+    }
+
+    fn draw_transparent(&self, nodes: &[Rc<RefCell<Renderable>>], lights: &Lights) {
+        if nodes.is_empty() {
+            return;
+        }
+
+        Gliw::enable(gl::BLEND);
+        unsafe {
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            // Still depth tested against the opaque renderables, but not against each other.
+            gl::DepthMask(gl::FALSE);
+        }
+
+        for node in nodes.iter() {
+            node.borrow().draw_lit(Matrix4::identity(), &self.camera, lights);
+        }
+
+        unsafe { gl::DepthMask(gl::TRUE); }
+        Gliw::disable(gl::BLEND);
+    }
+}
+
+impl NodeParent for Scene {
+    fn add_node(&mut self, node: Weak<RefCell<Renderable>>) -> NodeHandle {
+        // The &mut self can be just &self but this way it shows the logical mutation.
+
+        return self.render_queue.borrow_mut().add(node);
+    }
+}
+
+/// A node of the transform hierarchy formed by nested `Composition`s.
+///
+/// Caches the local and world matrices of its `Composition`. The local matrix is refreshed after
+/// the wrapped renderable is mutably accessed, and the world matrix after the local matrix or any
+/// of the ancestors' change.
+///
+/// See `Composition::world_matrix`.
+pub struct TransformNode {
+    parent: RefCell<Weak<TransformNode>>,
+    // The renderable the node belongs to, known once it is added to a `Scene` or a `Composition`.
+    owner: RefCell<Option<Weak<RefCell<Renderable>>>>,
+    local: Cell<Matrix4<f32>>,
+    local_dirty: Cell<bool>,
+    world: Cell<Matrix4<f32>>,
+    world_dirty: Cell<bool>,
+    // Incremented whenever `world` changes, so the children know when to recompute theirs.
+    version: Cell<u64>,
+    parent_version: Cell<u64>
+}
+
+impl TransformNode {
+    fn new(local: Matrix4<f32>) -> TransformNode {
+        return TransformNode {
+            parent: RefCell::new(Weak::new()),
+            owner: RefCell::new(None),
+            local: Cell::new(local),
+            local_dirty: Cell::new(false),
+            world: Cell::new(local),
+            world_dirty: Cell::new(true),
+            version: Cell::new(0),
+            parent_version: Cell::new(0)
+        };
+    }
+
+    /// Get the parent node. `None` for nodes directly in a `Scene` or not added anywhere.
+    pub fn parent(&self) -> Option<Rc<TransformNode>> {
+        return self.parent.borrow().upgrade();
+    }
+
+    /// Get the renderable the node belongs to.
+    ///
+    /// `None` until the renderable is added to a `Scene` or a `Composition`.
+    pub fn renderable(&self) -> Option<Rc<RefCell<Renderable>>> {
+        return self.owner.borrow().as_ref().and_then(|owner| owner.upgrade());
+    }
+
+    /// Check if `self` is the parent of `node`, or the parent of its parent and so on.
+    pub fn is_ancestor_of(&self, node: &TransformNode) -> bool {
+        let mut current = node.parent();
+
+        while let Some(ancestor) = current {
+            if &*ancestor as *const TransformNode == self as *const TransformNode {
+                return true;
+            }
+
+            current = ancestor.parent();
+        }
+
+        return false;
+    }
+
+    /// Get the cached model matrix of the renderable.
+    pub fn local_matrix(&self) -> Matrix4<f32> {
+        self.refresh_local();
+
+        return self.local.get();
+    }
+
+    /// Get the transformation from the renderable's model space to world space,
+    /// i.e. its model matrix multiplied by all of its ancestors'.
+    ///
+    /// An ancestor which is mutably borrowed at the time can't be refreshed
+    /// and its previous model matrix is used.
+    pub fn world_matrix(&self) -> Matrix4<f32> {
+        self.refresh_local();
+
+        match self.parent() {
+            Some(parent) => {
+                let parent_world = parent.world_matrix();
+
+                if self.world_dirty.get() || parent.version.get() != self.parent_version.get() {
+                    self.parent_version.set(parent.version.get());
+                    self.set_world(parent_world * self.local.get());
+                }
+            },
+            None => {
+                if self.world_dirty.get() {
+                    self.set_world(self.local.get());
+                }
+            }
+        }
+
+        return self.world.get();
+    }
+
+    fn set_world(&self, world: Matrix4<f32>) {
+        self.world.set(world);
+        self.world_dirty.set(false);
+        self.version.set(self.version.get() + 1);
+    }
+
+    fn set_local(&self, local: Matrix4<f32>) {
+        self.local.set(local);
+        self.local_dirty.set(false);
+        self.world_dirty.set(true);
+    }
+
+    /// Updates the local matrix through the owner, if it is not mutably borrowed.
+    fn refresh_local(&self) {
+        if !self.local_dirty.get() {
+            return;
+        }
+
+        if let Some(owner) = self.renderable() {
+            if let Ok(renderable) = owner.try_borrow() {
+                self.set_local(renderable.model_matrix());
+            }
+        }
+    }
+
+    /// Links the node of `node`, if it has one, to `parent`.
+    fn link(node: &Weak<RefCell<Renderable>>, parent: Option<&Rc<TransformNode>>) {
+        TransformNode::with_node(node, |transform| {
+            *transform.parent.borrow_mut() = match parent {
+                Some(parent) => Rc::downgrade(parent),
+                None => Weak::new()
+            };
+            *transform.owner.borrow_mut() = Some(node.clone());
+            transform.world_dirty.set(true);
+        });
+    }
+
+    /// Unlinks the node of `node` from `parent`, unless it was moved to another parent since.
+    fn unlink(node: &Weak<RefCell<Renderable>>, parent: &Weak<TransformNode>) {
+        TransformNode::with_node(node, |transform| {
+            let linked = match (transform.parent(), parent.upgrade()) {
+                (Some(current), Some(parent)) => Rc::ptr_eq(&current, &parent),
+                _ => false
+            };
+
+            if linked {
+                *transform.parent.borrow_mut() = Weak::new();
+                transform.world_dirty.set(true);
             }
         });
     }
+
+    fn with_node<F: FnOnce(&TransformNode)>(node: &Weak<RefCell<Renderable>>, f: F) {
+        if let Some(node_rc) = node.upgrade() {
+            if let Ok(renderable) = node_rc.try_borrow() {
+                if let Some(transform) = renderable.transform_node() {
+                    f(transform);
+                }
+            }
+        }
+    }
+}
+
+/// Get the view space depth of the center of a renderable directly in a scene.
+fn view_depth(renderable: &Renderable, view_matrix: &Matrix4<f32>) -> f32 {
+    let center = match renderable.bounds() {
+        Some(bounds) => bounds.aabb().center(),
+        None => Point3::new(0.0, 0.0, 0.0)
+    };
+
+    let world = renderable.model_matrix().transform_point(center);
+
+    // The camera looks down the negative z axis.
+    return -view_matrix.transform_point(world).z;
+}
+
+fn collect<T: Copy>(lights: &RefCell<Vec<Weak<RefCell<T>>>>) -> Vec<T> {
+    let mut collected = Vec::new();
+    lights.borrow_mut().retain(|light_wk| {
+        match light_wk.upgrade() {
+            Some(light) => {
+                collected.push(*light.borrow());
+                return true;
+            },
+            None => return false
+        }
+    });
+
+    return collected;
 }
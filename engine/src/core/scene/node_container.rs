@@ -1,30 +1,134 @@
 //! Internal container.
 
+extern crate cgmath;
+
+use self::cgmath::{Matrix4, SquareMatrix};
+
 use super::renderable::Renderable;
+use super::TransformNode;
+
+use core::SpatialGrid;
+
+use math::Bounds;
 
 use std::rc::{Rc, Weak};
-use std::cell::RefCell;
-use std::ops::{Deref, DerefMut};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Identifies a node for its whole life, even after being reparented. See `NodeHandle::id`.
+pub type NodeId = usize;
 
-// FIXME: if the priority of an object changes prior to it's insertion,
-// it will break the priority mechanism.
+static NEXT_NODE_ID: AtomicUsize = AtomicUsize::new(0);
 
 /// Container for wrapped `Renderables`.
 ///
+/// The nodes are kept sorted by priority and then by insertion order.
+/// Priorities are re-read on every `each` so changes after insertion take effect on the next frame.
+///
 /// See `Scene` for more info on how this object works.
 /// See `NodeContainer.wrap`.
 pub struct NodeContainer {
-    container: Vec<Weak<RefCell<Renderable>>>
+    container: Vec<Node>,
+    next_order: u64,
+    // The transform node of the `Composition` holding the container.
+    parent: Option<Rc<TransformNode>>,
+    index: Option<Rc<RefCell<SpatialGrid<NodeId>>>>
+}
+
+struct Node {
+    renderable: Weak<RefCell<Renderable>>,
+    link: Rc<NodeLink>,
+    // Cached to detect priority changes.
+    priority: u32,
+    order: u64,
+    // The model matrix the node was last indexed with, `None` if it is not indexed yet.
+    indexed: Option<Matrix4<f32>>
+}
+
+/// The state shared between a node and its `NodeHandle`.
+struct NodeLink {
+    id: Cell<NodeId>,
+    visible: Cell<bool>,
+    removed: Cell<bool>
+}
+
+/// Holds a `Renderable`'s place in a `Scene` or a `Composition`.
+///
+/// Returned by `Scene::add` and `Composition::attach`.
+/// Dropping the handle leaves the node where it is.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[macro_use] extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::{Scene, Camera, Cuboid};
+/// # use cgmath::{Point3, Vector3, Vector4};
+/// # fn main() {
+/// let tank = wrap!(Cuboid::new(
+///     Point3::new(0.0, 0.0, 0.0),
+///     Vector3::new(1.0, 0.5, 2.0),
+///     Vector4::new(0.2, 0.4, 0.2, 1.0)));
+///
+/// let mut scene = Scene::new(Camera::new());
+/// let handle = scene.add(Scene::node(&tank));
+///
+/// // Destroyed, the wreck is hidden while `tank` stays alive for the death animation.
+/// handle.set_visible(false);
+/// # }
+/// ```
+pub struct NodeHandle {
+    renderable: Weak<RefCell<Renderable>>,
+    link: Rc<NodeLink>,
+    parent: Option<Weak<TransformNode>>
+}
+
+/// Something holding `Renderable` nodes, i.e. a `Scene` or a `Composition`.
+pub trait NodeParent {
+    /// Adds a type erased `Renderable`.
+    ///
+    /// See `Scene::add` and `Composition::attach`.
+    fn add_node(&mut self, node: Weak<RefCell<Renderable>>) -> NodeHandle;
+
+    /// Get the transform node the added nodes are linked to. `None` for a `Scene`.
+    fn parent_node(&self) -> Option<&TransformNode> {
+        return None;
+    }
 }
 
 impl NodeContainer {
-    /// Create a new node container.
-    pub fn new() -> NodeContainer {
+    /// Create a new node container, with the transform node of its `Composition` if it has one.
+    pub fn new(parent: Option<Rc<TransformNode>>) -> NodeContainer {
         return NodeContainer {
-            container: Vec::new()
+            container: Vec::new(),
+            next_order: 0,
+            parent: parent,
+            index: None
         };
     }
 
+    /// Set the spatial index to keep the nodes in, removing them from the previous one.
+    ///
+    /// The nodes are indexed with their world bounds and moved whenever their model matrix changes.
+    /// Removed and dropped nodes are evicted. Both happen the next time the nodes are iterated.
+    pub fn set_index(&mut self, index: Option<Rc<RefCell<SpatialGrid<NodeId>>>>) {
+        if let Some(previous) = self.index.take() {
+            let mut previous = previous.borrow_mut();
+
+            for node in self.container.iter() {
+                if node.indexed.is_some() {
+                    previous.remove(node.link.id.get());
+                }
+            }
+        }
+
+        for node in self.container.iter_mut() {
+            node.indexed = None;
+        }
+
+        self.index = index;
+    }
+
     /// See `Scene.node`.
     #[inline]
     pub fn node<R: Renderable>(renderable: &Rc<RefCell<R>>) -> Weak<RefCell<R>> {
@@ -33,56 +137,238 @@ impl NodeContainer {
 
     /// Add a wrapped `Renderable`.
     ///
-    /// Since `Vec` does not have an `add` function it is safe to use `self`
-    /// instead of `this` with the `Deref` trait.
+    /// The returned handle is already removed if the `Renderable` was dropped.
     ///
     /// See `Scene.add`.
-    pub fn add<R>(&mut self, node: Weak<RefCell<R>>)
-        where R: Renderable + 'static
-    {
+    pub fn add(&mut self, node: Weak<RefCell<Renderable>>) -> NodeHandle {
         // The &mut self can be just &self but this way it shows the logical mutation.
 
+        let link = Rc::new(NodeLink {
+            id: Cell::new(NEXT_NODE_ID.fetch_add(1, Ordering::Relaxed)),
+            visible: Cell::new(true),
+            removed: Cell::new(false)
+        });
+        let handle = NodeHandle {
+            renderable: node.clone(),
+            link: link.clone(),
+            parent: self.parent.as_ref().map(Rc::downgrade)
+        };
+
         let node_priority = match node.upgrade() {
             Some(node_rc) => node_rc.borrow().priority(),
-            None => return
+            None => {
+                handle.remove();
+                return handle;
+            }
         };
 
-        let mut ins_pos: usize = 0;
-        let mut found: bool = false;
-
-        self.container.retain(|node_wk| {
-            match node_wk.upgrade() {
-                Some(node_rc) => {
-                    if !found {
-                        // < is preffered than <= for better performance.
-                        // This way less elements will be moved with the insertion.
-                        // This affects priority, see `Scene::add`.
-                        if node_priority < node_rc.borrow().priority() {
-                            found = true;
-                        } else {
-                            ins_pos += 1;
+        TransformNode::link(&node, self.parent.as_ref());
+
+        let order = self.next_order;
+        self.next_order += 1;
+
+        // Being the newest, the node goes after all nodes with the same or lower priority.
+        let ins_pos = match self.container.iter().position(|other| node_priority < other.priority) {
+            Some(pos) => pos,
+            None => self.container.len()
+        };
+
+        self.container.insert(ins_pos, Node {
+            renderable: node,
+            link: link,
+            priority: node_priority,
+            order: order,
+            indexed: None
+        });
+
+        return handle;
+    }
+
+    /// Check if the node of `handle` is in this container.
+    pub fn contains(&self, handle: &NodeHandle) -> bool {
+        return !handle.is_removed() && self.container.iter().any(|node| Rc::ptr_eq(&node.link, &handle.link));
+    }
+
+    /// Calls `f` for each alive and visible `Renderable` in order of priority.
+    ///
+    /// Removes the dropped nodes and re-sorts the rest if any priority has changed.
+    pub fn each<F: FnMut(&Renderable)>(&mut self, mut f: F) {
+        self.each_node(|_, renderable| f(renderable));
+    }
+
+    /// Same as `each` but also passes the nodes' ids.
+    pub fn each_node<F: FnMut(NodeId, &Renderable)>(&mut self, mut f: F) {
+        self.refresh();
+
+        for node in self.container.iter() {
+            if !node.link.visible.get() {
+                continue;
+            }
+
+            // A node can be dropped by a previous one while drawing.
+            if let Some(node_rc) = node.renderable.upgrade() {
+                f(node.link.id.get(), &*node_rc.borrow());
+            }
+        }
+    }
+
+    /// Get the alive and visible nodes with their ids in order of priority.
+    pub fn nodes(&mut self) -> Vec<(NodeId, Rc<RefCell<Renderable>>)> {
+        self.refresh();
+
+        return self.container.iter()
+            .filter(|node| node.link.visible.get())
+            .filter_map(|node| node.renderable.upgrade().map(|node_rc| (node.link.id.get(), node_rc)))
+            .collect();
+    }
+
+    /// Removes the dropped nodes, updates the index and re-sorts the nodes if any priority has changed.
+    fn refresh(&mut self) {
+        {
+            let index = self.index.as_ref();
+            self.container.retain(|node| {
+                let alive = !node.link.removed.get() && node.renderable.upgrade().is_some();
+
+                if !alive && node.indexed.is_some() {
+                    if let Some(index) = index {
+                        index.borrow_mut().remove(node.link.id.get());
+                    }
+                }
+
+                alive
+            });
+        }
+
+        let mut dirty = false;
+        for node in self.container.iter_mut() {
+            if let Some(node_rc) = node.renderable.upgrade() {
+                let renderable = node_rc.borrow();
+
+                let priority = renderable.priority();
+                if priority != node.priority {
+                    node.priority = priority;
+                    dirty = true;
+                }
+
+                if let Some(ref index) = self.index {
+                    let model_matrix = renderable.model_matrix();
+
+                    if node.indexed != Some(model_matrix) {
+                        node.indexed = Some(model_matrix);
+
+                        let id = node.link.id.get();
+                        match renderable.bounds() {
+                            Some(bounds) => index.borrow_mut().insert(id, bounds.transform(&model_matrix).aabb()),
+                            None => { index.borrow_mut().remove(id); }
                         }
                     }
-                    return true;
-                },
-                None => return false
+                }
             }
-        });
+        }
 
-        self.container.insert(ins_pos, node);
+        if dirty {
+            self.container.sort_by_key(|node| (node.priority, node.order));
+        }
     }
 }
 
-impl Deref for NodeContainer {
-    type Target = Vec<Weak<RefCell<Renderable>>>;
+impl NodeHandle {
+    /// Get the node's id, which stays the same when reparenting.
+    pub fn id(&self) -> NodeId {
+        return self.link.id.get();
+    }
+
+    /// Get the renderable's bounds in world space, see `Renderable::bounds`.
+    ///
+    /// `None` if the renderable is unbounded, dropped or mutably borrowed.
+    pub fn world_bounds(&self) -> Option<Bounds> {
+        let renderable = match self.renderable.upgrade() {
+            Some(renderable) => renderable,
+            None => return None
+        };
+        let renderable = match renderable.try_borrow() {
+            Ok(renderable) => renderable,
+            Err(_) => return None
+        };
+
+        let parent_world = match self.parent.as_ref().and_then(|parent| parent.upgrade()) {
+            Some(parent) => parent.world_matrix(),
+            None => Matrix4::identity()
+        };
 
-    fn deref(&self) -> &Self::Target {
-        return &self.container;
+        return renderable.bounds().map(|bounds| bounds.transform(&(parent_world * renderable.model_matrix())));
+    }
+
+    /// Removes the node from its parent. The `Renderable` itself is not affected.
+    pub fn remove(&self) {
+        if self.link.removed.get() {
+            return;
+        }
+
+        self.link.removed.set(true);
+
+        if let Some(ref parent) = self.parent {
+            TransformNode::unlink(&self.renderable, parent);
+        }
+    }
+
+    /// Check if the node was removed, either with `remove` or by dropping the `Renderable`.
+    pub fn is_removed(&self) -> bool {
+        return self.link.removed.get() || self.renderable.upgrade().is_none();
+    }
+
+    /// Show or hide the node, along with its children if it is a `Composition`.
+    /// Hidden nodes are not drawn in any pass, including the shadow ones.
+    pub fn set_visible(&self, visible: bool) {
+        self.link.visible.set(visible);
     }
-}
 
-impl DerefMut for NodeContainer {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        return &mut self.container;
+    /// Check if the node is drawn. Nodes are visible by default.
+    pub fn is_visible(&self) -> bool {
+        return self.link.visible.get();
+    }
+
+    /// Moves the node to `parent`, keeping its visibility. The handle follows the node.
+    ///
+    /// # Panics
+    ///
+    /// When reparenting a `Composition` into itself or one of its descendants.
+    pub fn reparent<P: NodeParent + ?Sized>(&mut self, parent: &mut P) {
+        if let Some(target) = parent.parent_node() {
+            if self.is_subtree_of(target) {
+                panic!(ERR_REPARENT_CYCLE);
+            }
+        }
+
+        let id = self.id();
+        let visible = self.is_visible();
+        self.remove();
+
+        *self = parent.add_node(self.renderable.clone());
+        self.link.id.set(id);
+        self.set_visible(visible);
+    }
+
+    /// Check if `target` is the transform node of the renderable or one of its descendants.
+    fn is_subtree_of(&self, target: &TransformNode) -> bool {
+        let renderable = match self.renderable.upgrade() {
+            Some(renderable) => renderable,
+            None => return false
+        };
+
+        return match renderable.try_borrow() {
+            Ok(node) => match node.transform_node() {
+                Some(transform) => transform as *const TransformNode == target as *const TransformNode ||
+                    transform.is_ancestor_of(target),
+                None => false
+            },
+            // Only the renderable being reparented into itself is mutably borrowed.
+            Err(_) => match target.renderable() {
+                Some(owner) => owner.as_ptr() as *const u8 == renderable.as_ptr() as *const u8,
+                None => false
+            }
+        };
     }
 }
+
+const ERR_REPARENT_CYCLE: &'static str = "Can not reparent a Composition into itself or one of its descendants";
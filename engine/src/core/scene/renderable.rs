@@ -3,6 +3,11 @@ extern crate cgmath;
 use self::cgmath::Matrix4;
 
 use super::camera::Camera;
+use super::id_picker::IdPass;
+use super::light::Lights;
+use super::TransformNode;
+
+use math::{Bounds, Ray, RayHit};
 
 /// Determines if an object is renderable and defines its properties.
 pub trait Renderable {
@@ -13,9 +18,86 @@ pub trait Renderable {
         return 0;
     }
 
+    /// Whether the renderable is translucent and has to be blended over what is behind it.
+    ///
+    /// A `Scene` draws the transparent renderables after the opaque ones, back to front by their
+    /// distance from the camera, with alpha blending and without writing depth. Within the same
+    /// `priority` the opaque ones are drawn front to back. Defaults to `false`.
+    fn is_transparent(&self) -> bool {
+        return false;
+    }
+
     /// Get the renderable's model matrix.
     fn model_matrix(&self) -> Matrix4<f32>;
 
     /// Draw call.
     fn draw(&self, draw_space: Matrix4<f32>, camera: &Camera);
+
+    /// Draw call of the forward render path, shading the object with the scene's `lights`.
+    ///
+    /// Defaults to `draw`, which ignores the lights.
+    #[allow(unused_variables)]
+    fn draw_lit(&self, draw_space: Matrix4<f32>, camera: &Camera, lights: &Lights) {
+        self.draw(draw_space, camera);
+    }
+
+    /// Whether the renderable implements `draw_gbuffer`.
+    ///
+    /// The deferred render path draws the ones which don't with `draw_lit` after the G-buffer
+    /// is shaded, since `draw` doesn't write the normals and the material. Defaults to `false`.
+    fn has_gbuffer_pass(&self) -> bool {
+        return false;
+    }
+
+    /// Draw call for the geometry pass of the deferred render path, if `has_gbuffer_pass`.
+    ///
+    /// See `DeferredRenderer` for the outputs the fragment shader is expected to write.
+    /// Defaults to drawing nothing.
+    #[allow(unused_variables)]
+    fn draw_gbuffer(&self, draw_space: Matrix4<f32>, camera: &Camera) {}
+
+    /// Draw call for the depth-only shadow pass, with `light_vp` in place of the camera's VP matrix.
+    ///
+    /// Called once per cascade of a `ShadowMap`. Only the depth is used, so the fragment shader
+    /// can be empty. Defaults to drawing nothing, i.e. not casting shadows.
+    #[allow(unused_variables)]
+    fn draw_shadow(&self, draw_space: Matrix4<f32>, light_vp: &Matrix4<f32>) {}
+
+    /// Draw call for the id pass of an `IdPicker`.
+    ///
+    /// The pass' program is bound, so the renderable only has to call `IdPass::set_model` and draw
+    /// its geometry with the positions at attribute location `0`. Defaults to drawing nothing,
+    /// i.e. not being pickable by id.
+    #[allow(unused_variables)]
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {}
+
+    /// Get the volume containing everything the renderable draws in model space,
+    /// i.e. before `model_matrix` is applied. Used by `Scene` for frustum culling.
+    ///
+    /// Defaults to `None`, meaning the renderable is never culled.
+    fn bounds(&self) -> Option<Bounds> {
+        return None;
+    }
+
+    /// Get where `ray` first hits the renderable, with `ray` and the hit in the space
+    /// the renderable is drawn in, i.e. the `draw_space` of `draw`. Used by `Scene::pick`.
+    ///
+    /// Defaults to hitting `bounds` after `model_matrix` is applied to them,
+    /// so unbounded renderables are never hit.
+    fn intersect_ray(&self, ray: &Ray) -> Option<RayHit> {
+        let model_matrix = self.model_matrix();
+
+        return match self.bounds() {
+            Some(Bounds::Box(aabb)) => ray.hit_obb(&aabb, &model_matrix),
+            Some(Bounds::Sphere(sphere)) => ray.hit_sphere(&sphere.transform(&model_matrix)),
+            None => None
+        };
+    }
+
+    /// Get the renderable's node in the transform hierarchy, if it takes part in one.
+    ///
+    /// Defaults to `None`. See `Composition`.
+    fn transform_node(&self) -> Option<&TransformNode> {
+        return None;
+    }
 }
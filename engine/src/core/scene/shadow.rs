@@ -0,0 +1,298 @@
+extern crate cgmath;
+
+use self::cgmath::{Matrix4, SquareMatrix, Point3, Vector3, Vector4, InnerSpace, EuclideanSpace};
+
+use gliw::{
+    gl, Gliw, DepthFunction,
+    Attachment, Framebuffer, FramebufferTarget,
+    Texture, TextureType, InternalFormat
+};
+
+use super::camera::Camera;
+use super::light::DirectionalLight;
+
+use std::cell::RefCell;
+
+/// The maximum number of cascades of a `ShadowMap`.
+pub const MAX_CASCADES: usize = 4;
+
+/// Quality and bias controls of a `ShadowMap`.
+///
+/// Shadow acne is fixed by raising the biases, peter panning (shadows detached from their casters)
+/// by lowering them.
+#[derive(Copy, Clone, Debug)]
+pub struct ShadowSettings {
+    /// Width and height of the depth map of each cascade in texels.
+    pub resolution: i32,
+    /// Number of cascades, in the range [1, `MAX_CASCADES`].
+    pub cascades: usize,
+    /// Distance from the camera after which nothing is shadowed. Clamped to the camera's far plane.
+    pub max_distance: f32,
+    /// Blend between uniform (`0.0`) and logarithmic (`1.0`) cascade splits.
+    /// Logarithmic splits give more resolution to the cascades close to the camera.
+    pub split_lambda: f32,
+    /// Constant offset of the receivers' depth, in the [0, 1] depth range of the shadow map.
+    pub depth_bias: f32,
+    /// Slope scaled offset of the casters' depth, see `glPolygonOffset`.
+    pub slope_bias: f32,
+    /// Offset of the receivers along their normal, in texels of the cascade they fall into.
+    pub normal_bias: f32,
+    /// Radius in texels of the percentage closer filtering kernel.
+    /// `0` takes a single, bilinearly filtered, sample.
+    pub pcf_radius: i32,
+    /// Distance towards the light, past the cascades, in which objects still cast shadows.
+    pub caster_distance: f32
+}
+
+impl ShadowSettings {
+    /// Create settings with three 2048x2048 cascades up to 50 units away and 3x3 PCF.
+    pub fn new() -> ShadowSettings {
+        return ShadowSettings {
+            resolution: 2048,
+            cascades: 3,
+            max_distance: 50.0,
+            split_lambda: 0.75,
+            depth_bias: 0.0005,
+            slope_bias: 2.0,
+            normal_bias: 1.0,
+            pcf_radius: 1,
+            caster_distance: 50.0
+        };
+    }
+}
+
+/// A slice of the camera frustum covered by one layer of a `ShadowMap`.
+#[derive(Copy, Clone, Debug)]
+pub struct Cascade {
+    /// The light's view projection matrix for the cascade.
+    pub vp_matrix: Matrix4<f32>,
+    /// View space distance from the camera at which the cascade ends.
+    pub split: f32,
+    /// World space size of a texel of the cascade.
+    pub texel_size: f32
+}
+
+/// Cascaded shadow map of a `DirectionalLight`.
+///
+/// The camera frustum is split into `ShadowSettings::cascades` slices along its depth and each
+/// slice gets its own depth map, covering a bounding sphere of the slice. The cascades are snapped
+/// to whole texels so the shadow edges do not shimmer when the camera moves.
+///
+/// The depth maps are layers of a `Depth32F` 2D array texture with depth comparison enabled,
+/// to be sampled with a `sampler2DArrayShadow`.
+///
+/// # References
+/// * [Cascaded Shadow Maps](https://msdn.microsoft.com/en-us/library/windows/desktop/ee416307(v=vs.85).aspx)
+/// * [Common Techniques to Improve Shadow Depth Maps](https://msdn.microsoft.com/en-us/library/windows/desktop/ee416324(v=vs.85).aspx)
+pub struct ShadowMap {
+    settings: ShadowSettings,
+    depth: Texture,
+    framebuffers: Vec<Framebuffer>,
+    cascades: RefCell<Vec<Cascade>>
+}
+
+impl ShadowMap {
+    /// Create a shadow map with the given settings.
+    ///
+    /// Fails if the framebuffers are incomplete.
+    ///
+    /// # Panics
+    /// Panics if the number of cascades is not in the range [1, `MAX_CASCADES`].
+    pub fn new(settings: ShadowSettings) -> Result<ShadowMap, String> {
+        let mut shadow_map = ShadowMap {
+            settings: settings,
+            depth: Texture::new(TextureType::Array2D),
+            framebuffers: Vec::new(),
+            cascades: RefCell::new(Vec::new())
+        };
+
+        return match shadow_map.alloc() {
+            Ok(_) => Ok(shadow_map),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &ShadowSettings {
+        return &self.settings;
+    }
+
+    /// Changes the settings, reallocating the depth maps if their size or count changed.
+    ///
+    /// # Panics
+    /// Same as `new`.
+    pub fn set_settings(&mut self, settings: ShadowSettings) -> Result<(), String> {
+        let realloc = settings.resolution != self.settings.resolution || settings.cascades != self.settings.cascades;
+        self.settings = settings;
+
+        if realloc {
+            return self.alloc();
+        }
+
+        return Ok(());
+    }
+
+    /// Get the cascades fitted by the last `render`.
+    pub fn cascades(&self) -> Vec<Cascade> {
+        return self.cascades.borrow().clone();
+    }
+
+    /// Get the depth map array.
+    pub fn texture(&self) -> &Texture {
+        return &self.depth;
+    }
+
+    /// Fits the cascades to `camera` and renders the depth maps.
+    ///
+    /// `draw_casters` is called once per cascade and should draw the depth of all shadow casters
+    /// with the given light view projection matrix, see `Renderable::draw_shadow`.
+    /// Leaves the last cascade's framebuffer bound.
+    pub fn render<F: Fn(&Matrix4<f32>)>(&self, light: &DirectionalLight, camera: &Camera, draw_casters: F) {
+        let cascades = self.fit_cascades(light, camera);
+
+        unsafe {
+            gl::Viewport(0, 0, self.settings.resolution, self.settings.resolution);
+            gl::DepthMask(gl::TRUE);
+            gl::PolygonOffset(self.settings.slope_bias, 1.0);
+        }
+
+        Gliw::enable(gl::DEPTH_TEST);
+        Gliw::depth_func(DepthFunction::Less);
+        Gliw::enable(gl::POLYGON_OFFSET_FILL);
+        // Casters between the light and the cascade are flattened onto the near plane instead of clipped.
+        Gliw::enable(gl::DEPTH_CLAMP);
+
+        for (framebuffer, cascade) in self.framebuffers.iter().zip(cascades.iter()) {
+            framebuffer.bind(FramebufferTarget::Both);
+            Gliw::clear(gl::DEPTH_BUFFER_BIT);
+
+            draw_casters(&cascade.vp_matrix);
+        }
+
+        Gliw::disable(gl::POLYGON_OFFSET_FILL);
+        Gliw::disable(gl::DEPTH_CLAMP);
+
+        *self.cascades.borrow_mut() = cascades;
+    }
+
+    fn alloc(&mut self) -> Result<(), String> {
+        if self.settings.cascades == 0 || self.settings.cascades > MAX_CASCADES {
+            panic!(ERR_CASCADE_COUNT);
+        }
+
+        let resolution = self.settings.resolution;
+        let target = gl::TEXTURE_2D_ARRAY;
+
+        self.depth.alloc_3d(resolution, resolution, self.settings.cascades as i32, InternalFormat::Depth32F);
+        unsafe {
+            // Linear filtering with comparison gives 2x2 PCF in hardware.
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(target, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32);
+            gl::TexParameteri(target, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+        }
+
+        self.framebuffers.clear();
+
+        for layer in 0..self.settings.cascades {
+            let framebuffer = Framebuffer::new();
+            framebuffer.attach_texture_layer(Attachment::Depth, &self.depth, 0, layer as i32);
+            framebuffer.draw_buffers(&[None]);
+
+            // Depth only, so there is no color attachment to read from either.
+            framebuffer.bind(FramebufferTarget::Read);
+            unsafe { gl::ReadBuffer(gl::NONE); }
+
+            let status = framebuffer.check_status();
+            if let Err(err) = status {
+                Framebuffer::bind_default(FramebufferTarget::Both);
+                return Err(err);
+            }
+
+            self.framebuffers.push(framebuffer);
+        }
+
+        Framebuffer::bind_default(FramebufferTarget::Both);
+
+        return Ok(());
+    }
+
+    fn fit_cascades(&self, light: &DirectionalLight, camera: &Camera) -> Vec<Cascade> {
+        let settings = &self.settings;
+
+        let (near, far) = match camera.projection().clip_planes() {
+            Some(planes) => planes,
+            None => {
+                // The clip planes of an OpenGL perspective projection.
+                let proj = camera.proj_matrix();
+                (proj.w.z / (proj.z.z - 1.0), proj.w.z / (proj.z.z + 1.0))
+            }
+        };
+        let shadow_far = settings.max_distance.min(far);
+
+        let inv_vp = camera.vp_matrix().invert().unwrap_or(Matrix4::identity());
+        let unproject = |x: f32, y: f32, z: f32| {
+            let world = inv_vp * Vector4::new(x, y, z, 1.0);
+            world.truncate() / world.w
+        };
+
+        let mut near_corners = Vec::with_capacity(4);
+        let mut far_corners = Vec::with_capacity(4);
+        for &(x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter() {
+            near_corners.push(unproject(x, y, -1.0));
+            far_corners.push(unproject(x, y, 1.0));
+        }
+
+        let direction = light.direction.normalize();
+        let up = if direction.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() };
+        let half_resolution = settings.resolution as f32 * 0.5;
+
+        let mut cascades = Vec::with_capacity(settings.cascades);
+        let mut previous_split = near;
+
+        for i in 0..settings.cascades {
+            let p = (i + 1) as f32 / settings.cascades as f32;
+            let log_split = near * (shadow_far / near).powf(p);
+            let uniform_split = near + (shadow_far - near) * p;
+            let split = settings.split_lambda * log_split + (1.0 - settings.split_lambda) * uniform_split;
+
+            // The view depth changes linearly along the frustum edges.
+            let mut corners = Vec::with_capacity(8);
+            for &distance in [previous_split, split].iter() {
+                let t = (distance - near) / (far - near);
+                for (near_corner, far_corner) in near_corners.iter().zip(far_corners.iter()) {
+                    corners.push(near_corner + (far_corner - near_corner) * t);
+                }
+            }
+
+            let center = corners.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, corner| sum + corner) / 8.0;
+            let radius = corners.iter().fold(0.0f32, |radius, corner| radius.max((corner - center).magnitude()));
+            // Keep the size stable as the camera rotates.
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let eye = Point3::from_vec(center - direction * (radius + settings.caster_distance));
+            let view = Matrix4::look_at(eye, Point3::from_vec(center), up);
+            let ortho = cgmath::ortho(-radius, radius, -radius, radius, 0.0, 2.0 * radius + settings.caster_distance);
+            let vp_matrix = ortho * view;
+
+            // Snap the projected world origin to a whole texel.
+            let origin = vp_matrix * Vector4::new(0.0, 0.0, 0.0, 1.0);
+            let offset_x = ((origin.x * half_resolution).round() - origin.x * half_resolution) / half_resolution;
+            let offset_y = ((origin.y * half_resolution).round() - origin.y * half_resolution) / half_resolution;
+
+            cascades.push(Cascade {
+                vp_matrix: Matrix4::from_translation(Vector3::new(offset_x, offset_y, 0.0)) * vp_matrix,
+                split: split,
+                texel_size: 2.0 * radius / settings.resolution as f32
+            });
+
+            previous_split = split;
+        }
+
+        return cascades;
+    }
+}
+
+const ERR_CASCADE_COUNT: &'static str = "Shadow map cascade count must be between 1 and MAX_CASCADES";
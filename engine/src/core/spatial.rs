@@ -0,0 +1,505 @@
+extern crate cgmath;
+
+use self::cgmath::{Point3, Vector3};
+
+use core::{NodeHandle, NodeId};
+
+use math::{Aabb, Frustum, Ray};
+
+use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::{f32, i32};
+
+/// Uniform grid over the XZ plane indexing axis aligned boxes by key.
+///
+/// Each item is stored in every cell its box overlaps, so the cells should be about the size of
+/// the typical item. Only occupied cells take memory, so the grid has no fixed extents.
+/// Items too large for the grid, e.g. with infinite bounds, are kept aside and tested by every query.
+///
+/// The items are not tracked automatically, `update` them after they move.
+/// An index of `NodeId`s can also be kept by a `Scene` for culling, see `Scene::set_spatial_index`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::SpatialGrid;
+/// # use engine::math::Aabb;
+/// # use cgmath::Point3;
+/// # fn main() {
+/// let mut tanks = SpatialGrid::new(8.0);
+/// tanks.insert(1, Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 3.0)));
+/// tanks.insert(2, Aabb::new(Point3::new(40.0, 0.0, 0.0), Point3::new(42.0, 1.0, 3.0)));
+///
+/// let in_range = tanks.query_radius(Point3::new(5.0, 0.0, 0.0), 10.0);
+/// assert_eq!(in_range, vec![1]);
+/// # }
+/// ```
+pub struct SpatialGrid<T: Copy + Eq + Hash> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<T>>,
+    items: HashMap<T, Item>,
+    // The items which are in no cell.
+    oversized: HashSet<T>,
+    // Extents of all cells and vertical extents of all items ever occupied, used to bound the queries.
+    extent: Option<CellRange>,
+    min_y: f32,
+    max_y: f32
+}
+
+struct Item {
+    bounds: Aabb,
+    // `None` for oversized items.
+    cells: Option<CellRange>
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct CellRange {
+    min: (i32, i32),
+    max: (i32, i32)
+}
+
+impl<T: Copy + Eq + Hash> SpatialGrid<T> {
+    /// Create an empty grid of square cells with sides of `cell_size`.
+    ///
+    /// # Panics
+    /// Panics if `cell_size` is not positive.
+    pub fn new(cell_size: f32) -> SpatialGrid<T> {
+        if !(cell_size > 0.0) {
+            panic!(ERR_CELL_SIZE);
+        }
+
+        return SpatialGrid {
+            cell_size: cell_size,
+            cells: HashMap::new(),
+            items: HashMap::new(),
+            oversized: HashSet::new(),
+            extent: None,
+            min_y: f32::INFINITY,
+            max_y: f32::NEG_INFINITY
+        };
+    }
+
+    /// Get the number of items.
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    /// Check if there are no items.
+    pub fn is_empty(&self) -> bool {
+        return self.items.is_empty();
+    }
+
+    /// Check if there is an item with `key`.
+    pub fn contains(&self, key: T) -> bool {
+        return self.items.contains_key(&key);
+    }
+
+    /// Get the box of the item with `key`.
+    pub fn bounds(&self, key: T) -> Option<Aabb> {
+        return self.items.get(&key).map(|item| item.bounds);
+    }
+
+    /// Add an item, or move it if there already is one with `key`.
+    pub fn insert(&mut self, key: T, bounds: Aabb) {
+        let cells = self.item_cells(&bounds);
+
+        if cells.is_some() {
+            self.min_y = self.min_y.min(bounds.min.y);
+            self.max_y = self.max_y.max(bounds.max.y);
+        }
+
+        let old_cells = match self.items.get_mut(&key) {
+            Some(item) => {
+                item.bounds = bounds;
+
+                // Moving within the same cells is the common case for small steps.
+                if item.cells == cells {
+                    return;
+                }
+
+                let old_cells = item.cells;
+                item.cells = cells;
+                Some(old_cells)
+            },
+            None => {
+                self.items.insert(key, Item {
+                    bounds: bounds,
+                    cells: cells
+                });
+                None
+            }
+        };
+
+        if let Some(old_cells) = old_cells {
+            self.unlink(key, old_cells);
+        }
+
+        self.link(key, cells);
+    }
+
+    /// Move the item with `key`. Returns `false` if there is no such item.
+    pub fn update(&mut self, key: T, bounds: Aabb) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+
+        self.insert(key, bounds);
+        return true;
+    }
+
+    /// Remove the item with `key`, returning its box.
+    pub fn remove(&mut self, key: T) -> Option<Aabb> {
+        return match self.items.remove(&key) {
+            Some(item) => {
+                self.unlink(key, item.cells);
+                Some(item.bounds)
+            },
+            None => None
+        };
+    }
+
+    /// Remove all items.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+        self.oversized.clear();
+        self.extent = None;
+        self.min_y = f32::INFINITY;
+        self.max_y = f32::NEG_INFINITY;
+    }
+
+    /// Get the keys of the items overlapping `aabb`.
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<T> {
+        return self.query_cells(self.cell_range(aabb), |_| true, |bounds| bounds.intersects(aabb));
+    }
+
+    /// Get the keys of the items with any part within `radius` of `center`.
+    pub fn query_radius(&self, center: Point3<f32>, radius: f32) -> Vec<T> {
+        let extents = Vector3::new(radius, radius, radius);
+        let range = self.cell_range(&Aabb::new(center + -extents, center + extents));
+
+        return self.query_cells(range, |_| true, |bounds| {
+            // The distance to the closest point of the box.
+            let dx = (bounds.min.x - center.x).max(0.0).max(center.x - bounds.max.x);
+            let dy = (bounds.min.y - center.y).max(0.0).max(center.y - bounds.max.y);
+            let dz = (bounds.min.z - center.z).max(0.0).max(center.z - bounds.max.z);
+
+            dx * dx + dy * dy + dz * dz <= radius * radius
+        });
+    }
+
+    /// Get the keys of the items which may be inside `frustum`.
+    ///
+    /// Only the cells under the frustum are visited, unless it is open, e.g. with an infinite far plane.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<T> {
+        let corners = frustum.corners();
+
+        let range = if corners.iter().all(|corner| corner.x.is_finite() && corner.z.is_finite()) {
+            let min = corners.iter().fold(Point3::new(f32::INFINITY, 0.0, f32::INFINITY), |min, corner| {
+                Point3::new(min.x.min(corner.x), 0.0, min.z.min(corner.z))
+            });
+            let max = corners.iter().fold(Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY), |max, corner| {
+                Point3::new(max.x.max(corner.x), 0.0, max.z.max(corner.z))
+            });
+
+            self.cell_range(&Aabb::new(min, max))
+        } else {
+            CellRange { min: (i32::MIN, i32::MIN), max: (i32::MAX, i32::MAX) }
+        };
+
+        return self.query_cells(
+            range,
+            |cell| frustum.intersects_aabb(&self.cell_box(cell)),
+            |bounds| frustum.intersects_aabb(bounds));
+    }
+
+    /// Get the keys of the items hit by `ray` within `max_distance`, with their distances
+    /// along the ray, closest first.
+    ///
+    /// Walks through the cells crossed by the ray.
+    ///
+    /// # References
+    /// - Amanatides, J., Woo, A. "A Fast Voxel Traversal Algorithm for Ray Tracing", 1987
+    pub fn query_ray(&self, ray: &Ray, max_distance: f32) -> Vec<(T, f32)> {
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+
+        for &key in self.oversized.iter() {
+            seen.insert(key);
+
+            if let Some(distance) = ray.intersect_aabb(&self.items[&key].bounds) {
+                if distance <= max_distance {
+                    hits.push((key, distance));
+                }
+            }
+        }
+
+        // The walk stops once it leaves the occupied cells, so the distance can be infinite.
+        if let Some(occupied) = self.extent {
+            let mut visit = |cell: (i32, i32)| {
+                if let Some(keys) = self.cells.get(&cell) {
+                    for &key in keys.iter() {
+                        if !seen.insert(key) {
+                            continue;
+                        }
+
+                        if let Some(distance) = ray.intersect_aabb(&self.items[&key].bounds) {
+                            if distance <= max_distance {
+                                hits.push((key, distance));
+                            }
+                        }
+                    }
+                }
+            };
+
+            let mut cell = self.cell_of(ray.origin.x, ray.origin.z);
+            let (step_x, mut next_x, delta_x) = self.traversal(ray.origin.x, ray.direction.x, cell.0);
+            let (step_z, mut next_z, delta_z) = self.traversal(ray.origin.z, ray.direction.z, cell.1);
+
+            let beyond = |cell: i32, step: i32, min: i32, max: i32| {
+                step >= 0 && cell > max || step <= 0 && cell < min
+            };
+
+            // Walking through a sparse extent can take longer than visiting every occupied cell.
+            let mut steps = 0;
+
+            loop {
+                visit(cell);
+
+                steps += 1;
+                if steps > self.cells.len() {
+                    for &cell in self.cells.keys() {
+                        visit(cell);
+                    }
+                    break;
+                }
+
+                if beyond(cell.0, step_x, occupied.min.0, occupied.max.0) ||
+                    beyond(cell.1, step_z, occupied.min.1, occupied.max.1) ||
+                    next_x.min(next_z) > max_distance ||
+                    step_x == 0 && step_z == 0
+                {
+                    break;
+                }
+
+                if next_x < next_z {
+                    cell.0 += step_x;
+                    next_x += delta_x;
+                } else {
+                    cell.1 += step_z;
+                    next_z += delta_z;
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        return hits;
+    }
+
+    /// Get the keys of the items passing `test` in the cells of `range` passing `cell_test`,
+    /// and of the oversized items passing `test`.
+    fn query_cells<C, F>(&self, range: CellRange, cell_test: C, test: F) -> Vec<T>
+        where C: Fn((i32, i32)) -> bool, F: Fn(&Aabb) -> bool
+    {
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+
+        {
+            let mut visit = |cell: (i32, i32), keys: &Vec<T>| {
+                if !cell_test(cell) {
+                    return;
+                }
+
+                for &key in keys.iter() {
+                    if seen.insert(key) && test(&self.items[&key].bounds) {
+                        found.push(key);
+                    }
+                }
+            };
+
+            if let Some(range) = self.extent.and_then(|extent| range.intersection(&extent)) {
+                // A range sparser than the occupied cells is cheaper to filter them with.
+                if range.count() <= self.cells.len() as u64 {
+                    for x in range.min.0..range.max.0 + 1 {
+                        for z in range.min.1..range.max.1 + 1 {
+                            if let Some(keys) = self.cells.get(&(x, z)) {
+                                visit((x, z), keys);
+                            }
+                        }
+                    }
+                } else {
+                    for (&cell, keys) in self.cells.iter() {
+                        if range.contains(cell) {
+                            visit(cell, keys);
+                        }
+                    }
+                }
+            }
+        }
+
+        for &key in self.oversized.iter() {
+            if test(&self.items[&key].bounds) {
+                found.push(key);
+            }
+        }
+
+        return found;
+    }
+
+    /// Get the step direction, the distance to the first cell boundary and the distance between
+    /// boundaries along one axis of a ray.
+    fn traversal(&self, origin: f32, direction: f32, cell: i32) -> (i32, f32, f32) {
+        if direction > 0.0 {
+            let boundary = (cell as f32 + 1.0) * self.cell_size;
+            return (1, (boundary - origin) / direction, self.cell_size / direction);
+        }
+
+        if direction < 0.0 {
+            let boundary = cell as f32 * self.cell_size;
+            return (-1, (boundary - origin) / direction, -self.cell_size / direction);
+        }
+
+        return (0, f32::INFINITY, f32::INFINITY);
+    }
+
+    fn cell_of(&self, x: f32, z: f32) -> (i32, i32) {
+        return ((x / self.cell_size).floor() as i32, (z / self.cell_size).floor() as i32);
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> CellRange {
+        return CellRange {
+            min: self.cell_of(bounds.min.x, bounds.min.z),
+            max: self.cell_of(bounds.max.x, bounds.max.z)
+        };
+    }
+
+    fn cell_box(&self, (x, z): (i32, i32)) -> Aabb {
+        return Aabb::new(
+            Point3::new(x as f32 * self.cell_size, self.min_y, z as f32 * self.cell_size),
+            Point3::new((x as f32 + 1.0) * self.cell_size, self.max_y, (z as f32 + 1.0) * self.cell_size));
+    }
+
+    /// Get the cells of an item, `None` if there are too many or its bounds are beyond the grid.
+    fn item_cells(&self, bounds: &Aabb) -> Option<CellRange> {
+        let finite = [bounds.min, bounds.max].iter().all(|point| {
+            point.x.is_finite() && point.y.is_finite() && point.z.is_finite()
+        });
+        if !finite {
+            return None;
+        }
+
+        // Casting saturates, so the cells at the limits may stand for any beyond them.
+        let range = self.cell_range(bounds);
+        let within = [range.min.0, range.min.1, range.max.0, range.max.1].iter().all(|&cell| {
+            cell > i32::MIN && cell < i32::MAX
+        });
+
+        return if within && range.count() <= MAX_ITEM_CELLS { Some(range) } else { None };
+    }
+
+    fn link(&mut self, key: T, range: Option<CellRange>) {
+        let range = match range {
+            Some(range) => range,
+            None => {
+                self.oversized.insert(key);
+                return;
+            }
+        };
+
+        for x in range.min.0..range.max.0 + 1 {
+            for z in range.min.1..range.max.1 + 1 {
+                self.cells.entry((x, z)).or_insert_with(Vec::new).push(key);
+            }
+        }
+
+        self.extent = Some(match self.extent {
+            Some(extent) => extent.union(&range),
+            None => range
+        });
+    }
+
+    fn unlink(&mut self, key: T, range: Option<CellRange>) {
+        let range = match range {
+            Some(range) => range,
+            None => {
+                self.oversized.remove(&key);
+                return;
+            }
+        };
+
+        let cells = &mut self.cells;
+        for x in range.min.0..range.max.0 + 1 {
+            for z in range.min.1..range.max.1 + 1 {
+                let empty = match cells.get_mut(&(x, z)) {
+                    Some(keys) => {
+                        keys.retain(|&other| other != key);
+                        keys.is_empty()
+                    },
+                    None => false
+                };
+
+                if empty {
+                    cells.remove(&(x, z));
+                }
+            }
+        }
+    }
+}
+
+impl CellRange {
+    fn contains(&self, (x, z): (i32, i32)) -> bool {
+        return x >= self.min.0 && x <= self.max.0 && z >= self.min.1 && z <= self.max.1;
+    }
+
+    fn count(&self) -> u64 {
+        let width = (self.max.0 as i64 - self.min.0 as i64 + 1) as u64;
+        let depth = (self.max.1 as i64 - self.min.1 as i64 + 1) as u64;
+
+        return width.saturating_mul(depth);
+    }
+
+    fn union(&self, other: &CellRange) -> CellRange {
+        return CellRange {
+            min: (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: (self.max.0.max(other.max.0), self.max.1.max(other.max.1))
+        };
+    }
+
+    fn intersection(&self, other: &CellRange) -> Option<CellRange> {
+        let range = CellRange {
+            min: (self.min.0.max(other.min.0), self.min.1.max(other.min.1)),
+            max: (self.max.0.min(other.max.0), self.max.1.min(other.max.1))
+        };
+
+        return if range.min.0 <= range.max.0 && range.min.1 <= range.max.1 { Some(range) } else { None };
+    }
+}
+
+impl SpatialGrid<NodeId> {
+    /// Add or move the node of `handle` with its current world bounds.
+    ///
+    /// A `Scene` keeps the nodes in its index up to date by itself, this is needed
+    /// only after their bounds change without moving, see `Scene::set_spatial_index`.
+    ///
+    /// Removes the node instead if it was removed from its parent or has no bounds.
+    /// Returns whether the node is in the grid afterwards.
+    pub fn insert_node(&mut self, handle: &NodeHandle) -> bool {
+        if !handle.is_removed() {
+            if let Some(bounds) = handle.world_bounds() {
+                self.insert(handle.id(), bounds.aabb());
+                return true;
+            }
+        }
+
+        self.remove(handle.id());
+        return false;
+    }
+}
+
+/// The most cells an item can be in before it is kept aside as oversized.
+const MAX_ITEM_CELLS: u64 = 4096;
+
+const ERR_CELL_SIZE: &'static str = "Spatial grid cell size must be positive";
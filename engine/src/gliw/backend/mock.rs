@@ -0,0 +1,400 @@
+extern crate gl as raw;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+/// Argument of a call recorded by `MockBackend`.
+///
+/// Pointers are recorded by address only, the data they point to is not captured.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MockValue {
+    Int(i64),
+    Float(f64),
+    Ptr(usize),
+}
+
+/// OpenGL call recorded by `MockBackend`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GlCall {
+    /// Name of the function without the `gl` prefix, e.g. `"DrawArrays"`.
+    pub name: &'static str,
+    pub args: Vec<MockValue>,
+}
+
+impl GlCall {
+    /// Get the integer argument at `index`.
+    ///
+    /// # Panics
+    /// Panics if the argument is not an integer.
+    pub fn int(&self, index: usize) -> i64 {
+        return match self.args[index] {
+            MockValue::Int(value) => value,
+            _ => panic!("{} {} of `{}`", ERR_NOT_INT, index, self.name)
+        };
+    }
+
+    /// Get the floating point argument at `index`.
+    ///
+    /// # Panics
+    /// Panics if the argument is not a floating point number.
+    pub fn float(&self, index: usize) -> f64 {
+        return match self.args[index] {
+            MockValue::Float(value) => value,
+            _ => panic!("{} {} of `{}`", ERR_NOT_FLOAT, index, self.name)
+        };
+    }
+}
+
+/// Backend which records all calls instead of executing them.
+///
+/// Simulates enough of OpenGL for the engine to run without a context:
+///
+/// * `glGen*` and `glCreate*` return unique handles starting from `1`.
+/// * Shader compilation, program linking, pipeline validation and framebuffer completeness checks always succeed.
+/// * `glGetIntegerv` returns the values set with `set_integer` or defaults of an OpenGL 4.5 context.
+/// `glGetIntegeri_v` returns the same value for all indices and `glGetQueryObjectuiv` the value set for its `pname`,
+/// e.g. `GL_QUERY_RESULT`.
+/// * `glGet*Location` return a stable location for each name.
+/// * `glGetBufferParameteriv` reports the `GL_BUFFER_SIZE` given to `glBufferData` for the bound buffer.
+/// * Fences are signaled unless `GL_SYNC_STATUS` is set to `GL_UNSIGNALED` with `set_integer`,
+/// `glClientWaitSync` never blocks.
+/// * `glGetError` reports the errors raised with `fail_on`, in order.
+/// * `glReadPixels` copies the bytes set with `set_pixels`, the rest of the output is left untouched.
+///
+/// See `set_backend`.
+pub struct MockBackend {
+    calls: RefCell<Vec<GlCall>>,
+    next_handle: Cell<u32>,
+    integers: RefCell<HashMap<u32, i32>>,
+    strings: HashMap<u32, CString>,
+    failures: RefCell<HashMap<String, u32>>,
+    errors: RefCell<VecDeque<u32>>,
+    locations: RefCell<Vec<CString>>,
+    extensions: RefCell<Vec<CString>>,
+    pixels: RefCell<Vec<u8>>,
+    bound_buffers: RefCell<HashMap<u32, u32>>,
+    buffer_sizes: RefCell<HashMap<u32, i32>>,
+}
+
+impl MockBackend {
+    /// Creates a mock with no recorded calls.
+    pub fn new() -> MockBackend {
+        let mut integers = HashMap::new();
+        integers.insert(raw::MAJOR_VERSION, 4);
+        integers.insert(raw::MINOR_VERSION, 5);
+        integers.insert(raw::NUM_EXTENSIONS, 0);
+        integers.insert(raw::DEPTH_FUNC, raw::LESS as i32);
+        integers.insert(raw::DEPTH_WRITEMASK, raw::TRUE as i32);
+        integers.insert(raw::SYNC_STATUS, raw::SIGNALED as i32);
+        integers.insert(raw::MAX_VERTEX_ATTRIBS, 16);
+        integers.insert(raw::MAX_TEXTURE_SIZE, 16384);
+        integers.insert(raw::MAX_3D_TEXTURE_SIZE, 2048);
+        integers.insert(raw::MAX_CUBE_MAP_TEXTURE_SIZE, 16384);
+        integers.insert(raw::MAX_ARRAY_TEXTURE_LAYERS, 2048);
+        integers.insert(raw::MAX_TEXTURE_IMAGE_UNITS, 16);
+        integers.insert(raw::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 80);
+        integers.insert(raw::MAX_RENDERBUFFER_SIZE, 16384);
+        integers.insert(raw::MAX_COLOR_ATTACHMENTS, 8);
+        integers.insert(raw::MAX_DRAW_BUFFERS, 8);
+        integers.insert(raw::MAX_SAMPLES, 8);
+        integers.insert(raw::MAX_UNIFORM_BUFFER_BINDINGS, 36);
+        integers.insert(raw::MAX_UNIFORM_BLOCK_SIZE, 65536);
+        integers.insert(raw::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS, 4);
+        integers.insert(raw::MAX_TRANSFORM_FEEDBACK_BUFFERS, 4);
+        integers.insert(raw::MAX_PATCH_VERTICES, 32);
+        integers.insert(raw::MAX_TESS_GEN_LEVEL, 64);
+        integers.insert(raw::MAX_ATOMIC_COUNTER_BUFFER_BINDINGS, 8);
+        integers.insert(raw::MAX_IMAGE_UNITS, 8);
+        integers.insert(raw::MAX_SHADER_STORAGE_BUFFER_BINDINGS, 8);
+        integers.insert(raw::MAX_COMPUTE_WORK_GROUP_COUNT, 65535);
+        integers.insert(raw::MAX_COMPUTE_WORK_GROUP_SIZE, 1024);
+        integers.insert(raw::MAX_COMPUTE_WORK_GROUP_INVOCATIONS, 1024);
+
+        let mut strings = HashMap::new();
+        strings.insert(raw::VENDOR, CString::new("Mock").unwrap());
+        strings.insert(raw::RENDERER, CString::new("MockBackend").unwrap());
+        strings.insert(raw::VERSION, CString::new("4.5").unwrap());
+        strings.insert(raw::SHADING_LANGUAGE_VERSION, CString::new("4.50").unwrap());
+
+        return MockBackend {
+            calls: RefCell::new(Vec::new()),
+            next_handle: Cell::new(1),
+            integers: RefCell::new(integers),
+            strings: strings,
+            failures: RefCell::new(HashMap::new()),
+            errors: RefCell::new(VecDeque::new()),
+            locations: RefCell::new(Vec::new()),
+            extensions: RefCell::new(Vec::new()),
+            pixels: RefCell::new(Vec::new()),
+            bound_buffers: RefCell::new(HashMap::new()),
+            buffer_sizes: RefCell::new(HashMap::new()),
+        };
+    }
+
+    /// Get all of the recorded calls in order.
+    pub fn calls(&self) -> Vec<GlCall> {
+        return self.calls.borrow().clone();
+    }
+
+    /// Get the recorded calls to the function `name` (without the `gl` prefix) in order.
+    pub fn calls_named(&self, name: &str) -> Vec<GlCall> {
+        return self.calls.borrow().iter().filter(|call| call.name == name).cloned().collect();
+    }
+
+    /// Forgets all of the recorded calls.
+    pub fn clear(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    /// Sets the value returned by `glGetIntegerv`, `glGetQueryObjectuiv` and `glGetSynciv` for `pname`.
+    ///
+    /// **Note:** the `Capabilities` of the thread are cached, call `Capabilities::invalidate`
+    /// for changes of the version or the limits to take effect.
+    pub fn set_integer(&self, pname: u32, value: i32) {
+        self.integers.borrow_mut().insert(pname, value);
+    }
+
+    /// Adds `name` to the extensions reported by `glGetStringi`.
+    ///
+    /// See `set_integer` regarding the cached `Capabilities`.
+    pub fn add_extension(&self, name: &str) {
+        let mut extensions = self.extensions.borrow_mut();
+        extensions.push(CString::new(name).unwrap());
+        self.integers.borrow_mut().insert(raw::NUM_EXTENSIONS, extensions.len() as i32);
+    }
+
+    /// Sets the bytes written by `glReadPixels`, from the start of its output.
+    pub fn set_pixels(&self, data: &[u8]) {
+        *self.pixels.borrow_mut() = data.to_vec();
+    }
+
+    /// Makes every subsequent call to the function `name` raise `error`, e.g. `gl::INVALID_OPERATION`.
+    pub fn fail_on(&self, name: &str, error: u32) {
+        self.failures.borrow_mut().insert(String::from(name), error);
+    }
+
+    /// Records a call and simulates its effect.
+    ///
+    /// Used by the `Backend` implementation, the pointers in `args` must be valid for `name`.
+    #[doc(hidden)]
+    pub unsafe fn call(&self, name: &'static str, args: Vec<MockValue>) -> MockValue {
+        if let Some(error) = self.failures.borrow().get(name) {
+            self.errors.borrow_mut().push_back(*error);
+        }
+
+        let ret = match name {
+            "GenBuffers" | "GenVertexArrays" | "GenTextures" | "GenQueries" | "GenFramebuffers" |
+            "GenRenderbuffers" | "GenProgramPipelines" | "GenTransformFeedbacks" => {
+                let out = ptr_arg(&args, 1) as *mut u32;
+                for i in 0..int_arg(&args, 0) as isize {
+                    *out.offset(i) = self.gen_handle();
+                }
+                MockValue::Int(0)
+            },
+            "CreateShader" | "CreateProgram" => MockValue::Int(self.gen_handle() as i64),
+            "GetShaderiv" | "GetProgramiv" | "GetProgramPipelineiv" => {
+                let value = match int_arg(&args, 1) as u32 {
+                    raw::INFO_LOG_LENGTH => 0,
+                    _ => raw::TRUE as i32
+                };
+                *(ptr_arg(&args, 2) as *mut i32) = value;
+                MockValue::Int(0)
+            },
+            "GetIntegerv" => {
+                let pname = int_arg(&args, 0) as u32;
+                *(ptr_arg(&args, 1) as *mut i32) = *self.integers.borrow().get(&pname).unwrap_or(&0);
+                MockValue::Int(0)
+            },
+            "GetIntegeri_v" => {
+                let pname = int_arg(&args, 0) as u32;
+                *(ptr_arg(&args, 2) as *mut i32) = *self.integers.borrow().get(&pname).unwrap_or(&0);
+                MockValue::Int(0)
+            },
+            "BindBuffer" => {
+                self.bound_buffers.borrow_mut().insert(int_arg(&args, 0) as u32, int_arg(&args, 1) as u32);
+                MockValue::Int(0)
+            },
+            "BufferData" => {
+                if let Some(&buffer) = self.bound_buffers.borrow().get(&(int_arg(&args, 0) as u32)) {
+                    self.buffer_sizes.borrow_mut().insert(buffer, int_arg(&args, 1) as i32);
+                }
+                MockValue::Int(0)
+            },
+            "GetBufferParameteriv" => {
+                let size = match self.bound_buffers.borrow().get(&(int_arg(&args, 0) as u32)) {
+                    Some(buffer) if int_arg(&args, 1) as u32 == raw::BUFFER_SIZE => {
+                        *self.buffer_sizes.borrow().get(buffer).unwrap_or(&0)
+                    },
+                    _ => 0
+                };
+                *(ptr_arg(&args, 2) as *mut i32) = size;
+                MockValue::Int(0)
+            },
+            "GetQueryObjectuiv" => {
+                let pname = int_arg(&args, 1) as u32;
+                *(ptr_arg(&args, 2) as *mut u32) = *self.integers.borrow().get(&pname).unwrap_or(&0) as u32;
+                MockValue::Int(0)
+            },
+            "GetAttribLocation" | "GetUniformLocation" => {
+                let name = CStr::from_ptr(ptr_arg(&args, 1) as *const c_char);
+                MockValue::Int(self.location(name) as i64)
+            },
+            "GetProgramResourceIndex" => {
+                let name = CStr::from_ptr(ptr_arg(&args, 2) as *const c_char);
+                MockValue::Int(self.location(name) as i64)
+            },
+            "GetString" => {
+                match self.strings.get(&(int_arg(&args, 0) as u32)) {
+                    Some(string) => MockValue::Ptr(string.as_ptr() as usize),
+                    None => MockValue::Ptr(0)
+                }
+            },
+            "GetStringi" => {
+                match self.extensions.borrow().get(int_arg(&args, 1) as usize) {
+                    Some(extension) => MockValue::Ptr(extension.as_ptr() as usize),
+                    None => {
+                        self.errors.borrow_mut().push_back(raw::INVALID_VALUE);
+                        MockValue::Ptr(0)
+                    }
+                }
+            },
+            "FenceSync" => MockValue::Ptr(self.gen_handle() as usize),
+            "GetSynciv" => {
+                let pname = int_arg(&args, 1) as u32;
+                *(ptr_arg(&args, 4) as *mut i32) = *self.integers.borrow().get(&pname).unwrap_or(&0);
+                MockValue::Int(0)
+            },
+            "ReadPixels" => {
+                let components = match int_arg(&args, 4) as u32 {
+                    raw::RED | raw::RED_INTEGER | raw::DEPTH_COMPONENT => 1,
+                    raw::RG | raw::RG_INTEGER => 2,
+                    raw::RGB | raw::RGB_INTEGER => 3,
+                    _ => 4
+                };
+                let component_size = match int_arg(&args, 5) as u32 {
+                    raw::UNSIGNED_BYTE | raw::BYTE => 1,
+                    raw::UNSIGNED_SHORT | raw::SHORT | raw::HALF_FLOAT => 2,
+                    _ => 4
+                };
+                let size = (int_arg(&args, 2) * int_arg(&args, 3)) as usize * components * component_size;
+
+                let pixels = self.pixels.borrow();
+                let out = ptr_arg(&args, 6) as *mut u8;
+                for (i, &byte) in pixels.iter().take(size).enumerate() {
+                    *out.offset(i as isize) = byte;
+                }
+                MockValue::Int(0)
+            },
+            "CheckFramebufferStatus" => MockValue::Int(raw::FRAMEBUFFER_COMPLETE as i64),
+            "ClientWaitSync" => MockValue::Int(raw::ALREADY_SIGNALED as i64),
+            "GetError" => MockValue::Int(self.errors.borrow_mut().pop_front().unwrap_or(raw::NO_ERROR) as i64),
+            _ => MockValue::Int(0)
+        };
+
+        self.calls.borrow_mut().push(GlCall { name: name, args: args });
+
+        return ret;
+    }
+
+    fn gen_handle(&self) -> u32 {
+        let handle = self.next_handle.get();
+        self.next_handle.set(handle + 1);
+        return handle;
+    }
+
+    fn location(&self, name: &CStr) -> i32 {
+        let mut locations = self.locations.borrow_mut();
+
+        if let Some(location) = locations.iter().position(|known| known.as_bytes() == name.to_bytes()) {
+            return location as i32;
+        }
+
+        locations.push(name.to_owned());
+        return locations.len() as i32 - 1;
+    }
+}
+
+fn int_arg(args: &[MockValue], index: usize) -> i64 {
+    return match args[index] {
+        MockValue::Int(value) => value,
+        _ => panic!(ERR_BAD_ARGS)
+    };
+}
+
+fn ptr_arg(args: &[MockValue], index: usize) -> usize {
+    return match args[index] {
+        MockValue::Ptr(value) => value,
+        _ => panic!(ERR_BAD_ARGS)
+    };
+}
+
+/// Conversion of OpenGL arguments for recording.
+pub trait MockArg {
+    fn to_mock(&self) -> MockValue;
+}
+
+/// Conversion of simulated results to the OpenGL return types.
+pub trait FromMock {
+    fn from_mock(value: MockValue) -> Self;
+}
+
+macro_rules! mock_int {
+    ($($t: ty),*) => {
+        $(
+            impl MockArg for $t {
+                fn to_mock(&self) -> MockValue {
+                    return MockValue::Int(*self as i64);
+                }
+            }
+
+            impl FromMock for $t {
+                fn from_mock(value: MockValue) -> $t {
+                    return match value {
+                        MockValue::Int(value) => value as $t,
+                        _ => 0
+                    };
+                }
+            }
+        )*
+    }
+}
+
+mock_int!(u8, i32, u32, i64, u64, isize);
+
+impl MockArg for f32 {
+    fn to_mock(&self) -> MockValue {
+        return MockValue::Float(*self as f64);
+    }
+}
+
+impl<T> MockArg for *const T {
+    fn to_mock(&self) -> MockValue {
+        return MockValue::Ptr(*self as usize);
+    }
+}
+
+impl<T> MockArg for *mut T {
+    fn to_mock(&self) -> MockValue {
+        return MockValue::Ptr(*self as usize);
+    }
+}
+
+impl<T> FromMock for *const T {
+    fn from_mock(value: MockValue) -> *const T {
+        return match value {
+            MockValue::Ptr(value) => value as *const T,
+            _ => 0 as *const T
+        };
+    }
+}
+
+impl FromMock for () {
+    fn from_mock(_: MockValue) {
+    }
+}
+
+const ERR_NOT_INT: &'static str = "Expected an integer for argument";
+const ERR_NOT_FLOAT: &'static str = "Expected a floating point number for argument";
+const ERR_BAD_ARGS: &'static str = "Unexpected arguments passed to the mock backend";
@@ -0,0 +1,264 @@
+//! Pluggable backend for all of the OpenGL calls made by the engine.
+//!
+//! Every module in the engine calls OpenGL through the `gl` module defined here. It re-exports
+//! all of the constants and types of the `gl` crate, but its functions dispatch to the `Backend`
+//! which is current for the calling thread. By default that is `GlBackend` which forwards to the
+//! `gl` crate. Tests can swap in a `MockBackend` to run engine logic without an OpenGL context.
+
+extern crate gl as raw;
+
+mod mock;
+
+pub use self::mock::{MockBackend, GlCall, MockValue};
+
+use self::mock::{MockArg, FromMock};
+use self::raw::types::*;
+
+use gliw::Capabilities;
+
+use std::cell::RefCell;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+/// Declares the OpenGL functions used by the engine.
+///
+/// Generates the `Backend` trait, its implementations for `GlBackend` and `MockBackend`
+/// and the dispatching functions of the `gl` module from a single list of signatures.
+macro_rules! gl_backend {
+    ($( fn $name: ident ( $( $arg: ident : $arg_ty: ty ),* ) -> $ret: ty; )*) => {
+        /// The OpenGL functions used by the engine.
+        ///
+        /// See the module level documentation.
+        #[allow(non_snake_case)]
+        pub trait Backend {
+            $( unsafe fn $name(&self, $( $arg: $arg_ty ),* ) -> $ret; )*
+        }
+
+        #[allow(non_snake_case)]
+        impl Backend for GlBackend {
+            $(
+                #[inline]
+                unsafe fn $name(&self, $( $arg: $arg_ty ),* ) -> $ret {
+                    raw::$name($( $arg ),*)
+                }
+            )*
+        }
+
+        #[allow(non_snake_case)]
+        impl Backend for MockBackend {
+            $(
+                unsafe fn $name(&self, $( $arg: $arg_ty ),* ) -> $ret {
+                    FromMock::from_mock(self.call(stringify!($name), vec![$( $arg.to_mock() ),*]))
+                }
+            )*
+        }
+
+        /// Drop-in replacement for the `gl` crate which dispatches to the current `Backend`.
+        #[allow(non_snake_case)]
+        pub mod gl {
+            pub use super::raw::*;
+
+            use super::raw::types::*;
+            use std::os::raw::c_void;
+
+            $(
+                #[inline]
+                pub unsafe fn $name($( $arg: $arg_ty ),*) -> $ret {
+                    super::with(|backend| backend.$name($( $arg ),*))
+                }
+            )*
+        }
+    }
+}
+
+/// Backend forwarding to the `gl` crate, i.e. the actual OpenGL driver.
+pub struct GlBackend;
+
+thread_local!(static BACKEND: RefCell<Rc<Backend>> = RefCell::new(Rc::new(GlBackend)));
+
+/// Set the backend for all OpenGL calls made on the current thread.
+///
+/// Returns the previous backend. Discards the cached `Capabilities` of the thread.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate engine;
+/// # fn main() {
+/// use engine::gliw::{self, Buffer, BufferType, MockBackend};
+/// use std::rc::Rc;
+///
+/// let mock = Rc::new(MockBackend::new());
+/// gliw::set_backend(mock.clone());
+///
+/// let vbo = Buffer::new(BufferType::Array);
+/// vbo.bind();
+///
+/// let binds = mock.calls_named("BindBuffer");
+/// assert_eq!(binds[0].int(1), vbo.handle() as i64);
+/// # }
+/// ```
+pub fn set_backend(backend: Rc<Backend>) -> Rc<Backend> {
+    Capabilities::invalidate();
+
+    return BACKEND.with(|current| {
+        let mut current = current.borrow_mut();
+        let previous = current.clone();
+        *current = backend;
+        previous
+    });
+}
+
+fn with<F, R>(f: F) -> R
+    where F: FnOnce(&Backend) -> R
+{
+    // The backend is cloned out so that it can be swapped even from within a call.
+    let backend = BACKEND.with(|current| current.borrow().clone());
+    return f(&*backend);
+}
+
+gl_backend! {
+    fn ActiveShaderProgram(pipeline: GLuint, program: GLuint) -> ();
+    fn ActiveTexture(texture: GLenum) -> ();
+    fn AttachShader(program: GLuint, shader: GLuint) -> ();
+    fn BeginQuery(target: GLenum, id: GLuint) -> ();
+    fn BeginTransformFeedback(primitive_mode: GLenum) -> ();
+    fn BindBuffer(target: GLenum, buffer: GLuint) -> ();
+    fn BindBufferBase(target: GLenum, index: GLuint, buffer: GLuint) -> ();
+    fn BindBufferRange(target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr) -> ();
+    fn BindFramebuffer(target: GLenum, framebuffer: GLuint) -> ();
+    fn BindImageTexture(unit: GLuint, texture: GLuint, level: GLint, layered: GLboolean, layer: GLint, access: GLenum, format: GLenum) -> ();
+    fn BindProgramPipeline(pipeline: GLuint) -> ();
+    fn BindRenderbuffer(target: GLenum, renderbuffer: GLuint) -> ();
+    fn BindTexture(target: GLenum, texture: GLuint) -> ();
+    fn BindTransformFeedback(target: GLenum, id: GLuint) -> ();
+    fn BindVertexArray(array: GLuint) -> ();
+    fn BlendFunc(sfactor: GLenum, dfactor: GLenum) -> ();
+    fn BlitFramebuffer(src_x0: GLint, src_y0: GLint, src_x1: GLint, src_y1: GLint, dst_x0: GLint, dst_y0: GLint, dst_x1: GLint, dst_y1: GLint, mask: GLbitfield, filter: GLenum) -> ();
+    fn BufferData(target: GLenum, size: GLsizeiptr, data: *const c_void, usage: GLenum) -> ();
+    fn BufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *const c_void) -> ();
+    fn CheckFramebufferStatus(target: GLenum) -> GLenum;
+    fn Clear(mask: GLbitfield) -> ();
+    fn ClearBufferfv(buffer: GLenum, drawbuffer: GLint, value: *const GLfloat) -> ();
+    fn ClearBufferuiv(buffer: GLenum, drawbuffer: GLint, value: *const GLuint) -> ();
+    fn ClearColor(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) -> ();
+    fn ClientWaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64) -> GLenum;
+    fn CompileShader(shader: GLuint) -> ();
+    fn CreateProgram() -> GLuint;
+    fn CreateShader(type_: GLenum) -> GLuint;
+    fn CullFace(mode: GLenum) -> ();
+    fn DeleteBuffers(n: GLsizei, buffers: *const GLuint) -> ();
+    fn DeleteFramebuffers(n: GLsizei, framebuffers: *const GLuint) -> ();
+    fn DeleteProgram(program: GLuint) -> ();
+    fn DeleteProgramPipelines(n: GLsizei, pipelines: *const GLuint) -> ();
+    fn DeleteQueries(n: GLsizei, ids: *const GLuint) -> ();
+    fn DeleteRenderbuffers(n: GLsizei, renderbuffers: *const GLuint) -> ();
+    fn DeleteShader(shader: GLuint) -> ();
+    fn DeleteSync(sync: GLsync) -> ();
+    fn DeleteTextures(n: GLsizei, textures: *const GLuint) -> ();
+    fn DeleteTransformFeedbacks(n: GLsizei, ids: *const GLuint) -> ();
+    fn DeleteVertexArrays(n: GLsizei, arrays: *const GLuint) -> ();
+    fn DepthFunc(func: GLenum) -> ();
+    fn DepthMask(flag: GLboolean) -> ();
+    fn DetachShader(program: GLuint, shader: GLuint) -> ();
+    fn Disable(cap: GLenum) -> ();
+    fn DisableVertexAttribArray(index: GLuint) -> ();
+    fn DispatchCompute(num_groups_x: GLuint, num_groups_y: GLuint, num_groups_z: GLuint) -> ();
+    fn DispatchComputeIndirect(indirect: GLintptr) -> ();
+    fn DrawArrays(mode: GLenum, first: GLint, count: GLsizei) -> ();
+    fn DrawBuffers(n: GLsizei, bufs: *const GLenum) -> ();
+    fn DrawElements(mode: GLenum, count: GLsizei, type_: GLenum, indices: *const c_void) -> ();
+    fn DrawTransformFeedback(mode: GLenum, id: GLuint) -> ();
+    fn Enable(cap: GLenum) -> ();
+    fn EnableVertexAttribArray(index: GLuint) -> ();
+    fn EndQuery(target: GLenum) -> ();
+    fn EndTransformFeedback() -> ();
+    fn FenceSync(condition: GLenum, flags: GLbitfield) -> GLsync;
+    fn FramebufferRenderbuffer(target: GLenum, attachment: GLenum, renderbuffertarget: GLenum, renderbuffer: GLuint) -> ();
+    fn FramebufferTexture2D(target: GLenum, attachment: GLenum, textarget: GLenum, texture: GLuint, level: GLint) -> ();
+    fn FramebufferTextureLayer(target: GLenum, attachment: GLenum, texture: GLuint, level: GLint, layer: GLint) -> ();
+    fn GenBuffers(n: GLsizei, buffers: *mut GLuint) -> ();
+    fn GenFramebuffers(n: GLsizei, framebuffers: *mut GLuint) -> ();
+    fn GenProgramPipelines(n: GLsizei, pipelines: *mut GLuint) -> ();
+    fn GenQueries(n: GLsizei, ids: *mut GLuint) -> ();
+    fn GenRenderbuffers(n: GLsizei, renderbuffers: *mut GLuint) -> ();
+    fn GenTextures(n: GLsizei, textures: *mut GLuint) -> ();
+    fn GenTransformFeedbacks(n: GLsizei, ids: *mut GLuint) -> ();
+    fn GenVertexArrays(n: GLsizei, arrays: *mut GLuint) -> ();
+    fn GenerateMipmap(target: GLenum) -> ();
+    fn GetAttribLocation(program: GLuint, name: *const GLchar) -> GLint;
+    fn GetBufferParameteriv(target: GLenum, pname: GLenum, params: *mut GLint) -> ();
+    fn GetBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *mut c_void) -> ();
+    fn GetError() -> GLenum;
+    fn GetIntegeri_v(target: GLenum, index: GLuint, data: *mut GLint) -> ();
+    fn GetIntegerv(pname: GLenum, data: *mut GLint) -> ();
+    fn GetProgramInfoLog(program: GLuint, buf_size: GLsizei, length: *mut GLsizei, info_log: *mut GLchar) -> ();
+    fn GetProgramPipelineInfoLog(pipeline: GLuint, buf_size: GLsizei, length: *mut GLsizei, info_log: *mut GLchar) -> ();
+    fn GetProgramPipelineiv(pipeline: GLuint, pname: GLenum, params: *mut GLint) -> ();
+    fn GetProgramResourceIndex(program: GLuint, program_interface: GLenum, name: *const GLchar) -> GLuint;
+    fn GetProgramiv(program: GLuint, pname: GLenum, params: *mut GLint) -> ();
+    fn GetQueryObjectuiv(id: GLuint, pname: GLenum, params: *mut GLuint) -> ();
+    fn GetShaderInfoLog(shader: GLuint, buf_size: GLsizei, length: *mut GLsizei, info_log: *mut GLchar) -> ();
+    fn GetShaderiv(shader: GLuint, pname: GLenum, params: *mut GLint) -> ();
+    fn GetString(name: GLenum) -> *const GLubyte;
+    fn GetStringi(name: GLenum, index: GLuint) -> *const GLubyte;
+    fn GetSynciv(sync: GLsync, pname: GLenum, buf_size: GLsizei, length: *mut GLsizei, values: *mut GLint) -> ();
+    fn GetUniformLocation(program: GLuint, name: *const GLchar) -> GLint;
+    fn LinkProgram(program: GLuint) -> ();
+    fn MemoryBarrier(barriers: GLbitfield) -> ();
+    fn PatchParameterfv(pname: GLenum, values: *const GLfloat) -> ();
+    fn PatchParameteri(pname: GLenum, value: GLint) -> ();
+    fn PauseTransformFeedback() -> ();
+    fn PolygonOffset(factor: GLfloat, units: GLfloat) -> ();
+    fn ProgramParameteri(program: GLuint, pname: GLenum, value: GLint) -> ();
+    fn ReadBuffer(src: GLenum) -> ();
+    fn ReadPixels(x: GLint, y: GLint, width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum, pixels: *mut c_void) -> ();
+    fn RenderbufferStorageMultisample(target: GLenum, samples: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei) -> ();
+    fn ResumeTransformFeedback() -> ();
+    fn ShaderSource(shader: GLuint, count: GLsizei, string: *const *const GLchar, length: *const GLint) -> ();
+    fn ShaderStorageBlockBinding(program: GLuint, storage_block_index: GLuint, storage_block_binding: GLuint) -> ();
+    fn TexImage2D(target: GLenum, level: GLint, internalformat: GLint, width: GLsizei, height: GLsizei, border: GLint, format: GLenum, type_: GLenum, pixels: *const c_void) -> ();
+    fn TexImage3D(target: GLenum, level: GLint, internalformat: GLint, width: GLsizei, height: GLsizei, depth: GLsizei, border: GLint, format: GLenum, type_: GLenum, pixels: *const c_void) -> ();
+    fn TexParameteri(target: GLenum, pname: GLenum, param: GLint) -> ();
+    fn TransformFeedbackVaryings(program: GLuint, count: GLsizei, varyings: *const *const GLchar, buffer_mode: GLenum) -> ();
+    fn Uniform1f(location: GLint, v0: GLfloat) -> ();
+    fn Uniform1fv(location: GLint, count: GLsizei, value: *const GLfloat) -> ();
+    fn Uniform1i(location: GLint, v0: GLint) -> ();
+    fn Uniform1iv(location: GLint, count: GLsizei, value: *const GLint) -> ();
+    fn Uniform1ui(location: GLint, v0: GLuint) -> ();
+    fn Uniform1uiv(location: GLint, count: GLsizei, value: *const GLuint) -> ();
+    fn Uniform2f(location: GLint, v0: GLfloat, v1: GLfloat) -> ();
+    fn Uniform2fv(location: GLint, count: GLsizei, value: *const GLfloat) -> ();
+    fn Uniform2i(location: GLint, v0: GLint, v1: GLint) -> ();
+    fn Uniform2iv(location: GLint, count: GLsizei, value: *const GLint) -> ();
+    fn Uniform2ui(location: GLint, v0: GLuint, v1: GLuint) -> ();
+    fn Uniform2uiv(location: GLint, count: GLsizei, value: *const GLuint) -> ();
+    fn Uniform3f(location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat) -> ();
+    fn Uniform3fv(location: GLint, count: GLsizei, value: *const GLfloat) -> ();
+    fn Uniform3i(location: GLint, v0: GLint, v1: GLint, v2: GLint) -> ();
+    fn Uniform3iv(location: GLint, count: GLsizei, value: *const GLint) -> ();
+    fn Uniform3ui(location: GLint, v0: GLuint, v1: GLuint, v2: GLuint) -> ();
+    fn Uniform3uiv(location: GLint, count: GLsizei, value: *const GLuint) -> ();
+    fn Uniform4f(location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat, v3: GLfloat) -> ();
+    fn Uniform4fv(location: GLint, count: GLsizei, value: *const GLfloat) -> ();
+    fn Uniform4i(location: GLint, v0: GLint, v1: GLint, v2: GLint, v3: GLint) -> ();
+    fn Uniform4iv(location: GLint, count: GLsizei, value: *const GLint) -> ();
+    fn Uniform4ui(location: GLint, v0: GLuint, v1: GLuint, v2: GLuint, v3: GLuint) -> ();
+    fn Uniform4uiv(location: GLint, count: GLsizei, value: *const GLuint) -> ();
+    fn UniformMatrix2fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix2x3fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix2x4fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix3fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix3x2fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix3x4fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix4fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix4x2fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UniformMatrix4x3fv(location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat) -> ();
+    fn UseProgram(program: GLuint) -> ();
+    fn UseProgramStages(pipeline: GLuint, stages: GLbitfield, program: GLuint) -> ();
+    fn ValidateProgramPipeline(pipeline: GLuint) -> ();
+    fn VertexAttribIPointer(index: GLuint, size: GLint, type_: GLenum, stride: GLsizei, pointer: *const c_void) -> ();
+    fn VertexAttribPointer(index: GLuint, size: GLint, type_: GLenum, normalized: GLboolean, stride: GLsizei, pointer: *const c_void) -> ();
+    fn Viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei) -> ();
+    fn WaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64) -> ();
+}
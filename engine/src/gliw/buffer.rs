@@ -1,5 +1,6 @@
-extern crate gl;
+use gliw::gl;
 
+use gliw::Capabilities;
 use gliw::error;
 
 use std::mem;
@@ -112,6 +113,82 @@ impl Buffer {
         }
     }
 
+    /// Wrapper for `glBufferSubData`.
+    ///
+    /// Updates a subset of the buffer's data starting at byte `offset`.
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if `offset` is negative.
+    pub fn buffer_sub_data<T>(&self, offset: isize, data: &[T]) {
+        if offset < 0 {
+            panic!(ERR_NEGATIVE_OFFSET);
+        }
+
+        self.bind();
+        unsafe {
+            gl::BufferSubData(
+                self.buf_type as u32,
+                offset,
+                (data.len() * mem::size_of::<T>()) as isize,
+                data.as_ptr() as *const c_void);
+        }
+    }
+
+    /// Wrapper for `glGetBufferSubData`.
+    ///
+    /// Reads back `data.len()` elements starting at byte `offset`.
+    /// Useful for inspecting the results of a compute shader.
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if `offset` is negative.
+    pub fn get_sub_data<T>(&self, offset: isize, data: &mut [T]) {
+        if offset < 0 {
+            panic!(ERR_NEGATIVE_OFFSET);
+        }
+
+        self.bind();
+        unsafe {
+            gl::GetBufferSubData(
+                self.buf_type as u32,
+                offset,
+                (data.len() * mem::size_of::<T>()) as isize,
+                data.as_mut_ptr() as *mut c_void);
+        }
+    }
+
+    /// Wrapper for `glBindBufferBase`.
+    ///
+    /// Binds the whole buffer to the indexed binding point `index` of it's target.
+    ///
+    /// # Panics
+    /// * Panics if the buffer's type is not one of `AtomicCounter`, `ShaderStorage`,
+    /// `TransformFeedback` or `Uniform`.
+    /// * Panics if `index` exceeds the number of binding points of the buffer's type,
+    /// e.g. `GL_MAX_UNIFORM_BUFFER_BINDINGS`.
+    pub fn bind_base(&self, index: u32) {
+        self.assert_indexed(index);
+        unsafe { gl::BindBufferBase(self.buf_type as u32, index, self.handle); }
+    }
+
+    /// Wrapper for `glBindBufferRange`.
+    ///
+    /// Binds `size` bytes starting at byte `offset` to the indexed binding point `index`.
+    ///
+    /// # Panics
+    /// * Same as `bind_base`.
+    /// * Panics if `offset` is negative or `size` is not positive.
+    pub fn bind_range(&self, index: u32, offset: isize, size: isize) {
+        self.assert_indexed(index);
+
+        if offset < 0 || size <= 0 {
+            panic!(ERR_INVALID_RANGE);
+        }
+
+        unsafe { gl::BindBufferRange(self.buf_type as u32, index, self.handle, offset, size); }
+    }
+
     /// Get the buffer's type (target).
     pub fn buf_type(&self) -> BufferType {
         return self.buf_type;
@@ -121,6 +198,26 @@ impl Buffer {
     pub fn handle(&self) -> u32 {
         return self.handle;
     }
+
+    fn assert_indexed(&self, index: u32) {
+        let caps = Capabilities::current();
+        let limits = &caps.limits;
+
+        let max_bindings = match self.buf_type {
+            BufferType::AtomicCounter => limits.max_atomic_counter_buffer_bindings,
+            BufferType::ShaderStorage => limits.max_shader_storage_buffer_bindings,
+            // Before OpenGL 4.0 there is a binding point for each separate attribute.
+            BufferType::TransformFeedback if limits.max_transform_feedback_buffers == 0 =>
+                limits.max_transform_feedback_separate_attribs,
+            BufferType::TransformFeedback => limits.max_transform_feedback_buffers,
+            BufferType::Uniform => limits.max_uniform_buffer_bindings,
+            _ => panic!(ERR_NOT_INDEXED)
+        };
+
+        if index as i32 >= max_bindings {
+            panic!(ERR_BINDING_LIMIT_EXCEEDED);
+        }
+    }
 }
 
 impl Drop for Buffer {
@@ -128,3 +225,8 @@ impl Drop for Buffer {
         unsafe { gl::DeleteBuffers(1, &self.handle); }
     }
 }
+
+const ERR_NEGATIVE_OFFSET: &'static str = "Buffer offset must be nonnegative";
+const ERR_INVALID_RANGE: &'static str = "Invalid buffer range - offset must be nonnegative and size must be positive";
+const ERR_NOT_INDEXED: &'static str = "Buffer type does not have indexed binding points";
+const ERR_BINDING_LIMIT_EXCEEDED: &'static str = "Indexed binding point limit exceeded";
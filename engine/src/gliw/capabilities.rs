@@ -0,0 +1,212 @@
+use gliw::gl;
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::rc::Rc;
+
+/// Implementation dependent limits of an OpenGL context.
+///
+/// Limits of features which the context does not support are `0`,
+/// e.g. `max_patch_vertices` on contexts older than OpenGL 4.0.
+#[derive(Clone, Debug)]
+pub struct Limits {
+    pub max_vertex_attribs: i32,
+    pub max_texture_size: i32,
+    pub max_3d_texture_size: i32,
+    pub max_cube_map_texture_size: i32,
+    pub max_array_texture_layers: i32,
+    pub max_texture_image_units: i32,
+    pub max_combined_texture_image_units: i32,
+    pub max_renderbuffer_size: i32,
+    pub max_color_attachments: i32,
+    pub max_draw_buffers: i32,
+    pub max_samples: i32,
+    pub max_uniform_buffer_bindings: i32,
+    pub max_uniform_block_size: i32,
+    pub max_transform_feedback_separate_attribs: i32,
+    pub max_transform_feedback_buffers: i32,
+    pub max_patch_vertices: i32,
+    pub max_tess_gen_level: i32,
+    pub max_atomic_counter_buffer_bindings: i32,
+    pub max_image_units: i32,
+    pub max_shader_storage_buffer_bindings: i32,
+    pub max_compute_work_group_count: [i32; 3],
+    pub max_compute_work_group_size: [i32; 3],
+    pub max_compute_work_group_invocations: i32,
+}
+
+/// Description of the features and limits of an OpenGL context.
+///
+/// Querying the context is slow so the capabilities are cached per thread, see `current`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw::Capabilities;
+/// let caps = Capabilities::current();
+/// println!("{} on {}, OpenGL {}.{}", caps.renderer, caps.vendor, caps.version.0, caps.version.1);
+///
+/// if caps.supports_compute() {
+///     // ...use compute shaders
+/// } else if caps.has_extension("GL_ARB_transform_feedback2") {
+///     // ...fall back to transform feedback
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+    /// The `(major, minor)` OpenGL version.
+    pub version: (i32, i32),
+    /// The `(major, minor)` GLSL version, e.g. `(4, 50)` for GLSL 4.50.
+    pub glsl_version: (i32, i32),
+    pub vendor: String,
+    pub renderer: String,
+    pub extensions: HashSet<String>,
+    pub limits: Limits,
+}
+
+impl Capabilities {
+    /// Queries the capabilities of the context which is current on this thread.
+    pub fn query() -> Capabilities {
+        let version = (get_integer(gl::MAJOR_VERSION), get_integer(gl::MINOR_VERSION));
+        let at_least = |major: i32, minor: i32| version >= (major, minor);
+
+        // Querying limits of unsupported features would only raise `GL_INVALID_ENUM`.
+        let get_integer_since = |major: i32, minor: i32, pname: u32| {
+            if at_least(major, minor) { get_integer(pname) } else { 0 }
+        };
+
+        let get_indexed_since = |major: i32, minor: i32, pname: u32| {
+            let mut values = [0; 3];
+            if at_least(major, minor) {
+                for i in 0..3 {
+                    unsafe { gl::GetIntegeri_v(pname, i as u32, &mut values[i]); }
+                }
+            }
+            values
+        };
+
+        let mut extensions = HashSet::new();
+        for i in 0..get_integer(gl::NUM_EXTENSIONS) {
+            if let Some(ext) = unsafe { to_string(gl::GetStringi(gl::EXTENSIONS, i as u32)) } {
+                extensions.insert(ext);
+            }
+        }
+
+        let glsl_version = unsafe { to_string(gl::GetString(gl::SHADING_LANGUAGE_VERSION)) };
+
+        return Capabilities {
+            version: version,
+            glsl_version: parse_glsl_version(&glsl_version.unwrap_or(String::new())),
+            vendor: unsafe { to_string(gl::GetString(gl::VENDOR)) }.unwrap_or(String::new()),
+            renderer: unsafe { to_string(gl::GetString(gl::RENDERER)) }.unwrap_or(String::new()),
+            extensions: extensions,
+            limits: Limits {
+                max_vertex_attribs: get_integer(gl::MAX_VERTEX_ATTRIBS),
+                max_texture_size: get_integer(gl::MAX_TEXTURE_SIZE),
+                max_3d_texture_size: get_integer(gl::MAX_3D_TEXTURE_SIZE),
+                max_cube_map_texture_size: get_integer(gl::MAX_CUBE_MAP_TEXTURE_SIZE),
+                max_array_texture_layers: get_integer(gl::MAX_ARRAY_TEXTURE_LAYERS),
+                max_texture_image_units: get_integer(gl::MAX_TEXTURE_IMAGE_UNITS),
+                max_combined_texture_image_units: get_integer(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS),
+                max_renderbuffer_size: get_integer(gl::MAX_RENDERBUFFER_SIZE),
+                max_color_attachments: get_integer(gl::MAX_COLOR_ATTACHMENTS),
+                max_draw_buffers: get_integer(gl::MAX_DRAW_BUFFERS),
+                max_samples: get_integer(gl::MAX_SAMPLES),
+                max_uniform_buffer_bindings: get_integer(gl::MAX_UNIFORM_BUFFER_BINDINGS),
+                max_uniform_block_size: get_integer(gl::MAX_UNIFORM_BLOCK_SIZE),
+                max_transform_feedback_separate_attribs: get_integer(gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS),
+                max_transform_feedback_buffers: get_integer_since(4, 0, gl::MAX_TRANSFORM_FEEDBACK_BUFFERS),
+                max_patch_vertices: get_integer_since(4, 0, gl::MAX_PATCH_VERTICES),
+                max_tess_gen_level: get_integer_since(4, 0, gl::MAX_TESS_GEN_LEVEL),
+                max_atomic_counter_buffer_bindings: get_integer_since(4, 2, gl::MAX_ATOMIC_COUNTER_BUFFER_BINDINGS),
+                max_image_units: get_integer_since(4, 2, gl::MAX_IMAGE_UNITS),
+                max_shader_storage_buffer_bindings: get_integer_since(4, 3, gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS),
+                max_compute_work_group_count: get_indexed_since(4, 3, gl::MAX_COMPUTE_WORK_GROUP_COUNT),
+                max_compute_work_group_size: get_indexed_since(4, 3, gl::MAX_COMPUTE_WORK_GROUP_SIZE),
+                max_compute_work_group_invocations: get_integer_since(4, 3, gl::MAX_COMPUTE_WORK_GROUP_INVOCATIONS),
+            }
+        };
+    }
+
+    /// Get the capabilities of the context which is current on this thread.
+    ///
+    /// The context is queried only on the first call, see `invalidate`.
+    pub fn current() -> Rc<Capabilities> {
+        if let Some(caps) = CURRENT.with(|current| current.borrow().clone()) {
+            return caps;
+        }
+
+        let caps = Rc::new(Capabilities::query());
+        CURRENT.with(|current| *current.borrow_mut() = Some(caps.clone()));
+
+        return caps;
+    }
+
+    /// Discards the capabilities cached by `current` for this thread.
+    ///
+    /// Must be called if another context is made current on the thread.
+    /// `set_backend` calls it internally.
+    pub fn invalidate() {
+        CURRENT.with(|current| *current.borrow_mut() = None);
+    }
+
+    /// Checks if the OpenGL version is at least `major.minor`.
+    pub fn supports_version(&self, major: i32, minor: i32) -> bool {
+        return self.version >= (major, minor);
+    }
+
+    /// Checks if the context exposes the extension `name`, e.g. `"GL_ARB_transform_feedback2"`.
+    pub fn has_extension(&self, name: &str) -> bool {
+        return self.extensions.contains(name);
+    }
+
+    /// Checks for geometry shaders (OpenGL 3.2).
+    pub fn supports_geometry_shaders(&self) -> bool {
+        return self.supports_version(3, 2);
+    }
+
+    /// Checks for tesselation shaders (OpenGL 4.0).
+    pub fn supports_tesselation(&self) -> bool {
+        return self.supports_version(4, 0);
+    }
+
+    /// Checks for transform feedback objects (OpenGL 4.0 or `GL_ARB_transform_feedback2`).
+    pub fn supports_transform_feedback_objects(&self) -> bool {
+        return self.supports_version(4, 0) || self.has_extension("GL_ARB_transform_feedback2");
+    }
+
+    /// Checks for separable programs (OpenGL 4.1 or `GL_ARB_separate_shader_objects`).
+    pub fn supports_separable_programs(&self) -> bool {
+        return self.supports_version(4, 1) || self.has_extension("GL_ARB_separate_shader_objects");
+    }
+
+    /// Checks for compute shaders, shader storage blocks and image load/store (OpenGL 4.3).
+    pub fn supports_compute(&self) -> bool {
+        return self.supports_version(4, 3);
+    }
+}
+
+thread_local!(static CURRENT: RefCell<Option<Rc<Capabilities>>> = RefCell::new(None));
+
+fn get_integer(pname: u32) -> i32 {
+    let mut value: i32 = 0;
+    unsafe { gl::GetIntegerv(pname, &mut value); }
+    return value;
+}
+
+unsafe fn to_string(string: *const u8) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+
+    return Some(CStr::from_ptr(string as *const _).to_string_lossy().into_owned());
+}
+
+/// Parses the `major.minor` prefix of `GL_SHADING_LANGUAGE_VERSION`, e.g. `"4.50 NVIDIA"`.
+fn parse_glsl_version(version: &str) -> (i32, i32) {
+    let number = version.split(' ').next().unwrap_or("");
+    let mut parts = number.split('.').map(|part| part.parse::<i32>().unwrap_or(0));
+
+    return (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+}
@@ -0,0 +1,195 @@
+use gliw::gl;
+
+use gliw::{Buffer, BufferType, Capabilities, Gliw, Program, ShaderType};
+
+use std::ffi::CString;
+
+/// Barrier bits for `Gliw::memory_barrier`.
+///
+/// Each variant specifies which kind of access to memory written by a previous
+/// shader invocation (compute or image load/store) should be synchronized.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum MemoryBarrier {
+    VertexAttribArray   = gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT,
+    ElementArray        = gl::ELEMENT_ARRAY_BARRIER_BIT,
+    Uniform             = gl::UNIFORM_BARRIER_BIT,
+    TextureFetch        = gl::TEXTURE_FETCH_BARRIER_BIT,
+    ShaderImageAccess   = gl::SHADER_IMAGE_ACCESS_BARRIER_BIT,
+    Command             = gl::COMMAND_BARRIER_BIT,
+    PixelBuffer         = gl::PIXEL_BUFFER_BARRIER_BIT,
+    TextureUpdate       = gl::TEXTURE_UPDATE_BARRIER_BIT,
+    BufferUpdate        = gl::BUFFER_UPDATE_BARRIER_BIT,
+    Framebuffer         = gl::FRAMEBUFFER_BARRIER_BIT,
+    TransformFeedback   = gl::TRANSFORM_FEEDBACK_BARRIER_BIT,
+    AtomicCounter       = gl::ATOMIC_COUNTER_BARRIER_BIT,
+    ShaderStorage       = gl::SHADER_STORAGE_BARRIER_BIT,
+    All                 = gl::ALL_BARRIER_BITS,
+}
+
+impl Gliw {
+    /// Wrapper for `glMemoryBarrier`.
+    ///
+    /// Combines all of the given `barriers` into a single call.
+    ///
+    /// # Panics
+    /// Panics if the context does not support compute shaders (OpenGL 4.3).
+    pub fn memory_barrier(barriers: &[MemoryBarrier]) {
+        assert_compute();
+
+        let mask = barriers.iter().fold(0, |mask, barrier| mask | *barrier as u32);
+        unsafe { gl::MemoryBarrier(mask); }
+    }
+}
+
+impl Program {
+    /// Wrapper for `glDispatchCompute`.
+    ///
+    /// Binds self internally.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use engine::gliw::{
+    /// #   Buffer, BufferType, BufferUsagePattern,
+    /// #   Gliw, MemoryBarrier, ProgramBuilder, Shader, ShaderType
+    /// # };
+    /// # let particles: [f32; 4] = [0.0; 4];
+    /// let cs = Shader::new(ShaderType::Compute, "<code>").unwrap();
+    /// let program = ProgramBuilder::new()
+    ///     .attach_cs(&cs)
+    ///     .link()
+    ///     .unwrap();
+    ///
+    /// let ssbo = Buffer::from_data(&particles, BufferType::ShaderStorage, BufferUsagePattern::DynamicCopy);
+    /// program.storage_block_binding("Particles", 0);
+    /// ssbo.bind_base(0);
+    ///
+    /// program.dispatch(particles.len() as u32 / 64 + 1, 1, 1);
+    /// Gliw::memory_barrier(&[MemoryBarrier::ShaderStorage, MemoryBarrier::VertexAttribArray]);
+    /// ```
+    ///
+    /// # Panics
+    /// * Panics if the context does not support compute shaders (OpenGL 4.3).
+    /// * Panics if any of the work group counts exceeds `GL_MAX_COMPUTE_WORK_GROUP_COUNT`.
+    /// * Panics if the program does not contain a compute shader.
+    ///
+    /// # References
+    /// * [Compute Shader](https://www.opengl.org/wiki/Compute_Shader)
+    /// * [Shader Storage Buffer Object](https://www.opengl.org/wiki/Shader_Storage_Buffer_Object)
+    pub fn dispatch(&self, x: u32, y: u32, z: u32) {
+        assert_compute();
+        self.assert_compute_stage();
+
+        let max_count = Capabilities::current().limits.max_compute_work_group_count;
+        if x > max_count[0] as u32 || y > max_count[1] as u32 || z > max_count[2] as u32 {
+            panic!(ERR_WORK_GROUP_COUNT);
+        }
+
+        self.bind();
+        unsafe { gl::DispatchCompute(x, y, z); }
+    }
+
+    /// Wrapper for `glDispatchComputeIndirect`.
+    ///
+    /// The work group counts are read from `buffer` at byte `offset` as three consecutive `u32`s.
+    /// Binds self and `buffer` internally.
+    ///
+    /// # Panics
+    /// * Panics if the context does not support compute shaders (OpenGL 4.3).
+    /// * Panics if `buffer` is not of type `BufferType::DispatchIndirect`.
+    /// * Panics if `offset` is negative or not a multiple of 4.
+    /// * Panics if the three `u32`s at `offset` don't fit in `buffer`.
+    /// * Panics if the program does not contain a compute shader.
+    pub fn dispatch_indirect(&self, buffer: &Buffer, offset: isize) {
+        assert_compute();
+        self.assert_compute_stage();
+
+        match buffer.buf_type() {
+            BufferType::DispatchIndirect => {},
+            _ => panic!(ERR_INDIRECT_BUFFER_TYPE)
+        }
+
+        if offset < 0 || offset % 4 != 0 {
+            panic!(ERR_INDIRECT_OFFSET);
+        }
+
+        self.bind();
+        buffer.bind();
+
+        unsafe {
+            let mut size = 0;
+            gl::GetBufferParameteriv(gl::DISPATCH_INDIRECT_BUFFER, gl::BUFFER_SIZE, &mut size);
+
+            if offset > size as isize - 12 {
+                panic!(ERR_INDIRECT_RANGE);
+            }
+
+            gl::DispatchComputeIndirect(offset);
+        }
+    }
+
+    /// Wrapper for `glShaderStorageBlockBinding`.
+    ///
+    /// Assigns the shader storage block `name` to the indexed `binding` point.
+    /// Use `Buffer::bind_base` or `Buffer::bind_range` to bind a buffer to the same point.
+    ///
+    /// # Panics
+    /// * Panics if the context does not support shader storage blocks (OpenGL 4.3).
+    /// * Panics if the program has no active shader storage block named `name`.
+    pub fn storage_block_binding(&self, name: &str, binding: u32) {
+        assert_compute();
+
+        unsafe {
+            let index = gl::GetProgramResourceIndex(
+                self.handle(),
+                gl::SHADER_STORAGE_BLOCK,
+                CString::new(name).unwrap().as_ptr());
+
+            if index == gl::INVALID_INDEX {
+                panic!("{} `{}`", ERR_NO_STORAGE_BLOCK, name);
+            }
+
+            gl::ShaderStorageBlockBinding(self.handle(), index, binding);
+        }
+    }
+
+    fn assert_compute_stage(&self) {
+        if self.stages() & ShaderType::Compute.stage_bit() == 0 {
+            panic!(ERR_NO_COMPUTE_SHADER);
+        }
+    }
+}
+
+/// Checks if the current context supports compute shaders and everything related to them,
+/// e.g. before building a compute program, dispatching or using image load/store.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw;
+/// if let Err(err) = gliw::require_compute() {
+///     println!("Falling back to CPU particles: {}", err);
+/// }
+/// ```
+pub fn require_compute() -> Result<(), String> {
+    if !Capabilities::current().supports_compute() {
+        return Err(String::from(ERR_COMPUTE_UNSUPPORTED));
+    }
+
+    return Ok(());
+}
+
+fn assert_compute() {
+    if let Err(err) = require_compute() {
+        panic!(err);
+    }
+}
+
+const ERR_COMPUTE_UNSUPPORTED: &'static str = "Compute shaders require OpenGL 4.3 or newer";
+const ERR_NO_COMPUTE_SHADER: &'static str = "Dispatch requires a program with a compute shader";
+const ERR_WORK_GROUP_COUNT: &'static str = "Work group count exceeds GL_MAX_COMPUTE_WORK_GROUP_COUNT";
+const ERR_INDIRECT_BUFFER_TYPE: &'static str = "Indirect dispatch requires a buffer of type `DispatchIndirect`";
+const ERR_INDIRECT_OFFSET: &'static str = "Indirect dispatch offset must be a nonnegative multiple of 4";
+const ERR_INDIRECT_RANGE: &'static str = "Indirect dispatch reads past the end of the buffer";
+const ERR_NO_STORAGE_BLOCK: &'static str = "No active shader storage block named";
@@ -1,4 +1,4 @@
-extern crate gl;
+use gliw::gl;
 
 /// Wrapper for error codes and their respective messages.
 pub struct Error {
@@ -0,0 +1,413 @@
+use gliw::gl;
+
+use gliw::{Capabilities, InternalFormat, Texture, TextureType, TextureFilter};
+
+use std::os::raw::c_void;
+
+/// Framebuffer binding targets.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum FramebufferTarget {
+    /// Target of rendering, clearing and blitting.
+    Draw    = gl::DRAW_FRAMEBUFFER,
+    /// Source of pixel reads and blitting.
+    Read    = gl::READ_FRAMEBUFFER,
+    /// Both `Draw` and `Read`.
+    Both    = gl::FRAMEBUFFER,
+}
+
+/// Framebuffer attachment points.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Attachment {
+    /// Color attachment with the given index, written by fragment shader output of the same location
+    /// unless remapped with `Framebuffer::draw_buffers`.
+    Color(u32),
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl Attachment {
+    fn gl_enum(&self) -> u32 {
+        return match *self {
+            Attachment::Color(index) => gl::COLOR_ATTACHMENT0 + index,
+            Attachment::Depth => gl::DEPTH_ATTACHMENT,
+            Attachment::Stencil => gl::STENCIL_ATTACHMENT,
+            Attachment::DepthStencil => gl::DEPTH_STENCIL_ATTACHMENT,
+        };
+    }
+}
+
+/// Wrapper for OpenGL Renderbuffer Object.
+///
+/// Storage for framebuffer attachments which are never sampled, e.g. a depth buffer
+/// used only for depth testing.
+///
+/// # References
+/// * [Renderbuffer Object](https://www.opengl.org/wiki/Renderbuffer_Object)
+pub struct Renderbuffer {
+    handle: u32
+}
+
+impl Renderbuffer {
+    /// Generates a renderbuffer.
+    pub fn new() -> Renderbuffer {
+        let mut rb = Renderbuffer {
+            handle: 0
+        };
+
+        unsafe { gl::GenRenderbuffers(1, &mut rb.handle as *mut u32); }
+
+        return rb;
+    }
+
+    /// Wrapper for `glBindRenderbuffer`.
+    pub fn bind(&self) {
+        unsafe { gl::BindRenderbuffer(gl::RENDERBUFFER, self.handle); }
+    }
+
+    /// Wrapper for `glRenderbufferStorage`.
+    ///
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if `width` or `height` is greater than `GL_MAX_RENDERBUFFER_SIZE`.
+    pub fn storage(&self, format: InternalFormat, width: i32, height: i32) {
+        self.storage_multisample(0, format, width, height);
+    }
+
+    /// Wrapper for `glRenderbufferStorageMultisample`.
+    ///
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// * Panics if `width` or `height` is greater than `GL_MAX_RENDERBUFFER_SIZE`.
+    /// * Panics if `samples` is greater than `GL_MAX_SAMPLES`.
+    pub fn storage_multisample(&self, samples: i32, format: InternalFormat, width: i32, height: i32) {
+        let caps = Capabilities::current();
+
+        if width > caps.limits.max_renderbuffer_size || height > caps.limits.max_renderbuffer_size {
+            panic!(ERR_RENDERBUFFER_SIZE);
+        }
+
+        if samples > caps.limits.max_samples {
+            panic!(ERR_SAMPLES);
+        }
+
+        self.bind();
+        unsafe { gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples, format as u32, width, height); }
+    }
+
+    /// Get the underlying OpenGL handle.
+    pub fn handle(&self) -> u32 {
+        return self.handle;
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteRenderbuffers(1, &self.handle); }
+    }
+}
+
+/// Wrapper for OpenGL Framebuffer Object.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw::{
+/// #   Attachment, Framebuffer, FramebufferTarget, InternalFormat,
+/// #   Renderbuffer, Texture, TextureType
+/// # };
+/// let color = Texture::new(TextureType::Tex2D);
+/// color.alloc_2d(800, 600, InternalFormat::RGBA16F);
+///
+/// let depth = Renderbuffer::new();
+/// depth.storage(InternalFormat::Depth24, 800, 600);
+///
+/// let fbo = Framebuffer::new();
+/// fbo.attach_texture(Attachment::Color(0), &color, 0);
+/// fbo.attach_renderbuffer(Attachment::Depth, &depth);
+/// fbo.check_status().unwrap();
+///
+/// fbo.bind(FramebufferTarget::Both);
+/// // ...render to `color`
+/// Framebuffer::bind_default(FramebufferTarget::Both);
+/// ```
+///
+/// # References
+/// * [Framebuffer Object](https://www.opengl.org/wiki/Framebuffer_Object)
+pub struct Framebuffer {
+    handle: u32
+}
+
+impl Framebuffer {
+    /// Generates a framebuffer.
+    pub fn new() -> Framebuffer {
+        let mut fbo = Framebuffer {
+            handle: 0
+        };
+
+        unsafe { gl::GenFramebuffers(1, &mut fbo.handle as *mut u32); }
+
+        return fbo;
+    }
+
+    /// Wrapper for `glBindFramebuffer`.
+    pub fn bind(&self, target: FramebufferTarget) {
+        unsafe { gl::BindFramebuffer(target as u32, self.handle); }
+    }
+
+    /// Binds the default framebuffer, i.e. the window.
+    pub fn bind_default(target: FramebufferTarget) {
+        unsafe { gl::BindFramebuffer(target as u32, 0); }
+    }
+
+    /// Wrapper for `glFramebufferTexture2D`.
+    ///
+    /// Attaches `level` of `texture`. Array and 3D textures are attached with `attach_texture_layer`.
+    /// Binds self to `FramebufferTarget::Draw` internally.
+    ///
+    /// # Panics
+    /// * Panics if `attachment` is a color attachment with index greater than or equal to
+    /// `GL_MAX_COLOR_ATTACHMENTS`.
+    /// * Panics if `texture` is not a 2D, rectangle or 2D multisample texture.
+    pub fn attach_texture(&self, attachment: Attachment, texture: &Texture, level: i32) {
+        assert_attachment(attachment);
+
+        match texture.tex_type() {
+            TextureType::Tex2D | TextureType::Rectangle | TextureType::Multisample2D => {},
+            _ => panic!(ERR_TEXTURE_TYPE)
+        }
+
+        self.bind(FramebufferTarget::Draw);
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::DRAW_FRAMEBUFFER,
+                attachment.gl_enum(),
+                texture.tex_type() as u32,
+                texture.handle(),
+                level);
+        }
+    }
+
+    /// Wrapper for `glFramebufferTextureLayer`.
+    ///
+    /// Attaches a single `layer` of `level` of an array, cube map or 3D `texture`.
+    /// Binds self to `FramebufferTarget::Draw` internally.
+    ///
+    /// # Panics
+    /// Panics if `attachment` is a color attachment with index greater than or equal to
+    /// `GL_MAX_COLOR_ATTACHMENTS`.
+    pub fn attach_texture_layer(&self, attachment: Attachment, texture: &Texture, level: i32, layer: i32) {
+        assert_attachment(attachment);
+
+        self.bind(FramebufferTarget::Draw);
+        unsafe { gl::FramebufferTextureLayer(gl::DRAW_FRAMEBUFFER, attachment.gl_enum(), texture.handle(), level, layer); }
+    }
+
+    /// Wrapper for `glFramebufferRenderbuffer`.
+    ///
+    /// Binds self to `FramebufferTarget::Draw` internally.
+    ///
+    /// # Panics
+    /// Same as `attach_texture_layer`.
+    pub fn attach_renderbuffer(&self, attachment: Attachment, renderbuffer: &Renderbuffer) {
+        assert_attachment(attachment);
+
+        self.bind(FramebufferTarget::Draw);
+        unsafe {
+            gl::FramebufferRenderbuffer(gl::DRAW_FRAMEBUFFER, attachment.gl_enum(), gl::RENDERBUFFER, renderbuffer.handle());
+        }
+    }
+
+    /// Wrapper for `glDrawBuffers`.
+    ///
+    /// Fragment shader output `i` is written to `attachments[i]`, `None` discards the output.
+    /// Binds self to `FramebufferTarget::Draw` internally.
+    ///
+    /// # Panics
+    /// * Panics if any of the `attachments` is not a color attachment.
+    /// * Panics if there are more `attachments` than `GL_MAX_DRAW_BUFFERS`.
+    pub fn draw_buffers(&self, attachments: &[Option<Attachment>]) {
+        if attachments.len() as i32 > Capabilities::current().limits.max_draw_buffers {
+            panic!(ERR_DRAW_BUFFERS_LIMIT_EXCEEDED);
+        }
+
+        let buffers: Vec<u32> = attachments.iter().map(|attachment| {
+            match *attachment {
+                Some(attachment @ Attachment::Color(_)) => {
+                    assert_attachment(attachment);
+                    attachment.gl_enum()
+                },
+                Some(_) => panic!(ERR_NOT_COLOR_ATTACHMENT),
+                None => gl::NONE
+            }
+        }).collect();
+
+        self.bind(FramebufferTarget::Draw);
+        unsafe { gl::DrawBuffers(buffers.len() as i32, buffers.as_ptr()); }
+    }
+
+    /// Wrapper for `glReadBuffer`.
+    ///
+    /// Selects the color attachment read by `blit` and pixel reads.
+    /// Binds self to `FramebufferTarget::Read` internally.
+    ///
+    /// # Panics
+    /// Panics if `attachment` is not a color attachment.
+    pub fn read_buffer(&self, attachment: Attachment) {
+        match attachment {
+            Attachment::Color(_) => assert_attachment(attachment),
+            _ => panic!(ERR_NOT_COLOR_ATTACHMENT)
+        }
+
+        self.bind(FramebufferTarget::Read);
+        unsafe { gl::ReadBuffer(attachment.gl_enum()); }
+    }
+
+    /// Wrapper for `glReadPixels`.
+    ///
+    /// Reads the `(x, y, width, height)` rectangle of the color `attachment` as RGBA with 8 bits per channel.
+    /// The rows are ordered from bottom to top.
+    /// Binds self to `FramebufferTarget::Read` internally.
+    ///
+    /// # Panics
+    /// Same as `read_buffer`.
+    pub fn read_pixels(&self, attachment: Attachment, x: i32, y: i32, width: i32, height: i32) -> Vec<u8> {
+        self.read_buffer(attachment);
+        return read_pixels(x, y, width, height);
+    }
+
+    /// Wrapper for `glReadPixels`.
+    ///
+    /// Reads the `(x, y, width, height)` rectangle of the unsigned integer color `attachment`,
+    /// e.g. `R32UI`, taking the red channel of each pixel. The rows are ordered from bottom to top.
+    /// Binds self to `FramebufferTarget::Read` internally.
+    ///
+    /// # Panics
+    /// Same as `read_buffer`.
+    pub fn read_integer_pixels(&self, attachment: Attachment, x: i32, y: i32, width: i32, height: i32) -> Vec<u32> {
+        self.read_buffer(attachment);
+
+        if width < 0 || height < 0 {
+            panic!(ERR_NEGATIVE_SIZE);
+        }
+
+        let mut data = vec![0u32; width as usize * height as usize];
+
+        unsafe {
+            gl::ReadPixels(x, y, width, height, gl::RED_INTEGER, gl::UNSIGNED_INT, data.as_mut_ptr() as *mut c_void);
+        }
+
+        return data;
+    }
+
+    /// Wrapper for `glReadPixels`.
+    ///
+    /// Same as `read_pixels`, but reads the back buffer of the default framebuffer, i.e. the frame
+    /// which is about to be presented. Binds the default framebuffer to `FramebufferTarget::Read` internally.
+    pub fn read_default_pixels(x: i32, y: i32, width: i32, height: i32) -> Vec<u8> {
+        Framebuffer::bind_default(FramebufferTarget::Read);
+        unsafe { gl::ReadBuffer(gl::BACK); }
+        return read_pixels(x, y, width, height);
+    }
+
+    /// Wrapper for `glCheckFramebufferStatus`.
+    ///
+    /// Binds self to `FramebufferTarget::Draw` internally.
+    pub fn check_status(&self) -> Result<(), String> {
+        self.bind(FramebufferTarget::Draw);
+
+        let status = unsafe { gl::CheckFramebufferStatus(gl::DRAW_FRAMEBUFFER) };
+
+        return match status {
+            gl::FRAMEBUFFER_COMPLETE => Ok(()),
+            gl::FRAMEBUFFER_UNDEFINED => Err(String::from(ERR_UNDEFINED)),
+            gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Err(String::from(ERR_INCOMPLETE_ATTACHMENT)),
+            gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Err(String::from(ERR_MISSING_ATTACHMENT)),
+            gl::FRAMEBUFFER_UNSUPPORTED => Err(String::from(ERR_UNSUPPORTED)),
+            gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => Err(String::from(ERR_INCOMPLETE_MULTISAMPLE)),
+            _ => Err(format!("{} 0x{:X}", ERR_INCOMPLETE, status))
+        };
+    }
+
+    /// Wrapper for `glBlitFramebuffer`.
+    ///
+    /// Copies the `(x, y, width, height)` rectangle `src` of this framebuffer to the rectangle `dst`
+    /// of `target`, or of the default framebuffer if `target` is `None`. `mask` is a combination of
+    /// `GL_COLOR_BUFFER_BIT`, `GL_DEPTH_BUFFER_BIT` and `GL_STENCIL_BUFFER_BIT`.
+    /// Binds self to `FramebufferTarget::Read` and `target` to `FramebufferTarget::Draw` internally.
+    ///
+    /// # Panics
+    /// Panics if `filter` is not `TextureFilter::Nearest` or `TextureFilter::Linear`.
+    pub fn blit(&self, target: Option<&Framebuffer>, src: (i32, i32, i32, i32), dst: (i32, i32, i32, i32), mask: u32, filter: TextureFilter) {
+        match filter {
+            TextureFilter::Nearest | TextureFilter::Linear => {},
+            _ => panic!(ERR_BLIT_FILTER)
+        }
+
+        self.bind(FramebufferTarget::Read);
+        match target {
+            Some(fbo) => fbo.bind(FramebufferTarget::Draw),
+            None => Framebuffer::bind_default(FramebufferTarget::Draw)
+        }
+
+        unsafe {
+            gl::BlitFramebuffer(
+                src.0, src.1, src.0 + src.2, src.1 + src.3,
+                dst.0, dst.1, dst.0 + dst.2, dst.1 + dst.3,
+                mask,
+                filter as u32);
+        }
+    }
+
+    /// Get the underlying OpenGL handle.
+    pub fn handle(&self) -> u32 {
+        return self.handle;
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.handle); }
+    }
+}
+
+fn read_pixels(x: i32, y: i32, width: i32, height: i32) -> Vec<u8> {
+    if width < 0 || height < 0 {
+        panic!(ERR_NEGATIVE_SIZE);
+    }
+
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+
+    unsafe {
+        // Rows of RGBA pixels are always 4 byte aligned, so the default pack alignment is fine.
+        gl::ReadPixels(x, y, width, height, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+    }
+
+    return data;
+}
+
+fn assert_attachment(attachment: Attachment) {
+    if let Attachment::Color(index) = attachment {
+        if index as i32 >= Capabilities::current().limits.max_color_attachments {
+            panic!(ERR_COLOR_ATTACHMENTS_LIMIT_EXCEEDED);
+        }
+    }
+}
+
+const ERR_RENDERBUFFER_SIZE: &'static str = "Renderbuffer size exceeds GL_MAX_RENDERBUFFER_SIZE";
+const ERR_SAMPLES: &'static str = "Sample count exceeds GL_MAX_SAMPLES";
+const ERR_TEXTURE_TYPE: &'static str = "Only 2D, rectangle and 2D multisample textures can be attached directly, use `attach_texture_layer` for the rest";
+const ERR_COLOR_ATTACHMENTS_LIMIT_EXCEEDED: &'static str = "Color attachments limit exceeded";
+const ERR_DRAW_BUFFERS_LIMIT_EXCEEDED: &'static str = "Draw buffers limit exceeded";
+const ERR_NOT_COLOR_ATTACHMENT: &'static str = "Expected a color attachment";
+const ERR_NEGATIVE_SIZE: &'static str = "Read size must be nonnegative";
+const ERR_BLIT_FILTER: &'static str = "Blit filter must be either `Nearest` or `Linear`";
+const ERR_UNDEFINED: &'static str = "Framebuffer incomplete - the default framebuffer does not exist";
+const ERR_INCOMPLETE_ATTACHMENT: &'static str = "Framebuffer incomplete - an attachment is incomplete or has a format which is not renderable";
+const ERR_MISSING_ATTACHMENT: &'static str = "Framebuffer incomplete - no images are attached";
+const ERR_UNSUPPORTED: &'static str = "Framebuffer incomplete - the combination of attachment formats is not supported";
+const ERR_INCOMPLETE_MULTISAMPLE: &'static str = "Framebuffer incomplete - the attachments have different sample counts";
+const ERR_INCOMPLETE: &'static str = "Framebuffer incomplete - status";
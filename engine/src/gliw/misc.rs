@@ -1,4 +1,6 @@
-extern crate gl;
+use gliw::gl;
+
+use gliw::Capabilities;
 
 #[repr(u32)]
 pub enum DepthFunction {
@@ -12,6 +14,20 @@ pub enum DepthFunction {
     Always      = gl::ALWAYS,
 }
 
+/// Primitive types for draw calls.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum DrawMode {
+    Points          = gl::POINTS,
+    LineStrip       = gl::LINE_STRIP,
+    LineLoop        = gl::LINE_LOOP,
+    Lines           = gl::LINES,
+    TriangleStrip   = gl::TRIANGLE_STRIP,
+    TriangleFan     = gl::TRIANGLE_FAN,
+    Triangles       = gl::TRIANGLES,
+    Patches         = gl::PATCHES,
+}
+
 /// Wrapper for OpenGL misc functions.
 pub struct Gliw;
 
@@ -35,4 +51,59 @@ impl Gliw {
     pub fn clear(mask: u32) {
         unsafe { gl::Clear(mask); }
     }
+
+    /// Get the `(major, minor)` OpenGL version of the current context.
+    ///
+    /// See `Capabilities`.
+    pub fn version() -> (i32, i32) {
+        return Capabilities::current().version;
+    }
+
+    /// Wrapper for `glPatchParameteri` with `GL_PATCH_VERTICES`.
+    ///
+    /// Sets the number of vertices per patch used by `DrawMode::Patches`.
+    ///
+    /// # Panics
+    /// * Panics if the context does not support tesselation (OpenGL 4.0).
+    /// * Panics if `count` is not between 1 and `GL_MAX_PATCH_VERTICES`.
+    pub fn patch_vertices(count: i32) {
+        let caps = Capabilities::current();
+
+        if !caps.supports_tesselation() {
+            panic!(ERR_TESSELATION_UNSUPPORTED);
+        }
+
+        if count < 1 || count > caps.limits.max_patch_vertices {
+            panic!(ERR_PATCH_VERTICES);
+        }
+
+        unsafe { gl::PatchParameteri(gl::PATCH_VERTICES, count); }
+    }
+
+    /// Wrapper for `glPatchParameterfv`.
+    ///
+    /// Sets the tesselation levels used when no tesselation control shader is present.
+    ///
+    /// # Panics
+    /// Panics if the context does not support tesselation (OpenGL 4.0).
+    pub fn patch_default_levels(outer: [f32; 4], inner: [f32; 2]) {
+        if !Capabilities::current().supports_tesselation() {
+            panic!(ERR_TESSELATION_UNSUPPORTED);
+        }
+
+        unsafe {
+            gl::PatchParameterfv(gl::PATCH_DEFAULT_OUTER_LEVEL, outer.as_ptr());
+            gl::PatchParameterfv(gl::PATCH_DEFAULT_INNER_LEVEL, inner.as_ptr());
+        }
+    }
+
+    /// Checks if the current context exposes the extension `name`, e.g. `"GL_ARB_transform_feedback2"`.
+    ///
+    /// See `Capabilities`.
+    pub fn has_extension(name: &str) -> bool {
+        return Capabilities::current().has_extension(name);
+    }
 }
+
+const ERR_TESSELATION_UNSUPPORTED: &'static str = "Tesselation requires OpenGL 4.0 or newer";
+const ERR_PATCH_VERTICES: &'static str = "Patch vertex count must be between 1 and GL_MAX_PATCH_VERTICES";
@@ -2,25 +2,41 @@
 //!
 //! # Remarks
 //! * Does not support immutable storage for any OpenGL objects yet.
+//! * All OpenGL calls go through `gliw::gl`, which dispatches to the current `Backend`.
+//! Use it instead of the `gl` crate so that the calls can be mocked.
 
 mod buffer;
+mod capabilities;
+mod compute;
+mod framebuffer;
 mod misc;
 mod program;
 mod shader;
+mod sync;
 mod texture;
+mod transform_feedback;
 mod uniform;
 mod vao;
 mod vert_attrib;
 
+mod backend;
 mod error;
 
+pub use self::backend::{gl, Backend, GlBackend, MockBackend, GlCall, MockValue, set_backend};
 pub use self::buffer::{Buffer, BufferType, BufferUsagePattern};
-pub use self::misc::{Gliw, DepthFunction};
+pub use self::capabilities::{Capabilities, Limits};
+pub use self::compute::{MemoryBarrier, require_compute};
+pub use self::framebuffer::{Framebuffer, FramebufferTarget, Attachment, Renderbuffer};
+pub use self::misc::{Gliw, DepthFunction, DrawMode};
 pub use self::program::Program;
 pub use self::program::builder::{ProgramBuilder, ProgramFromFileBuilder};
+pub use self::program::pipeline::ProgramPipeline;
 pub use self::shader::{Shader, ShaderType};
-pub use self::texture::{Texture, TextureType};
-pub use self::texture::builder::{TextureBuilder2D, ImageType, TextureCoordWrap, TextureFilter};
+pub use self::sync::{Fence, FenceStatus};
+pub use self::texture::{Texture, TextureType, InternalFormat, ImageAccess};
+pub use self::texture::builder::{TextureBuilder2D, TextureParams, Bitmap, ImageType, TextureCoordWrap, TextureFilter};
+pub use self::texture::pending::PendingTexture;
+pub use self::transform_feedback::{TransformFeedback, FeedbackBufferMode, FeedbackPrimitive};
 pub use self::uniform::{Uniform, UniformData};
 pub use self::vao::Vao;
 pub use self::vert_attrib::{VertexAttrib, AttribFloatFormat, AttribIntFormat};
@@ -1,6 +1,7 @@
-extern crate gl;
+use gliw::gl;
 
-use gliw::{Program, Shader, ShaderType};
+use gliw::{Capabilities, Program, Shader, ShaderType, FeedbackBufferMode};
+use gliw::compute;
 
 use std::rc::Rc;
 use std::ffi::CString;
@@ -28,6 +29,8 @@ pub struct ProgramBuilder<'a> {
     tes: Option<&'a Shader>,
     gs: Option<&'a Shader>,
     fs: Option<&'a Shader>,
+    varyings: Option<(&'a [&'a str], FeedbackBufferMode)>,
+    separable: bool,
 }
 
 impl<'a> ProgramBuilder<'a> {
@@ -39,12 +42,13 @@ impl<'a> ProgramBuilder<'a> {
             tes: None,
             gs: None,
             fs: None,
+            varyings: None,
+            separable: false,
         }
     }
 
     /// Set compute shader to attach.
     pub fn attach_cs(&mut self, shader: &'a Shader) -> &'a mut ProgramBuilder {
-        // TODO: check if shader is compute shader?
         self.cs = Some(shader);
         return self;
     }
@@ -79,11 +83,60 @@ impl<'a> ProgramBuilder<'a> {
         return self;
     }
 
+    /// Set the output variables to be captured by transform feedback.
+    ///
+    /// The varyings are configured right before linking. See `TransformFeedback`.
+    pub fn feedback_varyings(&mut self, varyings: &'a [&'a str], mode: FeedbackBufferMode) -> &'a mut ProgramBuilder {
+        self.varyings = Some((varyings, mode));
+        return self;
+    }
+
+    /// Link the program as separable so that it can be combined with other programs
+    /// in a `ProgramPipeline`.
+    ///
+    /// Requires OpenGL 4.1 or `GL_ARB_separate_shader_objects`.
+    pub fn separable(&mut self) -> &'a mut ProgramBuilder {
+        self.separable = true;
+        return self;
+    }
+
     /// Links a program object using the attached shaders.
+    ///
+    /// The attached shaders are validated before linking. Fails if:
+    ///
+    /// * No shaders are attached or a shader is attached as a stage of a different type.
+    /// * A compute shader is mixed with graphics stages.
+    /// * A non separable graphics program has no vertex shader.
+    /// * A tesselation control shader is attached without a tesselation evaluation shader.
+    /// * The context does not support some of the attached stages or separable programs.
+    /// * There are more separate feedback varyings than `GL_MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS`.
     pub fn link(&self) -> Result<Rc<Program>, String> {
+        let stages = match self.validate() {
+            Ok(stages) => stages,
+            Err(err) => return Err(err)
+        };
+
         unsafe {
             let prog = gl::CreateProgram();
 
+            if self.separable {
+                gl::ProgramParameteri(prog, gl::PROGRAM_SEPARABLE, gl::TRUE as i32);
+            }
+
+            if let Some((varyings, mode)) = self.varyings {
+                if let FeedbackBufferMode::Separate = mode {
+                    let max_separate = Capabilities::current().limits.max_transform_feedback_separate_attribs;
+                    if varyings.len() as i32 > max_separate {
+                        gl::DeleteProgram(prog);
+                        return Err(String::from(ERR_TOO_MANY_VARYINGS));
+                    }
+                }
+
+                let names: Vec<CString> = varyings.iter().map(|name| CString::new(*name).unwrap()).collect();
+                let name_ptrs: Vec<*const _> = names.iter().map(|name| name.as_ptr()).collect();
+                gl::TransformFeedbackVaryings(prog, name_ptrs.len() as i32, name_ptrs.as_ptr(), mode as u32);
+            }
+
             if let Some(shader) = self.cs { gl::AttachShader(prog, shader.handle()); }
             if let Some(shader) = self.vs { gl::AttachShader(prog, shader.handle()); }
             if let Some(shader) = self.tcs { gl::AttachShader(prog, shader.handle()); }
@@ -115,9 +168,71 @@ impl<'a> ProgramBuilder<'a> {
 
             return Ok(Rc::new(Program {
                 handle: prog,
+                stages: stages,
+                separable: self.separable,
             }));
         }
     }
+
+    /// Checks stage compatibility and support. Returns the bitmask of the attached stages.
+    fn validate(&self) -> Result<u32, String> {
+        let slots = [
+            (self.cs, ShaderType::Compute),
+            (self.vs, ShaderType::Vertex),
+            (self.tcs, ShaderType::TessControl),
+            (self.tes, ShaderType::TessEvaluation),
+            (self.gs, ShaderType::Geometry),
+            (self.fs, ShaderType::Fragment),
+        ];
+
+        let mut stages: u32 = 0;
+
+        for &(shader, expected) in slots.iter() {
+            if let Some(shader) = shader {
+                if shader.shader_type() != expected {
+                    return Err(format!("{}: expected {:?} but got {:?}", ERR_STAGE_TYPE_MISMATCH, expected, shader.shader_type()));
+                }
+                stages |= expected.stage_bit();
+            }
+        }
+
+        if stages == 0 {
+            return Err(String::from(ERR_NO_SHADERS));
+        }
+
+        if self.cs.is_some() {
+            if stages != ShaderType::Compute.stage_bit() {
+                return Err(String::from(ERR_COMPUTE_WITH_GRAPHICS));
+            }
+            if let Err(err) = compute::require_compute() {
+                return Err(err);
+            }
+        }
+
+        if self.cs.is_none() && self.vs.is_none() && !self.separable {
+            return Err(String::from(ERR_NO_VERTEX_SHADER));
+        }
+
+        if self.tcs.is_some() && self.tes.is_none() {
+            return Err(String::from(ERR_TCS_WITHOUT_TES));
+        }
+
+        let caps = Capabilities::current();
+
+        if (self.tcs.is_some() || self.tes.is_some()) && !caps.supports_tesselation() {
+            return Err(String::from(ERR_TESSELATION_UNSUPPORTED));
+        }
+
+        if self.gs.is_some() && !caps.supports_geometry_shaders() {
+            return Err(String::from(ERR_GEOMETRY_UNSUPPORTED));
+        }
+
+        if self.separable && !caps.supports_separable_programs() {
+            return Err(String::from(ERR_SEPARABLE_UNSUPPORTED));
+        }
+
+        return Ok(stages);
+    }
 }
 
 /// An utility builder class for compiling and linking a program using shader code from files.
@@ -139,6 +254,8 @@ pub struct ProgramFromFileBuilder<'a> {
     tes_path: Option<&'a str>,
     gs_path: Option<&'a str>,
     fs_path: Option<&'a str>,
+    varyings: Option<(&'a [&'a str], FeedbackBufferMode)>,
+    separable: bool,
 }
 
 impl<'a> ProgramFromFileBuilder<'a> {
@@ -150,6 +267,8 @@ impl<'a> ProgramFromFileBuilder<'a> {
             tes_path: None,
             gs_path: None,
             fs_path: None,
+            varyings: None,
+            separable: false,
         }
     }
 
@@ -189,6 +308,22 @@ impl<'a> ProgramFromFileBuilder<'a> {
         return self;
     }
 
+    /// Set the output variables to be captured by transform feedback.
+    ///
+    /// See `ProgramBuilder::feedback_varyings`.
+    pub fn feedback_varyings(&mut self, varyings: &'a [&'a str], mode: FeedbackBufferMode) -> &'a mut ProgramFromFileBuilder {
+        self.varyings = Some((varyings, mode));
+        return self;
+    }
+
+    /// Link the program as separable.
+    ///
+    /// See `ProgramBuilder::separable`.
+    pub fn separable(&mut self) -> &'a mut ProgramFromFileBuilder {
+        self.separable = true;
+        return self;
+    }
+
     /// Compiles the provided shaders and links them into a program.
     pub fn compile(&self) -> Result<Rc<Program>, String> {
         let cs: Shader;
@@ -199,6 +334,8 @@ impl<'a> ProgramFromFileBuilder<'a> {
         let fs: Shader;
 
         let mut prog_builder = ProgramBuilder::new();
+        prog_builder.varyings = self.varyings;
+        prog_builder.separable = self.separable;
 
         if let Some(filename) = self.cs_path {
             match Shader::from_file(ShaderType::Compute, filename) {
@@ -245,3 +382,13 @@ impl<'a> ProgramFromFileBuilder<'a> {
         return prog_builder.link();
     }
 }
+
+const ERR_TOO_MANY_VARYINGS: &'static str = "Too many separate transform feedback varyings";
+const ERR_NO_SHADERS: &'static str = "No shaders attached";
+const ERR_STAGE_TYPE_MISMATCH: &'static str = "Shader attached to the wrong stage";
+const ERR_COMPUTE_WITH_GRAPHICS: &'static str = "Compute shaders cannot be linked together with graphics stages";
+const ERR_NO_VERTEX_SHADER: &'static str = "Graphics programs require a vertex shader unless linked as separable";
+const ERR_TCS_WITHOUT_TES: &'static str = "Tesselation control shader requires a tesselation evaluation shader";
+const ERR_TESSELATION_UNSUPPORTED: &'static str = "Tesselation shaders require OpenGL 4.0 or newer";
+const ERR_GEOMETRY_UNSUPPORTED: &'static str = "Geometry shaders require OpenGL 3.2 or newer";
+const ERR_SEPARABLE_UNSUPPORTED: &'static str = "Separable programs require OpenGL 4.1 or GL_ARB_separate_shader_objects";
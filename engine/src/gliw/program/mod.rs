@@ -1,12 +1,15 @@
-extern crate gl;
+use gliw::gl;
 
 pub mod builder;
+pub mod pipeline;
 
 /// Wrapper for a linked OpenGL Program.
 ///
 /// Created using `ProgramBuilder` or `ProgramFromFileBuilder`.
 pub struct Program {
     handle: u32,
+    stages: u32,
+    separable: bool,
 }
 
 impl Program {
@@ -15,6 +18,20 @@ impl Program {
         unsafe { gl::UseProgram(self.handle); }
     }
 
+    /// Get the bitmask of the program's stages (`GL_*_SHADER_BIT`).
+    ///
+    /// See `ShaderType::stage_bit`.
+    pub fn stages(&self) -> u32 {
+        return self.stages;
+    }
+
+    /// Checks if the program has been linked as separable.
+    ///
+    /// See `ProgramBuilder::separable` and `ProgramPipeline`.
+    pub fn is_separable(&self) -> bool {
+        return self.separable;
+    }
+
     /// Get the underlying OpenGL handle.
     pub fn handle(&self) -> u32 {
         return self.handle;
@@ -0,0 +1,163 @@
+use gliw::gl;
+
+use gliw::Program;
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::ptr;
+use std::rc::Rc;
+
+/// Wrapper for OpenGL Program Pipeline Object.
+///
+/// Combines the stages of separable programs, e.g. one vertex program shared between
+/// many fragment programs or a geometry shader swapped in only for billboards.
+///
+/// **Note:** a program bound with `Program::bind` (which `Uniform::value` does internally)
+/// takes precedence over the pipeline, so call `bind` after setting uniforms.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw::{ProgramBuilder, ProgramPipeline, Shader, ShaderType};
+/// let vs = Shader::new(ShaderType::Vertex, "<code>").unwrap();
+/// let gs = Shader::new(ShaderType::Geometry, "<code>").unwrap();
+/// let fs = Shader::new(ShaderType::Fragment, "<code>").unwrap();
+///
+/// let vertex_prog = ProgramBuilder::new().attach_vs(&vs).separable().link().unwrap();
+/// let billboard_prog = ProgramBuilder::new().attach_gs(&gs).attach_fs(&fs).separable().link().unwrap();
+///
+/// let pipeline = ProgramPipeline::new();
+/// pipeline.use_program(&vertex_prog);
+/// pipeline.use_program(&billboard_prog);
+/// pipeline.validate().unwrap();
+/// pipeline.bind();
+/// ```
+///
+/// # References
+/// * [Program Pipeline Object](https://www.opengl.org/wiki/Shader_Compilation#Program_pipelines)
+pub struct ProgramPipeline {
+    handle: u32,
+    // Keeps the used programs alive for as long as the pipeline references them,
+    // along with the stages each one is used for.
+    programs: RefCell<Vec<(u32, Rc<Program>)>>
+}
+
+impl ProgramPipeline {
+    /// Generates a program pipeline.
+    pub fn new() -> ProgramPipeline {
+        let mut pipeline = ProgramPipeline {
+            handle: 0,
+            programs: RefCell::new(Vec::new())
+        };
+
+        unsafe { gl::GenProgramPipelines(1, &mut pipeline.handle as *mut u32); }
+
+        return pipeline;
+    }
+
+    /// Wrapper for `glBindProgramPipeline`.
+    ///
+    /// Unbinds the current program so that the pipeline takes effect.
+    pub fn bind(&self) {
+        unsafe {
+            gl::UseProgram(0);
+            gl::BindProgramPipeline(self.handle);
+        }
+    }
+
+    /// Wrapper for `glUseProgramStages`.
+    ///
+    /// Uses all of the stages of `program` in the pipeline.
+    ///
+    /// # Panics
+    /// Panics if `program` is not separable.
+    pub fn use_program(&self, program: &Rc<Program>) {
+        self.use_stages(program.stages(), program);
+    }
+
+    /// Wrapper for `glUseProgramStages`.
+    ///
+    /// Uses only the given `stages` (a bitmask of `GL_*_SHADER_BIT`, see `ShaderType::stage_bit`)
+    /// of `program` in the pipeline.
+    ///
+    /// # Panics
+    /// * Panics if `program` is not separable.
+    /// * Panics if `program` lacks any of the given `stages`.
+    pub fn use_stages(&self, stages: u32, program: &Rc<Program>) {
+        if !program.is_separable() {
+            panic!(ERR_NOT_SEPARABLE);
+        }
+
+        if stages & !program.stages() != 0 {
+            panic!(ERR_MISSING_STAGES);
+        }
+
+        unsafe { gl::UseProgramStages(self.handle, stages, program.handle()); }
+
+        let mut programs = self.programs.borrow_mut();
+        for entry in programs.iter_mut() {
+            if entry.1.handle() == program.handle() {
+                entry.0 |= stages;
+            } else {
+                entry.0 &= !stages;
+            }
+        }
+
+        // Drop programs which are no longer used for any stage.
+        programs.retain(|entry| entry.0 != 0);
+
+        if !programs.iter().any(|entry| entry.1.handle() == program.handle()) {
+            programs.push((stages, program.clone()));
+        }
+    }
+
+    /// Wrapper for `glActiveShaderProgram`.
+    ///
+    /// Sets the program which receives `glUniform*` calls while the pipeline is bound.
+    pub fn active_program(&self, program: &Rc<Program>) {
+        unsafe { gl::ActiveShaderProgram(self.handle, program.handle()); }
+    }
+
+    /// Wrapper for `glValidateProgramPipeline`.
+    ///
+    /// Checks if the pipeline can execute given the current OpenGL state,
+    /// e.g. that the interfaces between the stages match.
+    pub fn validate(&self) -> Result<(), String> {
+        unsafe {
+            gl::ValidateProgramPipeline(self.handle);
+
+            let mut status: i32 = 0;
+            gl::GetProgramPipelineiv(self.handle, gl::VALIDATE_STATUS, &mut status);
+            if status != (gl::TRUE as i32) {
+                let mut log_size: i32 = 0;
+                gl::GetProgramPipelineiv(self.handle, gl::INFO_LOG_LENGTH, &mut log_size);
+
+                if log_size <= 0 {
+                    return Err(String::from(ERR_VALIDATION_FAILED));
+                }
+
+                let buff = CString::from_vec_unchecked(vec![0u8; log_size as usize]);
+                gl::GetProgramPipelineInfoLog(self.handle, log_size, ptr::null_mut(), buff.as_ptr() as *mut _);
+
+                return Err(buff.to_str().unwrap().to_string());
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Get the underlying OpenGL handle.
+    pub fn handle(&self) -> u32 {
+        return self.handle;
+    }
+}
+
+impl Drop for ProgramPipeline {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgramPipelines(1, &self.handle); }
+    }
+}
+
+const ERR_NOT_SEPARABLE: &'static str = "Only separable programs can be used in a program pipeline";
+const ERR_MISSING_STAGES: &'static str = "The program does not contain all of the requested stages";
+const ERR_VALIDATION_FAILED: &'static str = "Program pipeline validation failed";
@@ -1,4 +1,4 @@
-extern crate gl;
+use gliw::gl;
 
 use std::ffi::CString;
 use std::fs::File;
@@ -7,6 +7,7 @@ use std::ptr;
 use std::io::Read;
 
 #[repr(u32)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ShaderType {
     Compute         = gl::COMPUTE_SHADER,
     Vertex          = gl::VERTEX_SHADER,
@@ -19,6 +20,7 @@ pub enum ShaderType {
 /// Wrapper for a compiled OpenGL Shader Object.
 pub struct Shader {
     handle: u32,
+    shader_type: ShaderType,
 }
 
 impl Shader {
@@ -45,7 +47,8 @@ impl Shader {
             }
 
             return Ok(Shader{
-                handle: shader
+                handle: shader,
+                shader_type: shader_type
             });
         }
     }
@@ -60,12 +63,31 @@ impl Shader {
         return Self::new(shader_type, &content);
     }
 
+    /// Get the shader's type (stage).
+    pub fn shader_type(&self) -> ShaderType {
+        return self.shader_type;
+    }
+
     /// Get the underlying OpenGL handle.
     pub fn handle (&self) -> u32 {
         return self.handle;
     }
 }
 
+impl ShaderType {
+    /// Get the program stage bit (`GL_*_SHADER_BIT`) corresponding to this shader type.
+    pub fn stage_bit(&self) -> u32 {
+        return match *self {
+            ShaderType::Compute => gl::COMPUTE_SHADER_BIT,
+            ShaderType::Vertex => gl::VERTEX_SHADER_BIT,
+            ShaderType::TessControl => gl::TESS_CONTROL_SHADER_BIT,
+            ShaderType::TessEvaluation => gl::TESS_EVALUATION_SHADER_BIT,
+            ShaderType::Geometry => gl::GEOMETRY_SHADER_BIT,
+            ShaderType::Fragment => gl::FRAGMENT_SHADER_BIT,
+        };
+    }
+}
+
 impl Drop for Shader {
     fn drop (&mut self) {
         unsafe { gl::DeleteShader(self.handle); }
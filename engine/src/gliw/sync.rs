@@ -0,0 +1,102 @@
+use gliw::gl;
+
+use gliw::gl::types::GLsync;
+
+/// Result of waiting on a `Fence`.
+#[repr(u32)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FenceStatus {
+    /// The fence was already signaled when the wait started.
+    AlreadySignaled     = gl::ALREADY_SIGNALED,
+    /// The fence got signaled during the wait.
+    ConditionSatisfied  = gl::CONDITION_SATISFIED,
+    /// The timeout expired before the fence got signaled.
+    TimeoutExpired      = gl::TIMEOUT_EXPIRED,
+    /// An error occurred, for example the context was lost.
+    WaitFailed          = gl::WAIT_FAILED,
+}
+
+impl FenceStatus {
+    /// Checks if the wait ended because the fence got signaled.
+    pub fn is_signaled(&self) -> bool {
+        return match *self {
+            FenceStatus::AlreadySignaled | FenceStatus::ConditionSatisfied => true,
+            _ => false
+        };
+    }
+}
+
+/// Wrapper for OpenGL Sync Object created by `glFenceSync`.
+///
+/// A fence gets signaled once all of the OpenGL commands issued before its creation
+/// have been completed by the GPU.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw::Fence;
+/// let fence = Fence::new();
+/// // ...issue some more commands and do some CPU work
+/// if !fence.is_signaled() {
+///     fence.client_wait(1_000_000);
+/// }
+/// ```
+///
+/// # References
+/// * [Sync Object](https://www.opengl.org/wiki/Sync_Object)
+pub struct Fence {
+    handle: GLsync
+}
+
+impl Fence {
+    /// Inserts a new fence into the command stream.
+    pub fn new() -> Fence {
+        return Fence {
+            handle: unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) }
+        };
+    }
+
+    /// Checks if the fence has been signaled without blocking.
+    pub fn is_signaled(&self) -> bool {
+        let mut status: i32 = 0;
+        unsafe {
+            gl::GetSynciv(self.handle, gl::SYNC_STATUS, 1, 0 as *mut i32, &mut status);
+        }
+
+        return status as u32 == gl::SIGNALED;
+    }
+
+    /// Wrapper for `glClientWaitSync`.
+    ///
+    /// Blocks the current thread until the fence gets signaled or `timeout` nanoseconds pass.
+    /// Flushes the command stream so that the fence is guaranteed to get signaled eventually.
+    pub fn client_wait(&self, timeout: u64) -> FenceStatus {
+        let status = unsafe { gl::ClientWaitSync(self.handle, gl::SYNC_FLUSH_COMMANDS_BIT, timeout) };
+
+        return match status {
+            gl::ALREADY_SIGNALED => FenceStatus::AlreadySignaled,
+            gl::CONDITION_SATISFIED => FenceStatus::ConditionSatisfied,
+            gl::TIMEOUT_EXPIRED => FenceStatus::TimeoutExpired,
+            _ => FenceStatus::WaitFailed
+        };
+    }
+
+    /// Wrapper for `glWaitSync`.
+    ///
+    /// Makes the GPU wait for the fence before executing any further commands.
+    /// Does not block the current thread.
+    pub fn gpu_wait(&self) {
+        unsafe { gl::WaitSync(self.handle, 0, gl::TIMEOUT_IGNORED); }
+    }
+
+    /// Get the underlying OpenGL handle.
+    pub fn handle(&self) -> GLsync {
+        return self.handle;
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteSync(self.handle); }
+    }
+}
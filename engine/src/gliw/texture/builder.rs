@@ -1,11 +1,16 @@
-extern crate gl;
+use gliw::gl;
 
 use super::{Texture, TextureType};
+use super::pending::PendingTexture;
 
 use std::fs::File;
 use std::io::{Read, ErrorKind};
 use std::os::raw::c_void;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
 
+#[derive(Copy, Clone)]
 pub enum ImageType {
     Bmp,
 }
@@ -94,12 +99,7 @@ pub enum TextureFilter {
 ///     tex.pass_to(&program, "tex", 0);
 /// ```
 pub struct TextureBuilder2D {
-    s_wrap: TextureCoordWrap,
-    t_wrap: TextureCoordWrap,
-    min_filter: TextureFilter,
-    mag_filter: TextureFilter,
-    gen_mipmap: bool,
-    middleware: Vec<Box<Fn(&Texture)>>,
+    params: TextureParams,
     path: String,
     img_type: ImageType
 }
@@ -107,12 +107,7 @@ pub struct TextureBuilder2D {
 impl TextureBuilder2D {
     pub fn new() -> TextureBuilder2D {
         return TextureBuilder2D {
-            s_wrap: TextureCoordWrap::Repeat,
-            t_wrap: TextureCoordWrap::Repeat,
-            min_filter: TextureFilter::None,
-            mag_filter: TextureFilter::None,
-            gen_mipmap: false,
-            middleware: Vec::<Box<Fn(&Texture)>>::new(),
+            params: TextureParams::new(),
             path: String::from(""),
             img_type: ImageType::Bmp
         }
@@ -130,8 +125,8 @@ impl TextureBuilder2D {
     /// Initially the wrap methods are set to `Repeat`
     /// in both OpenGL and this implementation.
     pub fn wrap(&mut self, s_wrap: TextureCoordWrap, t_wrap: TextureCoordWrap) -> &mut Self {
-        self.s_wrap = s_wrap;
-        self.t_wrap = t_wrap;
+        self.params.s_wrap = s_wrap;
+        self.params.t_wrap = t_wrap;
         return self;
     }
 
@@ -143,8 +138,8 @@ impl TextureBuilder2D {
     /// **Note:** `mag_filter` can only be `Nearest` or `Linear`.
     /// Otherwise it will be set to `TextureFilter::None`.
     pub fn filter(&mut self, min_filter: TextureFilter, mag_filter: TextureFilter) -> &mut Self {
-        self.min_filter = min_filter;
-        self.mag_filter = match mag_filter {
+        self.params.min_filter = min_filter;
+        self.params.mag_filter = match mag_filter {
             TextureFilter::Linear | TextureFilter::Nearest => mag_filter,
             _ => TextureFilter::None
         };
@@ -153,7 +148,7 @@ impl TextureBuilder2D {
 
     /// Wrapper for `glGenerateMipmap`.
     pub fn gen_mipmap(&mut self) -> &mut Self {
-        self.gen_mipmap = true;
+        self.params.gen_mipmap = true;
         return self;
     }
 
@@ -166,25 +161,92 @@ impl TextureBuilder2D {
     pub fn middleware<F>(&mut self, closure: F) -> &mut Self
         where F: Fn(&Texture) + 'static
     {
-        self.middleware.push(Box::new(closure));
+        self.params.middleware.push(Rc::new(closure));
         return self;
     }
 
     /// Loads the data from the file and passes it to OpenGL.
     pub fn load(&mut self) -> Result<Texture, String> {
+        // Resolve loading method
+        let bitmap = match decode(&self.path, self.img_type) {
+            Ok(bitmap) => bitmap,
+            Err(err) => return Err(err)
+        };
+
         let tex = Texture::new(TextureType::Tex2D);
 
         tex.bind();
+        bitmap.tex_image(bitmap.data.as_ptr() as *const c_void);
 
-        // Resolve loading method
-        let load_res = match self.img_type {
-            ImageType::Bmp => self.load_bmp(&tex)
-        };
+        self.params.apply(&tex);
 
-        if let Some(err) = load_res {
-            return Err(err);
-        }
+        return Ok(tex);
+    }
+
+    /// Loads the texture without stalling the render loop.
+    ///
+    /// The image is decoded on a worker thread and then streamed to OpenGL through a pixel unpack buffer.
+    /// The returned handle has to be polled from the thread owning the OpenGL context, usually once per frame.
+    /// All of the builder's parameters and middleware are applied once the upload has finished.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use engine::gliw::{TextureBuilder2D, ImageType, TextureFilter};
+    /// let mut pending = TextureBuilder2D::new()
+    ///     .source("huge_terrain.bmp", ImageType::Bmp)
+    ///     .filter(TextureFilter::LinearMipmapLinear, TextureFilter::Linear)
+    ///     .gen_mipmap()
+    ///     .load_async();
+    ///
+    /// loop {
+    ///     // ...render a frame
+    ///     if pending.poll() {
+    ///         let tex = pending.take().unwrap().unwrap();
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    pub fn load_async(&mut self) -> PendingTexture {
+        let (sender, receiver) = mpsc::channel();
+        let path = self.path.clone();
+        let img_type = self.img_type;
+
+        thread::spawn(move || {
+            // The receiver is gone only if the pending texture has been dropped.
+            let _ = sender.send(decode(&path, img_type));
+        });
+
+        return PendingTexture::new(receiver, self.params.clone());
+    }
+}
+
+/// Texture parameters and middleware collected by `TextureBuilder2D`.
+#[derive(Clone)]
+pub struct TextureParams {
+    s_wrap: TextureCoordWrap,
+    t_wrap: TextureCoordWrap,
+    min_filter: TextureFilter,
+    mag_filter: TextureFilter,
+    gen_mipmap: bool,
+    middleware: Vec<Rc<Fn(&Texture)>>
+}
+
+impl TextureParams {
+    /// Repeated wrapping, the default filters of OpenGL and no mipmaps or middleware.
+    pub fn new() -> TextureParams {
+        return TextureParams {
+            s_wrap: TextureCoordWrap::Repeat,
+            t_wrap: TextureCoordWrap::Repeat,
+            min_filter: TextureFilter::None,
+            mag_filter: TextureFilter::None,
+            gen_mipmap: false,
+            middleware: Vec::<Rc<Fn(&Texture)>>::new()
+        };
+    }
 
+    /// Applies all of the parameters to a texture which already has its data loaded.
+    pub fn apply(&self, tex: &Texture) {
         unsafe {
             tex.bind();
 
@@ -207,91 +269,114 @@ impl TextureBuilder2D {
         }
 
         // Execute all of the collected closures
-        for closure_box in &self.middleware {
+        for closure in &self.middleware {
             tex.bind();
-            (*closure_box)(&tex);
+            (*closure)(tex);
         }
+    }
+}
 
-        return Ok(tex);
+/// Decoded image data ready to be passed to OpenGL.
+pub struct Bitmap {
+    pub width: i32,
+    pub height: i32,
+    pub data: Vec<u8>
+}
+
+impl Bitmap {
+    /// Wrapper for `glTexImage2D` with the bitmap's format.
+    ///
+    /// `pixels` is either a pointer to `data` or an offset into a bound pixel unpack buffer.
+    /// The target texture must already be bound.
+    pub fn tex_image(&self, pixels: *const c_void) {
+        unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGB as i32,
+                self.width,
+                self.height,
+                0,
+                gl::BGR,
+                gl::UNSIGNED_BYTE,
+                pixels
+            );
+        }
     }
+}
 
-    /// As of now it only loads 24bpp bitmaps.
-    fn load_bmp(&self, tex: &Texture) -> Option<String> {
-        const BMP_HEADER_SIZE: usize = 54;
-        let mut header: [u8; BMP_HEADER_SIZE] = [0; BMP_HEADER_SIZE];
+/// Decodes an image file. Does not call any OpenGL functions so it is safe to call from any thread.
+fn decode(path: &str, img_type: ImageType) -> Result<Bitmap, String> {
+    return match img_type {
+        ImageType::Bmp => decode_bmp(path)
+    };
+}
 
-        let mut file;
+/// As of now it only loads 24bpp bitmaps.
+fn decode_bmp(path: &str) -> Result<Bitmap, String> {
+    const BMP_HEADER_SIZE: usize = 54;
+    let mut header: [u8; BMP_HEADER_SIZE] = [0; BMP_HEADER_SIZE];
 
-        // Open the file
-        match File::open(&self.path) {
-            Err(err) => return Some(String::from(format!("{}", err))),
-            Ok(f) => file = f
-        }
+    let mut file;
 
-        // Read the header
-        match file.read_exact(&mut header) {
-            Err(ref err) if err.kind() == ErrorKind::UnexpectedEof =>
-                return Some(String::from(INCORRECT_FORMAT)),
-            Err(err) =>
-                return Some(String::from(format!("{}", err))),
-            Ok(_) => {}
-        }
+    // Open the file
+    match File::open(path) {
+        Err(err) => return Err(String::from(format!("{}", err))),
+        Ok(f) => file = f
+    }
 
-        // Check if the format is truely bitmap
-        if header[0] != b'B' || header[1] != b'M' {
-            return Some(String::from(INCORRECT_FORMAT));
-        }
+    // Read the header
+    match file.read_exact(&mut header) {
+        Err(ref err) if err.kind() == ErrorKind::UnexpectedEof =>
+            return Err(String::from(INCORRECT_FORMAT)),
+        Err(err) =>
+            return Err(String::from(format!("{}", err))),
+        Ok(_) => {}
+    }
 
-        // Macro for easy header properties extraction
-        macro_rules! fprop {
-            ($prop: expr) => {
-                unsafe { *(& $prop as *const u8 as *const i32) }
-            }
-        }
+    // Check if the format is truely bitmap
+    if header[0] != b'B' || header[1] != b'M' {
+        return Err(String::from(INCORRECT_FORMAT));
+    }
 
-        // Check if the image is 24bpp
-        if fprop!(header[0x1E]) != 0 ||
-           fprop!(header[0x1C]) != 24 {
-            return Some(String::from(INCORRECT_FORMAT));
+    // Macro for easy header properties extraction
+    macro_rules! fprop {
+        ($prop: expr) => {
+            unsafe { *(& $prop as *const u8 as *const i32) }
         }
+    }
 
-        // Extract the information about the image
-        // let mut data_pos    = fprop!(header[0x0A]);
-        let mut image_size  = fprop!(header[0x22]);
-        let width           = fprop!(header[0x12]);
-        let height          = fprop!(header[0x16]);
+    // Check if the image is 24bpp
+    if fprop!(header[0x1E]) != 0 ||
+       fprop!(header[0x1C]) != 24 {
+        return Err(String::from(INCORRECT_FORMAT));
+    }
 
-        // Some BMP files are misformatted
-        // if data_pos == 0   { data_pos = BMP_HEADER_SIZE as i32; }
-        if image_size == 0 { image_size = width * height * 3; }
+    // Extract the information about the image
+    // let mut data_pos    = fprop!(header[0x0A]);
+    let mut image_size  = fprop!(header[0x22]);
+    let width           = fprop!(header[0x12]);
+    let height          = fprop!(header[0x16]);
 
-        let mut data: Vec<u8> = Vec::<u8>::new();
+    // Some BMP files are misformatted
+    // if data_pos == 0   { data_pos = BMP_HEADER_SIZE as i32; }
+    if image_size == 0 { image_size = width * height * 3; }
 
-        // Read the data
-        match file.read_to_end(&mut data) {
-            Err(err) => return Some(String::from(format!("{}", err))),
-            Ok(size) => if size != image_size as usize {
-                return Some(String::from(INCORRECT_FORMAT));
-            }
-        }
+    let mut data: Vec<u8> = Vec::<u8>::new();
 
-        // Load the texture
-        unsafe {
-            gl::TexImage2D(
-                tex.tex_type() as u32,
-                0,
-                gl::RGB as i32,
-                width,
-                height,
-                0,
-                gl::BGR,
-                gl::UNSIGNED_BYTE,
-                data.as_ptr() as *const c_void
-            );
+    // Read the data
+    match file.read_to_end(&mut data) {
+        Err(err) => return Err(String::from(format!("{}", err))),
+        Ok(size) => if size != image_size as usize {
+            return Err(String::from(INCORRECT_FORMAT));
         }
-
-        return None;
     }
+
+    return Ok(Bitmap {
+        width: width,
+        height: height,
+        data: data
+    });
 }
 
 const INCORRECT_FORMAT: &'static str = "Incorrect file format";
@@ -1,10 +1,16 @@
-extern crate gl;
+use gliw::gl;
 
 pub mod builder;
+pub mod pending;
 
+use gliw::Capabilities;
+use gliw::compute;
 use gliw::program::Program;
 use gliw::uniform::{Uniform, UniformData};
 
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
 use std::rc::Rc;
 
 #[repr(u32)]
@@ -23,6 +29,89 @@ pub enum TextureType {
     MultisampleArray2D  = gl::TEXTURE_2D_MULTISAMPLE_ARRAY,
 }
 
+/// Sized internal formats.
+///
+/// All of them can be used for texture and renderbuffer storage.
+/// All but the depth formats can be used for image load/store.
+#[repr(u32)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum InternalFormat {
+    R8              = gl::R8,
+    RG8             = gl::RG8,
+    RGBA8           = gl::RGBA8,
+    RGB10A2         = gl::RGB10_A2,
+    R16F            = gl::R16F,
+    RG16F           = gl::RG16F,
+    RGBA16F         = gl::RGBA16F,
+    R32F            = gl::R32F,
+    RG32F           = gl::RG32F,
+    RGBA32F         = gl::RGBA32F,
+    R32I            = gl::R32I,
+    RG32I           = gl::RG32I,
+    RGBA32I         = gl::RGBA32I,
+    R32UI           = gl::R32UI,
+    RG32UI          = gl::RG32UI,
+    RGBA32UI        = gl::RGBA32UI,
+    Depth24         = gl::DEPTH_COMPONENT24,
+    Depth32F        = gl::DEPTH_COMPONENT32F,
+    Depth24Stencil8 = gl::DEPTH24_STENCIL8,
+}
+
+impl InternalFormat {
+    /// Get the `(format, type)` pair describing client side pixel data for this format.
+    fn pixel_transfer(&self) -> (u32, u32) {
+        return match *self {
+            InternalFormat::R8 => (gl::RED, gl::UNSIGNED_BYTE),
+            InternalFormat::RG8 => (gl::RG, gl::UNSIGNED_BYTE),
+            InternalFormat::RGBA8 => (gl::RGBA, gl::UNSIGNED_BYTE),
+            InternalFormat::RGB10A2 => (gl::RGBA, gl::UNSIGNED_INT_2_10_10_10_REV),
+            InternalFormat::R16F | InternalFormat::R32F => (gl::RED, gl::FLOAT),
+            InternalFormat::RG16F | InternalFormat::RG32F => (gl::RG, gl::FLOAT),
+            InternalFormat::RGBA16F | InternalFormat::RGBA32F => (gl::RGBA, gl::FLOAT),
+            InternalFormat::R32I => (gl::RED_INTEGER, gl::INT),
+            InternalFormat::RG32I => (gl::RG_INTEGER, gl::INT),
+            InternalFormat::RGBA32I => (gl::RGBA_INTEGER, gl::INT),
+            InternalFormat::R32UI => (gl::RED_INTEGER, gl::UNSIGNED_INT),
+            InternalFormat::RG32UI => (gl::RG_INTEGER, gl::UNSIGNED_INT),
+            InternalFormat::RGBA32UI => (gl::RGBA_INTEGER, gl::UNSIGNED_INT),
+            InternalFormat::Depth24 | InternalFormat::Depth32F => (gl::DEPTH_COMPONENT, gl::FLOAT),
+            InternalFormat::Depth24Stencil8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+        };
+    }
+
+    /// Get the size in bytes of a pixel in the client side format given by `pixel_transfer`.
+    fn pixel_size(&self) -> usize {
+        return match *self {
+            InternalFormat::R8 => 1,
+            InternalFormat::RG8 => 2,
+            InternalFormat::RGBA8 | InternalFormat::RGB10A2 => 4,
+            InternalFormat::R16F | InternalFormat::R32F | InternalFormat::R32I | InternalFormat::R32UI => 4,
+            InternalFormat::RG16F | InternalFormat::RG32F | InternalFormat::RG32I | InternalFormat::RG32UI => 8,
+            InternalFormat::RGBA16F | InternalFormat::RGBA32F | InternalFormat::RGBA32I | InternalFormat::RGBA32UI => 16,
+            InternalFormat::Depth24 | InternalFormat::Depth32F | InternalFormat::Depth24Stencil8 => 4,
+        };
+    }
+
+    /// Checks if this is one of the depth or depth/stencil formats.
+    pub fn is_depth(&self) -> bool {
+        return match *self {
+            InternalFormat::Depth24 | InternalFormat::Depth32F | InternalFormat::Depth24Stencil8 => true,
+            _ => false
+        };
+    }
+}
+
+/// Access policy for image load/store.
+///
+/// See `Texture::bind_image`.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum ImageAccess {
+    ReadOnly    = gl::READ_ONLY,
+    WriteOnly   = gl::WRITE_ONLY,
+    ReadWrite   = gl::READ_WRITE,
+}
+
 /// Wrapper for OpenGL Texture Object.
 ///
 /// # References
@@ -54,18 +143,154 @@ impl Texture {
     }
 
     /// Passes the texture the the given `program` and `sampler_name` on `tex_unit`.
+    ///
+    /// # Panics
+    /// Panics if `tex_unit` is greater than or equal to `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
     pub fn pass_to(&self, prog: &Rc<Program>, sampler_name: &str, tex_unit: u32) {
-        unsafe {
-            // Avoiding `glGetError`
-            if tex_unit >= gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS {
-                panic!(ERR_TEXTURE_UNITS_LIMIT_EXCEEDED);
-            }
-            gl::ActiveTexture(gl::TEXTURE0 + tex_unit);
+        if tex_unit as i32 >= Capabilities::current().limits.max_combined_texture_image_units {
+            panic!(ERR_TEXTURE_UNITS_LIMIT_EXCEEDED);
         }
+
+        unsafe { gl::ActiveTexture(gl::TEXTURE0 + tex_unit); }
         self.bind();
         Uniform::new(prog, sampler_name).value(UniformData::Int1(tex_unit as i32));
     }
 
+    /// Allocates uninitialized storage for the base level of a 2D texture.
+    ///
+    /// Useful for textures which will be written by shaders, for example via `bind_image`
+    /// or as `Framebuffer` attachments.
+    /// The filters are set to `GL_NEAREST` so that the texture is complete without mipmaps.
+    /// Binds self internally.
+    pub fn alloc_2d(&self, width: i32, height: i32, format: InternalFormat) {
+        let (pixel_format, pixel_type) = format.pixel_transfer();
+
+        self.bind();
+        unsafe {
+            gl::TexImage2D(
+                self.tex_type as u32,
+                0,
+                format as i32,
+                width,
+                height,
+                0,
+                pixel_format,
+                pixel_type,
+                ptr::null());
+
+            gl::TexParameteri(self.tex_type as u32, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(self.tex_type as u32, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        }
+    }
+
+    /// Allocates uninitialized storage for the base level of a 3D or 2D array texture.
+    ///
+    /// `depth` is the number of layers for array textures.
+    /// The filters are set to `GL_NEAREST` so that the texture is complete without mipmaps.
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if the texture is not a 3D or 2D array texture.
+    pub fn alloc_3d(&self, width: i32, height: i32, depth: i32, format: InternalFormat) {
+        self.assert_3d();
+
+        let (pixel_format, pixel_type) = format.pixel_transfer();
+        let target = self.tex_type as u32;
+
+        self.bind();
+        unsafe {
+            gl::TexImage3D(
+                target,
+                0,
+                format as i32,
+                width,
+                height,
+                depth,
+                0,
+                pixel_format,
+                pixel_type,
+                ptr::null());
+
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        }
+    }
+
+    /// Uploads `data` as the base level of a 3D or 2D array texture.
+    ///
+    /// `data` is laid out row by row, then slice by slice, in the client side format matching
+    /// `format`, e.g. 4 bytes per pixel for `RGBA8` and 4 floats for `RGBA16F`.
+    /// The filters are set to `GL_LINEAR` and the wrapping to `GL_CLAMP_TO_EDGE`.
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// * Panics if the texture is not a 3D or 2D array texture.
+    /// * Panics if the size of `data` does not match the dimensions and `format`.
+    pub fn image_3d<T>(&self, width: i32, height: i32, depth: i32, format: InternalFormat, data: &[T]) {
+        self.assert_3d();
+
+        if data.len() * mem::size_of::<T>() != width as usize * height as usize * depth as usize * format.pixel_size() {
+            panic!(ERR_DATA_SIZE);
+        }
+
+        let (pixel_format, pixel_type) = format.pixel_transfer();
+        let target = self.tex_type as u32;
+
+        self.bind();
+        unsafe {
+            gl::TexImage3D(
+                target,
+                0,
+                format as i32,
+                width,
+                height,
+                depth,
+                0,
+                pixel_format,
+                pixel_type,
+                data.as_ptr() as *const c_void);
+
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+        }
+    }
+
+    /// Wrapper for `glBindImageTexture`.
+    ///
+    /// Binds `level` of the texture to the image `unit` for image load/store.
+    /// All layers are bound for array, cube map and 3D textures.
+    ///
+    /// # Panics
+    /// * Panics if the context does not support image load/store (OpenGL 4.3).
+    /// * Panics if `unit` is greater than or equal to `GL_MAX_IMAGE_UNITS`.
+    /// * Panics if `format` is a depth format.
+    pub fn bind_image(&self, unit: u32, level: i32, access: ImageAccess, format: InternalFormat) {
+        let layered = match self.tex_type {
+            TextureType::Tex3D |
+            TextureType::Array1D |
+            TextureType::Array2D |
+            TextureType::CubeMap |
+            TextureType::CubeMapArray |
+            TextureType::MultisampleArray2D => gl::TRUE,
+            _ => gl::FALSE
+        };
+
+        self.bind_image_impl(unit, level, layered, 0, access, format);
+    }
+
+    /// Wrapper for `glBindImageTexture`.
+    ///
+    /// Binds a single `layer` of a layered texture to the image `unit` for image load/store.
+    ///
+    /// # Panics
+    /// Same as `bind_image`.
+    pub fn bind_image_layer(&self, unit: u32, level: i32, layer: i32, access: ImageAccess, format: InternalFormat) {
+        self.bind_image_impl(unit, level, gl::FALSE, layer, access, format);
+    }
+
     /// Get the texture's type (target).
     pub fn tex_type(&self) -> TextureType {
         return self.tex_type;
@@ -75,6 +300,29 @@ impl Texture {
     pub fn handle(&self) -> u32 {
         return self.handle;
     }
+
+    fn assert_3d(&self) {
+        match self.tex_type {
+            TextureType::Tex3D | TextureType::Array2D => {},
+            _ => panic!(ERR_NOT_3D)
+        }
+    }
+
+    fn bind_image_impl(&self, unit: u32, level: i32, layered: u8, layer: i32, access: ImageAccess, format: InternalFormat) {
+        if let Err(err) = compute::require_compute() {
+            panic!(err);
+        }
+
+        if unit as i32 >= Capabilities::current().limits.max_image_units {
+            panic!(ERR_IMAGE_UNITS_LIMIT_EXCEEDED);
+        }
+
+        if format.is_depth() {
+            panic!(ERR_IMAGE_FORMAT);
+        }
+
+        unsafe { gl::BindImageTexture(unit, self.handle, level, layered, layer, access as u32, format as u32); }
+    }
 }
 
 impl Drop for Texture {
@@ -84,3 +332,7 @@ impl Drop for Texture {
 }
 
 const ERR_TEXTURE_UNITS_LIMIT_EXCEEDED: &'static str = "Texture units limit exceeded";
+const ERR_IMAGE_UNITS_LIMIT_EXCEEDED: &'static str = "Image units limit exceeded";
+const ERR_IMAGE_FORMAT: &'static str = "Depth formats can not be used for image load/store";
+const ERR_NOT_3D: &'static str = "Texture is not a 3D or 2D array texture";
+const ERR_DATA_SIZE: &'static str = "Texture data size does not match its dimensions and format";
@@ -0,0 +1,117 @@
+use gliw::gl;
+
+use super::{Texture, TextureType};
+use super::builder::{Bitmap, TextureParams};
+
+use gliw::{Buffer, BufferType, BufferUsagePattern, Fence};
+
+use std::mem;
+use std::ptr;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+enum UploadState {
+    /// The image is being decoded on a worker thread.
+    Decoding(Receiver<Result<Bitmap, String>>),
+    /// The data has been handed to OpenGL and it's being transferred from the unpack buffer.
+    Uploading(Texture, Buffer, Fence),
+    Ready(Texture),
+    Failed(String),
+    Taken
+}
+
+/// Handle to a texture being loaded by `TextureBuilder2D::load_async`.
+///
+/// Call `poll` on the thread owning the OpenGL context to advance the upload.
+/// Once it reports readiness the texture can be obtained with `take`.
+pub struct PendingTexture {
+    state: UploadState,
+    params: TextureParams
+}
+
+impl PendingTexture {
+    /// See `TextureBuilder2D::load_async`.
+    pub fn new(receiver: Receiver<Result<Bitmap, String>>, params: TextureParams) -> PendingTexture {
+        return PendingTexture {
+            state: UploadState::Decoding(receiver),
+            params: params
+        };
+    }
+
+    /// Advances the upload without blocking.
+    ///
+    /// Returns `true` if the texture is ready or the loading failed, i.e. `take` will return `Some`.
+    pub fn poll(&mut self) -> bool {
+        let state = mem::replace(&mut self.state, UploadState::Taken);
+
+        self.state = match state {
+            UploadState::Decoding(receiver) => {
+                match receiver.try_recv() {
+                    Ok(Ok(bitmap)) => Self::upload(bitmap),
+                    Ok(Err(err)) => UploadState::Failed(err),
+                    Err(TryRecvError::Empty) => UploadState::Decoding(receiver),
+                    Err(TryRecvError::Disconnected) => UploadState::Failed(String::from(ERR_WORKER_DIED))
+                }
+            },
+            UploadState::Uploading(tex, pbo, fence) => {
+                if fence.is_signaled() {
+                    // The pixel unpack buffer is no longer needed so it gets dropped here.
+                    self.params.apply(&tex);
+                    UploadState::Ready(tex)
+                } else {
+                    UploadState::Uploading(tex, pbo, fence)
+                }
+            },
+            other => other
+        };
+
+        return self.is_done();
+    }
+
+    /// Checks if the texture is ready to be taken.
+    pub fn is_ready(&self) -> bool {
+        return match self.state {
+            UploadState::Ready(_) => true,
+            _ => false
+        };
+    }
+
+    /// Checks if the texture is ready or the loading failed.
+    pub fn is_done(&self) -> bool {
+        return match self.state {
+            UploadState::Ready(_) | UploadState::Failed(_) => true,
+            _ => false
+        };
+    }
+
+    /// Takes the loaded texture or the loading error.
+    ///
+    /// Returns `None` if the loading is still in progress or the result has already been taken.
+    pub fn take(&mut self) -> Option<Result<Texture, String>> {
+        if !self.is_done() {
+            return None;
+        }
+
+        return match mem::replace(&mut self.state, UploadState::Taken) {
+            UploadState::Ready(tex) => Some(Ok(tex)),
+            UploadState::Failed(err) => Some(Err(err)),
+            _ => None
+        };
+    }
+
+    /// Streams the decoded data into a pixel unpack buffer and starts the transfer to the texture.
+    fn upload(bitmap: Bitmap) -> UploadState {
+        let pbo = Buffer::from_data(&bitmap.data, BufferType::PixelUnpack, BufferUsagePattern::StreamDraw);
+        let tex = Texture::new(TextureType::Tex2D);
+
+        tex.bind();
+        // With a bound pixel unpack buffer the pointer is an offset into the buffer.
+        bitmap.tex_image(ptr::null());
+
+        // Leaving the buffer bound would break any subsequent uploads from client memory.
+        unsafe { gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0); }
+
+        return UploadState::Uploading(tex, pbo, Fence::new());
+    }
+}
+
+const ERR_WORKER_DIED: &'static str = "The image decoding thread terminated unexpectedly";
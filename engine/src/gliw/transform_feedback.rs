@@ -0,0 +1,280 @@
+use gliw::gl;
+
+use gliw::{Buffer, BufferType, Capabilities, DrawMode};
+
+use std::cell::Cell;
+
+/// Specifies how captured varyings are written to the bound buffers.
+///
+/// See `ProgramBuilder::feedback_varyings`.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum FeedbackBufferMode {
+    /// All varyings are written to a single buffer bound on index `0`.
+    Interleaved = gl::INTERLEAVED_ATTRIBS,
+    /// Each varying is written to the buffer bound on its own index.
+    Separate    = gl::SEPARATE_ATTRIBS,
+}
+
+/// Primitive types that can be captured with transform feedback.
+///
+/// The primitive type used in the draw calls while capturing must match the chosen one,
+/// e.g. `Lines` accepts `GL_LINES`, `GL_LINE_LOOP` and `GL_LINE_STRIP`.
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub enum FeedbackPrimitive {
+    Points      = gl::POINTS,
+    Lines       = gl::LINES,
+    Triangles   = gl::TRIANGLES,
+}
+
+impl FeedbackPrimitive {
+    fn vertices(&self) -> i32 {
+        return match *self {
+            FeedbackPrimitive::Points => 1,
+            FeedbackPrimitive::Lines => 2,
+            FeedbackPrimitive::Triangles => 3,
+        };
+    }
+}
+
+/// Wrapper for OpenGL Transform Feedback Object.
+///
+/// On contexts older than OpenGL 4.0 without `GL_ARB_transform_feedback2` the default transform
+/// feedback object is used instead. In that case `pause` and `resume` are not available and `draw`
+/// falls back to reading a primitive query, which introduces a synchronization point.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use engine::gliw::{
+/// #   Buffer, BufferType, BufferUsagePattern, DrawMode, Gliw, ProgramBuilder, Shader, ShaderType,
+/// #   TransformFeedback, FeedbackBufferMode, FeedbackPrimitive
+/// # };
+/// # extern crate gl;
+/// # fn main() {
+/// # let particles: [f32; 4] = [0.0; 4];
+/// let vs = Shader::new(ShaderType::Vertex, "<code>").unwrap();
+/// let update = ProgramBuilder::new()
+///     .attach_vs(&vs)
+///     .feedback_varyings(&["out_position", "out_velocity"], FeedbackBufferMode::Interleaved)
+///     .link()
+///     .unwrap();
+///
+/// let output = Buffer::from_data(&particles, BufferType::TransformFeedback, BufferUsagePattern::StreamCopy);
+/// let feedback = TransformFeedback::new();
+/// feedback.bind_buffer(0, &output);
+///
+/// update.bind();
+/// Gliw::enable(gl::RASTERIZER_DISCARD);
+/// feedback.begin(FeedbackPrimitive::Points);
+/// // ...draw the input particles as points
+/// feedback.end();
+/// Gliw::disable(gl::RASTERIZER_DISCARD);
+///
+/// // ...bind a vao sourcing from `output`, then draw without knowing the vertex count
+/// feedback.draw(DrawMode::Points);
+/// # }
+/// ```
+///
+/// # References
+/// * [Transform Feedback](https://www.opengl.org/wiki/Transform_Feedback)
+pub struct TransformFeedback {
+    handle: u32,
+    query: u32,
+    primitive: Cell<Option<FeedbackPrimitive>>,
+    active: Cell<bool>,
+    paused: Cell<bool>,
+}
+
+impl TransformFeedback {
+    /// Generates a transform feedback object.
+    pub fn new() -> TransformFeedback {
+        let mut tf = TransformFeedback {
+            handle: 0,
+            query: 0,
+            primitive: Cell::new(None),
+            active: Cell::new(false),
+            paused: Cell::new(false),
+        };
+
+        unsafe {
+            if is_object_supported() {
+                gl::GenTransformFeedbacks(1, &mut tf.handle as *mut u32);
+            }
+            gl::GenQueries(1, &mut tf.query as *mut u32);
+        }
+
+        return tf;
+    }
+
+    /// Wrapper for `glBindTransformFeedback`.
+    pub fn bind(&self) {
+        if self.handle != 0 {
+            unsafe { gl::BindTransformFeedback(gl::TRANSFORM_FEEDBACK, self.handle); }
+        }
+    }
+
+    /// Binds `buffer` to the capture binding point `index` of this transform feedback object.
+    ///
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if `buffer` is not of type `BufferType::TransformFeedback`.
+    pub fn bind_buffer(&self, index: u32, buffer: &Buffer) {
+        match buffer.buf_type() {
+            BufferType::TransformFeedback => {},
+            _ => panic!(ERR_BUFFER_TYPE)
+        }
+
+        self.bind();
+        buffer.bind_base(index);
+    }
+
+    /// Wrapper for `glBeginTransformFeedback`.
+    ///
+    /// The program with the captured varyings must already be bound.
+    /// Binds self internally.
+    ///
+    /// # Panics
+    /// Panics if the transform feedback is already active.
+    pub fn begin(&self, primitive: FeedbackPrimitive) {
+        if self.active.get() {
+            panic!(ERR_ALREADY_ACTIVE);
+        }
+
+        self.bind();
+        unsafe {
+            gl::BeginQuery(gl::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN, self.query);
+            gl::BeginTransformFeedback(primitive as u32);
+        }
+
+        self.primitive.set(Some(primitive));
+        self.active.set(true);
+        self.paused.set(false);
+    }
+
+    /// Wrapper for `glPauseTransformFeedback`.
+    ///
+    /// # Panics
+    /// * Panics if the transform feedback is not active or is already paused.
+    /// * Panics if the context does not support transform feedback objects.
+    pub fn pause(&self) {
+        self.assert_object_supported();
+
+        if !self.active.get() || self.paused.get() {
+            panic!(ERR_NOT_RUNNING);
+        }
+
+        self.bind();
+        unsafe { gl::PauseTransformFeedback(); }
+        self.paused.set(true);
+    }
+
+    /// Wrapper for `glResumeTransformFeedback`.
+    ///
+    /// # Panics
+    /// * Panics if the transform feedback is not paused.
+    /// * Panics if the context does not support transform feedback objects.
+    pub fn resume(&self) {
+        self.assert_object_supported();
+
+        if !self.paused.get() {
+            panic!(ERR_NOT_PAUSED);
+        }
+
+        self.bind();
+        unsafe { gl::ResumeTransformFeedback(); }
+        self.paused.set(false);
+    }
+
+    /// Wrapper for `glEndTransformFeedback`.
+    ///
+    /// # Panics
+    /// Panics if the transform feedback is not active.
+    pub fn end(&self) {
+        if !self.active.get() {
+            panic!(ERR_NOT_ACTIVE);
+        }
+
+        self.bind();
+        unsafe {
+            gl::EndTransformFeedback();
+            gl::EndQuery(gl::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN);
+        }
+
+        self.active.set(false);
+        self.paused.set(false);
+    }
+
+    /// Draws the vertices captured by the last `begin`/`end` pair, without querying their count
+    /// on the CPU when the context supports it (`glDrawTransformFeedback`).
+    ///
+    /// The vao sourcing from the capture buffers must already be bound.
+    ///
+    /// # Panics
+    /// Panics if the transform feedback is active or nothing has been captured yet.
+    pub fn draw(&self, mode: DrawMode) {
+        if self.active.get() {
+            panic!(ERR_ALREADY_ACTIVE);
+        }
+
+        let primitive = match self.primitive.get() {
+            Some(primitive) => primitive,
+            None => panic!(ERR_NOTHING_CAPTURED)
+        };
+
+        unsafe {
+            if self.handle != 0 {
+                gl::DrawTransformFeedback(mode as u32, self.handle);
+            } else {
+                gl::DrawArrays(mode as u32, 0, self.primitives_written() as i32 * primitive.vertices());
+            }
+        }
+    }
+
+    /// Get the number of primitives written by the last `begin`/`end` pair.
+    ///
+    /// **Note:** this waits for the GPU to finish the capture.
+    pub fn primitives_written(&self) -> u32 {
+        let mut count: u32 = 0;
+        unsafe { gl::GetQueryObjectuiv(self.query, gl::QUERY_RESULT, &mut count); }
+        return count;
+    }
+
+    /// Get the underlying OpenGL handle.
+    ///
+    /// A value of `0` means that the default transform feedback object is used.
+    pub fn handle(&self) -> u32 {
+        return self.handle;
+    }
+
+    fn assert_object_supported(&self) {
+        if self.handle == 0 {
+            panic!(ERR_OBJECT_UNSUPPORTED);
+        }
+    }
+}
+
+impl Drop for TransformFeedback {
+    fn drop(&mut self) {
+        unsafe {
+            if self.handle != 0 {
+                gl::DeleteTransformFeedbacks(1, &self.handle);
+            }
+            gl::DeleteQueries(1, &self.query);
+        }
+    }
+}
+
+fn is_object_supported() -> bool {
+    return Capabilities::current().supports_transform_feedback_objects();
+}
+
+const ERR_BUFFER_TYPE: &'static str = "Transform feedback requires a buffer of type `TransformFeedback`";
+const ERR_ALREADY_ACTIVE: &'static str = "Transform feedback is already active";
+const ERR_NOT_ACTIVE: &'static str = "Transform feedback is not active";
+const ERR_NOT_RUNNING: &'static str = "Transform feedback is not active or is already paused";
+const ERR_NOT_PAUSED: &'static str = "Transform feedback is not paused";
+const ERR_NOTHING_CAPTURED: &'static str = "Transform feedback has not captured anything yet";
+const ERR_OBJECT_UNSUPPORTED: &'static str = "Transform feedback objects require OpenGL 4.0 or GL_ARB_transform_feedback2";
@@ -1,4 +1,4 @@
-extern crate gl;
+use gliw::gl;
 
 use gliw::program::Program;
 
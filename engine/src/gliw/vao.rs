@@ -1,4 +1,4 @@
-extern crate gl;
+use gliw::gl;
 
 /// Wrapper for OpenGL Vertex Array Object.
 ///
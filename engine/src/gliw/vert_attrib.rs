@@ -1,6 +1,6 @@
-extern crate gl;
+use gliw::gl;
 
-use gliw::{Vao, Buffer, Program};
+use gliw::{Vao, Buffer, Capabilities, Program};
 use gliw::error;
 
 use std::ffi::CString;
@@ -122,13 +122,8 @@ impl VertexAttrib {
             panic!(NEGATIVE_STRIDE);
         }
 
-        // TODO: is this correct?
-        unsafe {
-            let mut max_vertex_attribs: i32 = 0;
-            gl::GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut max_vertex_attribs);
-            if self.handle >= max_vertex_attribs {
-                panic!(error::GL_MAX_VERTEX_ATTRIBS.msg);
-            }
+        if self.handle >= Capabilities::current().limits.max_vertex_attribs {
+            panic!(error::GL_MAX_VERTEX_ATTRIBS.msg);
         }
 
         vao.bind();
@@ -174,15 +169,10 @@ impl VertexAttrib {
             panic!(NEGATIVE_STRIDE);
         }
 
-        unsafe {
-            let mut max_vertex_attribs: i32 = 0;
-            gl::GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut max_vertex_attribs);
-            if self.handle >= max_vertex_attribs {
-                panic!(error::GL_MAX_VERTEX_ATTRIBS.msg);
-            }
+        if self.handle >= Capabilities::current().limits.max_vertex_attribs {
+            panic!(error::GL_MAX_VERTEX_ATTRIBS.msg);
         }
 
-
         match format {
             AttribIntFormat::Byte(size @ 1...4)    => unsafe { gl::VertexAttribIPointer(self.handle as u32, size, gl::BYTE, stride, offset); },
             AttribIntFormat::Ubyte(size @ 1...4)   => unsafe { gl::VertexAttribIPointer(self.handle as u32, size, gl::UNSIGNED_BYTE, stride, offset); },
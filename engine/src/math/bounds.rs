@@ -0,0 +1,150 @@
+extern crate cgmath;
+
+use self::cgmath::{EuclideanSpace, InnerSpace, Transform, Point3, Vector3, Matrix4};
+
+/// Axis aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>
+}
+
+/// Bounding sphere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32
+}
+
+/// The bounding volume of a `Renderable`, see `Renderable::bounds`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bounds {
+    Box(Aabb),
+    Sphere(BoundingSphere)
+}
+
+impl Aabb {
+    /// Create a box from its minimum and maximum corners.
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Aabb {
+        return Aabb {
+            min: min,
+            max: max
+        };
+    }
+
+    /// Create the smallest box containing all `points`. `None` if there are no points.
+    pub fn from_points(points: &[Point3<f32>]) -> Option<Aabb> {
+        let first = match points.first() {
+            Some(first) => *first,
+            None => return None
+        };
+
+        return Some(points.iter().fold(Aabb::new(first, first), |aabb, &point| {
+            aabb.union(&Aabb::new(point, point))
+        }));
+    }
+
+    /// Get the center of the box.
+    pub fn center(&self) -> Point3<f32> {
+        return self.min.midpoint(self.max);
+    }
+
+    /// Get the half sizes of the box along each axis.
+    pub fn extents(&self) -> Vector3<f32> {
+        return (self.max - self.min) * 0.5;
+    }
+
+    /// Get the smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        return Aabb::new(
+            Point3::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y), self.min.z.min(other.min.z)),
+            Point3::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y), self.max.z.max(other.max.z)));
+    }
+
+    /// Check if `point` is inside the box or on its surface.
+    pub fn contains(&self, point: Point3<f32>) -> bool {
+        return point.x >= self.min.x && point.x <= self.max.x &&
+            point.y >= self.min.y && point.y <= self.max.y &&
+            point.z >= self.min.z && point.z <= self.max.z;
+    }
+
+    /// Check if the boxes overlap.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        return self.min.x <= other.max.x && self.max.x >= other.min.x &&
+            self.min.y <= other.max.y && self.max.y >= other.min.y &&
+            self.min.z <= other.max.z && self.max.z >= other.min.z;
+    }
+
+    /// Get the smallest axis aligned box containing this one after `matrix` is applied to it.
+    ///
+    /// # References
+    /// - Arvo, J. "Transforming Axis-Aligned Bounding Boxes", Graphics Gems, 1990
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Aabb {
+        let center = matrix.transform_point(self.center());
+        let extents = self.extents();
+
+        // Each new extent is the sum of the old ones projected on the new axis.
+        let extent = |row: usize| {
+            matrix[0][row].abs() * extents.x + matrix[1][row].abs() * extents.y + matrix[2][row].abs() * extents.z
+        };
+        let extents = Vector3::new(extent(0), extent(1), extent(2));
+
+        return Aabb::new(center + -extents, center + extents);
+    }
+
+    /// Get the sphere enclosing the box.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        return BoundingSphere::new(self.center(), self.extents().magnitude());
+    }
+}
+
+impl BoundingSphere {
+    /// Create a sphere from its center and radius.
+    pub fn new(center: Point3<f32>, radius: f32) -> BoundingSphere {
+        return BoundingSphere {
+            center: center,
+            radius: radius
+        };
+    }
+
+    /// Get the sphere containing this one after `matrix` is applied to it.
+    ///
+    /// The radius is scaled by the largest scale of `matrix`.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> BoundingSphere {
+        let scale = matrix.x.truncate().magnitude()
+            .max(matrix.y.truncate().magnitude())
+            .max(matrix.z.truncate().magnitude());
+
+        return BoundingSphere::new(matrix.transform_point(self.center), self.radius * scale);
+    }
+
+    /// Get the axis aligned box enclosing the sphere.
+    pub fn aabb(&self) -> Aabb {
+        let extents = Vector3::new(self.radius, self.radius, self.radius);
+
+        return Aabb::new(self.center + -extents, self.center + extents);
+    }
+}
+
+impl Bounds {
+    /// Get the volume after `matrix` is applied to it.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Bounds {
+        return match *self {
+            Bounds::Box(ref aabb) => Bounds::Box(aabb.transform(matrix)),
+            Bounds::Sphere(ref sphere) => Bounds::Sphere(sphere.transform(matrix))
+        };
+    }
+
+    /// Get the axis aligned box enclosing the volume.
+    pub fn aabb(&self) -> Aabb {
+        return match *self {
+            Bounds::Box(aabb) => aabb,
+            Bounds::Sphere(ref sphere) => sphere.aabb()
+        };
+    }
+
+    /// Get the smallest box containing both volumes.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        return Bounds::Box(self.aabb().union(&other.aabb()));
+    }
+}
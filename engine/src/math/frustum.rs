@@ -0,0 +1,141 @@
+extern crate cgmath;
+
+use self::cgmath::{EuclideanSpace, InnerSpace, Matrix, Point3, Vector3, Vector4, Matrix4};
+
+use super::{Aabb, BoundingSphere, Bounds};
+
+/// A plane with a unit `normal`, containing the points `p` for which `normal.dot(p) + distance == 0`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32
+}
+
+/// The volume visible through a view-projection matrix, bounded by six inward facing planes.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate engine;
+/// # extern crate cgmath;
+/// # use engine::core::Camera;
+/// # use engine::math::{Frustum, BoundingSphere};
+/// # use cgmath::Point3;
+/// # fn main() {
+/// # let camera = Camera::new();
+/// let frustum = Frustum::from_matrix(&camera.vp_matrix());
+/// let visible = frustum.intersects_sphere(&BoundingSphere::new(Point3::new(0.0, 0.0, -5.0), 1.0));
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far.
+    pub planes: [Plane; 6]
+}
+
+impl Plane {
+    /// Create a plane from the coefficients of its equation `ax + by + cz + d = 0`, normalizing them.
+    pub fn from_coefficients(coefficients: Vector4<f32>) -> Plane {
+        let normal = coefficients.truncate();
+        let length = normal.magnitude();
+
+        return Plane {
+            normal: normal / length,
+            distance: coefficients.w / length
+        };
+    }
+
+    /// Get the signed distance from the plane to `point`, positive on the side the normal points to.
+    pub fn distance_to(&self, point: Point3<f32>) -> f32 {
+        return self.normal.dot(point.to_vec()) + self.distance;
+    }
+}
+
+impl Frustum {
+    /// Extracts the planes of the frustum of a view-projection matrix with OpenGL's clip space.
+    ///
+    /// The planes are in the space the matrix transforms from, e.g. world space for `Camera::vp_matrix`.
+    ///
+    /// # References
+    /// - Gribb, G., Hartmann, K. "Fast Extraction of Viewing Frustum Planes from the World-View-Projection Matrix", 2001
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Frustum {
+        let x = matrix.row(0);
+        let y = matrix.row(1);
+        let z = matrix.row(2);
+        let w = matrix.row(3);
+
+        return Frustum {
+            planes: [
+                Plane::from_coefficients(w + x),
+                Plane::from_coefficients(w - x),
+                Plane::from_coefficients(w + y),
+                Plane::from_coefficients(w - y),
+                Plane::from_coefficients(w + z),
+                Plane::from_coefficients(w - z)
+            ]
+        };
+    }
+
+    /// Get the corners of the frustum, the near ones first.
+    ///
+    /// Within each four, `x` and then `y` alternate between the left/right and bottom/top planes.
+    /// The corners are not finite if the frustum is open, e.g. with an infinite far plane.
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let mut corners = [Point3::new(0.0, 0.0, 0.0); 8];
+
+        for (index, corner) in corners.iter_mut().enumerate() {
+            *corner = intersection(
+                &self.planes[index & 1],
+                &self.planes[2 + (index >> 1 & 1)],
+                &self.planes[4 + (index >> 2)]);
+        }
+
+        return corners;
+    }
+
+    /// Check if `point` is inside the frustum.
+    pub fn contains(&self, point: Point3<f32>) -> bool {
+        return self.planes.iter().all(|plane| plane.distance_to(point) >= 0.0);
+    }
+
+    /// Check if any part of `sphere` may be inside the frustum.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        return self.planes.iter().all(|plane| plane.distance_to(sphere.center) >= -sphere.radius);
+    }
+
+    /// Check if any part of `aabb` may be inside the frustum.
+    ///
+    /// Conservative: a box near a corner of the frustum can pass while being outside.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        return self.planes.iter().all(|plane| {
+            // The corner furthest along the normal. Axes along the plane don't matter,
+            // and are zeroed so that unbounded boxes don't make the distance NaN.
+            let furthest = |normal: f32, min: f32, max: f32| {
+                if normal > 0.0 { max } else if normal < 0.0 { min } else { 0.0 }
+            };
+            let corner = Point3::new(
+                furthest(plane.normal.x, aabb.min.x, aabb.max.x),
+                furthest(plane.normal.y, aabb.min.y, aabb.max.y),
+                furthest(plane.normal.z, aabb.min.z, aabb.max.z));
+
+            plane.distance_to(corner) >= 0.0
+        });
+    }
+
+    /// Check if any part of `bounds` may be inside the frustum.
+    pub fn intersects(&self, bounds: &Bounds) -> bool {
+        return match *bounds {
+            Bounds::Box(ref aabb) => self.intersects_aabb(aabb),
+            Bounds::Sphere(ref sphere) => self.intersects_sphere(sphere)
+        };
+    }
+}
+
+/// Get the point where three planes meet.
+fn intersection(a: &Plane, b: &Plane, c: &Plane) -> Point3<f32> {
+    let bc = b.normal.cross(c.normal);
+    let ca = c.normal.cross(a.normal);
+    let ab = a.normal.cross(b.normal);
+
+    return Point3::from_vec((bc * -a.distance + ca * -b.distance + ab * -c.distance) / a.normal.dot(bc));
+}
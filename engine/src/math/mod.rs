@@ -2,6 +2,14 @@
 
 extern crate cgmath;
 
+mod bounds;
+mod frustum;
+mod ray;
+
+pub use self::bounds::{Aabb, BoundingSphere, Bounds};
+pub use self::frustum::{Frustum, Plane};
+pub use self::ray::{Ray, RayHit};
+
 use self::cgmath::{Matrix4, Quaternion};
 
 /// Rotation Mat4 from Quat.
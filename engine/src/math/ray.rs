@@ -0,0 +1,246 @@
+extern crate cgmath;
+
+use self::cgmath::{InnerSpace, Matrix, SquareMatrix, Transform, Point3, Vector3, Matrix4};
+
+use super::{Aabb, BoundingSphere, Bounds};
+
+use std::f32;
+
+/// A half-line starting at `origin`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    /// Unit vector, unless the ray was transformed, see `transform`.
+    pub direction: Vector3<f32>
+}
+
+/// Where a ray hits a surface.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// The distance along the ray, in multiples of its direction's length.
+    pub distance: f32,
+    pub point: Point3<f32>,
+    /// Unit normal of the surface at `point`.
+    pub normal: Vector3<f32>
+}
+
+impl Ray {
+    /// Create a ray, normalizing `direction`.
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Ray {
+        return Ray {
+            origin: origin,
+            direction: direction.normalize()
+        };
+    }
+
+    /// Get the point at `distance` along the ray.
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        return self.origin + self.direction * distance;
+    }
+
+    /// Get the ray after `matrix` is applied to it.
+    ///
+    /// The direction is not normalized, so the distances of the hits stay the same as along
+    /// the original ray. Used to test against objects in their model space.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Ray {
+        return Ray {
+            origin: matrix.transform_point(self.origin),
+            direction: matrix.transform_vector(self.direction)
+        };
+    }
+
+    /// Get the distance along the ray to where it enters `aabb`, `0` if it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        return self.hit_aabb(aabb).map(|hit| hit.distance);
+    }
+
+    /// Get the distance along the ray to where it enters `sphere`, `0` if it starts inside.
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        return self.hit_sphere(sphere).map(|hit| hit.distance);
+    }
+
+    /// Get the distance along the ray to where it enters `bounds`, `0` if it starts inside.
+    pub fn intersect(&self, bounds: &Bounds) -> Option<f32> {
+        return self.hit_bounds(bounds).map(|hit| hit.distance);
+    }
+
+    /// Get the distance along the ray to where it crosses the plane of points `p`
+    /// with `normal.dot(p - point) == 0`. `None` if it's parallel or the plane is behind.
+    pub fn intersect_plane(&self, point: Point3<f32>, normal: Vector3<f32>) -> Option<f32> {
+        let denominator = normal.dot(self.direction);
+
+        if denominator.abs() < 1e-6 {
+            return None;
+        }
+
+        let distance = normal.dot(point - self.origin) / denominator;
+
+        return if distance >= 0.0 { Some(distance) } else { None };
+    }
+
+    /// Get the point where the ray crosses the horizontal plane at `height`, e.g. the ground
+    /// under the cursor. `None` if the ray is parallel to the plane or points away from it.
+    pub fn intersect_ground(&self, height: f32) -> Option<Point3<f32>> {
+        return self.intersect_plane(Point3::new(0.0, height, 0.0), Vector3::new(0.0, 1.0, 0.0))
+            .map(|distance| self.at(distance));
+    }
+
+    /// Get where the ray enters `aabb`.
+    ///
+    /// If the ray starts inside, the hit is at its origin with the normal facing the ray.
+    ///
+    /// # References
+    /// - Williams, A. et al. "An Efficient and Robust Ray-Box Intersection Algorithm", 2005
+    pub fn hit_aabb(&self, aabb: &Aabb) -> Option<RayHit> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        let mut normal = -self.direction.normalize();
+
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+
+            if direction == 0.0 {
+                if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (aabb.min[axis] - origin) / direction;
+            let t2 = (aabb.max[axis] - origin) / direction;
+
+            // The ray enters through the face it reaches first.
+            let entry = t1.min(t2);
+            if entry > near {
+                near = entry;
+                normal = Vector3::new(0.0, 0.0, 0.0);
+                normal[axis] = -direction.signum();
+            }
+
+            far = far.min(t1.max(t2));
+
+            if near > far {
+                return None;
+            }
+        }
+
+        return Some(RayHit {
+            distance: near,
+            point: self.at(near),
+            normal: normal
+        });
+    }
+
+    /// Get where the ray enters `sphere`.
+    ///
+    /// If the ray starts inside, the hit is at its origin with the normal facing the ray.
+    pub fn hit_sphere(&self, sphere: &BoundingSphere) -> Option<RayHit> {
+        let to_origin = self.origin - sphere.center;
+        let c = to_origin.magnitude2() - sphere.radius * sphere.radius;
+
+        if c <= 0.0 {
+            return Some(RayHit {
+                distance: 0.0,
+                point: self.origin,
+                normal: -self.direction.normalize()
+            });
+        }
+
+        // Solves |origin + t * direction - center|^2 = radius^2 for the smaller t.
+        let a = self.direction.magnitude2();
+        let b = to_origin.dot(self.direction);
+        let discriminant = b * b - a * c;
+
+        if b > 0.0 || discriminant < 0.0 {
+            return None;
+        }
+
+        let distance = (-b - discriminant.sqrt()) / a;
+        let point = self.at(distance);
+
+        return Some(RayHit {
+            distance: distance,
+            point: point,
+            normal: (point - sphere.center).normalize()
+        });
+    }
+
+    /// Get where the ray enters `bounds`.
+    pub fn hit_bounds(&self, bounds: &Bounds) -> Option<RayHit> {
+        return match *bounds {
+            Bounds::Box(ref aabb) => self.hit_aabb(aabb),
+            Bounds::Sphere(ref sphere) => self.hit_sphere(sphere)
+        };
+    }
+
+    /// Get where the ray enters the oriented box made by applying `matrix` to `aabb`,
+    /// e.g. a model space box and the model matrix. `None` if `matrix` is not invertible.
+    pub fn hit_obb(&self, aabb: &Aabb, matrix: &Matrix4<f32>) -> Option<RayHit> {
+        return matrix.invert()
+            .and_then(|inverse| self.transform(&inverse).hit_aabb(aabb))
+            .map(|hit| hit.transform(matrix));
+    }
+
+    /// Get where the ray hits the triangle `a`, `b`, `c` from either side.
+    ///
+    /// The normal is the triangle's, facing the side from which `a`, `b`, `c` is counter-clockwise.
+    ///
+    /// # References
+    /// - Möller, T., Trumbore, B. "Fast, Minimum Storage Ray/Triangle Intersection", 1997
+    pub fn hit_triangle(&self, a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Option<RayHit> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+
+        if determinant.abs() < 1e-8 {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let to_origin = self.origin - a;
+
+        let u = to_origin.dot(p) * inverse_determinant;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = to_origin.cross(edge1);
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+
+        return Some(RayHit {
+            distance: distance,
+            point: self.at(distance),
+            normal: edge1.cross(edge2).normalize()
+        });
+    }
+}
+
+impl RayHit {
+    /// Get the hit after `matrix` is applied to it, keeping the distance.
+    ///
+    /// Meant for hits of a ray which was transformed by the inverse of `matrix`, see `Ray::transform`.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> RayHit {
+        // The inverse transpose keeps the normal perpendicular under non-uniform scaling.
+        let normal = match matrix.invert() {
+            Some(inverse) => inverse.transpose().transform_vector(self.normal),
+            None => matrix.transform_vector(self.normal)
+        };
+
+        return RayHit {
+            distance: self.distance,
+            point: matrix.transform_point(self.point),
+            normal: normal.normalize()
+        };
+    }
+}
@@ -0,0 +1,103 @@
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Point3, Vector3};
+
+use engine::core::{Camera, Projection};
+
+use common::{assert_near, assert_near_within};
+
+/// Looking straight down at the ground from `y = 50`, with `-z` at the top of the screen.
+fn minimap() -> Camera {
+    let mut camera = Camera::new();
+    camera.orthographic(-20.0, 20.0, -20.0, 20.0, 1.0, 100.0);
+    camera.look_at(Point3::new(0.0, 50.0, 0.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+    return camera;
+}
+
+#[test]
+fn projection_keeps_its_parameters() {
+    let mut camera = Camera::new();
+    camera.perspective(45.0, 4.0 / 3.0, 0.1, 100.0);
+
+    assert_eq!(camera.projection(), Projection::Perspective { fovy: 45.0, aspect: 4.0 / 3.0, near: 0.1, far: 100.0 });
+    assert_eq!(camera.projection().clip_planes(), Some((0.1, 100.0)));
+
+    camera.set_viewport_size(1920, 1080);
+    assert_eq!(camera.projection(), Projection::Perspective { fovy: 45.0, aspect: 16.0 / 9.0, near: 0.1, far: 100.0 });
+    assert_eq!(camera.proj_matrix(), camera.projection().matrix());
+
+    // Minimized window.
+    camera.set_viewport_size(0, 0);
+    assert_eq!(camera.projection(), Projection::Perspective { fovy: 45.0, aspect: 16.0 / 9.0, near: 0.1, far: 100.0 });
+
+    let matrices = Camera::from_matrices(camera.view_matrix(), camera.proj_matrix());
+    assert_eq!(matrices.projection().clip_planes(), None);
+}
+
+#[test]
+fn orthographic_aspect_keeps_height_and_center() {
+    let mut camera = Camera::new();
+    camera.orthographic(0.0, 800.0, 0.0, 600.0, -1.0, 1.0);
+    camera.set_aspect(2.0);
+
+    assert_eq!(camera.projection(), Projection::Orthographic {
+        left: -200.0, right: 1000.0, bottom: 0.0, top: 600.0, near: -1.0, far: 1.0
+    });
+}
+
+#[test]
+fn orientation_getters() {
+    let mut camera = Camera::new();
+    camera.look_at(Point3::new(0.0, 0.0, 10.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+
+    assert_near(camera.position(), Point3::new(0.0, 0.0, 10.0));
+    assert_near(camera.forward(), Vector3::new(0.0, 0.0, -1.0));
+    assert_near(camera.up(), Vector3::new(0.0, 1.0, 0.0));
+    assert_near(camera.right(), Vector3::new(1.0, 0.0, 0.0));
+
+    let camera = minimap();
+    assert_near(camera.position(), Point3::new(0.0, 50.0, 0.0));
+    assert_near(camera.forward(), Vector3::new(0.0, -1.0, 0.0));
+    assert_near(camera.up(), Vector3::new(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn project_is_the_inverse_of_unproject() {
+    let mut camera = Camera::new();
+    camera.perspective(60.0, 4.0 / 3.0, 0.1, 100.0);
+    camera.look_at(Point3::new(4.0, 3.0, 6.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+
+    let point = Point3::new(1.0, 0.5, -2.0);
+    let screen = camera.project(point, 800.0, 600.0).unwrap();
+    assert!(screen.z > 0.0 && screen.z < 1.0);
+    // The depth loses precision through the inverse projection.
+    assert_near_within(camera.unproject(screen.x, screen.y, screen.z, 800.0, 600.0).unwrap(), point, 1e-4);
+
+    // The center of the view.
+    let screen = camera.project(Point3::new(0.0, 0.0, 0.0), 800.0, 600.0).unwrap();
+    assert!((screen.x - 400.0).abs() < 1e-3 && (screen.y - 300.0).abs() < 1e-3);
+
+    // Behind the camera.
+    assert_eq!(camera.project(Point3::new(8.0, 6.0, 12.0), 800.0, 600.0), None);
+}
+
+#[test]
+fn orthographic_screen_to_ground() {
+    let camera = minimap();
+
+    assert_near(camera.screen_to_ground(100.0, 100.0, 200.0, 200.0, 0.0).unwrap(), Point3::new(0.0, 0.0, 0.0));
+    // The top left corner.
+    assert_near(camera.screen_to_ground(0.0, 0.0, 200.0, 200.0, 0.0).unwrap(), Point3::new(-20.0, 0.0, -20.0));
+    assert_near(camera.screen_to_ground(150.0, 100.0, 200.0, 200.0, 2.0).unwrap(), Point3::new(10.0, 2.0, 0.0));
+
+    let screen = camera.project(Point3::new(10.0, 0.0, 5.0), 200.0, 200.0).unwrap();
+    assert_near(screen, Point3::new(150.0, 125.0, screen.z));
+
+    // All rays are parallel.
+    let a = camera.screen_ray(0.0, 0.0, 200.0, 200.0).unwrap();
+    let b = camera.screen_ray(200.0, 200.0, 200.0, 200.0).unwrap();
+    assert_near(a.direction, b.direction);
+}
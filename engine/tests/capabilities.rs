@@ -0,0 +1,114 @@
+extern crate engine;
+
+mod common;
+
+use engine::gliw::{
+    gl, AttribFloatFormat, AttribIntFormat, Buffer, BufferType, Capabilities, MockBackend,
+    Program, ProgramBuilder, Shader, ShaderType, Texture, TextureType, Vao, VertexAttrib
+};
+
+use common::mock;
+
+use std::ptr;
+use std::rc::Rc;
+
+#[test]
+fn capabilities_are_queried_from_the_backend() {
+    let mock = mock();
+    mock.add_extension("GL_ARB_bindless_texture");
+
+    let caps = Capabilities::current();
+    assert_eq!(caps.version, (4, 5));
+    assert_eq!(caps.glsl_version, (4, 50));
+    assert_eq!(caps.limits.max_vertex_attribs, 16);
+    assert_eq!(caps.limits.max_compute_work_group_count, [65535; 3]);
+    assert!(caps.has_extension("GL_ARB_bindless_texture"));
+    assert!(caps.supports_compute());
+}
+
+#[test]
+fn limits_of_unsupported_features_are_not_queried() {
+    let mock = mock();
+    mock.set_integer(gl::MAJOR_VERSION, 3);
+    mock.set_integer(gl::MINOR_VERSION, 3);
+    Capabilities::invalidate();
+
+    let caps = Capabilities::current();
+    let limits = &caps.limits;
+    assert_eq!(limits.max_patch_vertices, 0);
+    assert_eq!(limits.max_compute_work_group_count, [0; 3]);
+    assert_eq!(limits.max_texture_size, 16384);
+}
+
+#[test]
+fn set_backend_invalidates_the_cache() {
+    let first = mock();
+    first.set_integer(gl::MAX_TEXTURE_SIZE, 1024);
+    Capabilities::invalidate();
+    assert_eq!(Capabilities::current().limits.max_texture_size, 1024);
+
+    // Cached until invalidated.
+    first.set_integer(gl::MAX_TEXTURE_SIZE, 2048);
+    assert_eq!(Capabilities::current().limits.max_texture_size, 1024);
+
+    let second = mock();
+    assert_eq!(Capabilities::current().limits.max_texture_size, 16384);
+
+    second.clear();
+    Capabilities::current();
+    assert_eq!(second.calls_named("GetIntegerv").len(), 0);
+}
+
+fn texture_units_mock() -> (Rc<MockBackend>, Texture, Rc<Program>) {
+    let mock = mock();
+    mock.set_integer(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 4);
+
+    let vs = Shader::new(ShaderType::Vertex, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_vs(&vs).link().unwrap();
+
+    return (mock, Texture::new(TextureType::Tex2D), program);
+}
+
+#[test]
+fn textures_are_passed_to_units_within_the_limit() {
+    let (mock, texture, program) = texture_units_mock();
+
+    texture.pass_to(&program, "sampler", 3);
+
+    let units = mock.calls_named("ActiveTexture");
+    assert_eq!(units[0].int(0), (gl::TEXTURE0 + 3) as i64);
+}
+
+#[test]
+#[should_panic]
+fn textures_past_the_texture_unit_limit_panic() {
+    let (_mock, texture, program) = texture_units_mock();
+    texture.pass_to(&program, "sampler", 4);
+}
+
+fn vertex_attribs_mock() -> (Vao, Buffer) {
+    mock().set_integer(gl::MAX_VERTEX_ATTRIBS, 4);
+    return (Vao::new(), Buffer::new(BufferType::Array));
+}
+
+#[test]
+fn vertex_attribs_within_the_limit() {
+    let (vao, vbo) = vertex_attribs_mock();
+
+    VertexAttrib::new(3).data_float_format(&vao, &vbo, AttribFloatFormat::Float(3), 0, ptr::null());
+    VertexAttrib::new(3).data_int_format(&vao, &vbo, AttribIntFormat::Int(1), 0, ptr::null());
+}
+
+#[test]
+#[should_panic]
+fn float_vertex_attribs_past_the_limit_panic() {
+    let (vao, vbo) = vertex_attribs_mock();
+    VertexAttrib::new(4).data_float_format(&vao, &vbo, AttribFloatFormat::Float(3), 0, ptr::null());
+}
+
+#[test]
+#[should_panic]
+fn int_vertex_attribs_past_the_limit_panic() {
+    let (vao, vbo) = vertex_attribs_mock();
+    VertexAttrib::new(4).data_int_format(&vao, &vbo, AttribIntFormat::Int(1), 0, ptr::null());
+}
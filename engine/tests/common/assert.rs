@@ -0,0 +1,16 @@
+extern crate cgmath;
+
+use self::cgmath::{InnerSpace, Vector3};
+
+use std::fmt::Debug;
+use std::ops::Sub;
+
+/// Asserts that two points or two vectors are within `1e-5` of each other.
+pub fn assert_near<T: Sub<Output = Vector3<f32>> + Copy + Debug>(a: T, b: T) {
+    assert_near_within(a, b, 1e-5);
+}
+
+/// Same as `assert_near` with a `tolerance`, e.g. for values through an inverse matrix.
+pub fn assert_near_within<T: Sub<Output = Vector3<f32>> + Copy + Debug>(a: T, b: T, tolerance: f32) {
+    assert!((a - b).magnitude() < tolerance, "{:?} != {:?}", a, b);
+}
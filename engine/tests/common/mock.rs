@@ -0,0 +1,10 @@
+use engine::gliw::{self, MockBackend};
+
+use std::rc::Rc;
+
+/// Records the OpenGL calls of the current thread instead of executing them.
+pub fn mock() -> Rc<MockBackend> {
+    let mock = Rc::new(MockBackend::new());
+    gliw::set_backend(mock.clone());
+    return mock;
+}
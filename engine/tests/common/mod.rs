@@ -1,5 +1,14 @@
 //! Common utility functions used by unit tests
 
+// Each test crate uses only some of them.
+#![allow(dead_code, unused_imports)]
+
+mod assert;
 mod gl_util;
+mod mock;
+mod scene;
 
-pub use gl_util::init_gl;
+pub use self::assert::{assert_near, assert_near_within};
+pub use self::gl_util::init_gl;
+pub use self::mock::mock;
+pub use self::scene::{Marker, camera, camera_at, drawn_ids, gbuffer_ids};
@@ -0,0 +1,115 @@
+extern crate cgmath;
+
+use self::cgmath::{Matrix4, Point3, Vector3};
+
+use engine::core::{Camera, Renderable};
+use engine::gliw::{gl, MockBackend};
+use engine::math::{Aabb, Bounds};
+
+/// Renderable which identifies itself by issuing a single point draw with `id` as the first vertex,
+/// or a line draw in the geometry pass.
+pub struct Marker {
+    pub id: i32,
+    pub translation: Vector3<f32>,
+    pub priority: u32,
+    pub transparent: bool,
+    // Bounded by a unit box around the translation.
+    pub bounded: bool
+}
+
+impl Marker {
+    /// An opaque marker at the origin, without bounds.
+    pub fn new(id: i32) -> Marker {
+        return Marker {
+            id: id,
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            priority: 0,
+            transparent: false,
+            bounded: false
+        };
+    }
+
+    pub fn at(mut self, x: f32, y: f32, z: f32) -> Marker {
+        self.translation = Vector3::new(x, y, z);
+        return self;
+    }
+
+    pub fn with_priority(mut self, priority: u32) -> Marker {
+        self.priority = priority;
+        return self;
+    }
+
+    pub fn transparent(mut self) -> Marker {
+        self.transparent = true;
+        return self;
+    }
+
+    /// Bounded by a unit box, so that it can be culled.
+    pub fn bounded(mut self) -> Marker {
+        self.bounded = true;
+        return self;
+    }
+}
+
+impl Renderable for Marker {
+    fn priority(&self) -> u32 {
+        return self.priority;
+    }
+
+    fn is_transparent(&self) -> bool {
+        return self.transparent;
+    }
+
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_translation(self.translation);
+    }
+
+    fn draw(&self, _: Matrix4<f32>, _: &Camera) {
+        unsafe { gl::DrawArrays(gl::POINTS, self.id, 1); }
+    }
+
+    fn has_gbuffer_pass(&self) -> bool {
+        return true;
+    }
+
+    fn draw_gbuffer(&self, _: Matrix4<f32>, _: &Camera) {
+        unsafe { gl::DrawArrays(gl::LINES, self.id, 2); }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        if !self.bounded {
+            return None;
+        }
+
+        return Some(Bounds::Box(Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5))));
+    }
+}
+
+/// Get the ids of the markers drawn in order.
+pub fn drawn_ids(mock: &MockBackend) -> Vec<i64> {
+    return mock.calls_named("DrawArrays").iter()
+        .filter(|call| call.int(0) == gl::POINTS as i64)
+        .map(|call| call.int(1))
+        .collect();
+}
+
+/// Looking down the negative z axis from `z` with a 90 degree field of view.
+pub fn camera_at(z: f32) -> Camera {
+    let mut camera = Camera::new();
+    camera.perspective(90.0, 1.0, 0.1, 100.0);
+    camera.look_at(Point3::new(0.0, 0.0, z), Point3::new(0.0, 0.0, z - 1.0), Vector3::new(0.0, 1.0, 0.0));
+    return camera;
+}
+
+/// Looking at the origin from `z = 10`, so a higher `z` is closer.
+pub fn camera() -> Camera {
+    return camera_at(10.0);
+}
+
+/// Get the ids of the markers drawn in the geometry pass in order.
+pub fn gbuffer_ids(mock: &MockBackend) -> Vec<i64> {
+    return mock.calls_named("DrawArrays").iter()
+        .filter(|call| call.int(0) == gl::LINES as i64)
+        .map(|call| call.int(1))
+        .collect();
+}
@@ -0,0 +1,84 @@
+extern crate engine;
+
+mod common;
+
+use engine::gliw::{self, gl, Buffer, BufferType, BufferUsagePattern, ProgramBuilder, Shader, ShaderType};
+
+use common::mock;
+
+#[test]
+fn dispatch_ignores_errors_of_previous_calls() {
+    let mock = mock();
+
+    let cs = Shader::new(ShaderType::Compute, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_cs(&cs).link().unwrap();
+
+    mock.fail_on("Viewport", gl::INVALID_VALUE);
+    unsafe { gl::Viewport(0, 0, -1, -1); }
+
+    program.dispatch(4, 1, 1);
+
+    assert_eq!(mock.calls_named("DispatchCompute").len(), 1);
+}
+
+#[test]
+#[should_panic]
+fn dispatch_requires_a_compute_shader() {
+    mock();
+
+    let vs = Shader::new(ShaderType::Vertex, "<code>").unwrap();
+    let fs = Shader::new(ShaderType::Fragment, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_vs(&vs).attach_fs(&fs).link().unwrap();
+
+    program.dispatch(4, 1, 1);
+}
+
+#[test]
+#[should_panic]
+fn dispatch_checks_work_group_counts_past_i32() {
+    mock();
+
+    let cs = Shader::new(ShaderType::Compute, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_cs(&cs).link().unwrap();
+
+    program.dispatch(1, ::std::u32::MAX, 1);
+}
+
+#[test]
+fn dispatch_indirect_reads_within_the_buffer() {
+    let mock = mock();
+
+    let cs = Shader::new(ShaderType::Compute, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_cs(&cs).link().unwrap();
+    let buffer = Buffer::from_data(&[4u32, 1, 1, 8, 1, 1], BufferType::DispatchIndirect, BufferUsagePattern::StaticDraw);
+
+    program.dispatch_indirect(&buffer, 0);
+    program.dispatch_indirect(&buffer, 12);
+    assert_eq!(mock.calls_named("DispatchComputeIndirect").len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn dispatch_indirect_panics_past_the_end_of_the_buffer() {
+    mock();
+
+    let cs = Shader::new(ShaderType::Compute, "<code>").unwrap();
+    let program = ProgramBuilder::new().attach_cs(&cs).link().unwrap();
+    let buffer = Buffer::from_data(&[4u32, 1, 1, 8, 1, 1], BufferType::DispatchIndirect, BufferUsagePattern::StaticDraw);
+
+    program.dispatch_indirect(&buffer, 16);
+}
+
+#[test]
+fn require_compute_checks_the_version() {
+    mock();
+    assert!(gliw::require_compute().is_ok());
+
+    // On its own thread, since the capabilities are cached per thread.
+    let result = ::std::thread::spawn(|| {
+        mock().set_integer(gl::MINOR_VERSION, 1);
+        gliw::require_compute()
+    }).join().unwrap();
+
+    assert!(result.is_err());
+}
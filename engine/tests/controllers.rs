@@ -0,0 +1,195 @@
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Point3, Vector3, InnerSpace};
+
+use engine::core::{
+    Camera, InputState, Control, CameraController,
+    RtsController, OrbitController, FollowController, FreeFlyController
+};
+use engine::math::Aabb;
+
+use common::assert_near;
+
+fn input() -> InputState {
+    let mut input = InputState::new(800, 600);
+    input.move_cursor(400.0, 300.0);
+    return input;
+}
+
+#[test]
+fn input_state_accumulates_per_frame() {
+    let mut input = InputState::new(800, 600);
+
+    input.set_control(Control::MoveForward, true);
+    input.set_control(Control::MoveBack, true);
+    assert_eq!(input.axis(Control::MoveBack, Control::MoveForward), 0.0);
+    input.set_control(Control::MoveBack, false);
+    assert_eq!(input.axis(Control::MoveBack, Control::MoveForward), 1.0);
+
+    // The first position has nothing to move from.
+    input.move_cursor(100.0, 100.0);
+    input.move_cursor(110.0, 95.0);
+    input.move_cursor(120.0, 90.0);
+    input.scroll(1.0);
+    input.scroll(2.0);
+    assert_eq!(input.cursor(), Some((120.0, 90.0)));
+    assert_eq!(input.cursor_delta(), cgmath::Vector2::new(20.0, -10.0));
+    assert_eq!(input.scroll_delta(), 3.0);
+
+    input.end_frame();
+    assert_eq!(input.cursor_delta(), cgmath::Vector2::new(0.0, 0.0));
+    assert_eq!(input.scroll_delta(), 0.0);
+    assert!(input.is_held(Control::MoveForward));
+
+    input.leave_cursor();
+    assert_eq!(input.cursor(), None);
+}
+
+#[test]
+fn rts_scrolls_relative_to_the_view() {
+    let mut camera = Camera::new();
+    let mut rts = RtsController::new(Point3::new(0.0, 0.0, 0.0));
+    rts.edge_margin = 0.0;
+
+    let mut input = input();
+    input.set_control(Control::MoveForward, true);
+    rts.update(&mut camera, &input, 0.5);
+
+    // Half a second at the default 20 units per second.
+    assert_near(rts.focus, Point3::new(0.0, 0.0, -10.0));
+    assert_near(camera.forward(), Vector3::new(0.0, -rts.pitch.to_radians().sin(), -rts.pitch.to_radians().cos()));
+    assert!(((camera.position() - rts.focus).magnitude() - 20.0).abs() < 1e-3);
+
+    // Forward is to the right of the map after turning 90 degrees right.
+    input.set_control(Control::MoveForward, false);
+    input.set_control(Control::RotateRight, true);
+    rts.update(&mut camera, &input, 1.0);
+    assert!((rts.yaw + 90.0).abs() < 1e-3);
+
+    input.set_control(Control::RotateRight, false);
+    input.set_control(Control::MoveForward, true);
+    rts.update(&mut camera, &input, 0.5);
+    assert_near(rts.focus, Point3::new(10.0, 0.0, -10.0));
+}
+
+#[test]
+fn rts_edge_scrolling_zoom_and_bounds() {
+    let mut camera = Camera::new();
+    let mut rts = RtsController::new(Point3::new(0.0, 0.0, 0.0));
+    rts.bounds = Some(Aabb::new(Point3::new(-5.0, 0.0, -5.0), Point3::new(5.0, 0.0, 5.0)));
+
+    let mut input = input();
+    input.move_cursor(799.0, 0.0);
+    input.end_frame();
+
+    // Top right corner, clamped to the map.
+    rts.update(&mut camera, &input, 1.0);
+    assert_near(rts.focus, Point3::new(5.0, 0.0, -5.0));
+
+    input.move_cursor(400.0, 300.0);
+    input.scroll(100.0);
+    rts.update(&mut camera, &input, 0.1);
+    assert_eq!(rts.distance, rts.min_distance);
+    assert_near(rts.focus, Point3::new(5.0, 0.0, -5.0));
+
+    input.end_frame();
+    input.scroll(-1.0);
+    rts.update(&mut camera, &input, 0.1);
+    assert!((rts.distance - rts.min_distance / 0.9).abs() < 1e-3);
+}
+
+#[test]
+fn orbit_rotates_around_the_target() {
+    let mut camera = Camera::new();
+    let target = Point3::new(1.0, 0.0, 2.0);
+    let mut orbit = OrbitController::new(target, 10.0);
+    orbit.pitch = 0.0;
+    orbit.auto_rotate = 90.0;
+
+    let mut input = input();
+    orbit.update(&mut camera, &input, 1.0);
+
+    // From the +z side to the +x side.
+    assert_near(camera.position(), Point3::new(11.0, 0.0, 2.0));
+    assert_near(camera.forward(), Vector3::new(-1.0, 0.0, 0.0));
+
+    orbit.auto_rotate = 0.0;
+    input.set_control(Control::Look, true);
+    input.move_cursor(400.0, 1000.0);
+    orbit.update(&mut camera, &input, 0.0);
+
+    // Dragging down looks from above, up to the limit.
+    assert_eq!(orbit.pitch, orbit.max_pitch);
+    assert!(((camera.position() - target).magnitude() - 10.0).abs() < 1e-3);
+    assert!(camera.position().y > 9.0);
+}
+
+#[test]
+fn follow_trails_the_target_and_settles() {
+    let mut camera = Camera::new();
+    let mut follow = FollowController::new();
+    let input = input();
+
+    follow.set_target(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+    follow.update(&mut camera, &input, 0.016);
+
+    // Starts at the rest position.
+    assert_near(camera.position(), Point3::new(0.0, 4.0, 8.0));
+
+    // The tank turns to +x and drives on, ignoring the slope.
+    follow.set_target(Point3::new(5.0, 0.0, 0.0), Vector3::new(1.0, 0.5, 0.0));
+    follow.update(&mut camera, &input, 0.016);
+
+    let rest = Point3::new(-3.0, 4.0, 0.0);
+    let lagging = (camera.position() - rest).magnitude();
+    assert!(lagging > 1.0);
+
+    let mut previous = lagging;
+    for _ in 0..200 {
+        follow.update(&mut camera, &input, 0.016);
+
+        // Critically damped, so it never overshoots.
+        let distance = (camera.position() - rest).magnitude();
+        assert!(distance <= previous + 1e-4);
+        previous = distance;
+    }
+    assert_near(camera.position(), rest);
+
+    follow.set_target(Point3::new(50.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    follow.snap();
+    follow.update(&mut camera, &input, 0.016);
+    assert_near(camera.position(), Point3::new(42.0, 4.0, 0.0));
+}
+
+#[test]
+fn free_fly_moves_along_the_view() {
+    let mut camera = Camera::new();
+    camera.look_at(Point3::new(0.0, 0.0, 10.0), Point3::new(10.0, 0.0, 10.0), Vector3::new(0.0, 1.0, 0.0));
+
+    let mut fly = FreeFlyController::from_camera(&camera);
+    assert_near(fly.forward(), Vector3::new(1.0, 0.0, 0.0));
+
+    let mut input = input();
+    input.set_control(Control::MoveForward, true);
+    input.set_control(Control::Boost, true);
+    fly.update(&mut camera, &input, 0.5);
+
+    assert_near(camera.position(), Point3::new(10.0, 0.0, 10.0));
+    assert_near(camera.forward(), Vector3::new(1.0, 0.0, 0.0));
+
+    // Looking up by moving the cursor up.
+    input.set_control(Control::MoveForward, false);
+    input.set_control(Control::Boost, false);
+    input.set_control(Control::Look, true);
+    input.move_cursor(400.0, 300.0 - 45.0 / fly.look_sensitivity);
+    fly.update(&mut camera, &input, 0.0);
+    assert!((fly.pitch - 45.0).abs() < 1e-3);
+
+    input.end_frame();
+    input.set_control(Control::MoveUp, true);
+    fly.update(&mut camera, &input, 1.0);
+    assert_near(camera.position(), Point3::new(10.0, 5.0, 10.0));
+}
@@ -0,0 +1,144 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Matrix4, Point3, Vector3, Deg};
+
+use engine::core::{Camera, Composition, Renderable, Scene, CullStats};
+use engine::math::{Aabb, BoundingSphere, Bounds, Frustum};
+
+use common::{Marker, camera_at, drawn_ids, mock};
+
+use std::f32;
+
+#[test]
+fn frustum_planes_from_the_camera() {
+    let frustum = Frustum::from_matrix(&camera_at(0.0).vp_matrix());
+
+    assert!(frustum.contains(Point3::new(0.0, 0.0, -10.0)));
+    assert!(!frustum.contains(Point3::new(0.0, 0.0, 10.0)));
+    assert!(!frustum.contains(Point3::new(0.0, 0.0, -200.0)));
+    assert!(!frustum.contains(Point3::new(11.0, 0.0, -10.0)));
+
+    // Partially inside.
+    assert!(frustum.intersects_sphere(&BoundingSphere::new(Point3::new(10.5, 0.0, -10.0), 1.0)));
+    assert!(frustum.intersects_aabb(&Aabb::new(Point3::new(9.5, -1.0, -11.0), Point3::new(12.0, 1.0, -9.0))));
+    assert!(!frustum.intersects_aabb(&Aabb::new(Point3::new(-1.0, -1.0, 1.0), Point3::new(1.0, 1.0, 2.0))));
+}
+
+#[test]
+fn frustum_corners() {
+    let corners = Frustum::from_matrix(&camera_at(0.0).vp_matrix()).corners();
+
+    // Near, then far, from the bottom left.
+    let expected: [Point3<f32>; 8] = [
+        Point3::new(-0.1, -0.1, -0.1), Point3::new(0.1, -0.1, -0.1),
+        Point3::new(-0.1, 0.1, -0.1), Point3::new(0.1, 0.1, -0.1),
+        Point3::new(-100.0, -100.0, -100.0), Point3::new(100.0, -100.0, -100.0),
+        Point3::new(-100.0, 100.0, -100.0), Point3::new(100.0, 100.0, -100.0)
+    ];
+
+    for (corner, expected) in corners.iter().zip(expected.iter()) {
+        let tolerance = 1e-3 * expected.z.abs();
+        assert!((corner.x - expected.x).abs() < tolerance, "{:?} != {:?}", corner, expected);
+        assert!((corner.y - expected.y).abs() < tolerance, "{:?} != {:?}", corner, expected);
+        assert!((corner.z - expected.z).abs() < tolerance, "{:?} != {:?}", corner, expected);
+    }
+
+    // Unbounded boxes are still inside.
+    let infinite = f32::INFINITY;
+    let frustum = Frustum::from_matrix(&camera_at(0.0).vp_matrix());
+    assert!(frustum.intersects_aabb(&Aabb::new(Point3::new(-infinite, 0.0, -infinite), Point3::new(infinite, 1.0, infinite))));
+}
+
+#[test]
+fn transformed_bounds_enclose_the_volume() {
+    let aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    let matrix = Matrix4::from_translation(Vector3::new(5.0, 0.0, 0.0)) *
+        Matrix4::from_angle_y(Deg::new(45.0)) *
+        Matrix4::from_scale(2.0);
+
+    let transformed = aabb.transform(&matrix);
+    let half_diagonal = 2.0 * 2.0f32.sqrt();
+    assert!((transformed.max.x - (5.0 + half_diagonal)).abs() < 1e-5);
+    assert!((transformed.max.y - 2.0).abs() < 1e-5);
+    assert!((transformed.min.z + half_diagonal).abs() < 1e-5);
+
+    let sphere = BoundingSphere::new(Point3::new(1.0, 0.0, 0.0), 1.0).transform(&matrix);
+    assert_eq!(sphere.radius, 2.0);
+
+    let union = Bounds::Sphere(BoundingSphere::new(Point3::new(0.0, 0.0, 0.0), 1.0))
+        .union(&Bounds::Box(Aabb::new(Point3::new(2.0, 2.0, 2.0), Point3::new(3.0, 3.0, 3.0))));
+    assert_eq!(union.aabb(), Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(3.0, 3.0, 3.0)));
+}
+
+#[test]
+fn scene_skips_renderables_outside_the_frustum() {
+    let mock = mock();
+
+    let ahead = wrap!(Marker::new(1).at(0.0, 0.0, -10.0).bounded());
+    let behind = wrap!(Marker::new(2).at(0.0, 0.0, 10.0).bounded());
+    let aside = wrap!(Marker::new(3).at(30.0, 0.0, -10.0).bounded());
+    let edge = wrap!(Marker::new(4).at(10.4, 0.0, -10.0).bounded());
+
+    let mut scene = Scene::new(camera_at(0.0));
+    scene.add(Scene::node(&ahead));
+    scene.add(Scene::node(&behind));
+    scene.add(Scene::node(&aside));
+    scene.add(Scene::node(&edge));
+    scene.draw();
+
+    assert_eq!(drawn_ids(&mock), vec![1, 4]);
+    assert_eq!(scene.cull_stats(), CullStats { drawn: 2, culled: 2 });
+
+    // Moving into view.
+    aside.borrow_mut().translation = Vector3::new(-3.0, 0.0, -10.0);
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 3, 4]);
+
+    scene.set_culling(false);
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock).len(), 4);
+    assert_eq!(scene.cull_stats(), CullStats { drawn: 4, culled: 0 });
+}
+
+#[test]
+fn compositions_are_culled_with_their_children() {
+    let mock = mock();
+
+    // The parent is behind the camera but its child is ahead.
+    let parent = wrap!(Composition::new(Marker::new(1).at(0.0, 0.0, 5.0).bounded()));
+    let child = wrap!(Marker::new(2).at(0.0, 0.0, -15.0).bounded());
+    let mut handle = parent.borrow_mut().attach(Scene::node(&child));
+
+    let mut scene = Scene::new(camera_at(0.0));
+    scene.add(Scene::node(&parent));
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 2]);
+
+    let bounds = parent.borrow().world_bounds().unwrap().aabb();
+    assert_eq!(bounds, Aabb::new(Point3::new(-0.5, -0.5, -10.5), Point3::new(0.5, 0.5, 5.5)));
+
+    // Hidden children don't count.
+    handle.set_visible(false);
+    mock.clear();
+    scene.draw();
+    assert!(drawn_ids(&mock).is_empty());
+    assert_eq!(scene.cull_stats(), CullStats { drawn: 0, culled: 1 });
+
+    // Unbounded children make the composition unbounded.
+    handle.remove();
+    struct Unbounded;
+    impl Renderable for Unbounded {
+        fn model_matrix(&self) -> Matrix4<f32> { return Matrix4::from_scale(1.0); }
+        fn draw(&self, _: Matrix4<f32>, _: &Camera) {}
+    }
+    let unbounded = wrap!(Unbounded);
+    handle = parent.borrow_mut().attach(Scene::node(&unbounded));
+    assert!(parent.borrow().contains(&handle));
+    assert!(parent.borrow().world_bounds().is_none());
+}
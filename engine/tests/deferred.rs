@@ -0,0 +1,158 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Matrix4, Point3, Vector3};
+
+use engine::core::{
+    Camera, DeferredRenderer, DirectionalLight, PointLight, RenderPath, Renderable, Scene, ShadowSettings
+};
+use engine::gliw::{gl, GlCall};
+
+use common::mock;
+
+use std::rc::Rc;
+
+/// Identifies its draws by their primitive: points when lit, lines in the G-buffer
+/// and line strips in the shadow pass.
+struct Marker {
+    id: i32,
+    gbuffer: bool
+}
+
+impl Renderable for Marker {
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_scale(1.0);
+    }
+
+    fn draw(&self, _: Matrix4<f32>, _: &Camera) {
+        unsafe { gl::DrawArrays(gl::POINTS, self.id, 1); }
+    }
+
+    fn has_gbuffer_pass(&self) -> bool {
+        return self.gbuffer;
+    }
+
+    fn draw_gbuffer(&self, _: Matrix4<f32>, _: &Camera) {
+        unsafe { gl::DrawArrays(gl::LINES, self.id, 2); }
+    }
+
+    fn draw_shadow(&self, _: Matrix4<f32>, _: &Matrix4<f32>) {
+        unsafe { gl::DrawArrays(gl::LINE_STRIP, self.id, 2); }
+    }
+}
+
+fn camera() -> Camera {
+    let mut camera = Camera::new();
+    camera.perspective(60.0, 4.0 / 3.0, 0.1, 100.0);
+    camera.look_at(Point3::new(0.0, 2.0, 10.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    return camera;
+}
+
+/// The (internal format, width, height) of each `glTexImage2D` call.
+fn allocations(calls: &[GlCall]) -> Vec<(i64, i64, i64)> {
+    return calls.iter()
+        .filter(|call| call.name == "TexImage2D")
+        .map(|call| (call.int(2), call.int(3), call.int(4)))
+        .collect();
+}
+
+fn draw_position(calls: &[GlCall], mode: u32, id: i64) -> usize {
+    return calls.iter()
+        .position(|call| call.name == "DrawArrays" && call.int(0) == mode as i64 && call.int(1) == id)
+        .expect("draw not found");
+}
+
+#[test]
+fn allocates_and_attaches_the_gbuffer() {
+    let mock = mock();
+
+    DeferredRenderer::new(800, 600).unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(allocations(&calls), vec![
+        (gl::RGBA8 as i64, 800, 600),
+        (gl::RGBA16F as i64, 800, 600),
+        (gl::RG8 as i64, 800, 600),
+        (gl::RGBA16F as i64, 800, 600),
+        (gl::DEPTH_COMPONENT24 as i64, 800, 600),
+        (gl::RGBA16F as i64, 800, 600)
+    ]);
+
+    let attachments: Vec<i64> = mock.calls_named("FramebufferTexture2D").iter().map(|call| call.int(1)).collect();
+    assert_eq!(attachments, vec![
+        gl::COLOR_ATTACHMENT0 as i64,
+        gl::COLOR_ATTACHMENT1 as i64,
+        gl::COLOR_ATTACHMENT2 as i64,
+        gl::COLOR_ATTACHMENT3 as i64,
+        gl::DEPTH_ATTACHMENT as i64,
+        // The light accumulation buffer.
+        gl::COLOR_ATTACHMENT0 as i64
+    ]);
+
+    // Albedo, normal, material and emissive.
+    assert_eq!(mock.calls_named("DrawBuffers")[0].int(0), 4);
+    assert_eq!(mock.calls_named("CheckFramebufferStatus").len(), 2);
+}
+
+#[test]
+fn runs_the_passes_in_order() {
+    let mock = mock();
+
+    let mut renderer = DeferredRenderer::new(800, 600).unwrap();
+    renderer.enable_shadows(ShadowSettings::new()).unwrap();
+
+    let deferred = wrap!(Marker { id: 1, gbuffer: true });
+    let forward = wrap!(Marker { id: 2, gbuffer: false });
+    let light = wrap!(PointLight::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 1.0, 1.0), 3.0));
+
+    let mut scene = Scene::new(camera());
+    scene.set_render_path(RenderPath::Deferred(renderer));
+    scene.set_directional_light(Some(DirectionalLight::new(Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 1.0, 1.0))));
+    scene.add_light(Rc::downgrade(&light));
+    scene.add(Scene::node(&forward));
+    scene.add(Scene::node(&deferred));
+
+    mock.clear();
+    scene.draw();
+
+    let calls = mock.calls();
+    let shadow = draw_position(&calls, gl::LINE_STRIP, 1);
+    let geometry = draw_position(&calls, gl::LINES, 1);
+    let light_volume = calls.iter().position(|call| call.name == "DrawElements").unwrap();
+    let composition = calls.iter().rposition(|call| call.name == "DrawArrays" && call.int(0) == gl::TRIANGLES as i64).unwrap();
+    let forward_draw = draw_position(&calls, gl::POINTS, 2);
+
+    assert!(shadow < geometry);
+    assert!(geometry < light_volume);
+    assert!(light_volume < composition);
+    assert!(composition < forward_draw);
+
+    // Without a G-buffer pass the renderable is only drawn forward, and not left with zero normals.
+    assert!(!calls.iter().any(|call| call.name == "DrawArrays" && call.int(0) == gl::LINES as i64 && call.int(1) == 2));
+    assert!(!calls.iter().any(|call| call.name == "DrawArrays" && call.int(0) == gl::POINTS as i64 && call.int(1) == 1));
+}
+
+#[test]
+fn resize_reallocates_the_targets() {
+    let mock = mock();
+
+    let mut renderer = DeferredRenderer::new(800, 600).unwrap();
+
+    mock.clear();
+    renderer.resize(1280, 720);
+
+    let sizes: Vec<(i64, i64)> = allocations(&mock.calls()).iter().map(|&(_, width, height)| (width, height)).collect();
+    assert_eq!(sizes, vec![(1280, 720); 6]);
+
+    let mut scene = Scene::new(camera());
+    scene.set_render_path(RenderPath::Deferred(renderer));
+
+    mock.clear();
+    scene.draw();
+
+    let viewport = &mock.calls_named("Viewport")[0];
+    assert_eq!((viewport.int(2), viewport.int(3)), (1280, 720));
+}
@@ -0,0 +1,356 @@
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Matrix4, SquareMatrix, Vector3, Quaternion};
+
+use engine::core::{
+    Camera, Scene, Renderable, GltfAsset,
+    Channel, AnimationPath, Interpolation,
+    Image, ImageFormat
+};
+
+use common::mock;
+
+use std::env;
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in values {
+        let bits: u32 = unsafe { std::mem::transmute(*value) };
+        bytes.extend_from_slice(&[bits as u8, (bits >> 8) as u8, (bits >> 16) as u8, (bits >> 24) as u8]);
+    }
+    return bytes;
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+
+    let mut bin = bin.to_vec();
+    while bin.len() % 4 != 0 {
+        bin.push(0);
+    }
+
+    let mut data = b"glTF".to_vec();
+    push_u32(&mut data, 2);
+    push_u32(&mut data, (12 + 8 + json.len() + 8 + bin.len()) as u32);
+    push_u32(&mut data, json.len() as u32);
+    push_u32(&mut data, 0x4E4F534A);
+    data.extend_from_slice(&json);
+    push_u32(&mut data, bin.len() as u32);
+    push_u32(&mut data, 0x004E4942);
+    data.extend_from_slice(&bin);
+    return data;
+}
+
+fn base64(data: &[u8]) -> String {
+    let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+
+    for chunk in data.chunks(3) {
+        let bits = (chunk[0] as u32) << 16 |
+            (*chunk.get(1).unwrap_or(&0) as u32) << 8 |
+            *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(alphabet[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    return encoded;
+}
+
+/// A triangle drawn by a parent and a scaled child node, with a translation and a rotation animation.
+fn triangle_glb() -> Vec<u8> {
+    return triangle_glb_with_times([0.0, 1.0]);
+}
+
+fn triangle_glb_with_times(times: [f32; 2]) -> Vec<u8> {
+    let mut bin = floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+    // Indices, padded to 4 bytes.
+    bin.extend_from_slice(&[0, 0, 1, 0, 2, 0, 0, 0]);
+    // Keyframe times, translations and rotations.
+    bin.extend_from_slice(&floats(&times));
+    bin.extend_from_slice(&floats(&[0.0, 0.0, 0.0, 2.0, 0.0, 0.0]));
+    bin.extend_from_slice(&floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.70710677, 0.0, 0.70710677]));
+
+    let json = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "name": "parent", "mesh": 0, "translation": [0, 1, 0], "children": [1] },
+            { "name": "child", "mesh": 0, "matrix": [2,0,0,0, 0,2,0,0, 0,0,2,0, 3,0,0,1] }
+        ],
+        "meshes": [{
+            "name": "triangle",
+            "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+        }],
+        "materials": [{
+            "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0, "roughnessFactor": 0.5 },
+            "emissiveFactor": [0, 0.5, 0]
+        }],
+        "buffers": [{ "byteLength": 108 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 6 },
+            { "buffer": 0, "byteOffset": 44, "byteLength": 64 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+            { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+            { "bufferView": 2, "componentType": 5126, "count": 2, "type": "SCALAR" },
+            { "bufferView": 2, "byteOffset": 8, "componentType": 5126, "count": 2, "type": "VEC3" },
+            { "bufferView": 2, "byteOffset": 32, "componentType": 5126, "count": 2, "type": "VEC4" }
+        ],
+        "animations": [{
+            "name": "wobble",
+            "samplers": [
+                { "input": 2, "output": 3 },
+                { "input": 2, "output": 4, "interpolation": "LINEAR" }
+            ],
+            "channels": [
+                { "sampler": 0, "target": { "node": 1, "path": "translation" } },
+                { "sampler": 1, "target": { "node": 0, "path": "rotation" } }
+            ]
+        }]
+    }"#;
+
+    return glb(json, &bin);
+}
+
+#[test]
+fn glb_hierarchy_is_drawn_through_compositions() {
+    let mock = mock();
+
+    let asset = GltfAsset::from_glb(&triangle_glb(), &env::temp_dir()).unwrap();
+    assert_eq!(asset.nodes().len(), 2);
+    assert_eq!(asset.roots(), &[0]);
+
+    let child = asset.node("child").unwrap().borrow();
+    assert_eq!(child.translation, Vector3::new(3.0, 0.0, 0.0));
+    assert_eq!(child.scale, Vector3::new(2.0, 2.0, 2.0));
+
+    let mesh = child.mesh().unwrap();
+    assert_eq!(mesh.part_count(), 1);
+    assert_eq!(mesh.material(0).base_color.x, 1.0);
+    assert_eq!(mesh.material(0).emissive, Vector3::new(0.0, 0.5, 0.0));
+    assert_eq!(mesh.material(0).shininess, 30.0);
+
+    let mut scene = Scene::new(Camera::new());
+    asset.add_to(&mut scene);
+    mock.clear();
+    scene.draw();
+
+    let counts: Vec<i64> = mock.calls_named("DrawElements").iter().map(|call| call.int(1)).collect();
+    assert_eq!(counts, vec![3, 3]);
+}
+
+#[test]
+fn animations_pose_the_nodes() {
+    let _mock = mock();
+
+    let asset = GltfAsset::from_glb(&triangle_glb(), &env::temp_dir()).unwrap();
+    let wobble = asset.animation("wobble").unwrap();
+    assert_eq!(asset.animations()[wobble].duration(), 1.0);
+
+    // Loops after the duration.
+    asset.animate(wobble, 1.5);
+
+    let child = asset.node("child").unwrap().borrow();
+    assert_eq!(child.translation, Vector3::new(1.0, 0.0, 0.0));
+
+    // Half way to 90 degrees around Y.
+    let parent = asset.node("parent").unwrap().borrow();
+    let expected = Quaternion::new(0.9238795, 0.0, 0.38268343, 0.0);
+    assert!((parent.rotation.s - expected.s).abs() < 1e-5 && (parent.rotation.v.y - expected.v.y).abs() < 1e-5);
+
+    // The parent's rotation and translation are applied to the child.
+    let matrix = parent.model_matrix();
+    assert!((matrix.w.y - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn gltf_with_data_uri_and_strip() {
+    let mock = mock();
+
+    let positions = floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0]);
+    let json = format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "nodes": [{{ "mesh": 0 }}, {{ "name": "empty" }}],
+        "meshes": [{{ "primitives": [
+            {{ "attributes": {{ "POSITION": 0 }}, "mode": 5 }},
+            {{ "attributes": {{ "POSITION": 0 }}, "mode": 1 }}
+        ] }}],
+        "buffers": [{{ "byteLength": 48, "uri": "data:application/octet-stream;base64,{}" }}],
+        "bufferViews": [{{ "buffer": 0, "byteLength": 48 }}],
+        "accessors": [{{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }}]
+    }}"#, base64(&positions));
+
+    let asset = GltfAsset::from_gltf(&json, &env::temp_dir()).unwrap();
+
+    // Without scenes, every node without a parent is a root.
+    assert_eq!(asset.roots(), &[0, 1]);
+    assert!(asset.node("empty").unwrap().borrow().mesh().is_none());
+
+    // The line primitive is skipped and the strip becomes two triangles.
+    let node = asset.nodes()[0].borrow();
+    let mesh = node.mesh().unwrap();
+    assert_eq!(mesh.part_count(), 1);
+
+    mock.clear();
+    node.draw(Matrix4::identity(), &Camera::new());
+    assert_eq!(mock.calls_named("DrawElements")[0].int(1), 6);
+}
+
+#[test]
+fn node_cycles_are_rejected() {
+    let json = r#"{
+        "asset": { "version": "2.0" },
+        "nodes": [{ "children": [1] }, { "children": [0] }]
+    }"#;
+
+    let err = GltfAsset::from_gltf(json, &env::temp_dir()).err().unwrap();
+    assert!(err.contains("cycle"), "{}", err);
+
+    let err = GltfAsset::from_gltf(r#"{ "asset": { "version": "1.0" } }"#, &env::temp_dir()).err().unwrap();
+    assert!(err.contains("version"), "{}", err);
+}
+
+#[test]
+fn huge_accessor_counts_are_errors() {
+    let _mock = mock();
+
+    let accessors = [
+        r#"{ "componentType": 5126, "count": 1e30, "type": "VEC3" }"#,
+        r#"{ "componentType": 5126, "count": 1e9, "type": "VEC3" }"#,
+        r#"{ "bufferView": 0, "componentType": 5126, "count": 1e30, "type": "VEC3" }"#,
+        r#"{ "bufferView": 0, "byteOffset": 1e30, "componentType": 5126, "count": 1, "type": "VEC3" }"#
+    ];
+
+    for accessor in accessors.iter() {
+        let json = format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+            "buffers": [{{ "byteLength": 12, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAA" }}],
+            "bufferViews": [{{ "buffer": 0, "byteLength": 12, "byteStride": 12 }}],
+            "accessors": [{}]
+        }}"#, accessor);
+
+        let err = GltfAsset::from_gltf(&json, &env::temp_dir()).err().unwrap();
+        assert!(err.contains("accessor 0"), "{}", err);
+    }
+}
+
+#[test]
+fn channel_interpolations() {
+    let step = Channel {
+        node: 0,
+        path: AnimationPath::Scale,
+        interpolation: Interpolation::Step,
+        times: vec![0.0, 1.0],
+        values: vec![1.0, 1.0, 1.0, 3.0, 3.0, 3.0]
+    };
+    assert_eq!(step.sample(0.99), vec![1.0, 1.0, 1.0]);
+    assert_eq!(step.sample(1.0), vec![3.0, 3.0, 3.0]);
+    assert_eq!(step.sample(-1.0), vec![1.0, 1.0, 1.0]);
+
+    // Zero tangents ease in and out, so the middle is still half way.
+    let spline = Channel {
+        node: 0,
+        path: AnimationPath::Translation,
+        interpolation: Interpolation::CubicSpline,
+        times: vec![0.0, 2.0],
+        values: vec![
+            0.0, 0.0, 0.0,  0.0, 0.0, 0.0,  0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0,  4.0, 0.0, 0.0,  0.0, 0.0, 0.0
+        ]
+    };
+    assert_eq!(spline.sample(1.0), vec![2.0, 0.0, 0.0]);
+    assert_eq!(spline.sample(0.5)[0], 0.625);
+}
+
+/// Two materials sharing the given image as their base color texture.
+fn textured_gltf(image: &str, buffer: &[u8]) -> String {
+    return format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "materials": [
+            {{ "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": 0 }} }} }},
+            {{ "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": 0 }} }} }}
+        ],
+        "textures": [{{ "source": 0 }}],
+        "images": [{}],
+        "buffers": [{{ "byteLength": {}, "uri": "data:application/octet-stream;base64,{}" }}],
+        "bufferViews": [{{ "buffer": 0, "byteLength": {} }}]
+    }}"#, image, buffer.len(), base64(buffer), buffer.len());
+}
+
+#[test]
+fn png_textures_from_data_uris_and_buffer_views() {
+    let mock = mock();
+    let png = Image::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]).encode(ImageFormat::Png);
+
+    let data_uri = format!(r#"{{ "uri": "data:image/png;base64,{}" }}"#, base64(&png));
+    let buffer_view = r#"{ "bufferView": 0, "mimeType": "image/png" }"#;
+
+    for image in [data_uri.as_str(), buffer_view].iter() {
+        mock.clear();
+        GltfAsset::from_gltf(&textured_gltf(image, &png), &env::temp_dir()).unwrap();
+
+        // Once for both materials.
+        let uploads = mock.calls_named("TexImage2D");
+        assert_eq!(uploads.len(), 1);
+        assert_eq!((uploads[0].int(3), uploads[0].int(4)), (2, 1));
+    }
+}
+
+#[test]
+fn unsupported_images_are_untextured() {
+    let mock = mock();
+    let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+
+    let json = textured_gltf(r#"{ "bufferView": 0, "mimeType": "image/jpeg" }"#, &jpeg);
+    assert!(GltfAsset::from_gltf(&json, &env::temp_dir()).is_ok());
+    assert_eq!(mock.calls_named("TexImage2D").len(), 0);
+
+    // Missing files and corrupt data are still errors.
+    let json = textured_gltf(r#"{ "uri": "missing.png" }"#, &jpeg);
+    assert!(GltfAsset::from_gltf(&json, &env::temp_dir()).is_err());
+
+    let mut png = Image::new(1, 1, vec![0; 4]).encode(ImageFormat::Png);
+    png.truncate(20);
+    let json = textured_gltf(r#"{ "bufferView": 0, "mimeType": "image/png" }"#, &png);
+    let err = GltfAsset::from_gltf(&json, &env::temp_dir()).err().unwrap();
+    assert!(err.contains("image 0"), "{}", err);
+}
+
+#[test]
+fn deeply_nested_json_is_an_error() {
+    let depth = 100000;
+    let json = format!(r#"{{ "asset": {{ "version": "2.0" }}, "extras": {}{} }}"#, "[".repeat(depth), "]".repeat(depth));
+
+    let err = GltfAsset::from_gltf(&json, &env::temp_dir()).err().unwrap();
+    assert!(err.contains("nested"), "{}", err);
+}
+
+#[test]
+fn keyframe_times_must_increase() {
+    let _mock = mock();
+
+    for &times in [[1.0, 0.5], [0.0, 0.0], [0.0, ::std::f32::NAN], [0.0, ::std::f32::INFINITY]].iter() {
+        let err = GltfAsset::from_glb(&triangle_glb_with_times(times), &env::temp_dir()).err().unwrap();
+        assert!(err.contains("keyframe times"), "{}", err);
+    }
+}
@@ -0,0 +1,164 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Matrix4, Point3, Vector3, Vector4};
+
+use engine::core::{Camera, Composition, Cuboid, IdPicker, IdPass, Renderable, Scene};
+use engine::gliw::gl;
+
+use common::{camera, mock};
+
+fn cuboid(x: f32, z: f32) -> Cuboid {
+    return Cuboid::new(Point3::new(x, 0.0, z), Vector3::new(1.0, 1.0, 1.0), Vector4::new(1.0, 1.0, 1.0, 1.0));
+}
+
+/// Little endian bytes of the id buffer's pixels.
+fn pixels(values: &[u32]) -> Vec<u8> {
+    return values.iter().flat_map(|&value| {
+        vec![value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+    }).collect();
+}
+
+/// Never culled and not pickable by id.
+struct Backdrop;
+
+impl Renderable for Backdrop {
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_scale(1.0);
+    }
+
+    fn draw(&self, _: Matrix4<f32>, _: &Camera) {}
+}
+
+/// Draws a single point with the id pass.
+struct Marker;
+
+impl Renderable for Marker {
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_scale(1.0);
+    }
+
+    fn draw(&self, _: Matrix4<f32>, _: &Camera) {}
+
+    fn draw_id(&self, draw_space: Matrix4<f32>, pass: &IdPass) {
+        pass.set_model(draw_space * self.model_matrix());
+        unsafe { gl::DrawArrays(gl::POINTS, 0, 1); }
+    }
+}
+
+#[test]
+fn draws_the_visible_nodes_ids() {
+    let mock = mock();
+    let picker = IdPicker::new(800, 600).unwrap();
+
+    let visible = wrap!(cuboid(0.0, 0.0));
+    let behind = wrap!(cuboid(0.0, 20.0));
+    let backdrop = wrap!(Backdrop);
+    let tank = wrap!(Composition::new(Marker));
+    let turret = wrap!(Marker);
+    tank.borrow_mut().attach(Scene::node(&turret));
+
+    let mut scene = Scene::new(camera());
+    let visible_handle = scene.add(Scene::node(&visible));
+    scene.add(Scene::node(&behind));
+    scene.add(Scene::node(&backdrop));
+    let tank_handle = scene.add(Scene::node(&tank));
+
+    mock.set_integer(gl::DRAW_FRAMEBUFFER_BINDING, 7);
+    mock.clear();
+    scene.draw_ids(&picker);
+
+    // The backdrop's id is set but it draws nothing, the culled cuboid is skipped.
+    let ids: Vec<i64> = mock.calls_named("Uniform1ui").iter().map(|call| call.int(1)).collect();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[0], visible_handle.id() as i64 + 1);
+    assert_eq!(ids[2], tank_handle.id() as i64 + 1);
+
+    assert_eq!(mock.calls_named("DrawElements").len(), 1);
+    // Both the composition and its child are drawn with the composition's id.
+    assert_eq!(mock.calls_named("DrawArrays").len(), 2);
+
+    assert_eq!(mock.calls_named("ClearBufferuiv").len(), 1);
+    let binds = mock.calls_named("BindFramebuffer");
+    let restored = binds.last().unwrap();
+    assert_eq!((restored.int(0), restored.int(1)), (gl::DRAW_FRAMEBUFFER as i64, 7));
+}
+
+#[test]
+fn restores_the_depth_state_and_program() {
+    let mock = mock();
+    let picker = IdPicker::new(800, 600).unwrap();
+
+    let mut scene = Scene::new(camera());
+    let visible = wrap!(cuboid(0.0, 0.0));
+    scene.add(Scene::node(&visible));
+
+    // Depth testing is disabled by default.
+    mock.clear();
+    scene.draw_ids(&picker);
+
+    let disables = mock.calls_named("Disable");
+    assert_eq!(disables.last().map(|call| call.int(0)), Some(gl::DEPTH_TEST as i64));
+    assert_eq!(mock.calls_named("DepthFunc").last().unwrap().int(0), gl::LESS as i64);
+    assert_eq!(mock.calls_named("DepthMask").last().unwrap().int(0), gl::TRUE as i64);
+
+    // A transparent pass in progress, with its own program bound.
+    mock.set_integer(gl::DEPTH_TEST, 1);
+    mock.set_integer(gl::DEPTH_FUNC, gl::LEQUAL as i32);
+    mock.set_integer(gl::DEPTH_WRITEMASK, gl::FALSE as i32);
+    mock.set_integer(gl::CURRENT_PROGRAM, 42);
+    mock.clear();
+    scene.draw_ids(&picker);
+
+    assert!(mock.calls_named("Disable").iter().all(|call| call.int(0) != gl::DEPTH_TEST as i64));
+    assert_eq!(mock.calls_named("DepthFunc").last().unwrap().int(0), gl::LEQUAL as i64);
+    assert_eq!(mock.calls_named("DepthMask").last().unwrap().int(0), gl::FALSE as i64);
+    assert_eq!(mock.calls_named("UseProgram").last().unwrap().int(0), 42);
+}
+
+#[test]
+fn reads_ids_under_the_cursor() {
+    let mock = mock();
+    let picker = IdPicker::new(800, 600).unwrap();
+
+    mock.set_pixels(&pixels(&[43]));
+    mock.clear();
+    assert_eq!(picker.id_at(10, 20), Some(42));
+
+    // The rows are read from the bottom.
+    let read = mock.calls_named("ReadPixels");
+    assert_eq!((read[0].int(0), read[0].int(1), read[0].int(2), read[0].int(3)), (10, 579, 1, 1));
+    assert_eq!(read[0].int(4), gl::RED_INTEGER as i64);
+
+    mock.set_pixels(&pixels(&[0]));
+    assert_eq!(picker.id_at(10, 20), None);
+
+    mock.clear();
+    assert_eq!(picker.id_at(800, 20), None);
+    assert_eq!(picker.id_at(-1, 20), None);
+    assert!(mock.calls_named("ReadPixels").is_empty());
+}
+
+#[test]
+fn reads_ids_in_rectangles() {
+    let mock = mock();
+    let picker = IdPicker::new(800, 600).unwrap();
+
+    mock.set_pixels(&pixels(&[0, 6, 6, 3, 0, 6]));
+    mock.clear();
+    assert_eq!(picker.ids_in(100, 100, 3, 2), vec![2, 5]);
+
+    let read = mock.calls_named("ReadPixels");
+    assert_eq!((read[0].int(0), read[0].int(1), read[0].int(2), read[0].int(3)), (100, 498, 3, 2));
+
+    // Dragged from the bottom right corner past the window's edge.
+    mock.clear();
+    picker.ids_in(10, 5, -20, -10);
+    let read = mock.calls_named("ReadPixels");
+    assert_eq!((read[0].int(0), read[0].int(1), read[0].int(2), read[0].int(3)), (0, 595, 10, 5));
+
+    assert!(picker.ids_in(900, 100, 10, 10).is_empty());
+}
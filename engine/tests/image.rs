@@ -0,0 +1,179 @@
+extern crate engine;
+
+use engine::core::{Image, ImageFormat};
+
+fn u32_be(bytes: &[u8]) -> u32 {
+    return (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32;
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    return (bytes[3] as u32) << 24 | (bytes[2] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[0] as u32;
+}
+
+/// The (type, data, crc) of each chunk.
+fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>, u32)> {
+    let mut chunks = Vec::new();
+    let mut offset = 8;
+
+    while offset < png.len() {
+        let length = u32_be(&png[offset..]) as usize;
+        let chunk_type = String::from_utf8(png[offset + 4..offset + 8].to_vec()).unwrap();
+        let data = png[offset + 8..offset + 8 + length].to_vec();
+        let crc = u32_be(&png[offset + 8 + length..]);
+
+        chunks.push((chunk_type, data, crc));
+        offset += 12 + length;
+    }
+
+    return chunks;
+}
+
+/// 5x5 RGB of `(50 * x, 50 * y, 10 * x * y)` with the rows filtered by each filter type in turn,
+/// compressed with dynamic Huffman codes.
+const FILTERED_RGB: [u8; 122] = [
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x05, 0x08, 0x02, 0x00, 0x00, 0x00, 0x02, 0x0D, 0xB1,
+    0xB2, 0x00, 0x00, 0x00, 0x41, 0x49, 0x44, 0x41, 0x54, 0x78, 0xDA, 0x3D, 0xCA, 0xAB, 0x11, 0xC0,
+    0x30, 0x0C, 0x04, 0xD1, 0x97, 0x0F, 0x32, 0x16, 0x11, 0x31, 0x09, 0x31, 0x56, 0x4D, 0xA9, 0x29,
+    0x35, 0xA5, 0xAC, 0xD8, 0x24, 0x37, 0x4B, 0x76, 0xF6, 0xA0, 0xB8, 0x79, 0x78, 0xD9, 0xA6, 0x95,
+    0xF6, 0xB3, 0xAF, 0x5A, 0x4D, 0x85, 0xEA, 0x6A, 0x1C, 0xF3, 0x9A, 0x19, 0xB9, 0xD6, 0x33, 0xAF,
+    0x73, 0x75, 0x8D, 0xA0, 0x33, 0x3E, 0x2A, 0x39, 0x06, 0xAF, 0x34, 0x78, 0x16, 0xCB, 0x00, 0x00,
+    0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+
+/// 3x2 palette with 2 bits per pixel of red, green, blue and white, where red is half transparent.
+/// Compressed with fixed Huffman codes.
+const PALETTE: [u8; 106] = [
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x02, 0x03, 0x00, 0x00, 0x00, 0xE0, 0x1A, 0x8E,
+    0x89, 0x00, 0x00, 0x00, 0x0C, 0x50, 0x4C, 0x54, 0x45, 0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00,
+    0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFB, 0x00, 0x60, 0xF6, 0x00, 0x00, 0x00, 0x01, 0x74, 0x52, 0x4E,
+    0x53, 0x80, 0xAD, 0x5E, 0x5B, 0x46, 0x00, 0x00, 0x00, 0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x01,
+    0x63, 0x90, 0x60, 0x78, 0x02, 0x00, 0x01, 0x30, 0x00, 0xFD, 0x44, 0x4E, 0xA0, 0x20, 0x00, 0x00,
+    0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+
+#[test]
+fn png_signature_and_chunks() {
+    let png = Image::new(1, 1, vec![255, 0, 0, 255]).encode(ImageFormat::Png);
+
+    assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+
+    let chunks = chunks(&png);
+    let types: Vec<&str> = chunks.iter().map(|chunk| chunk.0.as_str()).collect();
+    assert_eq!(types, vec!["IHDR", "IDAT", "IEND"]);
+
+    // 1x1, 8 bits per channel RGBA, with the well known checksum.
+    assert_eq!(chunks[0].1, vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+    assert_eq!(chunks[0].2, 0x1F15C489);
+
+    assert!(chunks[2].1.is_empty());
+    assert_eq!(chunks[2].2, 0xAE426082);
+}
+
+#[test]
+fn png_stores_the_filtered_scanlines() {
+    let png = Image::new(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]).encode(ImageFormat::Png);
+    let idat = chunks(&png)[1].1.clone();
+
+    assert_eq!(&idat[..2], &[0x78, 0x01]);
+
+    // A single final stored block of the filter byte and the pixels.
+    assert_eq!(&idat[2..7], &[1, 9, 0, !9, 0xFF]);
+    assert_eq!(&idat[7..16], &[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+    // Adler-32 of the 9 bytes, where `a` is 1 plus their sum and `b` the sum of each running `a`.
+    assert_eq!(u32_be(&idat[16..]), 129 << 16 | 37);
+}
+
+#[test]
+fn png_splits_large_images_into_blocks() {
+    // 2 rows of 1 + 40000 bytes, more than a stored block can hold.
+    let png = Image::new(10000, 2, vec![7; 80000]).encode(ImageFormat::Png);
+    let idat = chunks(&png)[1].1.clone();
+
+    let first = (idat[3] as usize) | (idat[4] as usize) << 8;
+    assert_eq!(idat[2], 0);
+    assert_eq!(first, 0xFFFF);
+
+    let second = &idat[7 + first..];
+    assert_eq!(second[0], 1);
+    assert_eq!((second[1] as usize) | (second[2] as usize) << 8, 80002 - 0xFFFF);
+}
+
+#[test]
+fn bmp_header_and_row_padding() {
+    // Top row red and green, bottom row blue and white.
+    let image = Image::new(2, 2, vec![
+        255, 0, 0, 255,   0, 255, 0, 255,
+        0, 0, 255, 255,   255, 255, 255, 255
+    ]);
+    let bmp = image.encode(ImageFormat::Bmp);
+
+    // 2 rows of 6 bytes padded to 8.
+    assert_eq!(&bmp[..2], b"BM");
+    assert_eq!(u32_le(&bmp[2..]), 54 + 16);
+    assert_eq!(bmp.len(), 54 + 16);
+    assert_eq!(u32_le(&bmp[10..]), 54);
+    assert_eq!(u32_le(&bmp[14..]), 40);
+    assert_eq!(u32_le(&bmp[18..]), 2);
+    assert_eq!(u32_le(&bmp[22..]), 2);
+    assert_eq!(&bmp[26..30], &[1, 0, 24, 0]);
+    assert_eq!(u32_le(&bmp[34..]), 16);
+
+    // Bottom to top, as BGR.
+    assert_eq!(&bmp[54..62], &[255, 0, 0, 255, 255, 255, 0, 0]);
+    assert_eq!(&bmp[62..70], &[0, 0, 255, 0, 255, 0, 0, 0]);
+}
+
+#[test]
+fn png_round_trip() {
+    let data: Vec<u8> = (0..3 * 2 * 4).map(|i| (i * 10) as u8).collect();
+    let image = Image::new(3, 2, data.clone());
+
+    let decoded = Image::decode(&image.encode(ImageFormat::Png)).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    assert_eq!(decoded.data(), &data[..]);
+}
+
+#[test]
+fn png_filters_and_huffman_codes() {
+    let image = Image::decode(&FILTERED_RGB).unwrap();
+    assert_eq!((image.width(), image.height()), (5, 5));
+
+    for y in 0..5 {
+        for x in 0..5 {
+            assert_eq!(image.pixel(x, y), [50 * x as u8, 50 * y as u8, 10 * (x * y) as u8, 255]);
+        }
+    }
+
+    let image = Image::decode(&PALETTE).unwrap();
+    assert_eq!(image.data(), &[
+        255, 0, 0, 128,   0, 255, 0, 255,   0, 0, 255, 255,
+        255, 255, 255, 255,   0, 0, 255, 255,   0, 255, 0, 255
+    ][..]);
+}
+
+#[test]
+fn png_corruption_is_an_error() {
+    let mut corrupt = FILTERED_RGB.to_vec();
+    corrupt[50] ^= 0xFF;
+    assert!(Image::decode(&corrupt).err().unwrap().contains("checksum"));
+
+    assert!(Image::decode(&FILTERED_RGB[..60]).is_err());
+
+    // JPEG.
+    assert!(Image::decode(&[0xFF, 0xD8, 0xFF, 0xE0]).is_err());
+}
+
+#[test]
+fn bmp_round_trip() {
+    let image = Image::new(3, 2, vec![
+        255, 0, 0, 255,   0, 255, 0, 255,   0, 0, 255, 255,
+        10, 20, 30, 255,   40, 50, 60, 255,   70, 80, 90, 255
+    ]);
+
+    let decoded = Image::decode(&image.encode(ImageFormat::Bmp)).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    assert_eq!(decoded.data(), image.data());
+}
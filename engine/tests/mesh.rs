@@ -0,0 +1,167 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Vector3, Vector4};
+
+use engine::core::{obj, Camera, Image, ImageFormat, Mesh, MeshRenderable, Material, Model, Scene};
+use engine::gliw::gl;
+
+use common::mock;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Creates a fresh temporary directory for the test's files.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("engine_mesh_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+const CUBE_OBJ: &'static str = "
+    # Two faces of a cube with a material each
+    mtllib cube.mtl
+    o cube
+    v 0 0 0
+    v 1 0 0
+    v 1 1 0
+    v 0 1 0
+    v 1 0 -1
+    v 1 1 -1
+    vt 0 0
+    vt 1 0
+    vt 1 1
+    vt 0 1
+    vn 0 0 1
+    vn 1 0 0
+    usemtl red
+    f 1/1/1 2/2/1 3/3/1 4/4/1
+    usemtl glowing
+    f 2/1/2 5/2/2 6/3/2
+    f -5/1/2 -1/3/2 -4/4/2
+";
+
+const CUBE_MTL: &'static str = "
+    newmtl red
+    Kd 1.0 0.0 0.0
+    Ks 0.2 0.4 0.6
+    Ns 500
+    d 0.5
+
+    newmtl glowing
+    Kd 0.5 0.5 0.5
+    Ke 0.0 1.0 0.0
+";
+
+#[test]
+fn obj_parts_follow_materials() {
+    let _mock = mock();
+
+    let dir = temp_dir("parts");
+    File::create(dir.join("cube.obj")).unwrap().write_all(CUBE_OBJ.as_bytes()).unwrap();
+    File::create(dir.join("cube.mtl")).unwrap().write_all(CUBE_MTL.as_bytes()).unwrap();
+
+    let model = Model::load_obj(dir.join("cube.obj")).unwrap();
+    assert_eq!(model.parts.len(), 2);
+
+    // The quad is split into two triangles sharing two vertices.
+    let red = &model.parts[0];
+    assert_eq!(red.name, "cube");
+    assert_eq!(red.mesh.vertex_count(), 4);
+    assert_eq!(red.mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(red.mesh.uvs.len(), 4);
+    assert!(red.mesh.colors.is_empty());
+    assert_eq!(red.material.base_color, Vector4::new(1.0, 0.0, 0.0, 0.5));
+    assert!((red.material.specular - 0.4).abs() < 1e-6);
+    assert_eq!(red.material.shininess, 256.0);
+
+    // The negative indices refer to the same vertices as the positive ones.
+    let glowing = &model.parts[1];
+    assert_eq!(glowing.mesh.vertex_count(), 4);
+    assert_eq!(glowing.mesh.triangle_count(), 2);
+    assert_eq!(glowing.mesh.normals[0], Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(glowing.material.emissive, Vector3::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn mtl_textures_are_decoded_by_signature() {
+    let mock = mock();
+
+    let dir = temp_dir("textures");
+    let png = Image::new(2, 1, vec![255; 8]).encode(ImageFormat::Png);
+    File::create(dir.join("diffuse.png")).unwrap().write_all(&png).unwrap();
+    File::create(dir.join("photo.jpg")).unwrap().write_all(&[0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+    File::create(dir.join("broken.png")).unwrap().write_all(&png[..20]).unwrap();
+
+    let src = "newmtl png\nmap_Kd -s 1 1 1 diffuse.png\nnewmtl jpeg\nmap_Kd photo.jpg\n";
+    let materials = obj::parse_mtl(src, &dir).unwrap();
+    assert!(materials["png"].texture.is_some());
+    assert!(materials["jpeg"].texture.is_none());
+
+    let uploads = mock.calls_named("TexImage2D");
+    assert_eq!(uploads.len(), 1);
+    assert_eq!((uploads[0].int(3), uploads[0].int(4)), (2, 1));
+
+    // Corrupt and missing images are still errors.
+    assert!(obj::parse_mtl("newmtl broken\nmap_Kd broken.png\n", &dir).is_err());
+    assert!(obj::parse_mtl("newmtl missing\nmap_Kd missing.png\n", &dir).is_err());
+}
+
+#[test]
+fn obj_without_normals_gets_smooth_ones() {
+    let src = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 -1\nf 1 2 3\nf 1 3 4\n";
+    let model = obj::parse(src, &env::temp_dir()).unwrap();
+
+    let mesh = &model.parts[0].mesh;
+    assert_eq!(model.parts[0].name, "default");
+    assert_eq!(mesh.normals.len(), 4);
+    assert_eq!(mesh.normals[1], Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(mesh.normals[3], Vector3::new(-1.0, 0.0, 0.0));
+
+    // Shared by both triangles, which have the same area.
+    let shared = mesh.normals[0];
+    assert!((shared.x + 0.70710677).abs() < 1e-6 && (shared.z - 0.70710677).abs() < 1e-6);
+}
+
+#[test]
+fn obj_reports_the_invalid_line() {
+    let err = obj::parse("v 0 0 0\nv 1 0 0\nf 1 2 3\n", &env::temp_dir()).err().unwrap();
+    assert!(err.ends_with("line 3: f 1 2 3"), "{}", err);
+}
+
+#[test]
+fn mesh_renderable_draws_each_part() {
+    let mock = mock();
+
+    let model = obj::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\ng second\nf 3 2 1\nf 1 2 3\n", &env::temp_dir()).unwrap();
+    let renderable = wrap!(MeshRenderable::from_model(&model));
+    let triangle = wrap!(MeshRenderable::new(&model.parts[0].mesh, Material::new(Vector4::new(1.0, 1.0, 1.0, 1.0))));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&renderable));
+    scene.add(Scene::node(&triangle));
+    mock.clear();
+    scene.draw();
+
+    let draws = mock.calls_named("DrawElements");
+    let counts: Vec<i64> = draws.iter().map(|call| call.int(1)).collect();
+    assert_eq!(counts, vec![3, 6, 3]);
+    assert!(draws.iter().all(|call| call.int(2) == gl::UNSIGNED_INT as i64));
+}
+
+#[test]
+#[should_panic(expected = "Mesh index out of range")]
+fn mesh_upload_checks_indices() {
+    let _mock = mock();
+
+    let mut mesh = Mesh::new();
+    mesh.positions = vec![Vector3::new(0.0, 0.0, 0.0); 3];
+    mesh.indices = vec![0, 1, 3];
+    mesh.upload();
+}
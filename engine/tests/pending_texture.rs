@@ -0,0 +1,77 @@
+extern crate engine;
+
+mod common;
+
+use engine::gliw::{gl, Bitmap, PendingTexture, TextureParams};
+
+use common::mock;
+
+use std::sync::mpsc;
+
+fn bitmap() -> Bitmap {
+    return Bitmap {
+        width: 2,
+        height: 1,
+        data: vec![0; 6]
+    };
+}
+
+#[test]
+fn decoded_bitmaps_are_uploaded_once_the_fence_is_signaled() {
+    let mock = mock();
+    let (sender, receiver) = mpsc::channel();
+    let mut pending = PendingTexture::new(receiver, TextureParams::new());
+
+    // Decoding.
+    assert!(!pending.poll());
+    assert!(pending.take().is_none());
+    assert_eq!(mock.calls_named("TexImage2D").len(), 0);
+
+    // Uploading.
+    sender.send(Ok(bitmap())).unwrap();
+    mock.set_integer(gl::SYNC_STATUS, gl::UNSIGNALED as i32);
+    assert!(!pending.poll());
+    assert!(!pending.poll());
+    assert!(!pending.is_ready());
+
+    let uploads = mock.calls_named("TexImage2D");
+    assert_eq!(uploads.len(), 1);
+    assert_eq!((uploads[0].int(3), uploads[0].int(4)), (2, 1));
+    assert_eq!(mock.calls_named("TexParameteri").len(), 0);
+
+    // Ready, with the parameters applied.
+    mock.set_integer(gl::SYNC_STATUS, gl::SIGNALED as i32);
+    assert!(pending.poll());
+    assert!(pending.is_ready());
+    assert!(mock.calls_named("TexParameteri").len() > 0);
+
+    assert!(pending.take().unwrap().is_ok());
+    assert!(pending.take().is_none());
+    assert!(!pending.poll());
+}
+
+#[test]
+fn decoding_errors_fail_the_upload() {
+    mock();
+    let (sender, receiver) = mpsc::channel();
+    let mut pending = PendingTexture::new(receiver, TextureParams::new());
+
+    sender.send(Err(String::from("corrupt"))).unwrap();
+    assert!(pending.poll());
+    assert!(pending.is_done() && !pending.is_ready());
+    assert_eq!(pending.take().unwrap().err().unwrap(), "corrupt");
+}
+
+#[test]
+fn workers_which_disconnect_fail_the_upload() {
+    mock();
+    let (sender, receiver) = mpsc::channel::<Result<Bitmap, String>>();
+    let mut pending = PendingTexture::new(receiver, TextureParams::new());
+
+    ::std::thread::spawn(move || {
+        drop(sender);
+    }).join().unwrap();
+
+    assert!(pending.poll());
+    assert!(pending.take().unwrap().is_err());
+}
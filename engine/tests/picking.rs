@@ -0,0 +1,145 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3, Vector4, Deg};
+
+use engine::core::{Composition, Cuboid, Material, Mesh, MeshRenderable, Renderable, Scene};
+use engine::gliw::{self, MockBackend};
+use engine::math::{Aabb, BoundingSphere, Ray};
+
+use common::camera;
+
+use std::rc::Rc;
+
+fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+    return (a - b).magnitude() < 1e-4;
+}
+
+#[test]
+fn ray_hits() {
+    let unit = Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5));
+    let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+    let hit = ray.hit_aabb(&unit).unwrap();
+    assert_eq!(hit.distance, 4.5);
+    assert_eq!(hit.normal, Vector3::new(-1.0, 0.0, 0.0));
+
+    // Stretched along x and turned by 90 degrees, the box is 4 long along z.
+    let matrix = Matrix4::from_angle_y(Deg(90.0)) * Matrix4::from_nonuniform_scale(4.0, 1.0, 1.0);
+    let hit = ray.hit_obb(&unit, &matrix).unwrap();
+    assert!((hit.distance - 4.5).abs() < 1e-4);
+    assert!(close(hit.normal, Vector3::new(-1.0, 0.0, 0.0)));
+    let hit = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0)).hit_obb(&unit, &matrix).unwrap();
+    assert!((hit.distance - 3.0).abs() < 1e-4);
+    assert!(close(hit.normal, Vector3::new(0.0, 0.0, 1.0)));
+
+    let hit = ray.hit_sphere(&BoundingSphere::new(Point3::new(0.0, 0.0, 0.0), 2.0)).unwrap();
+    assert_eq!(hit.distance, 3.0);
+    assert_eq!(hit.point, Point3::new(-2.0, 0.0, 0.0));
+    assert!(ray.hit_sphere(&BoundingSphere::new(Point3::new(0.0, 3.0, 0.0), 2.0)).is_none());
+
+    let triangle = (Point3::new(0.0, -1.0, -1.0), Point3::new(0.0, 1.0, -1.0), Point3::new(0.0, 0.0, 1.0));
+    let hit = ray.hit_triangle(triangle.0, triangle.1, triangle.2).unwrap();
+    assert_eq!(hit.distance, 5.0);
+    assert!(close(hit.normal, Vector3::new(1.0, 0.0, 0.0)));
+    let behind = Ray::new(Point3::new(1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert!(behind.hit_triangle(triangle.0, triangle.1, triangle.2).is_none());
+
+    let down = Ray::new(Point3::new(1.0, 10.0, 1.0), Vector3::new(1.0, -1.0, 0.0));
+    let ground = down.intersect_ground(2.0).unwrap();
+    assert!((ground - Point3::new(9.0, 2.0, 1.0)).magnitude() < 1e-4);
+    assert!(down.intersect_ground(20.0).is_none());
+}
+
+#[test]
+fn screen_rays() {
+    let camera = camera();
+
+    let center = camera.screen_ray(400.0, 300.0, 800.0, 600.0).unwrap();
+    assert!(close(center.direction, Vector3::new(0.0, 0.0, -1.0)));
+    assert!((center.origin.z - 9.9).abs() < 1e-3);
+
+    // The right edge of a 90 degree frustum, with the window's y going down.
+    let corner = camera.screen_ray(800.0, 600.0, 800.0, 600.0).unwrap();
+    assert!(close(corner.direction, Vector3::new(1.0, -1.0, -1.0).normalize()));
+
+    let far = camera.unproject(400.0, 300.0, 1.0, 800.0, 600.0).unwrap();
+    assert!((far.z + 90.0).abs() < 1e-2);
+}
+
+#[test]
+fn scene_picks_the_closest_visible_node() {
+    gliw::set_backend(Rc::new(MockBackend::new()));
+
+    let red = Vector4::new(1.0, 0.0, 0.0, 1.0);
+    let front = wrap!(Cuboid::new(Point3::new(0.0, 0.0, 2.0), Vector3::new(1.0, 1.0, 1.0), red));
+    let back = wrap!(Cuboid::new(Point3::new(0.0, 0.0, -2.0), Vector3::new(1.0, 1.0, 1.0), red));
+    let aside = wrap!(Cuboid::new(Point3::new(5.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0), red));
+
+    let mut scene = Scene::new(camera());
+    let back_handle = scene.add(Scene::node(&back));
+    let front_handle = scene.add(Scene::node(&front));
+    scene.add(Scene::node(&aside));
+
+    let hit = scene.pick(400.0, 300.0, 800.0, 600.0).unwrap();
+    assert_eq!(hit.id, front_handle.id());
+    assert!(Rc::ptr_eq(&hit.renderable, &(front.clone() as Rc<_>)));
+    // From the near plane.
+    assert!((hit.distance - 7.4).abs() < 1e-3);
+    assert!(close(hit.normal, Vector3::new(0.0, 0.0, 1.0)));
+
+    front_handle.set_visible(false);
+    assert_eq!(scene.pick(400.0, 300.0, 800.0, 600.0).unwrap().id, back_handle.id());
+
+    // Nothing but the ground at the top left corner.
+    assert!(scene.pick(0.0, 0.0, 800.0, 600.0).is_none());
+}
+
+#[test]
+fn compositions_are_picked_whole() {
+    gliw::set_backend(Rc::new(MockBackend::new()));
+
+    let green = Vector4::new(0.0, 1.0, 0.0, 1.0);
+    let hull = wrap!(Composition::new(Cuboid::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(1.0, 1.0, 1.0), green)));
+    let turret = wrap!(Cuboid::new(Point3::new(0.0, 0.0, 4.0), Vector3::new(1.0, 1.0, 1.0), green));
+    hull.borrow_mut().attach(Scene::node(&turret));
+
+    let mut scene = Scene::new(camera());
+    let hull_handle = scene.add(Scene::node(&hull));
+
+    // The turret is in front of the hull, at z = 1 in world space.
+    let hit = scene.pick(400.0, 300.0, 800.0, 600.0).unwrap();
+    assert_eq!(hit.id, hull_handle.id());
+    assert!((hit.point.z - 1.5).abs() < 1e-3);
+}
+
+#[test]
+fn mesh_picking() {
+    gliw::set_backend(Rc::new(MockBackend::new()));
+
+    // A right triangle facing +z, only covering the lower left half of its bounds.
+    let mut mesh = Mesh::new();
+    mesh.positions = vec![Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, -1.0, 0.0), Vector3::new(0.0, 1.0, 0.0)];
+    mesh.indices = vec![0, 1, 2];
+
+    let mut renderable = MeshRenderable::new(&mesh, Material::new(Vector4::new(1.0, 1.0, 1.0, 1.0)));
+    renderable.scale = 2.0;
+
+    let outside = Ray::new(Point3::new(0.5, 1.5, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(renderable.intersect_ray(&outside).is_some());
+
+    renderable.set_pick_mesh(Some(mesh));
+    assert!(renderable.intersect_ray(&outside).is_none());
+
+    let inside = Ray::new(Point3::new(0.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let hit = renderable.intersect_ray(&inside).unwrap();
+    assert!((hit.distance - 5.0).abs() < 1e-4);
+    assert!(close(hit.normal, Vector3::new(0.0, 0.0, 1.0)));
+
+    // The normal faces the ray from behind too.
+    let behind = Ray::new(Point3::new(0.5, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+    assert!(close(renderable.intersect_ray(&behind).unwrap().normal, Vector3::new(0.0, 0.0, -1.0)));
+}
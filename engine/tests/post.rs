@@ -0,0 +1,107 @@
+extern crate engine;
+
+mod common;
+
+use engine::core::{PostEffect, PostStack, RenderGraph, TargetDesc, TargetPool, ToneMapOperator};
+use engine::gliw::{gl, Attachment, InternalFormat};
+
+use common::mock;
+
+fn tone_map() -> PostEffect {
+    return PostEffect::ToneMap { exposure: 1.0, operator: ToneMapOperator::Aces };
+}
+
+fn vignette() -> PostEffect {
+    return PostEffect::Vignette { intensity: 0.5, radius: 0.75, softness: 0.5 };
+}
+
+/// The passes scheduled for `post` after an `opaque` pass rendering the input.
+fn schedule(post: &PostStack) -> Vec<String> {
+    let mut graph = RenderGraph::new(800, 600);
+    let hdr = graph.create_target("hdr", TargetDesc::new(800, 600, InternalFormat::RGBA16F));
+
+    graph.add_pass("opaque").write(Attachment::Color(0), hdr);
+    post.add_passes(&mut graph, hdr, RenderGraph::backbuffer(), 800, 600);
+
+    return graph.schedule().iter().map(|name| name.to_string()).collect();
+}
+
+#[test]
+fn passes_follow_the_effect_order() {
+    mock();
+
+    let mut post = PostStack::new().unwrap();
+    post.push(tone_map());
+    post.push(vignette());
+    post.push(PostEffect::Fxaa);
+    assert_eq!(schedule(&post), vec!["opaque", "tone_map", "vignette", "fxaa"]);
+
+    post.move_effect(2, 0);
+    assert_eq!(schedule(&post), vec!["opaque", "fxaa", "tone_map", "vignette"]);
+}
+
+#[test]
+fn disabled_effects_are_skipped() {
+    mock();
+
+    let mut post = PostStack::new().unwrap();
+    post.push(tone_map());
+    post.push(vignette());
+
+    post.set_enabled(0, false);
+    assert!(!post.is_enabled(0));
+    assert_eq!(schedule(&post), vec!["opaque", "vignette"]);
+
+    // The input is still presented without any effect.
+    post.set_enabled(1, false);
+    assert_eq!(schedule(&post), vec!["opaque", "post_copy"]);
+
+    post.set_enabled(0, true);
+    post.set_enabled(1, true);
+    assert_eq!(schedule(&post), vec!["opaque", "tone_map", "vignette"]);
+}
+
+#[test]
+fn bloom_blurs_at_half_resolution() {
+    let mock = mock();
+
+    let mut post = PostStack::new().unwrap();
+    post.push(PostEffect::Bloom { threshold: 1.0, intensity: 0.5, iterations: 2 });
+    post.push(tone_map());
+
+    assert_eq!(schedule(&post), vec![
+        "opaque", "bloom_bright",
+        "bloom_blur_h", "bloom_blur_v", "bloom_blur_h", "bloom_blur_v",
+        "bloom", "tone_map"
+    ]);
+
+    let mut graph = RenderGraph::new(800, 600);
+    let hdr = graph.create_target("hdr", TargetDesc::new(800, 600, InternalFormat::RGBA16F));
+    graph.add_pass("opaque").write(Attachment::Color(0), hdr);
+    post.add_passes(&mut graph, hdr, RenderGraph::backbuffer(), 800, 600);
+
+    mock.clear();
+    graph.execute(&mut TargetPool::new());
+
+    let calls = mock.calls();
+    let allocations: Vec<usize> = (0..calls.len()).filter(|&index| calls[index].name == "TexImage2D").collect();
+
+    // The blur ping-pongs between 2 half resolution textures, which are filtered when upsampled.
+    let half: Vec<usize> = allocations.iter().cloned().filter(|&index| calls[index].int(3) == 400).collect();
+    assert_eq!(half.len(), 2);
+
+    for &index in half.iter() {
+        assert_eq!(calls[index].int(4), 300);
+
+        let filters: Vec<i64> = calls[index + 1..].iter()
+            .take_while(|call| call.name == "TexParameteri")
+            .filter(|call| call.int(1) == gl::TEXTURE_MAG_FILTER as i64)
+            .map(|call| call.int(2))
+            .collect();
+        assert_eq!(filters.last(), Some(&(gl::LINEAR as i64)));
+    }
+
+    // Pooled textures are never refiltered while drawing.
+    let first_draw = calls.iter().position(|call| call.name == "DrawArrays").unwrap();
+    assert!(!calls[first_draw..].iter().any(|call| call.name == "TexParameteri"));
+}
@@ -0,0 +1,96 @@
+extern crate engine;
+extern crate cgmath;
+
+use cgmath::{Point3, Vector4, InnerSpace};
+
+use engine::core::{Mesh, MeshRenderable, Primitive};
+use engine::gliw::{self, MockBackend};
+
+use std::rc::Rc;
+
+fn primitives() -> Vec<Primitive> {
+    return vec![
+        Primitive::UvSphere { radius: 2.0, segments: 8, rings: 6 },
+        Primitive::Icosphere { radius: 2.0, subdivisions: 2 },
+        Primitive::Cylinder { radius: 1.0, height: 3.0, segments: 7 },
+        Primitive::Cone { radius: 1.0, height: 2.0, segments: 5 },
+        Primitive::Capsule { radius: 0.5, height: 1.0, segments: 6, rings: 3 },
+        Primitive::Torus { radius: 2.0, tube_radius: 0.5, segments: 12, sides: 6 },
+        Primitive::Plane { width: 4.0, depth: 2.0, x_segments: 4, z_segments: 2 },
+        Primitive::Arrow { length: 1.0, shaft_radius: 0.05, head_radius: 0.1, head_length: 0.25, segments: 8 }
+    ];
+}
+
+#[test]
+fn primitives_have_outward_counter_clockwise_triangles() {
+    for primitive in primitives() {
+        let mesh = primitive.mesh();
+
+        assert_eq!(mesh.normals.len(), mesh.vertex_count(), "{:?}", primitive);
+        assert_eq!(mesh.uvs.len(), mesh.vertex_count(), "{:?}", primitive);
+        assert!(mesh.triangle_count() > 0);
+
+        for normal in &mesh.normals {
+            assert!((normal.magnitude() - 1.0).abs() < 1e-5, "{:?}", primitive);
+        }
+
+        for uv in &mesh.uvs {
+            assert!(uv.x >= 0.0 && uv.x <= 1.0 && uv.y >= 0.0 && uv.y <= 1.0, "{:?}", primitive);
+        }
+
+        for triangle in mesh.indices.chunks(3) {
+            let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+            let face = (mesh.positions[b] - mesh.positions[a]).cross(mesh.positions[c] - mesh.positions[a]);
+
+            // Triangles collapsed at the poles have no facing.
+            if face.magnitude() < 1e-6 {
+                continue;
+            }
+
+            let normal = mesh.normals[a] + mesh.normals[b] + mesh.normals[c];
+            assert!(face.dot(normal) > 0.0, "{:?} {:?}", primitive, triangle);
+        }
+    }
+}
+
+#[test]
+fn tessellation_sets_the_counts() {
+    let sphere = Mesh::uv_sphere(1.0, 16, 8);
+    assert_eq!(sphere.vertex_count(), 17 * 9);
+    assert_eq!(sphere.triangle_count(), 2 * 16 * 8);
+
+    // 12 vertices, 30 edges and 20 faces, each subdivision splitting every edge and face.
+    let icosphere = Mesh::icosphere(1.0, 1);
+    assert_eq!(icosphere.vertex_count(), 12 + 30);
+    assert_eq!(icosphere.triangle_count(), 80);
+    for position in &icosphere.positions {
+        assert!((position.magnitude() - 1.0).abs() < 1e-5);
+    }
+
+    let plane = Mesh::plane(1.0, 1.0, 3, 2);
+    assert_eq!(plane.vertex_count(), 4 * 3);
+    assert_eq!(plane.triangle_count(), 12);
+
+    let cylinder = Mesh::cylinder(0.5, 2.0, 10);
+    let top = cylinder.positions.iter().fold(-1.0f32, |top, p| top.max(p.y));
+    assert_eq!(top, 1.0);
+}
+
+#[test]
+#[should_panic]
+fn too_few_segments_panic() {
+    Mesh::cylinder(1.0, 1.0, 2);
+}
+
+#[test]
+fn primitive_renderables_are_placed() {
+    let mock = Rc::new(MockBackend::new());
+    gliw::set_backend(mock.clone());
+
+    let primitive = Primitive::Cone { radius: 1.0, height: 1.0, segments: 4 };
+    let cone = MeshRenderable::primitive(Point3::new(0.0, 2.0, 0.0), primitive, Vector4::new(1.0, 0.0, 0.0, 1.0));
+
+    assert_eq!(cone.position, Point3::new(0.0, 2.0, 0.0));
+    assert_eq!(cone.part_count(), 1);
+    assert_eq!(cone.material(0).base_color, Vector4::new(1.0, 0.0, 0.0, 1.0));
+}
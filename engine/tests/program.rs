@@ -0,0 +1,152 @@
+extern crate engine;
+
+mod common;
+
+use engine::gliw::{gl, Capabilities, FeedbackBufferMode, MockBackend, ProgramBuilder, ProgramPipeline, Shader, ShaderType};
+
+use common::mock;
+
+fn shader(shader_type: ShaderType) -> Shader {
+    return Shader::new(shader_type, "<code>").unwrap();
+}
+
+fn set_version(mock: &MockBackend, major: i32, minor: i32) {
+    mock.set_integer(gl::MAJOR_VERSION, major);
+    mock.set_integer(gl::MINOR_VERSION, minor);
+    Capabilities::invalidate();
+}
+
+#[test]
+fn stages_are_validated() {
+    mock();
+
+    let cs = shader(ShaderType::Compute);
+    let vs = shader(ShaderType::Vertex);
+    let tcs = shader(ShaderType::TessControl);
+    let tes = shader(ShaderType::TessEvaluation);
+    let fs = shader(ShaderType::Fragment);
+
+    let err = ProgramBuilder::new().link().err().unwrap();
+    assert!(err.contains("No shaders"), "{}", err);
+
+    let err = ProgramBuilder::new().attach_vs(&fs).link().err().unwrap();
+    assert!(err.contains("wrong stage"), "{}", err);
+
+    let err = ProgramBuilder::new().attach_cs(&cs).attach_vs(&vs).link().err().unwrap();
+    assert!(err.contains("Compute shaders cannot be linked"), "{}", err);
+
+    let err = ProgramBuilder::new().attach_fs(&fs).link().err().unwrap();
+    assert!(err.contains("vertex shader"), "{}", err);
+    assert!(ProgramBuilder::new().attach_fs(&fs).separable().link().is_ok());
+
+    let err = ProgramBuilder::new().attach_vs(&vs).attach_tcs(&tcs).attach_fs(&fs).link().err().unwrap();
+    assert!(err.contains("Tesselation control shader"), "{}", err);
+    assert!(ProgramBuilder::new().attach_vs(&vs).attach_tes(&tes).attach_fs(&fs).link().is_ok());
+
+    let program = ProgramBuilder::new().attach_vs(&vs).attach_tcs(&tcs).attach_tes(&tes).attach_fs(&fs).link().unwrap();
+    assert_eq!(program.stages(), gl::VERTEX_SHADER_BIT | gl::TESS_CONTROL_SHADER_BIT |
+                                 gl::TESS_EVALUATION_SHADER_BIT | gl::FRAGMENT_SHADER_BIT);
+}
+
+#[test]
+fn separate_varyings_are_limited() {
+    let mock = mock();
+    mock.set_integer(gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS, 2);
+
+    let vs = shader(ShaderType::Vertex);
+    let varyings = ["a", "b", "c"];
+
+    let err = ProgramBuilder::new().attach_vs(&vs).feedback_varyings(&varyings, FeedbackBufferMode::Separate)
+        .link().err().unwrap();
+    assert!(err.contains("Too many"), "{}", err);
+
+    assert!(ProgramBuilder::new().attach_vs(&vs).feedback_varyings(&varyings, FeedbackBufferMode::Interleaved)
+        .link().is_ok());
+}
+
+#[test]
+fn stages_are_gated_by_the_version() {
+    let mock = mock();
+
+    let cs = shader(ShaderType::Compute);
+    let vs = shader(ShaderType::Vertex);
+    let tcs = shader(ShaderType::TessControl);
+    let tes = shader(ShaderType::TessEvaluation);
+    let gs = shader(ShaderType::Geometry);
+    let fs = shader(ShaderType::Fragment);
+
+    set_version(&mock, 3, 1);
+    let err = ProgramBuilder::new().attach_vs(&vs).attach_gs(&gs).attach_fs(&fs).link().err().unwrap();
+    assert!(err.contains("Geometry shaders require"), "{}", err);
+
+    set_version(&mock, 3, 3);
+    assert!(ProgramBuilder::new().attach_vs(&vs).attach_gs(&gs).attach_fs(&fs).link().is_ok());
+    let err = ProgramBuilder::new().attach_vs(&vs).attach_tcs(&tcs).attach_tes(&tes).link().err().unwrap();
+    assert!(err.contains("Tesselation shaders require"), "{}", err);
+
+    set_version(&mock, 4, 0);
+    assert!(ProgramBuilder::new().attach_vs(&vs).attach_tcs(&tcs).attach_tes(&tes).link().is_ok());
+    let err = ProgramBuilder::new().attach_vs(&vs).separable().link().err().unwrap();
+    assert!(err.contains("Separable programs require"), "{}", err);
+
+    set_version(&mock, 4, 2);
+    assert!(ProgramBuilder::new().attach_vs(&vs).separable().link().is_ok());
+    let err = ProgramBuilder::new().attach_cs(&cs).link().err().unwrap();
+    assert!(err.contains("Compute shaders require"), "{}", err);
+
+    set_version(&mock, 4, 3);
+    assert!(ProgramBuilder::new().attach_cs(&cs).link().is_ok());
+}
+
+#[test]
+fn separable_programs_through_the_extension() {
+    let mock = mock();
+    mock.set_integer(gl::MINOR_VERSION, 0);
+    mock.add_extension("GL_ARB_separate_shader_objects");
+    Capabilities::invalidate();
+
+    let vs = shader(ShaderType::Vertex);
+    assert!(ProgramBuilder::new().attach_vs(&vs).separable().link().is_ok());
+}
+
+#[test]
+fn pipelines_use_the_stages_of_separable_programs() {
+    let mock = mock();
+
+    let vs = shader(ShaderType::Vertex);
+    let fs = shader(ShaderType::Fragment);
+    let vertex = ProgramBuilder::new().attach_vs(&vs).separable().link().unwrap();
+    let both = ProgramBuilder::new().attach_vs(&vs).attach_fs(&fs).separable().link().unwrap();
+
+    let pipeline = ProgramPipeline::new();
+    pipeline.use_program(&both);
+    pipeline.use_stages(gl::VERTEX_SHADER_BIT, &vertex);
+
+    let uses = mock.calls_named("UseProgramStages");
+    assert_eq!(uses.len(), 2);
+    assert_eq!((uses[0].int(1), uses[0].int(2)),
+               ((gl::VERTEX_SHADER_BIT | gl::FRAGMENT_SHADER_BIT) as i64, both.handle() as i64));
+    assert_eq!((uses[1].int(1), uses[1].int(2)), (gl::VERTEX_SHADER_BIT as i64, vertex.handle() as i64));
+}
+
+#[test]
+#[should_panic]
+fn pipelines_reject_programs_which_are_not_separable() {
+    mock();
+
+    let vs = shader(ShaderType::Vertex);
+    let program = ProgramBuilder::new().attach_vs(&vs).link().unwrap();
+
+    ProgramPipeline::new().use_program(&program);
+}
+
+#[test]
+#[should_panic]
+fn pipelines_reject_missing_stages() {
+    mock();
+
+    let vs = shader(ShaderType::Vertex);
+    let program = ProgramBuilder::new().attach_vs(&vs).separable().link().unwrap();
+
+    ProgramPipeline::new().use_stages(gl::VERTEX_SHADER_BIT | gl::FRAGMENT_SHADER_BIT, &program);
+}
@@ -0,0 +1,147 @@
+extern crate engine;
+
+mod common;
+
+use engine::core::{RenderGraph, TargetDesc, TargetPool};
+use engine::gliw::{Attachment, InternalFormat};
+
+use common::mock;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn desc() -> TargetDesc {
+    return TargetDesc::new(400, 300, InternalFormat::RGBA8);
+}
+
+/// Declares a bloom-like chain `opaque -> bright -> blur -> post` in shuffled order,
+/// along with an `unused` pass whose output nothing reads.
+fn chain(graph: &mut RenderGraph) {
+    let hdr = graph.create_target("hdr", desc());
+    let bright = graph.create_target("bright", desc());
+    let blurred = graph.create_target("blurred", desc());
+    let unused = graph.create_target("unused", desc());
+
+    graph.add_pass("post").read(blurred).write(Attachment::Color(0), RenderGraph::backbuffer());
+    graph.add_pass("unused").write(Attachment::Color(0), unused);
+    graph.add_pass("blur").read(bright).write(Attachment::Color(0), blurred);
+    graph.add_pass("opaque").write(Attachment::Color(0), hdr);
+    graph.add_pass("bright").read(hdr).write(Attachment::Color(0), bright);
+}
+
+#[test]
+fn schedules_writers_before_readers() {
+    mock();
+
+    let mut graph = RenderGraph::new(800, 600);
+    chain(&mut graph);
+
+    assert_eq!(graph.schedule(), vec!["opaque", "bright", "blur", "post"]);
+}
+
+#[test]
+fn culls_passes_whose_output_is_unused() {
+    let mock = mock();
+    let executed = Rc::new(RefCell::new(Vec::new()));
+
+    let mut graph = RenderGraph::new(800, 600);
+    let unused = graph.create_target("unused", desc());
+    let kept = graph.create_target("kept", desc());
+
+    {
+        let executed = executed.clone();
+        graph.add_pass("unused")
+            .write(Attachment::Color(0), unused)
+            .execute(move |ctx| executed.borrow_mut().push(ctx.name().to_string()));
+    }
+    {
+        let executed = executed.clone();
+        graph.add_pass("kept")
+            .write(Attachment::Color(0), kept)
+            .keep()
+            .execute(move |ctx| executed.borrow_mut().push(ctx.name().to_string()));
+    }
+    {
+        let executed = executed.clone();
+        graph.add_pass("present")
+            .write(Attachment::Color(0), RenderGraph::backbuffer())
+            .execute(move |ctx| executed.borrow_mut().push(ctx.name().to_string()));
+    }
+
+    graph.execute(&mut TargetPool::new());
+
+    assert_eq!(*executed.borrow(), vec!["kept", "present"]);
+
+    // Only the kept target is allocated.
+    assert_eq!(mock.calls_named("TexImage2D").len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "cyclicly")]
+fn panics_on_cycles() {
+    mock();
+
+    let mut graph = RenderGraph::new(800, 600);
+    let a = graph.create_target("a", desc());
+    let b = graph.create_target("b", desc());
+
+    graph.add_pass("ping").read(a).write(Attachment::Color(0), b).keep();
+    graph.add_pass("pong").read(b).write(Attachment::Color(0), a).keep();
+
+    graph.execute(&mut TargetPool::new());
+}
+
+/// Runs the chain, optionally sampling `hdr` once more at the end of the frame,
+/// and returns the texture handles sampled by `bright`, `blur` and `post`.
+fn sampled_handles(sample_hdr_last: bool) -> Vec<u32> {
+    let sampled = Rc::new(RefCell::new(vec![0; 3]));
+
+    let mut graph = RenderGraph::new(800, 600);
+    let hdr = graph.create_target("hdr", desc());
+    let bright = graph.create_target("bright", desc());
+    let blurred = graph.create_target("blurred", desc());
+
+    let passes = [
+        ("bright", hdr, bright),
+        ("blur", bright, blurred),
+        ("post", blurred, RenderGraph::backbuffer())
+    ];
+
+    graph.add_pass("opaque").write(Attachment::Color(0), hdr);
+
+    for (index, &(name, input, output)) in passes.iter().enumerate() {
+        let sampled = sampled.clone();
+        graph.add_pass(name).read(input).write(Attachment::Color(0), output).execute(move |ctx| {
+            sampled.borrow_mut()[index] = ctx.texture(input).handle();
+        });
+    }
+
+    if sample_hdr_last {
+        graph.add_pass("sample hdr").read(hdr).keep();
+    }
+
+    graph.execute(&mut TargetPool::new());
+
+    let handles = sampled.borrow().clone();
+    return handles;
+}
+
+#[test]
+fn aliases_only_targets_with_disjoint_lifetimes() {
+    let mock = mock();
+
+    // `hdr` is last read by `bright`, before `blurred` is first written by `blur`.
+    let handles = sampled_handles(false);
+    assert_eq!(handles[0], handles[2]);
+    assert!(handles[0] != handles[1]);
+    assert_eq!(mock.calls_named("TexImage2D").len(), 2);
+
+    mock.clear();
+
+    // Reading `hdr` after `post` keeps it alive while `blurred` is in use.
+    let handles = sampled_handles(true);
+    assert!(handles[0] != handles[1]);
+    assert!(handles[0] != handles[2]);
+    assert!(handles[1] != handles[2]);
+    assert_eq!(mock.calls_named("TexImage2D").len(), 3);
+}
@@ -0,0 +1,339 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Matrix4, Point3, Vector3, Vector4, Quaternion, Rad, Rotation3, InnerSpace};
+
+use engine::core::{Camera, Composition, Cuboid, Renderable, Scene};
+use engine::gliw::{gl, Uniform, UniformData, ProgramBuilder, Shader, ShaderType};
+
+use common::{Marker, assert_near, drawn_ids, mock};
+
+/// Renderable which only has a transformation.
+struct Placed {
+    translation: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    scale: f32
+}
+
+impl Placed {
+    fn at(x: f32, y: f32, z: f32) -> Placed {
+        return Placed {
+            translation: Vector3::new(x, y, z),
+            rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            scale: 1.0
+        };
+    }
+}
+
+impl Renderable for Placed {
+    fn model_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::from_translation(self.translation) *
+            Matrix4::from(self.rotation) *
+            Matrix4::from_scale(self.scale);
+    }
+
+    fn draw(&self, _: Matrix4<f32>, _: &Camera) {}
+}
+
+#[test]
+fn scene_draws_in_priority_order() {
+    let mock = mock();
+
+    let a = wrap!(Marker::new(1).with_priority(2));
+    let b = wrap!(Marker::new(2));
+    let c = wrap!(Marker::new(3).with_priority(1));
+    let d = wrap!(Marker::new(4));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&a));
+    scene.add(Scene::node(&b));
+    scene.add(Scene::node(&c));
+    scene.add(Scene::node(&d));
+    scene.draw();
+
+    // Equal priorities keep the order of insertion.
+    assert_eq!(drawn_ids(&mock), vec![2, 4, 3, 1]);
+}
+
+#[test]
+fn scene_drops_destroyed_renderables() {
+    let mock = mock();
+
+    let a = wrap!(Marker::new(1));
+    let b = wrap!(Marker::new(2));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&a));
+    scene.add(Scene::node(&b));
+    drop(a);
+    scene.draw();
+
+    assert_eq!(drawn_ids(&mock), vec![2]);
+}
+
+#[test]
+fn priority_changes_reorder_the_scene() {
+    let mock = mock();
+
+    let a = wrap!(Marker::new(1));
+    let b = wrap!(Marker::new(2));
+    let c = wrap!(Marker::new(3));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&a));
+    scene.add(Scene::node(&b));
+    scene.add(Scene::node(&c));
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 2, 3]);
+
+    a.borrow_mut().priority = 1;
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![2, 3, 1]);
+
+    // Back to an equal priority, the order of insertion wins again.
+    a.borrow_mut().priority = 0;
+    c.borrow_mut().priority = 1;
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 2, 3]);
+
+    // Nodes added later go after the ones with equal priority, even those that were moved.
+    let d = wrap!(Marker::new(4).with_priority(1));
+    scene.add(Scene::node(&d));
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn priority_changes_with_dropped_renderables() {
+    let mock = mock();
+
+    let a = wrap!(Marker::new(1).with_priority(2));
+    let b = wrap!(Marker::new(2).with_priority(1));
+    let c = wrap!(Marker::new(3));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&a));
+    scene.add(Scene::node(&b));
+    scene.add(Scene::node(&c));
+
+    drop(b);
+    a.borrow_mut().priority = 0;
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 3]);
+}
+
+#[test]
+fn priority_changes_reorder_composition_children() {
+    let mock = mock();
+
+    let child_a = wrap!(Marker::new(2));
+    let child_b = wrap!(Marker::new(3));
+    let parent = wrap!(Composition::new(Marker::new(1)));
+    parent.borrow_mut().attach(Scene::node(&child_a));
+    parent.borrow_mut().attach(Scene::node(&child_b));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&parent));
+
+    child_a.borrow_mut().priority = 3;
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 3, 2]);
+}
+
+#[test]
+fn composition_draws_parent_before_children() {
+    let mock = mock();
+
+    let child_a = wrap!(Marker::new(2).with_priority(1));
+    let child_b = wrap!(Marker::new(3));
+    let parent = wrap!(Composition::new(Marker::new(1).with_priority(5)));
+    parent.borrow_mut().attach(Scene::node(&child_a));
+    parent.borrow_mut().attach(Scene::node(&child_b));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&parent));
+    scene.draw();
+
+    assert_eq!(drawn_ids(&mock), vec![1, 3, 2]);
+}
+
+#[test]
+fn handles_hide_and_remove_nodes() {
+    let mock = mock();
+
+    let a = wrap!(Marker::new(1));
+    let b = wrap!(Marker::new(2));
+
+    let mut scene = Scene::new(Camera::new());
+    let handle_a = scene.add(Scene::node(&a));
+    let handle_b = scene.add(Scene::node(&b));
+    assert!(scene.contains(&handle_a) && scene.contains(&handle_b));
+
+    handle_a.set_visible(false);
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![2]);
+    assert!(scene.contains(&handle_a));
+
+    handle_a.set_visible(true);
+    handle_b.remove();
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1]);
+    assert!(!scene.contains(&handle_b) && handle_b.is_removed());
+
+    // The removed renderable is still alive.
+    assert_eq!(b.borrow().id, 2);
+
+    drop(a);
+    assert!(!scene.contains(&handle_a));
+}
+
+#[test]
+fn handles_reparent_nodes() {
+    let mock = mock();
+
+    let child = wrap!(Marker::new(2));
+    let parent = wrap!(Composition::new(Marker::new(1).with_priority(1)));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&parent));
+    let mut handle = scene.add(Scene::node(&child));
+    handle.set_visible(false);
+
+    handle.reparent(&mut *parent.borrow_mut());
+    assert!(!scene.contains(&handle));
+    assert!(parent.borrow().contains(&handle));
+    assert!(!handle.is_visible());
+
+    handle.set_visible(true);
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1, 2]);
+
+    handle.reparent(&mut scene);
+    assert!(scene.contains(&handle) && !parent.borrow().contains(&handle));
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![2, 1]);
+}
+
+#[test]
+#[should_panic(expected = "descendants")]
+fn rejects_reparenting_into_a_descendant() {
+    let hull = wrap!(Composition::new(Placed::at(1.0, 0.0, 0.0)));
+    let turret = wrap!(Composition::new(Placed::at(0.0, 1.0, 0.0)));
+    let muzzle = wrap!(Composition::new(Placed::at(0.0, 0.0, 2.0)));
+    turret.borrow_mut().attach(Scene::node(&muzzle));
+
+    let mut scene = Scene::new(Camera::new());
+    let mut handle = scene.add(Scene::node(&hull));
+    hull.borrow_mut().attach(Scene::node(&turret));
+
+    handle.reparent(&mut *muzzle.borrow_mut());
+}
+
+#[test]
+#[should_panic(expected = "itself")]
+fn rejects_reparenting_into_itself() {
+    let hull = wrap!(Composition::new(Placed::at(1.0, 0.0, 0.0)));
+
+    let mut scene = Scene::new(Camera::new());
+    let mut handle = scene.add(Scene::node(&hull));
+
+    handle.reparent(&mut *hull.borrow_mut());
+}
+
+#[test]
+fn nested_compositions_have_world_transforms() {
+    let hull = wrap!(Composition::new(Placed::at(1.0, 0.0, 0.0)));
+    let turret = wrap!(Composition::new(Placed::at(0.0, 1.0, 0.0)));
+    let muzzle = wrap!(Composition::new(Placed::at(0.0, 0.0, 2.0)));
+    hull.borrow_mut().attach(Scene::node(&turret));
+    turret.borrow_mut().attach(Scene::node(&muzzle));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&hull));
+
+    assert_near(muzzle.borrow().world_position(), Point3::new(1.0, 1.0, 2.0));
+    assert!(hull.borrow().parent().is_none());
+    assert!(hull.borrow().transform().is_ancestor_of(muzzle.borrow().transform()));
+    assert!(!muzzle.borrow().transform().is_ancestor_of(hull.borrow().transform()));
+
+    let parent = muzzle.borrow().parent().unwrap();
+    assert_eq!(parent.borrow().model_matrix(), Matrix4::from_translation(Vector3::new(0.0, 1.0, 0.0)));
+
+    // Changes of the ancestors are picked up.
+    hull.borrow_mut().translation = Vector3::new(5.0, 0.0, 0.0);
+    turret.borrow_mut().rotation = Quaternion::from_angle_y(Rad(std::f32::consts::FRAC_PI_2));
+    turret.borrow_mut().scale = 2.0;
+
+    let muzzle = muzzle.borrow();
+    assert_near(muzzle.world_position(), Point3::new(9.0, 1.0, 0.0));
+    assert_near(muzzle.local_to_world(Point3::new(0.0, 0.0, 1.0)), Point3::new(11.0, 1.0, 0.0));
+    assert_near(muzzle.world_to_local(Point3::new(11.0, 1.0, 0.0)).unwrap(), Point3::new(0.0, 0.0, 1.0));
+    assert!((muzzle.local_to_world_vector(Vector3::new(0.0, 0.0, 1.0)) - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+    // The scale is ignored.
+    let orientation = muzzle.world_orientation();
+    let expected = Quaternion::from_angle_y(Rad(std::f32::consts::FRAC_PI_2));
+    assert!((orientation.s - expected.s).abs() < 1e-5 && (orientation.v.y - expected.v.y).abs() < 1e-5);
+}
+
+#[test]
+fn removed_compositions_leave_the_hierarchy() {
+    let parent = wrap!(Composition::new(Placed::at(1.0, 0.0, 0.0)));
+    let child = wrap!(Composition::new(Placed::at(0.0, 1.0, 0.0)));
+    let mut handle = parent.borrow_mut().attach(Scene::node(&child));
+
+    assert_near(child.borrow().world_position(), Point3::new(1.0, 1.0, 0.0));
+
+    handle.remove();
+    assert!(child.borrow().transform().parent().is_none());
+    assert_near(child.borrow().world_position(), Point3::new(0.0, 1.0, 0.0));
+
+    let other = wrap!(Composition::new(Placed::at(0.0, 0.0, 3.0)));
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&other));
+
+    handle = scene.add(Scene::node(&child));
+    handle.reparent(&mut *other.borrow_mut());
+    assert_near(child.borrow().world_position(), Point3::new(0.0, 1.0, 3.0));
+    assert!(child.borrow().parent().is_some());
+}
+
+#[test]
+fn cuboid_draws_all_faces() {
+    let mock = mock();
+
+    let cuboid = wrap!(Cuboid::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 1.0, 1.0),
+        Vector4::new(1.0, 0.0, 0.0, 1.0)));
+
+    let mut scene = Scene::new(Camera::new());
+    scene.add(Scene::node(&cuboid));
+    mock.clear();
+    scene.draw();
+
+    let draws = mock.calls_named("DrawElements");
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].int(0), gl::TRIANGLES as i64);
+    assert_eq!(draws[0].int(1), 36);
+}
+
+#[test]
+#[should_panic(expected = "does not match the type of the uniform")]
+fn uniform_type_mismatch_panics() {
+    let mock = mock();
+
+    let vs = Shader::new(ShaderType::Vertex, "").unwrap();
+    let program = ProgramBuilder::new().attach_vs(&vs).link().unwrap();
+
+    mock.fail_on("Uniform4fv", gl::INVALID_OPERATION);
+    Uniform::new(&program, "color").value(UniformData::FloatVec(4, &[0.0, 0.0, 0.0, 1.0]));
+}
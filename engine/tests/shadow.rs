@@ -0,0 +1,118 @@
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Point3, Vector3, Vector4};
+
+use engine::core::{Camera, Cascade, DirectionalLight, ShadowMap, ShadowSettings};
+
+use common::mock;
+
+const FOVY: f32 = 60.0;
+const ASPECT: f32 = 16.0 / 9.0;
+
+fn camera(far: f32) -> Camera {
+    let mut camera = Camera::new();
+    camera.perspective(FOVY, ASPECT, 0.1, far);
+    camera.look_at(Point3::new(4.0, 3.0, 6.0), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    return camera;
+}
+
+fn cascades(settings: ShadowSettings, camera: &Camera) -> Vec<Cascade> {
+    let shadow_map = ShadowMap::new(settings).unwrap();
+    let light = DirectionalLight::new(Vector3::new(-1.0, -2.0, -0.5), Vector3::new(1.0, 1.0, 1.0));
+
+    shadow_map.render(&light, camera, |_| {});
+    return shadow_map.cascades();
+}
+
+fn settings(cascades: usize, split_lambda: f32) -> ShadowSettings {
+    let mut settings = ShadowSettings::new();
+    settings.cascades = cascades;
+    settings.split_lambda = split_lambda;
+    return settings;
+}
+
+fn splits(cascades: &[Cascade]) -> Vec<f32> {
+    return cascades.iter().map(|cascade| cascade.split).collect();
+}
+
+fn assert_splits(actual: Vec<f32>, expected: Vec<f32>) {
+    assert_eq!(actual.len(), expected.len());
+
+    for (a, b) in actual.iter().zip(expected.iter()) {
+        assert!((a - b).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn uniform_and_logarithmic_splits() {
+    mock();
+
+    let camera = camera(100.0);
+
+    // Up to the default 50 units, in equal parts.
+    let uniform = cascades(settings(4, 0.0), &camera);
+    assert_splits(splits(&uniform), vec![12.575, 25.05, 37.525, 50.0]);
+
+    // Each split is the same ratio further than the previous, `0.1 * 500 ^ (i / 4)`.
+    let logarithmic = cascades(settings(4, 1.0), &camera);
+    let ratio = 500.0f32.powf(0.25);
+    assert_splits(splits(&logarithmic), vec![0.1 * ratio, 0.1 * ratio.powi(2), 0.1 * ratio.powi(3), 50.0]);
+}
+
+#[test]
+fn splits_increase_up_to_the_shadow_distance() {
+    mock();
+
+    for &lambda in [0.0, 0.25, 0.5, 0.75, 1.0].iter() {
+        let splits = splits(&cascades(settings(4, lambda), &camera(100.0)));
+
+        assert!(splits[0] > 0.1);
+        for pair in splits.windows(2) {
+            assert!(pair[0] < pair[1], "{:?}", splits);
+        }
+        assert!((splits[3] - 50.0).abs() < 1e-3);
+    }
+
+    // Clamped to the far plane.
+    let splits = splits(&cascades(settings(3, 0.75), &camera(20.0)));
+    assert!((splits[2] - 20.0).abs() < 1e-3);
+}
+
+#[test]
+fn cascades_contain_their_slice() {
+    mock();
+
+    let camera = camera(100.0);
+    let settings = settings(4, 0.75);
+    let cascades = cascades(settings, &camera);
+
+    let half_height = (FOVY.to_radians() / 2.0).tan();
+    let half_width = half_height * ASPECT;
+
+    // Snapping to whole texels moves the cascade by up to half a texel.
+    let tolerance = 1.0 / settings.resolution as f32;
+
+    let mut previous_split = 0.1;
+    for cascade in cascades.iter() {
+        for &distance in [previous_split, cascade.split].iter() {
+            for &(x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter() {
+                let corner = camera.position() + distance * (
+                    camera.forward() +
+                    camera.right() * x * half_width +
+                    camera.up() * y * half_height);
+
+                let clip = cascade.vp_matrix * Vector4::new(corner.x, corner.y, corner.z, 1.0);
+                let ndc = clip.truncate() / clip.w;
+
+                assert!(ndc.x.abs() <= 1.0 + tolerance, "{:?} outside of the cascade at {}", ndc, cascade.split);
+                assert!(ndc.y.abs() <= 1.0 + tolerance, "{:?} outside of the cascade at {}", ndc, cascade.split);
+                assert!(ndc.z.abs() <= 1.0, "{:?} outside of the cascade at {}", ndc, cascade.split);
+            }
+        }
+
+        previous_split = cascade.split;
+    }
+}
@@ -0,0 +1,184 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use cgmath::{Point3, Vector3};
+
+use engine::core::{Camera, Scene, SpatialGrid, CullStats};
+use engine::math::{Aabb, Frustum, Ray};
+
+use common::{Marker, camera, camera_at, drawn_ids, mock};
+
+use std::f32;
+
+fn unit_box(x: f32, y: f32, z: f32) -> Aabb {
+    return Aabb::new(Point3::new(x - 0.5, y - 0.5, z - 0.5), Point3::new(x + 0.5, y + 0.5, z + 0.5));
+}
+
+fn sorted(mut keys: Vec<u32>) -> Vec<u32> {
+    keys.sort();
+    return keys;
+}
+
+#[test]
+fn items_move_between_cells() {
+    let mut grid = SpatialGrid::new(4.0);
+    grid.insert(1, unit_box(0.0, 0.0, 0.0));
+    grid.insert(2, unit_box(10.0, 0.0, 10.0));
+    // Spans several cells.
+    grid.insert(3, Aabb::new(Point3::new(-10.0, 0.0, -1.0), Point3::new(10.0, 1.0, 1.0)));
+    assert_eq!(grid.len(), 3);
+
+    assert_eq!(sorted(grid.query_radius(Point3::new(1.0, 0.0, 1.0), 1.0)), vec![1, 3]);
+    assert_eq!(grid.query_radius(Point3::new(10.0, 0.0, 8.75), 1.0), vec![2]);
+
+    assert!(grid.update(2, unit_box(-20.0, 0.0, -20.0)));
+    assert!(grid.query_radius(Point3::new(10.0, 0.0, 8.75), 1.0).is_empty());
+    assert_eq!(grid.query_aabb(&unit_box(-20.5, 0.0, -20.0)), vec![2]);
+    assert!(!grid.update(4, unit_box(0.0, 0.0, 0.0)));
+
+    assert_eq!(grid.remove(3), Some(Aabb::new(Point3::new(-10.0, 0.0, -1.0), Point3::new(10.0, 1.0, 1.0))));
+    assert_eq!(grid.remove(3), None);
+    assert_eq!(grid.query_aabb(&Aabb::new(Point3::new(-100.0, -1.0, -100.0), Point3::new(100.0, 1.0, 100.0))).len(), 2);
+
+    // The radius is measured to the closest point of the box, in 3D.
+    assert_eq!(grid.query_radius(Point3::new(0.0, 2.0, 0.0), 1.6), vec![1]);
+    assert!(grid.query_radius(Point3::new(0.0, 2.0, 0.0), 1.4).is_empty());
+}
+
+#[test]
+fn rays_hit_the_items_in_order() {
+    let mut grid = SpatialGrid::new(2.0);
+    grid.insert(1, unit_box(10.0, 0.0, 0.0));
+    grid.insert(2, unit_box(5.0, 0.0, 0.0));
+    grid.insert(3, unit_box(5.0, 3.0, 0.0));
+    grid.insert(4, unit_box(-5.0, 0.0, 0.0));
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(grid.query_ray(&ray, f32::INFINITY), vec![(2, 4.5), (1, 9.5)]);
+    assert_eq!(grid.query_ray(&ray, 5.0), vec![(2, 4.5)]);
+
+    // Starting outside of the occupied cells and going diagonally.
+    let ray = Ray::new(Point3::new(-30.0, 0.0, -30.0), Vector3::new(1.0, 0.0, 1.0));
+    assert!(grid.query_ray(&ray, f32::INFINITY).is_empty());
+    let ray = Ray::new(Point3::new(-10.0, 0.0, -15.0), Vector3::new(1.0, 0.0, 3.0));
+    assert_eq!(grid.query_ray(&ray, f32::INFINITY).len(), 1);
+
+    // Straight down.
+    let ray = Ray::new(Point3::new(5.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+    let hits: Vec<u32> = grid.query_ray(&ray, f32::INFINITY).into_iter().map(|hit| hit.0).collect();
+    assert_eq!(hits, vec![3, 2]);
+}
+
+#[test]
+fn frustum_query() {
+    let mut grid = SpatialGrid::new(4.0);
+    grid.insert(1, unit_box(0.0, 0.0, -10.0));
+    grid.insert(2, unit_box(0.0, 0.0, 10.0));
+
+    assert_eq!(grid.query_frustum(&Frustum::from_matrix(&camera_at(0.0).vp_matrix())), vec![1]);
+}
+
+#[test]
+fn scene_culls_through_the_index() {
+    let mock = mock();
+
+    let ahead = wrap!(Marker::new(1).at(0.0, 0.0, -10.0).bounded());
+    let behind = wrap!(Marker::new(2).at(0.0, 0.0, 10.0).bounded());
+    let far_behind = wrap!(Marker::new(3).at(0.0, 0.0, 20.0).bounded());
+
+    let index = wrap!(SpatialGrid::new(8.0));
+    let mut scene = Scene::new(camera_at(0.0));
+
+    let ahead_handle = scene.add(Scene::node(&ahead));
+    let behind_handle = scene.add(Scene::node(&behind));
+
+    // The nodes added before and after setting the index are indexed on the next draw.
+    scene.set_spatial_index(Some(index.clone()));
+    scene.add(Scene::node(&far_behind));
+    assert!(index.borrow().is_empty());
+
+    scene.draw();
+    assert_eq!(drawn_ids(&mock), vec![1]);
+    assert_eq!(scene.cull_stats(), CullStats { drawn: 1, culled: 2 });
+    assert_eq!(index.borrow().len(), 3);
+    assert_eq!(index.borrow().bounds(behind_handle.id()), Some(unit_box(0.0, 0.0, 10.0)));
+
+    // The index follows the nodes as they move.
+    behind.borrow_mut().translation = Vector3::new(0.0, 0.0, -20.0);
+    mock.clear();
+    scene.draw();
+    assert_eq!(drawn_ids(&mock).len(), 2);
+    assert_eq!(index.borrow().bounds(behind_handle.id()), Some(unit_box(0.0, 0.0, -20.0)));
+
+    // And evicts them once removed or dropped.
+    behind_handle.remove();
+    drop(far_behind);
+    scene.draw();
+    assert_eq!(index.borrow().len(), 1);
+    assert!(index.borrow().contains(ahead_handle.id()));
+
+    assert!(!index.borrow_mut().insert_node(&behind_handle));
+
+    // Replacing the index takes the nodes out of the previous one.
+    scene.set_spatial_index(None);
+    assert!(index.borrow().is_empty());
+}
+
+#[test]
+fn frustum_query_visits_only_the_cells_under_the_frustum() {
+    let mut grid = SpatialGrid::new(1.0);
+    for x in 0..100 {
+        for z in 0..100 {
+            grid.insert(x * 100 + z, unit_box(x as f32 * 10.0, 0.0, z as f32 * 10.0));
+        }
+    }
+
+    let mut camera = Camera::new();
+    camera.perspective(60.0, 1.0, 0.1, 25.0);
+    camera.look_at(Point3::new(500.0, 0.0, 500.0), Point3::new(500.0, 0.0, 490.0), Vector3::new(0.0, 1.0, 0.0));
+    let frustum = Frustum::from_matrix(&camera.vp_matrix());
+
+    // The boxes ahead within the far plane, but not behind or beyond it.
+    let found = sorted(grid.query_frustum(&frustum));
+    assert!(found.contains(&5049) && found.contains(&5048));
+    assert!(!found.contains(&5051) && !found.contains(&5047));
+
+    // Finds every box with its center inside, and no box testing every one wouldn't.
+    for key in 0..10000 {
+        let bounds = grid.bounds(key).unwrap();
+
+        if frustum.contains(bounds.center()) {
+            assert!(found.contains(&key));
+        }
+        if found.contains(&key) {
+            assert!(frustum.intersects_aabb(&bounds));
+        }
+    }
+}
+
+#[test]
+fn unbounded_items_are_kept_aside() {
+    let mut grid = SpatialGrid::new(4.0);
+    grid.insert(1, unit_box(0.0, 0.0, 0.0));
+    grid.insert(2, Aabb::new(Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY), Point3::new(f32::INFINITY, 1.0, f32::INFINITY)));
+    grid.insert(3, Aabb::new(Point3::new(-1e30, 0.0, -1e30), Point3::new(1e30, 1.0, 1e30)));
+    grid.insert(4, unit_box(3e9, 0.0, 3e9));
+
+    assert_eq!(sorted(grid.query_radius(Point3::new(0.0, 0.0, 0.0), 1.0)), vec![1, 2, 3]);
+    assert_eq!(sorted(grid.query_aabb(&Aabb::new(Point3::new(-1e38, 0.0, -1e38), Point3::new(1e38, 1.0, 1e38)))), vec![1, 2, 3, 4]);
+
+    let ray = Ray::new(Point3::new(-10.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    let hits: Vec<u32> = grid.query_ray(&ray, f32::INFINITY).into_iter().map(|hit| hit.0).collect();
+    assert_eq!(sorted(hits), vec![1, 2, 3]);
+
+    assert_eq!(sorted(grid.query_frustum(&Frustum::from_matrix(&camera().vp_matrix()))), vec![1, 2, 3]);
+
+    // Moving into the grid and back out.
+    grid.insert(2, unit_box(8.0, 0.0, 0.0));
+    assert_eq!(sorted(grid.query_aabb(&unit_box(8.0, 0.0, 0.0))), vec![2, 3]);
+    assert!(grid.remove(3).is_some());
+    assert!(grid.query_radius(Point3::new(-50.0, 0.0, -50.0), 1.0).is_empty());
+}
@@ -0,0 +1,145 @@
+extern crate engine;
+
+mod common;
+
+use engine::gliw::{
+    gl, Buffer, BufferType, Capabilities, DrawMode, FeedbackPrimitive, MockBackend, TransformFeedback
+};
+
+use common::mock;
+
+use std::rc::Rc;
+
+/// A mock of a context without transform feedback objects, i.e. OpenGL 3.3.
+fn legacy_mock() -> Rc<MockBackend> {
+    let mock = mock();
+    mock.set_integer(gl::MAJOR_VERSION, 3);
+    mock.set_integer(gl::MINOR_VERSION, 3);
+    Capabilities::invalidate();
+    return mock;
+}
+
+#[test]
+fn begin_pause_resume_end() {
+    let mock = mock();
+
+    let feedback = TransformFeedback::new();
+    assert!(feedback.handle() != 0);
+
+    feedback.bind_buffer(0, &Buffer::new(BufferType::TransformFeedback));
+    feedback.begin(FeedbackPrimitive::Triangles);
+    feedback.pause();
+    feedback.resume();
+    feedback.pause();
+    feedback.end();
+
+    let names: Vec<_> = mock.calls().iter().map(|call| call.name)
+        .filter(|name| name.contains("TransformFeedback") || name.contains("Quer"))
+        .collect();
+    assert_eq!(names, [
+        "GenTransformFeedbacks", "GenQueries",
+        "BindTransformFeedback",
+        "BindTransformFeedback", "BeginQuery", "BeginTransformFeedback",
+        "BindTransformFeedback", "PauseTransformFeedback",
+        "BindTransformFeedback", "ResumeTransformFeedback",
+        "BindTransformFeedback", "PauseTransformFeedback",
+        "BindTransformFeedback", "EndTransformFeedback", "EndQuery"
+    ]);
+
+    assert_eq!(mock.calls_named("BeginTransformFeedback")[0].int(0), gl::TRIANGLES as i64);
+
+    // Can be restarted once ended.
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.end();
+
+    mock.clear();
+    feedback.draw(DrawMode::Points);
+    assert_eq!(mock.calls_named("DrawTransformFeedback")[0].int(1), feedback.handle() as i64);
+    assert_eq!(mock.calls_named("GetQueryObjectuiv").len(), 0);
+}
+
+#[test]
+#[should_panic]
+fn begin_twice_panics() {
+    mock();
+
+    let feedback = TransformFeedback::new();
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.begin(FeedbackPrimitive::Points);
+}
+
+#[test]
+#[should_panic]
+fn pause_twice_panics() {
+    mock();
+
+    let feedback = TransformFeedback::new();
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.pause();
+    feedback.pause();
+}
+
+#[test]
+#[should_panic]
+fn resume_without_pause_panics() {
+    mock();
+
+    let feedback = TransformFeedback::new();
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.resume();
+}
+
+#[test]
+#[should_panic]
+fn end_without_begin_panics() {
+    mock();
+    TransformFeedback::new().end();
+}
+
+#[test]
+#[should_panic]
+fn draw_while_active_panics() {
+    mock();
+
+    let feedback = TransformFeedback::new();
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.draw(DrawMode::Points);
+}
+
+#[test]
+#[should_panic]
+fn draw_before_capturing_panics() {
+    mock();
+    TransformFeedback::new().draw(DrawMode::Points);
+}
+
+#[test]
+fn legacy_contexts_draw_the_queried_count() {
+    let mock = legacy_mock();
+
+    let feedback = TransformFeedback::new();
+    assert_eq!(feedback.handle(), 0);
+
+    feedback.begin(FeedbackPrimitive::Triangles);
+    feedback.end();
+    assert_eq!(mock.calls_named("GenTransformFeedbacks").len(), 0);
+    assert_eq!(mock.calls_named("BindTransformFeedback").len(), 0);
+
+    mock.set_integer(gl::QUERY_RESULT, 5);
+    feedback.draw(DrawMode::Triangles);
+
+    let draws = mock.calls_named("DrawArrays");
+    assert_eq!(draws.len(), 1);
+    assert_eq!((draws[0].int(0), draws[0].int(2)), (gl::TRIANGLES as i64, 15));
+    assert_eq!(mock.calls_named("DrawTransformFeedback").len(), 0);
+}
+
+#[test]
+#[should_panic]
+fn legacy_contexts_cannot_pause() {
+    legacy_mock();
+
+    let feedback = TransformFeedback::new();
+    feedback.begin(FeedbackPrimitive::Points);
+    feedback.pause();
+}
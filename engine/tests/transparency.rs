@@ -0,0 +1,126 @@
+#[macro_use]
+extern crate engine;
+extern crate cgmath;
+
+mod common;
+
+use engine::core::{DeferredRenderer, RenderPath, Scene};
+use engine::gliw::{gl, GlCall};
+
+use common::{Marker, camera, drawn_ids, gbuffer_ids, mock};
+
+fn position(calls: &[GlCall], f: &Fn(&GlCall) -> bool) -> usize {
+    return calls.iter().position(|call| f(call)).expect("call not found");
+}
+
+#[test]
+fn opaque_front_to_back_then_transparent_back_to_front() {
+    let mock = mock();
+
+    let far_glass = wrap!(Marker::new(1).at(0.0, 0.0, -5.0).transparent());
+    let near_wall = wrap!(Marker::new(2).at(0.0, 0.0, 5.0));
+    let near_glass = wrap!(Marker::new(3).at(0.0, 0.0, 5.0).transparent());
+    let far_wall = wrap!(Marker::new(4).at(0.0, 0.0, -5.0));
+    let middle_glass = wrap!(Marker::new(5).at(0.0, 0.0, 0.0).transparent());
+
+    let mut scene = Scene::new(camera());
+    scene.add(Scene::node(&near_glass));
+    scene.add(Scene::node(&far_wall));
+    scene.add(Scene::node(&far_glass));
+    scene.add(Scene::node(&middle_glass));
+    scene.add(Scene::node(&near_wall));
+    scene.draw();
+
+    assert_eq!(drawn_ids(&mock), vec![2, 4, 1, 5, 3]);
+}
+
+#[test]
+fn priority_overrides_depth() {
+    let mock = mock();
+
+    let near = wrap!(Marker::new(1).at(0.0, 0.0, 5.0).with_priority(1));
+    let far = wrap!(Marker::new(2).at(0.0, 0.0, -5.0));
+    let near_glass = wrap!(Marker::new(3).at(0.0, 0.0, 5.0).transparent());
+    let far_glass = wrap!(Marker::new(4).at(0.0, 0.0, -5.0).transparent().with_priority(1));
+
+    let mut scene = Scene::new(camera());
+    scene.add(Scene::node(&near));
+    scene.add(Scene::node(&far));
+    scene.add(Scene::node(&near_glass));
+    scene.add(Scene::node(&far_glass));
+    scene.draw();
+
+    assert_eq!(drawn_ids(&mock), vec![2, 1, 3, 4]);
+}
+
+#[test]
+fn transparent_pass_blends_without_depth_writes() {
+    let mock = mock();
+
+    let wall = wrap!(Marker::new(1).at(0.0, 0.0, 0.0));
+    let glass = wrap!(Marker::new(2).at(0.0, 0.0, 5.0).transparent());
+
+    let mut scene = Scene::new(camera());
+    scene.add(Scene::node(&wall));
+    scene.add(Scene::node(&glass));
+    scene.draw();
+
+    let calls = mock.calls();
+    let is_draw = |id: i64| move |call: &GlCall| call.name == "DrawArrays" && call.int(1) == id;
+
+    let wall_draw = position(&calls, &is_draw(1));
+    let glass_draw = position(&calls, &is_draw(2));
+    let blend = position(&calls, &|call| call.name == "Enable" && call.int(0) == gl::BLEND as i64);
+    let depth_mask = position(&calls, &|call| call.name == "DepthMask" && call.int(0) == gl::FALSE as i64);
+
+    assert!(wall_draw < blend && blend < glass_draw);
+    assert!(wall_draw < depth_mask && depth_mask < glass_draw);
+
+    let blend_func = mock.calls_named("BlendFunc");
+    assert_eq!(blend_func.last().unwrap().int(0), gl::SRC_ALPHA as i64);
+    assert_eq!(blend_func.last().unwrap().int(1), gl::ONE_MINUS_SRC_ALPHA as i64);
+
+    // The state is restored for the next frame.
+    let last = |name: &str| calls.iter().rposition(|call| call.name == name).unwrap();
+    assert!(last("DepthMask") > glass_draw && calls[last("DepthMask")].int(0) == gl::TRUE as i64);
+    assert!(last("Disable") > glass_draw && calls[last("Disable")].int(0) == gl::BLEND as i64);
+}
+
+#[test]
+fn opaque_scene_leaves_the_blend_state() {
+    let mock = mock();
+
+    let wall = wrap!(Marker::new(1).at(0.0, 0.0, 0.0));
+
+    let mut scene = Scene::new(camera());
+    scene.add(Scene::node(&wall));
+    scene.draw();
+
+    assert!(mock.calls_named("BlendFunc").is_empty());
+    assert!(mock.calls_named("DepthMask").is_empty());
+}
+
+#[test]
+fn deferred_path_draws_transparent_forward() {
+    let mock = mock();
+
+    let wall = wrap!(Marker::new(1).at(0.0, 0.0, 0.0));
+    let far_glass = wrap!(Marker::new(2).at(0.0, 0.0, -5.0).transparent());
+    let near_glass = wrap!(Marker::new(3).at(0.0, 0.0, 5.0).transparent());
+
+    let mut scene = Scene::new(camera());
+    scene.set_render_path(RenderPath::Deferred(DeferredRenderer::new(800, 600).unwrap()));
+    scene.add(Scene::node(&near_glass));
+    scene.add(Scene::node(&wall));
+    scene.add(Scene::node(&far_glass));
+    scene.draw();
+
+    assert_eq!(gbuffer_ids(&mock), vec![1]);
+    assert_eq!(drawn_ids(&mock), vec![2, 3]);
+
+    // After the composition into the target framebuffer.
+    let calls = mock.calls();
+    let composition = calls.iter().rposition(|call| call.name == "DrawArrays" && call.int(0) == gl::TRIANGLES as i64).unwrap();
+    let glass_draw = calls.iter().position(|call| call.name == "DrawArrays" && call.int(0) == gl::POINTS as i64).unwrap();
+    assert!(composition < glass_draw);
+}
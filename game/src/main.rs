@@ -4,15 +4,22 @@ extern crate cgmath;
 extern crate glfw;
 extern crate gl;
 
-use engine::gliw::{Gliw, DepthFunction, ProgramBuilder, Shader, ShaderType};
+use engine::gliw::{Gliw, DepthFunction, ProgramBuilder, Shader, ShaderType, Attachment, InternalFormat};
 
-use engine::core::{Camera, Renderable, Scene, Composition, Cuboid, Color, Entity, Event, Data};
+use engine::core::{
+    Camera, Renderable, Scene, Composition, Cuboid, Color, Entity, Event, Data,
+    DeferredRenderer, PointLight, SpotLight, DirectionalLight, ShadowSettings, RenderPath,
+    RenderGraph, TargetDesc, TargetPool,
+    PostStack, PostEffect, ToneMapOperator, ColorLut,
+    InputState, Control, CameraController, OrbitController, FreeFlyController
+};
 
-use cgmath::{Point3, Vector3};
+use cgmath::{Point3, Vector3, Vector4};
 
-use glfw::{Action, Context, Key};
+use glfw::{Action, Context, Key, MouseButton};
 
 use std::ops::DerefMut;
+use std::rc::Rc;
 
 mod simple_plain;
 mod simple_component;
@@ -23,7 +30,6 @@ use self::simple_component::AntiClockwiseRotation;
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
 
-    glfw.window_hint(glfw::WindowHint::Samples(4));
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
 
@@ -36,6 +42,12 @@ fn main() {
 
     window.make_current();
     window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+    window.set_size_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_cursor_enter_polling(true);
+    window.set_mouse_button_polling(true);
+    window.set_scroll_polling(true);
     glfw.set_swap_interval(1);
 
     gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
@@ -45,7 +57,6 @@ fn main() {
     Gliw::enable(gl::DEPTH_TEST);
     Gliw::depth_func(DepthFunction::Less);
     Gliw::enable(gl::CULL_FACE);
-    Gliw::clear_color(0.0, 0.0, 0.4, 0.0);
 
     let mut camera = Camera::new();
     camera.look_at(
@@ -124,22 +135,86 @@ fn main() {
     scene.add(Scene::node(&cuboid5));
     scene.add(Scene::node(&cuboid6));
 
+    let lights = [
+        wrap!(PointLight::new(Point3::new(0.0, 1.5, 0.0), Vector3::new(1.0, 0.9, 0.7), 4.0)),
+        wrap!(PointLight::new(Point3::new(2.0, 1.0, 0.0), Vector3::new(1.0, 0.3, 0.1), 3.0)),
+        wrap!(PointLight::new(Point3::new(-2.0, 1.0, 0.0), Vector3::new(0.2, 0.4, 1.0), 3.0)),
+    ];
+
+    for light in lights.iter() {
+        scene.add_light(Rc::downgrade(light));
+    }
+
+    // Spot light shining down on the composition
+    let spot = wrap!(SpotLight::new(
+        Point3::new(-2.0, 3.0, 1.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.9),
+        6.0));
+    scene.add_spot_light(Rc::downgrade(&spot));
+
+    let (mut width, mut height) = window.get_framebuffer_size();
+    let mut sun = DirectionalLight::new(Vector3::new(-0.4, -1.0, -0.3), Vector3::new(1.0, 0.95, 0.85));
+    sun.intensity = 0.6;
+    scene.set_directional_light(Some(sun));
+
+    let mut shadow_settings = ShadowSettings::new();
+    shadow_settings.max_distance = 20.0;
+
+    let mut renderer = DeferredRenderer::new(width, height).unwrap();
+    renderer.enable_shadows(shadow_settings).unwrap();
+    scene.set_render_path(RenderPath::Deferred(renderer));
+
+    // Anti-aliasing is done by FXAA since the scene is rendered offscreen.
+    let mut post = PostStack::new().unwrap();
+    let bloom = post.push(PostEffect::Bloom { threshold: 0.8, intensity: 0.6, iterations: 2 });
+    post.push(PostEffect::ToneMap { exposure: 1.2, operator: ToneMapOperator::Aces });
+    let grade = post.push(PostEffect::ColorGrade {
+        lut: Rc::new(ColorLut::from_fn(16, |c| Vector3::new(c.x * 1.05, c.y, c.z * 0.9))),
+        strength: 1.0
+    });
+    post.push(PostEffect::Vignette { intensity: 0.6, radius: 0.55, softness: 0.45 });
+    let fxaa = post.push(PostEffect::Fxaa);
+    let mut pool = TargetPool::new();
+
+    // F1-F3 toggle the effects
+    let toggles = [(Key::F1, fxaa), (Key::F2, bloom), (Key::F3, grade)];
+
+    // The cursor is in window coordinates, which differ from the framebuffer's pixels on HiDPI screens.
+    let (window_width, window_height) = window.get_size();
+
+    // Orbits the composition on its own, dragging with the right mouse button rotates it.
+    // F4 toggles a free-fly camera for debugging.
+    let mut input = InputState::new(window_width, window_height);
+    let mut orbit = OrbitController::new(Point3::new(0.0, 0.0, 0.0), 7.0);
+    orbit.pitch = 25.0;
+    orbit.auto_rotate = 30.0;
+    let mut free_fly: Option<FreeFlyController> = None;
+
     let animation_speed = 2.0;
-    let camera_speed = 0.5;
     let cuboid3_scale = cuboid3.borrow().scale;
     let cuboid4_pos_x = cuboid4.borrow().position.x;
 
     cuboid6.borrow_mut().add(AntiClockwiseRotation::new(animation_speed));
 
-    while !window.should_close() {
-        Gliw::clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    let mut last_time = glfw.get_time();
 
+    while !window.should_close() {
         cuboid3.borrow_mut().scale = cuboid3_scale +
             (f64::sin(glfw.get_time() * animation_speed) as f32) * 0.75;
 
         cuboid4.borrow_mut().position.x = cuboid4_pos_x +
             f64::sin(glfw.get_time() * animation_speed) as f32;
 
+        // Orbiting colored lights
+        for (i, light) in lights.iter().enumerate().skip(1) {
+            let angle = glfw.get_time() * animation_speed * 0.5 + i as f64 * std::f64::consts::PI;
+            light.borrow_mut().position = Point3::new(
+                2.0 * f64::cos(angle) as f32,
+                1.0,
+                2.0 * f64::sin(angle) as f32);
+        }
+
         // Trigger the AntiClockwiseRotation component
         let cuboid6_ent = (*cuboid6.borrow_mut()).deref_mut() as *mut Entity;
         cuboid6.borrow_mut().emit(Event("rotate"),
@@ -153,30 +228,108 @@ fn main() {
                 f64::cos(glfw.get_time() * animation_speed) as f32),
             Vector3::new(0.0, 1.0, 0.0));
 
-        scene.camera_mut().look_at(
-            Point3::<f32>::new(
-                4.0 * f64::cos(glfw.get_time() * camera_speed) as f32,
-                3.0 * (f64::cos(glfw.get_time() * camera_speed) * 0.5 + 1.0) as f32,
-                6.0 * f64::sin(glfw.get_time() * camera_speed) as f32),
-            Point3::<f32>::new(0.0, 0.0, 0.0),
-            Vector3::<f32>::new(0.0, 1.0, 0.0));
+        let time = glfw.get_time();
+        let delta = (time - last_time) as f32;
+        last_time = time;
+
+        match free_fly {
+            Some(ref mut free_fly) => free_fly.update(scene.camera_mut(), &input, delta),
+            None => orbit.update(scene.camera_mut(), &input, delta)
+        }
+        input.end_frame();
+
+        post.set_time(glfw.get_time() as f32);
 
-        scene.draw();
+        {
+            let mut graph = RenderGraph::new(width, height);
+            let hdr = graph.create_target("hdr", TargetDesc::new(width, height, InternalFormat::RGBA16F));
+            let depth = graph.create_target("depth", TargetDesc::new(width, height, InternalFormat::Depth24));
+
+            graph.add_scene_pass(&scene)
+                .write(Attachment::Color(0), hdr)
+                .write(Attachment::Depth, depth)
+                .clear_color(Vector4::new(0.0, 0.0, 0.4, 0.0))
+                .clear_depth(1.0);
+
+            post.add_passes(&mut graph, hdr, RenderGraph::backbuffer(), width, height);
+            graph.execute(&mut pool);
+        }
 
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut window, event);
+            if let glfw::WindowEvent::FramebufferSize(new_width, new_height) = event {
+                if new_width > 0 && new_height > 0 {
+                    width = new_width;
+                    height = new_height;
+
+                    scene.camera_mut().set_viewport_size(width, height);
+                    if let RenderPath::Deferred(ref mut renderer) = *scene.render_path_mut() {
+                        renderer.resize(width, height);
+                    }
+                }
+            }
+
+            if let glfw::WindowEvent::Size(new_width, new_height) = event {
+                if new_width > 0 && new_height > 0 {
+                    input.set_viewport_size(new_width, new_height);
+                }
+            }
+
+            if let glfw::WindowEvent::Key(Key::F4, _, Action::Press, _) = event {
+                free_fly = match free_fly {
+                    Some(_) => None,
+                    None => Some(FreeFlyController::from_camera(scene.camera()))
+                };
+            }
+
+            handle_input_event(&mut input, &event);
+            handle_window_event(&mut window, &mut post, &toggles, event);
         }
     }
 }
 
-fn handle_window_event(window: &mut glfw::Window, event: glfw::WindowEvent) {
+fn handle_window_event(window: &mut glfw::Window, post: &mut PostStack, toggles: &[(Key, usize)], event: glfw::WindowEvent) {
     match event {
         glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
             window.set_should_close(true)
         }
+        glfw::WindowEvent::Key(key, _, Action::Press, _) => {
+            for &(toggle_key, effect) in toggles.iter() {
+                if key == toggle_key {
+                    let enabled = post.is_enabled(effect);
+                    post.set_enabled(effect, !enabled);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_input_event(input: &mut InputState, event: &glfw::WindowEvent) {
+    match *event {
+        glfw::WindowEvent::Key(key, _, action, _) if action != Action::Repeat => {
+            let control = match key {
+                Key::W => Control::MoveForward,
+                Key::S => Control::MoveBack,
+                Key::A => Control::MoveLeft,
+                Key::D => Control::MoveRight,
+                Key::Space => Control::MoveUp,
+                Key::LeftControl => Control::MoveDown,
+                Key::Q => Control::RotateLeft,
+                Key::E => Control::RotateRight,
+                Key::LeftShift => Control::Boost,
+                _ => return
+            };
+            input.set_control(control, action == Action::Press);
+        }
+        glfw::WindowEvent::MouseButton(MouseButton::Button2, action, _) => {
+            input.set_control(Control::Look, action == Action::Press);
+        }
+        glfw::WindowEvent::CursorPos(x, y) => input.move_cursor(x as f32, y as f32),
+        glfw::WindowEvent::CursorEnter(false) => input.leave_cursor(),
+        glfw::WindowEvent::Scroll(_, y) => input.scroll(y as f32),
         _ => {}
     }
 }